@@ -0,0 +1,142 @@
+//! Key bindings triggered by a sequence of keys, like `Ctrl+A` followed by `C`.
+
+use std::time::{Duration, Instant};
+
+use glutin::event::{KeyboardInput, ModifiersState, VirtualKeyCode};
+
+/// Keys of a chord which have been pressed so far
+#[derive(Debug, Default)]
+pub struct ChordState {
+    keys: Vec<(KeyboardInput, ModifiersState)>,
+
+    /// Time at which the pending chord is abandoned.
+    deadline: Option<Instant>,
+}
+
+impl ChordState {
+    /// Keys pressed so far, empty once the chord has timed out.
+    pub fn keys(&self) -> &[(KeyboardInput, ModifiersState)] {
+        if self.is_expired() {
+            &[]
+        } else {
+            &self.keys
+        }
+    }
+
+    /// Check if a chord is waiting for its next key.
+    #[inline]
+    pub fn pending(&self) -> bool {
+        !self.keys().is_empty()
+    }
+
+    /// Add the next key of the chord, restarting the timeout.
+    pub fn push(&mut self, input: KeyboardInput, mods: ModifiersState, timeout: Duration) {
+        if self.is_expired() {
+            self.keys.clear();
+        }
+
+        self.keys.push((input, mods));
+        self.deadline = Some(Instant::now() + timeout);
+    }
+
+    /// Abandon the pending chord.
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.deadline = None;
+    }
+
+    /// Time at which the pending chord is abandoned.
+    #[inline]
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Check if the pending chord has timed out.
+    pub fn is_expired(&self) -> bool {
+        self.deadline.map_or(false, |deadline| deadline <= Instant::now())
+    }
+
+    /// Text of the pending keys, like `Ctrl+A C`.
+    pub fn indicator(&self) -> String {
+        let keys: Vec<String> =
+            self.keys().iter().map(|&(input, mods)| key_name(input, mods)).collect();
+        keys.join(" ")
+    }
+}
+
+/// Check if a key is only a modifier, which does not advance chords.
+pub fn is_modifier(input: KeyboardInput) -> bool {
+    match input.virtual_keycode {
+        Some(VirtualKeyCode::LShift)
+        | Some(VirtualKeyCode::RShift)
+        | Some(VirtualKeyCode::LControl)
+        | Some(VirtualKeyCode::RControl)
+        | Some(VirtualKeyCode::LAlt)
+        | Some(VirtualKeyCode::RAlt)
+        | Some(VirtualKeyCode::LWin)
+        | Some(VirtualKeyCode::RWin) => true,
+        _ => false,
+    }
+}
+
+fn key_name(input: KeyboardInput, mods: ModifiersState) -> String {
+    let mut name = String::new();
+    if mods.ctrl() {
+        name.push_str("Ctrl+");
+    }
+    if mods.alt() {
+        name.push_str("Alt+");
+    }
+    if mods.logo() {
+        name.push_str("Super+");
+    }
+    if mods.shift() {
+        name.push_str("Shift+");
+    }
+
+    match input.virtual_keycode {
+        Some(key) => name.push_str(&format!("{:?}", key)),
+        None => name.push_str(&format!("<{}>", input.scancode)),
+    }
+
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use glutin::event::ElementState;
+
+    #[allow(deprecated)]
+    fn key(keycode: VirtualKeyCode) -> KeyboardInput {
+        KeyboardInput {
+            scancode: 0,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(keycode),
+            modifiers: ModifiersState::default(),
+        }
+    }
+
+    #[test]
+    fn pending_chord() {
+        let mut chord = ChordState::default();
+        assert!(!chord.pending());
+
+        let timeout = Duration::from_secs(60);
+        chord.push(key(VirtualKeyCode::A), ModifiersState::CTRL, timeout);
+        chord.push(key(VirtualKeyCode::C), ModifiersState::empty(), timeout);
+        assert!(chord.pending());
+        assert_eq!(chord.indicator(), "Ctrl+A C");
+
+        // Timed out chords start over
+        chord.push(key(VirtualKeyCode::X), ModifiersState::SHIFT, Duration::from_secs(0));
+        assert!(!chord.pending());
+        chord.push(key(VirtualKeyCode::Y), ModifiersState::empty(), timeout);
+        assert_eq!(chord.indicator(), "Y");
+
+        chord.clear();
+        assert!(!chord.pending());
+        assert_eq!(chord.deadline(), None);
+    }
+}
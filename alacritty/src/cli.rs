@@ -18,7 +18,7 @@ use std::path::PathBuf;
 use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg};
 use log::{self, error, LevelFilter};
 
-use alacritty_terminal::config::{Delta, Dimensions, Shell, DEFAULT_NAME};
+use alacritty_terminal::config::{Delta, Dimensions, Shell, StartupMode, DEFAULT_NAME};
 use alacritty_terminal::index::{Column, Line};
 
 use crate::config::Config;
@@ -37,6 +37,8 @@ pub struct Options {
     pub ref_test: bool,
     pub dimensions: Option<Dimensions>,
     pub position: Option<Delta<i32>>,
+    pub monitor: Option<String>,
+    pub startup_mode: Option<StartupMode>,
     pub title: Option<String>,
     pub class: Option<String>,
     pub embed: Option<String>,
@@ -56,6 +58,8 @@ impl Default for Options {
             ref_test: false,
             dimensions: None,
             position: None,
+            monitor: None,
+            startup_mode: None,
             title: None,
             class: None,
             embed: None,
@@ -126,6 +130,19 @@ impl Options {
                          manager if unset [default: unset]",
                     ),
             )
+            .arg(
+                Arg::with_name("monitor")
+                    .long("monitor")
+                    .takes_value(true)
+                    .help("Defines the name of the monitor the window is opened on"),
+            )
+            .arg(
+                Arg::with_name("startup-mode")
+                    .long("startup-mode")
+                    .takes_value(true)
+                    .possible_values(STARTUP_MODES)
+                    .help("Defines the startup mode of the window"),
+            )
             .arg(
                 Arg::with_name("title")
                     .long("title")
@@ -214,6 +231,9 @@ impl Options {
             }
         }
 
+        options.monitor = matches.value_of("monitor").map(ToOwned::to_owned);
+        options.startup_mode = matches.value_of("startup-mode").and_then(parse_startup_mode);
+
         options.class = matches.value_of("class").map(ToOwned::to_owned);
         options.title = matches.value_of("title").map(ToOwned::to_owned);
         options.embed = matches.value_of("embed").map(ToOwned::to_owned);
@@ -276,6 +296,10 @@ impl Options {
         config.window.dimensions = self.dimensions.unwrap_or(config.window.dimensions);
         config.window.title = self.title.unwrap_or(config.window.title);
         config.window.position = self.position.or(config.window.position);
+        config.window.monitor = self.monitor.or(config.window.monitor);
+        if let Some(startup_mode) = self.startup_mode {
+            config.window.set_startup_mode(startup_mode);
+        }
         config.window.embed = self.embed.and_then(|embed| embed.parse().ok());
 
         if let Some(class) = self.class {
@@ -300,8 +324,27 @@ impl Options {
     }
 }
 
+/// Startup modes accepted by `--startup-mode`.
+#[cfg(not(target_os = "macos"))]
+const STARTUP_MODES: &[&str] = &["Windowed", "Maximized", "Fullscreen"];
+#[cfg(target_os = "macos")]
+const STARTUP_MODES: &[&str] = &["Windowed", "Maximized", "Fullscreen", "SimpleFullscreen"];
+
+fn parse_startup_mode(mode: &str) -> Option<StartupMode> {
+    match mode {
+        "Windowed" => Some(StartupMode::Windowed),
+        "Maximized" => Some(StartupMode::Maximized),
+        "Fullscreen" => Some(StartupMode::Fullscreen),
+        #[cfg(target_os = "macos")]
+        "SimpleFullscreen" => Some(StartupMode::SimpleFullscreen),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use alacritty_terminal::config::StartupMode;
+
     use crate::cli::Options;
     use crate::config::Config;
 
@@ -335,4 +378,16 @@ mod tests {
 
         assert!(config.dynamic_title());
     }
+
+    #[test]
+    fn startup_mode_overrides_config() {
+        let mut config = Config::default();
+        config.window.start_maximized = Some(true);
+
+        let mut options = Options::default();
+        options.startup_mode = super::parse_startup_mode("Fullscreen");
+        let config = options.into_config(config);
+
+        assert_eq!(config.window.startup_mode(), StartupMode::Fullscreen);
+    }
 }
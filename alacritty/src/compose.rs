@@ -0,0 +1,48 @@
+//! Dead key and Compose sequences, whose keys only produce the composed character.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use glutin::event::ScanCode;
+
+/// Pending Compose sequence and the keys it has swallowed.
+#[derive(Debug, Default)]
+pub struct ComposeState {
+    /// Time at which the pending sequence is abandoned.
+    deadline: Option<Instant>,
+
+    /// Keys which were pressed as part of the sequence and have not been released yet.
+    keys: HashSet<ScanCode>,
+}
+
+impl ComposeState {
+    /// Start a new sequence after the Compose key has been pressed.
+    pub fn start(&mut self, timeout: Duration) {
+        self.deadline = Some(Instant::now() + timeout);
+    }
+
+    /// Check if a sequence is waiting for its next key.
+    pub fn active(&self) -> bool {
+        self.deadline.map_or(false, |deadline| deadline > Instant::now())
+    }
+
+    /// Complete or abandon the pending sequence.
+    ///
+    /// Keys which are still held remain swallowed until they are released.
+    #[inline]
+    pub fn finish(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Swallow a key pressed as part of the sequence.
+    #[inline]
+    pub fn swallow(&mut self, scancode: ScanCode) {
+        self.keys.insert(scancode);
+    }
+
+    /// Release a key, returning `true` if its press was swallowed by a sequence.
+    #[inline]
+    pub fn release(&mut self, scancode: ScanCode) -> bool {
+        self.keys.remove(&scancode)
+    }
+}
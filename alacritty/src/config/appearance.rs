@@ -0,0 +1,124 @@
+use glutin::window::Theme;
+use serde::de::Error as SerdeError;
+use serde::{Deserialize, Deserializer};
+
+use alacritty_terminal::config::failure_default;
+
+/// Minutes in a day.
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Automatic switching between a dark and a light color scheme
+#[serde(default)]
+#[derive(Default, Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Appearance {
+    /// Color scheme used with a dark appearance, the `colors` section without a name
+    #[serde(deserialize_with = "failure_default")]
+    dark: Option<String>,
+
+    /// Color scheme used with a light appearance, the `colors` section without a name
+    #[serde(deserialize_with = "failure_default")]
+    light: Option<String>,
+
+    /// Fixed times of day for switching, instead of following the system
+    #[serde(deserialize_with = "failure_default")]
+    pub schedule: Option<Schedule>,
+}
+
+impl Appearance {
+    /// Check if the color scheme should be switched automatically.
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.dark.is_some() || self.light.is_some()
+    }
+
+    /// Name of the color scheme for an appearance, `None` for the base colors.
+    pub fn color_scheme(&self, theme: Theme) -> Option<&str> {
+        match theme {
+            Theme::Dark => self.dark.as_deref(),
+            Theme::Light => self.light.as_deref(),
+        }
+    }
+}
+
+/// Local times of day at which the appearance changes
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    light: TimeOfDay,
+    dark: TimeOfDay,
+}
+
+impl Schedule {
+    /// Appearance at a minute of the day.
+    pub fn theme(self, minute: u32) -> Theme {
+        let (light, dark) = (self.light.0, self.dark.0);
+        let is_light = if light <= dark {
+            minute >= light && minute < dark
+        } else {
+            minute >= light || minute < dark
+        };
+
+        if is_light {
+            Theme::Light
+        } else {
+            Theme::Dark
+        }
+    }
+
+    /// Minutes from a minute of the day until the next switch, at least one.
+    pub fn minutes_until_switch(self, minute: u32) -> u32 {
+        let until = |time: TimeOfDay| (time.0 + MINUTES_PER_DAY - minute - 1) % MINUTES_PER_DAY + 1;
+        until(self.light).min(until(self.dark))
+    }
+}
+
+/// Minute of the day, written as `HH:MM`
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct TimeOfDay(u32);
+
+impl<'a> Deserialize<'a> for TimeOfDay {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let time = String::deserialize(deserializer)?;
+
+        let mut parts = time.splitn(2, ':').map(str::parse::<u32>);
+        match (parts.next(), parts.next()) {
+            (Some(Ok(hours)), Some(Ok(minutes))) if hours < 24 && minutes < 60 => {
+                Ok(TimeOfDay(hours * 60 + minutes))
+            },
+            _ => Err(D::Error::custom(format!("invalid time of day {:?}, expected HH:MM", time))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schedule() {
+        let appearance: Appearance =
+            serde_yaml::from_str("light: day\nschedule: {light: '07:30', dark: '19:00'}").unwrap();
+        let schedule = appearance.schedule.unwrap();
+
+        assert!(appearance.enabled());
+        assert_eq!(appearance.color_scheme(Theme::Light), Some("day"));
+        assert_eq!(appearance.color_scheme(Theme::Dark), None);
+
+        assert_eq!(schedule.theme(7 * 60 + 29), Theme::Dark);
+        assert_eq!(schedule.theme(7 * 60 + 30), Theme::Light);
+        assert_eq!(schedule.theme(19 * 60), Theme::Dark);
+        assert_eq!(schedule.minutes_until_switch(7 * 60 + 30), 11 * 60 + 30);
+        assert_eq!(schedule.minutes_until_switch(23 * 60), 8 * 60 + 30);
+
+        // Schedules can wrap around midnight
+        let schedule: Schedule = serde_yaml::from_str("{light: '22:00', dark: '06:00'}").unwrap();
+        assert_eq!(schedule.theme(23 * 60), Theme::Light);
+        assert_eq!(schedule.theme(60), Theme::Light);
+        assert_eq!(schedule.theme(12 * 60), Theme::Dark);
+
+        assert!(serde_yaml::from_str::<Schedule>("{light: '24:00', dark: '06:00'}").is_err());
+        assert!(!Appearance::default().enabled());
+    }
+}
@@ -0,0 +1,37 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use alacritty_terminal::config::failure_default;
+
+use crate::config::bindings::CommandWrapper;
+
+/// Audio player receiving the bell sound file as its only argument
+#[cfg(not(any(target_os = "macos", windows)))]
+pub const SOUND_PLAYER: Option<&str> = Some("paplay");
+#[cfg(target_os = "macos")]
+pub const SOUND_PLAYER: Option<&str> = Some("afplay");
+#[cfg(windows)]
+pub const SOUND_PLAYER: Option<&str> = None;
+
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Bell {
+    /// Mark the window as urgent while it is not focused
+    #[serde(deserialize_with = "failure_default")]
+    pub urgent: bool,
+
+    /// Sound file played with the system's audio player
+    #[serde(deserialize_with = "failure_default")]
+    pub sound: Option<PathBuf>,
+
+    /// Program run on every bell
+    #[serde(deserialize_with = "failure_default")]
+    pub command: Option<CommandWrapper>,
+}
+
+impl Default for Bell {
+    fn default() -> Self {
+        Bell { urgent: true, sound: None, command: None }
+    }
+}
@@ -16,7 +16,7 @@
 use std::fmt::{self, Debug, Display};
 
 use glutin::event::VirtualKeyCode::*;
-use glutin::event::{ModifiersState, MouseButton, VirtualKeyCode};
+use glutin::event::{KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode};
 use serde::de::Error as SerdeError;
 use serde::de::{self, MapAccess, Unexpected, Visitor};
 use serde::{Deserialize, Deserializer};
@@ -25,6 +25,8 @@ use serde_yaml::Value as SerdeValue;
 use alacritty_terminal::term::TermMode;
 use alacritty_terminal::vi_mode::ViMotion;
 
+use crate::config::hints::Hint;
+
 /// Describes a state and action to take in that state
 ///
 /// This is the shared component of `MouseBinding` and `KeyBinding`
@@ -46,13 +48,16 @@ pub struct Binding<T> {
     ///
     /// For example, this might be a key like "G", or a mouse button.
     pub trigger: T,
+
+    /// Keys which have to be pressed in order before the trigger
+    pub chord: Vec<ChordKey>,
 }
 
 /// Bindings that are triggered by a keyboard key
 pub type KeyBinding = Binding<Key>;
 
 /// Bindings that are triggered by a mouse button
-pub type MouseBinding = Binding<MouseButton>;
+pub type MouseBinding = Binding<MouseEvent>;
 
 impl<T: Eq> Binding<T> {
     #[inline]
@@ -68,8 +73,11 @@ impl<T: Eq> Binding<T> {
 
     #[inline]
     pub fn triggers_match(&self, binding: &Binding<T>) -> bool {
-        // Check the binding's key and modifiers
-        if self.trigger != binding.trigger || self.mods != binding.mods {
+        // Check the binding's key, modifiers and chord
+        if self.trigger != binding.trigger
+            || self.mods != binding.mods
+            || self.chord != binding.chord
+        {
             return false;
         }
 
@@ -84,6 +92,61 @@ impl<T: Eq> Binding<T> {
         (self.mode == binding.mode || self.mode.intersects(binding.mode))
             && (self.notmode == binding.notmode || self.notmode.intersects(binding.notmode))
     }
+
+    /// Check if the pending keys are exactly the chord of this binding.
+    #[inline]
+    pub fn chord_matches(&self, keys: &[(KeyboardInput, ModifiersState)]) -> bool {
+        self.chord.len() == keys.len() && self.chord_starts_with(keys)
+    }
+
+    /// Check if a key press is the next key of this binding's chord after the pending keys.
+    pub fn is_chord_continued_by(
+        &self,
+        mode: TermMode,
+        keys: &[(KeyboardInput, ModifiersState)],
+        input: KeyboardInput,
+        mods: ModifiersState,
+    ) -> bool {
+        self.chord.len() > keys.len()
+            && mode.contains(self.mode)
+            && !mode.intersects(self.notmode)
+            && self.chord_starts_with(keys)
+            && self.chord[keys.len()].matches(input, mods)
+    }
+
+    fn chord_starts_with(&self, keys: &[(KeyboardInput, ModifiersState)]) -> bool {
+        keys.len() <= self.chord.len()
+            && self.chord.iter().zip(keys).all(|(key, &(input, mods))| key.matches(input, mods))
+    }
+}
+
+/// Mouse input which can trigger a binding
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouseEvent {
+    Button(MouseButton),
+    WheelUp,
+    WheelDown,
+}
+
+/// Key press which is part of a chord
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ChordKey {
+    key: Key,
+    #[serde(default)]
+    mods: ModsWrapper,
+}
+
+impl ChordKey {
+    /// Check if a key press with the current modifiers is this key.
+    pub fn matches(self, input: KeyboardInput, mods: ModifiersState) -> bool {
+        let key = match (self.key, input.virtual_keycode) {
+            (Key::Scancode(scancode), _) => scancode == input.scancode,
+            (Key::Keycode(key), Some(keycode)) => key == keycode,
+            (Key::Keycode(_), None) => false,
+        };
+
+        key && self.mods.into_inner() == mods
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
@@ -104,6 +167,10 @@ pub enum Action {
     #[serde(skip)]
     ViAction(ViAction),
 
+    /// Start selecting a match of a hint.
+    #[serde(skip)]
+    Hint(Hint),
+
     /// Paste contents of system clipboard.
     Paste,
 
@@ -117,6 +184,9 @@ pub enum Action {
     /// Paste contents of selection buffer.
     PasteSelection,
 
+    /// Paste contents of system clipboard, aligning every line with the cursor column.
+    PasteBlock,
+
     /// Increase font size.
     IncreaseFontSize,
 
@@ -126,6 +196,15 @@ pub enum Action {
     /// Reset font size to the config value.
     ResetFontSize,
 
+    /// Increase background opacity.
+    IncreaseOpacity,
+
+    /// Decrease background opacity.
+    DecreaseOpacity,
+
+    /// Reset background opacity to the config value.
+    ResetOpacity,
+
     /// Scroll exactly one page up.
     ScrollPageUp,
 
@@ -150,9 +229,39 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Scroll to the previous shell prompt.
+    ScrollToPreviousPrompt,
+
+    /// Scroll to the next shell prompt.
+    ScrollToNextPrompt,
+
+    /// Select the output of the last shell command.
+    SelectLastCommandOutput,
+
+    /// Copy the output of the last shell command to the clipboard.
+    CopyLastCommandOutput,
+
     /// Clear the display buffer(s) to remove history.
     ClearHistory,
 
+    /// Push the screen into the history, keeping the current prompt.
+    ClearScreen,
+
+    /// Write history and visible area to a text file.
+    ExportScrollback,
+
+    /// Write history and visible area to an HTML file, preserving colors and styles.
+    ExportScrollbackHtml,
+
+    /// Show the time at which each line was completed, also adding it to exported scrollback.
+    ToggleLineTimestamps,
+
+    /// Switch to a named color scheme.
+    ColorScheme(String),
+
+    /// Switch to the next color scheme, cycling back to the `colors`.
+    NextColorScheme,
+
     /// Hide the Alacritty window.
     Hide,
 
@@ -181,6 +290,9 @@ pub enum Action {
     /// Toggle vi mode.
     ToggleViMode,
 
+    /// Toggle the render timer and glyph cache statistics.
+    ToggleDebugOverlay,
+
     /// Allow receiving char input.
     ReceiveChar,
 
@@ -216,8 +328,22 @@ pub enum ViAction {
     ToggleBlockSelection,
     /// Toggle semantic vi selection.
     ToggleSemanticSelection,
+    /// Swap the ends of the selection, to adjust where it was started.
+    SwapSelectionEnds,
     /// Launch the URL below the vi mode cursor.
     Open,
+    /// Move to the next occurrence of the following character in the line.
+    InlineSearchForward,
+    /// Move to the previous occurrence of the following character in the line.
+    InlineSearchBackward,
+    /// Move to right before the next occurrence of the following character in the line.
+    InlineSearchForwardShort,
+    /// Move to right after the previous occurrence of the following character in the line.
+    InlineSearchBackwardShort,
+    /// Repeat the last inline search.
+    InlineSearchNext,
+    /// Repeat the last inline search in the opposite direction.
+    InlineSearchPrevious,
 }
 
 impl From<ViAction> for Action {
@@ -282,6 +408,7 @@ macro_rules! bindings {
                 mode: _mode,
                 notmode: _notmode,
                 action: $action.into(),
+                chord: Vec::new(),
             });
         )*
 
@@ -292,7 +419,7 @@ macro_rules! bindings {
 pub fn default_mouse_bindings() -> Vec<MouseBinding> {
     bindings!(
         MouseBinding;
-        MouseButton::Middle, ~TermMode::VI; Action::PasteSelection;
+        MouseEvent::Button(MouseButton::Middle), ~TermMode::VI; Action::PasteSelection;
     )
 }
 
@@ -375,6 +502,7 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         V,      ModifiersState::SHIFT, +TermMode::VI; ViAction::ToggleLineSelection;
         V,      ModifiersState::CTRL,  +TermMode::VI; ViAction::ToggleBlockSelection;
         V,      ModifiersState::ALT,   +TermMode::VI; ViAction::ToggleSemanticSelection;
+        O,                             +TermMode::VI; ViAction::SwapSelectionEnds;
         Return,                        +TermMode::VI; ViAction::Open;
         K,                             +TermMode::VI; ViMotion::Up;
         J,                             +TermMode::VI; ViMotion::Down;
@@ -397,6 +525,12 @@ pub fn default_key_bindings() -> Vec<KeyBinding> {
         W,      ModifiersState::SHIFT, +TermMode::VI; ViMotion::WordRight;
         E,      ModifiersState::SHIFT, +TermMode::VI; ViMotion::WordRightEnd;
         Key5,   ModifiersState::SHIFT, +TermMode::VI; ViMotion::Bracket;
+        F,                             +TermMode::VI; ViAction::InlineSearchForward;
+        F,      ModifiersState::SHIFT, +TermMode::VI; ViAction::InlineSearchBackward;
+        T,                             +TermMode::VI; ViAction::InlineSearchForwardShort;
+        T,      ModifiersState::SHIFT, +TermMode::VI; ViAction::InlineSearchBackwardShort;
+        Semicolon,                     +TermMode::VI; ViAction::InlineSearchNext;
+        Comma,                         +TermMode::VI; ViAction::InlineSearchPrevious;
     );
 
     //   Code     Modifiers
@@ -479,6 +613,8 @@ fn common_keybindings() -> Vec<KeyBinding> {
         C,        ModifiersState::CTRL | ModifiersState::SHIFT; Action::Copy;
         C,        ModifiersState::CTRL | ModifiersState::SHIFT, +TermMode::VI; Action::ClearSelection;
         Insert,   ModifiersState::SHIFT, ~TermMode::VI; Action::PasteSelection;
+        V,        ModifiersState::CTRL | ModifiersState::SHIFT | ModifiersState::ALT, ~TermMode::VI;
+            Action::PasteBlock;
         Key0,     ModifiersState::CTRL;  Action::ResetFontSize;
         Equals,   ModifiersState::CTRL;  Action::IncreaseFontSize;
         Add,      ModifiersState::CTRL;  Action::IncreaseFontSize;
@@ -513,6 +649,7 @@ pub fn platform_key_bindings() -> Vec<KeyBinding> {
         Insert, ModifiersState::SHIFT, ~TermMode::VI; Action::Esc("\x1b[2;2~".into());
         K, ModifiersState::LOGO, ~TermMode::VI; Action::Esc("\x0c".into());
         V, ModifiersState::LOGO, ~TermMode::VI; Action::Paste;
+        V, ModifiersState::LOGO | ModifiersState::ALT, ~TermMode::VI; Action::PasteBlock;
         N, ModifiersState::LOGO; Action::SpawnNewInstance;
         F, ModifiersState::CTRL | ModifiersState::LOGO; Action::ToggleFullscreen;
         K, ModifiersState::LOGO; Action::ClearHistory;
@@ -553,11 +690,18 @@ impl<'a> Deserialize<'a> for Key {
     }
 }
 
-struct ModeWrapper {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ModeWrapper {
     pub mode: TermMode,
     pub not_mode: TermMode,
 }
 
+impl Default for ModeWrapper {
+    fn default() -> Self {
+        ModeWrapper { mode: TermMode::empty(), not_mode: TermMode::empty() }
+    }
+}
+
 impl<'a> Deserialize<'a> for ModeWrapper {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -570,7 +714,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
                 f.write_str(
-                    "a combination of AppCursor | AppKeypad | Alt | Vi, possibly with negation (~)",
+                    "a combination of AppCursor | AppKeypad | Alt | Vi | Mouse, possibly with \
+                     negation (~)",
                 )
             }
 
@@ -590,6 +735,8 @@ impl<'a> Deserialize<'a> for ModeWrapper {
                         "~alt" => res.not_mode |= TermMode::ALT_SCREEN,
                         "vi" => res.mode |= TermMode::VI,
                         "~vi" => res.not_mode |= TermMode::VI,
+                        "mouse" => res.mode |= TermMode::MOUSE_MODE,
+                        "~mouse" => res.not_mode |= TermMode::MOUSE_MODE,
                         _ => return Err(E::invalid_value(Unexpected::Str(modifier), &self)),
                     }
                 }
@@ -601,52 +748,56 @@ impl<'a> Deserialize<'a> for ModeWrapper {
     }
 }
 
-struct MouseButtonWrapper(MouseButton);
+struct MouseEventWrapper(MouseEvent);
 
-impl MouseButtonWrapper {
-    fn into_inner(self) -> MouseButton {
+impl MouseEventWrapper {
+    fn into_inner(self) -> MouseEvent {
         self.0
     }
 }
 
-impl<'a> Deserialize<'a> for MouseButtonWrapper {
+impl<'a> Deserialize<'a> for MouseEventWrapper {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'a>,
     {
-        struct MouseButtonVisitor;
+        struct MouseEventVisitor;
 
-        impl<'a> Visitor<'a> for MouseButtonVisitor {
-            type Value = MouseButtonWrapper;
+        impl<'a> Visitor<'a> for MouseEventVisitor {
+            type Value = MouseEventWrapper;
 
             fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("Left, Right, Middle, or a number from 0 to 255")
+                f.write_str("Left, Right, Middle, WheelUp, WheelDown, or a number from 0 to 255")
             }
 
-            fn visit_u64<E>(self, value: u64) -> Result<MouseButtonWrapper, E>
+            fn visit_u64<E>(self, value: u64) -> Result<MouseEventWrapper, E>
             where
                 E: de::Error,
             {
                 match value {
-                    0..=255 => Ok(MouseButtonWrapper(MouseButton::Other(value as u8))),
+                    0..=255 => {
+                        Ok(MouseEventWrapper(MouseEvent::Button(MouseButton::Other(value as u8))))
+                    },
                     _ => Err(E::invalid_value(Unexpected::Unsigned(value), &self)),
                 }
             }
 
-            fn visit_str<E>(self, value: &str) -> Result<MouseButtonWrapper, E>
+            fn visit_str<E>(self, value: &str) -> Result<MouseEventWrapper, E>
             where
                 E: de::Error,
             {
                 match value {
-                    "Left" => Ok(MouseButtonWrapper(MouseButton::Left)),
-                    "Right" => Ok(MouseButtonWrapper(MouseButton::Right)),
-                    "Middle" => Ok(MouseButtonWrapper(MouseButton::Middle)),
+                    "Left" => Ok(MouseEventWrapper(MouseEvent::Button(MouseButton::Left))),
+                    "Right" => Ok(MouseEventWrapper(MouseEvent::Button(MouseButton::Right))),
+                    "Middle" => Ok(MouseEventWrapper(MouseEvent::Button(MouseButton::Middle))),
+                    "WheelUp" => Ok(MouseEventWrapper(MouseEvent::WheelUp)),
+                    "WheelDown" => Ok(MouseEventWrapper(MouseEvent::WheelDown)),
                     _ => Err(E::invalid_value(Unexpected::Str(value), &self)),
                 }
             }
         }
 
-        deserializer.deserialize_any(MouseButtonVisitor)
+        deserializer.deserialize_any(MouseEventVisitor)
     }
 }
 
@@ -655,7 +806,8 @@ impl<'a> Deserialize<'a> for MouseButtonWrapper {
 #[derive(PartialEq, Eq)]
 struct RawBinding {
     key: Option<Key>,
-    mouse: Option<MouseButton>,
+    mouse: Option<MouseEvent>,
+    chord: Vec<ChordKey>,
     mods: ModifiersState,
     mode: TermMode,
     notmode: TermMode,
@@ -671,6 +823,7 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                chord: self.chord,
             })
         } else {
             Err(self)
@@ -685,6 +838,7 @@ impl RawBinding {
                 action: self.action,
                 mode: self.mode,
                 notmode: self.notmode,
+                chord: self.chord,
             })
         } else {
             Err(self)
@@ -697,7 +851,8 @@ impl<'a> Deserialize<'a> for RawBinding {
     where
         D: Deserializer<'a>,
     {
-        const FIELDS: &[&str] = &["key", "mods", "mode", "action", "chars", "mouse", "command"];
+        const FIELDS: &[&str] =
+            &["key", "mods", "mode", "action", "chars", "mouse", "command", "chord"];
 
         enum Field {
             Key,
@@ -707,6 +862,7 @@ impl<'a> Deserialize<'a> for RawBinding {
             Chars,
             Mouse,
             Command,
+            Chord,
         }
 
         impl<'a> Deserialize<'a> for Field {
@@ -735,6 +891,7 @@ impl<'a> Deserialize<'a> for RawBinding {
                             "chars" => Ok(Field::Chars),
                             "mouse" => Ok(Field::Mouse),
                             "command" => Ok(Field::Command),
+                            "chord" => Ok(Field::Chord),
                             _ => Err(E::unknown_field(value, FIELDS)),
                         }
                     }
@@ -762,8 +919,9 @@ impl<'a> Deserialize<'a> for RawBinding {
                 let mut action: Option<Action> = None;
                 let mut mode: Option<TermMode> = None;
                 let mut not_mode: Option<TermMode> = None;
-                let mut mouse: Option<MouseButton> = None;
+                let mut mouse: Option<MouseEvent> = None;
                 let mut command: Option<CommandWrapper> = None;
+                let mut chord: Option<Vec<ChordKey>> = None;
 
                 use de::Error;
 
@@ -853,7 +1011,7 @@ impl<'a> Deserialize<'a> for RawBinding {
                                 return Err(<V::Error as Error>::duplicate_field("mouse"));
                             }
 
-                            mouse = Some(map.next_value::<MouseButtonWrapper>()?.into_inner());
+                            mouse = Some(map.next_value::<MouseEventWrapper>()?.into_inner());
                         },
                         Field::Command => {
                             if command.is_some() {
@@ -862,6 +1020,13 @@ impl<'a> Deserialize<'a> for RawBinding {
 
                             command = Some(map.next_value::<CommandWrapper>()?);
                         },
+                        Field::Chord => {
+                            if chord.is_some() {
+                                return Err(<V::Error as Error>::duplicate_field("chord"));
+                            }
+
+                            chord = Some(map.next_value()?);
+                        },
                     }
                 }
 
@@ -899,7 +1064,12 @@ impl<'a> Deserialize<'a> for RawBinding {
                     return Err(V::Error::custom("bindings require mouse button or key"));
                 }
 
-                Ok(RawBinding { mode, notmode: not_mode, action, key, mouse, mods })
+                let chord = chord.unwrap_or_default();
+                if mouse.is_some() && !chord.is_empty() {
+                    return Err(V::Error::custom("chords are only supported for key bindings"));
+                }
+
+                Ok(RawBinding { mode, notmode: not_mode, action, key, mouse, chord, mods })
             }
         }
 
@@ -961,7 +1131,7 @@ impl CommandWrapper {
 /// Our deserialize impl wouldn't be covered by a derive(Deserialize); see the
 /// impl below.
 #[derive(Debug, Copy, Clone, Hash, Default, Eq, PartialEq)]
-pub struct ModsWrapper(ModifiersState);
+pub struct ModsWrapper(pub ModifiersState);
 
 impl ModsWrapper {
     pub fn into_inner(self) -> ModifiersState {
@@ -1009,11 +1179,12 @@ impl<'a> de::Deserialize<'a> for ModsWrapper {
 
 #[cfg(test)]
 mod tests {
-    use glutin::event::ModifiersState;
+    use glutin::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
 
     use alacritty_terminal::term::TermMode;
 
-    use crate::config::{Action, Binding};
+    use crate::config::bindings::MouseBinding;
+    use crate::config::{Action, Binding, KeyBinding, MouseEvent};
 
     type MockBinding = Binding<usize>;
 
@@ -1025,6 +1196,7 @@ mod tests {
                 mode: TermMode::empty(),
                 notmode: TermMode::empty(),
                 trigger: Default::default(),
+                chord: Vec::new(),
             }
         }
     }
@@ -1192,4 +1364,46 @@ mod tests {
         assert!(!binding.is_triggered_by(TermMode::ALT_SCREEN, mods, &t));
         assert!(!binding.is_triggered_by(TermMode::ALT_SCREEN | TermMode::INSERT, mods, &t));
     }
+
+    #[test]
+    #[allow(deprecated)]
+    fn binding_chord() {
+        let yaml = "{ chord: [{ key: A, mods: Control }], key: C, action: ClearHistory }";
+        let binding: KeyBinding = serde_yaml::from_str(yaml).unwrap();
+
+        let key = |keycode| KeyboardInput {
+            scancode: 0,
+            state: ElementState::Pressed,
+            virtual_keycode: Some(keycode),
+            modifiers: ModifiersState::default(),
+        };
+        let prefix = [(key(VirtualKeyCode::A), ModifiersState::CTRL)];
+
+        let mode = TermMode::empty();
+        let ctrl = ModifiersState::CTRL;
+        let none = ModifiersState::empty();
+        assert!(binding.is_chord_continued_by(mode, &[], key(VirtualKeyCode::A), ctrl));
+        assert!(!binding.is_chord_continued_by(mode, &[], key(VirtualKeyCode::A), none));
+        assert!(!binding.is_chord_continued_by(mode, &prefix, key(VirtualKeyCode::C), ctrl));
+        assert!(binding.chord_matches(&prefix));
+        assert!(!binding.chord_matches(&[]));
+
+        // Chords distinguish otherwise identical bindings
+        let plain: KeyBinding = serde_yaml::from_str("{ key: C, action: ClearHistory }").unwrap();
+        assert!(!binding.triggers_match(&plain));
+    }
+
+    #[test]
+    fn mouse_binding_modes() {
+        let yaml = r#"{ mouse: WheelUp, mode: Alt|~Mouse, chars: "\x1bOA" }"#;
+        let binding: MouseBinding = serde_yaml::from_str(yaml).unwrap();
+
+        let mods = ModifiersState::empty();
+        let mouse_mode = TermMode::ALT_SCREEN | TermMode::MOUSE_MODE;
+        assert!(binding.is_triggered_by(TermMode::ALT_SCREEN, mods, &MouseEvent::WheelUp));
+        assert!(!binding.is_triggered_by(mouse_mode, mods, &MouseEvent::WheelUp));
+        assert!(!binding.is_triggered_by(TermMode::empty(), mods, &MouseEvent::WheelUp));
+        assert!(!binding.is_triggered_by(TermMode::ALT_SCREEN, mods, &MouseEvent::WheelDown));
+        assert_eq!(binding.action, Action::Esc(String::from("\x1bOA")));
+    }
 }
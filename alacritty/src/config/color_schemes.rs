@@ -0,0 +1,88 @@
+use serde::{Deserialize, Deserializer};
+
+use alacritty_terminal::config::{diagnostics, Colors};
+
+/// Named color schemes which can replace the `colors` at runtime
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct ColorSchemes {
+    /// Colors of the `colors` section, used while no scheme is active
+    base: Colors,
+
+    /// Schemes in the order of the config
+    schemes: Vec<(String, Colors)>,
+}
+
+impl ColorSchemes {
+    /// Set the colors which are used while no scheme is active.
+    pub fn set_base(&mut self, colors: Colors) {
+        self.base = colors;
+    }
+
+    /// Colors of a scheme, or the base colors without a name.
+    pub fn get(&self, name: Option<&str>) -> Option<&Colors> {
+        match name {
+            Some(name) => self.schemes.iter().find(|(n, _)| n == name).map(|(_, colors)| colors),
+            None => Some(&self.base),
+        }
+    }
+
+    /// Name of the scheme after `name`, cycling back to the base colors after the last one.
+    pub fn next(&self, name: Option<&str>) -> Option<&str> {
+        let index = match name {
+            Some(name) => self.schemes.iter().position(|(n, _)| n == name).map_or(0, |i| i + 1),
+            None => 0,
+        };
+
+        self.schemes.get(index).map(|(name, _)| name.as_str())
+    }
+}
+
+impl<'a> Deserialize<'a> for ColorSchemes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let mapping = serde_yaml::Mapping::deserialize(deserializer)?;
+
+        // Skip all invalid schemes
+        let mut schemes = Vec::with_capacity(mapping.len());
+        for (name, colors) in mapping {
+            let name = match String::deserialize(name) {
+                Ok(name) => name,
+                Err(err) => {
+                    diagnostics::invalid_value(format_args!("{}; ignoring scheme", err));
+                    continue;
+                },
+            };
+
+            match Colors::deserialize(colors) {
+                Ok(colors) => schemes.push((name, colors)),
+                Err(err) => {
+                    diagnostics::invalid_value(format_args!("{}; ignoring scheme {:?}", err, name))
+                },
+            }
+        }
+
+        Ok(ColorSchemes { base: Colors::default(), schemes })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycle_schemes() {
+        let yaml = "light: {primary: {background: '#ffffff'}}\ndark: {}\n";
+        let schemes: ColorSchemes = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(schemes.next(None), Some("light"));
+        assert_eq!(schemes.next(Some("light")), Some("dark"));
+        assert_eq!(schemes.next(Some("dark")), None);
+
+        assert_eq!(schemes.get(None), Some(&Colors::default()));
+        assert_eq!(schemes.get(Some("dark")), Some(&Colors::default()));
+        assert_ne!(schemes.get(Some("light")), Some(&Colors::default()));
+        assert_eq!(schemes.get(Some("missing")), None);
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use glutin::event::{ModifiersState, VirtualKeyCode};
+use regex::Regex;
+use serde::de::Error as SerdeError;
+use serde::{Deserialize, Deserializer};
+
+use alacritty_terminal::config::{diagnostics, failure_default};
+
+use crate::config::bindings::{Action, CommandWrapper, Key, KeyBinding, ModeWrapper, ModsWrapper};
+use crate::config::mouse::Url;
+
+/// Characters used for hint labels by default.
+const DEFAULT_ALPHABET: &str = "jfkdls;ahgurieowpq";
+
+/// Regex matching URLs for the default hint.
+const URL_REGEX: &str = "(ipfs:|ipns:|magnet:|mailto:|gemini:|gopher:|https:|http:|news:|file:\
+                         |git:|ssh:|ftp:)[^\u{0000}-\u{001F}\u{007F}-\u{009F}<>\"\\s{-}\\^⟨⟩`]+";
+
+/// Regex matching `path:line:column` locations of compiler output for the default file hint.
+const FILE_REGEX: &str = r"(?:~/|\.{0,2}/)?(?:[\w.+-]+/)*[\w+-][\w.+-]*\.\w+:[0-9]+(?::[0-9]+)?";
+
+/// Keyboard-driven selection of visible text
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Hints {
+    /// Characters for the hint labels
+    #[serde(deserialize_with = "deserialize_alphabet")]
+    alphabet: String,
+
+    /// All hints which can be started through their binding
+    #[serde(deserialize_with = "deserialize_hints")]
+    pub enabled: Vec<Hint>,
+
+    /// Program for opening the files of `Edit` hints
+    #[serde(deserialize_with = "failure_default")]
+    pub editor: Option<CommandWrapper>,
+}
+
+impl Default for Hints {
+    fn default() -> Self {
+        let url = Hint {
+            regex: HintRegex::new(URL_REGEX).unwrap(),
+            action: HintAction::Copy,
+            command: Url::default().launcher,
+            anchored: false,
+            binding: Some(HintBinding {
+                key: Key::Keycode(VirtualKeyCode::U),
+                mods: ModsWrapper(ModifiersState::CTRL | ModifiersState::SHIFT),
+                mode: Default::default(),
+            }),
+        };
+
+        let file = Hint {
+            regex: HintRegex::new(FILE_REGEX).unwrap(),
+            action: HintAction::Edit,
+            command: None,
+            anchored: false,
+            binding: Some(HintBinding {
+                key: Key::Keycode(VirtualKeyCode::E),
+                mods: ModsWrapper(ModifiersState::CTRL | ModifiersState::SHIFT),
+                mode: Default::default(),
+            }),
+        };
+
+        Hints { alphabet: DEFAULT_ALPHABET.into(), enabled: vec![url, file], editor: None }
+    }
+}
+
+impl Hints {
+    #[inline]
+    pub fn alphabet(&self) -> &str {
+        &self.alphabet
+    }
+
+    /// Key bindings which start the hints.
+    pub fn key_bindings(&self) -> Vec<KeyBinding> {
+        self.enabled
+            .iter()
+            .filter_map(|hint| {
+                let binding = hint.binding.as_ref()?;
+                Some(KeyBinding {
+                    trigger: binding.key,
+                    mods: binding.mods.into_inner(),
+                    mode: binding.mode.mode,
+                    notmode: binding.mode.not_mode,
+                    action: Action::Hint(hint.clone()),
+                    chord: Vec::new(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Regex for finding text, with its matches handled by an action
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    /// Text which can be selected
+    pub regex: HintRegex,
+
+    /// Action performed on the selected text
+    #[serde(default, deserialize_with = "failure_default")]
+    pub action: HintAction,
+
+    /// Program which receives the selected text as its last argument, instead of the action
+    #[serde(default)]
+    pub command: Option<CommandWrapper>,
+
+    /// Only match text surrounded by semantic escape characters or line boundaries
+    #[serde(default, deserialize_with = "failure_default")]
+    pub anchored: bool,
+
+    /// Key which starts the hint
+    #[serde(default)]
+    binding: Option<HintBinding>,
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HintAction {
+    /// Copy the text to the clipboard
+    Copy,
+    /// Paste the text into the terminal
+    Paste,
+    /// Select the text
+    Select,
+    /// Move the vi mode cursor to the start of the text
+    MoveViModeCursor,
+    /// Open the file of a `path:line:column` location in the editor
+    Edit,
+}
+
+impl Default for HintAction {
+    fn default() -> Self {
+        HintAction::Copy
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+struct HintBinding {
+    key: Key,
+    #[serde(default)]
+    mods: ModsWrapper,
+    #[serde(default)]
+    mode: ModeWrapper,
+}
+
+/// Compiled regex of a hint
+#[derive(Clone)]
+pub struct HintRegex(Regex);
+
+impl HintRegex {
+    fn new(regex: &str) -> Result<Self, regex::Error> {
+        Regex::new(regex).map(HintRegex)
+    }
+
+    #[inline]
+    pub fn regex(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl PartialEq for HintRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for HintRegex {}
+
+impl fmt::Debug for HintRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HintRegex").field(&self.0.as_str()).finish()
+    }
+}
+
+impl<'a> Deserialize<'a> for HintRegex {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let regex = String::deserialize(deserializer)?;
+        HintRegex::new(&regex).map_err(D::Error::custom)
+    }
+}
+
+fn deserialize_alphabet<'a, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let value = serde_yaml::Value::deserialize(deserializer)?;
+    match String::deserialize(value) {
+        // Labels need at least two characters to distinguish more than one match
+        Ok(alphabet) if alphabet.chars().count() >= 2 => Ok(alphabet),
+        Ok(alphabet) => {
+            diagnostics::invalid_value(format_args!(
+                "hint alphabet {:?} is too short; using default value",
+                alphabet
+            ));
+            Ok(DEFAULT_ALPHABET.into())
+        },
+        Err(err) => {
+            diagnostics::invalid_value(format_args!("{}; using default value", err));
+            Ok(DEFAULT_ALPHABET.into())
+        },
+    }
+}
+
+fn deserialize_hints<'a, D>(deserializer: D) -> Result<Vec<Hint>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let values = Vec::<serde_yaml::Value>::deserialize(deserializer)?;
+
+    // Skip all invalid hints
+    let mut hints = Vec::with_capacity(values.len());
+    for value in values {
+        match Hint::deserialize(value) {
+            Ok(hint) => hints.push(hint),
+            Err(err) => {
+                diagnostics::invalid_value(format_args!("{}; ignoring hint", err));
+            },
+        }
+    }
+
+    Ok(hints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_url_regex() {
+        let hints = Hints::default();
+        let regex = hints.enabled[0].regex.regex();
+
+        let text = "see <https://example.org/a?b=c> and git://host/repo.";
+        let urls: Vec<&str> = regex.find_iter(text).map(|m| m.as_str()).collect();
+        assert_eq!(urls, vec!["https://example.org/a?b=c", "git://host/repo."]);
+    }
+
+    #[test]
+    fn default_file_regex() {
+        let hints = Hints::default();
+        let regex = hints.enabled[1].regex.regex();
+
+        let text = "--> src/main.rs:10:5 and ../lib.c:3: error, not version 1.2 or host:80";
+        let files: Vec<&str> = regex.find_iter(text).map(|m| m.as_str()).collect();
+        assert_eq!(files, vec!["src/main.rs:10:5", "../lib.c:3"]);
+    }
+
+    #[test]
+    fn invalid_hints_are_skipped() {
+        let yaml = "enabled:\n  - regex: '[a-'\n  - regex: '[a-z]+'\n    action: Select\n";
+        let hints: Hints = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(hints.enabled.len(), 1);
+        assert_eq!(hints.enabled[0].action, HintAction::Select);
+        assert_eq!(hints.alphabet(), DEFAULT_ALPHABET);
+    }
+}
@@ -2,26 +2,44 @@ use std::env;
 use std::fmt::{self, Display, Formatter};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[cfg(windows)]
 use dirs;
 use log::{error, warn};
+use serde::Deserialize;
+use serde_yaml::Value;
 
+use alacritty_terminal::config::diagnostics::{self, TrackedValue};
 use alacritty_terminal::config::{Config as TermConfig, LOG_TARGET_CONFIG};
+use alacritty_terminal::util;
 
+mod appearance;
+mod bell;
 mod bindings;
+mod color_schemes;
+mod hints;
 pub mod monitor;
 mod mouse;
+mod notifications;
+mod paste;
 mod ui_config;
+mod validation;
 
-pub use crate::config::bindings::{Action, Binding, Key, ViAction};
+pub use crate::config::bell::SOUND_PLAYER;
+pub use crate::config::bindings::{Action, Binding, Key, KeyBinding, MouseEvent, ViAction};
+pub use crate::config::hints::{Hint, HintAction};
 #[cfg(test)]
 pub use crate::config::mouse::{ClickHandler, Mouse};
+pub use crate::config::notifications::NotificationMode;
 use crate::config::ui_config::UIConfig;
+use crate::config::validation::ConfigFile;
 
 pub type Config = TermConfig<UIConfig>;
 
+/// Maximum depth of nested config imports.
+const IMPORT_RECURSION_LIMIT: usize = 5;
+
 /// Result from config loading
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -144,6 +162,61 @@ pub fn reload_from(path: &PathBuf) -> Result<Config> {
 }
 
 fn read_config(path: &PathBuf) -> Result<Config> {
+    let mut files = Vec::new();
+    let value = read_config_value(path, &mut files, IMPORT_RECURSION_LIMIT)?;
+
+    let mut config = config_from_value(value, &files)?;
+    config.ui_config.config_paths = files.into_iter().map(|file| file.path).collect();
+
+    Ok(config)
+}
+
+#[cfg(test)]
+fn parse_config(contents: &str) -> Result<Config> {
+    config_from_value(apply_overrides(parse_value(contents)?), &[])
+}
+
+/// Deserialize the config, reporting all problems with it at their location in the `files`.
+fn config_from_value(value: Value, files: &[ConfigFile]) -> Result<Config> {
+    // Empty and commented out files use the default config
+    if value.is_null() {
+        return Ok(Config::default());
+    }
+
+    let (config, diagnostics) =
+        diagnostics::collect(|| Config::deserialize(TrackedValue(value.clone())));
+    validation::report(&value, diagnostics, files);
+
+    let mut config = config?;
+    print_deprecation_warnings(&config);
+    expand_env_vars(&mut config);
+    config.ui_config.add_hint_bindings();
+
+    // Start with the configured color scheme
+    config.ui_config.color_schemes.set_base(config.colors.clone());
+    let color_scheme = config.ui_config.color_scheme.as_deref();
+    match config.ui_config.color_schemes.get(color_scheme) {
+        Some(colors) => config.colors = colors.clone(),
+        None => {
+            error!(
+                target: LOG_TARGET_CONFIG,
+                "Problem with config: unknown color scheme {:?}; using colors", color_scheme
+            );
+            config.ui_config.color_scheme = None;
+        },
+    }
+
+    Ok(config)
+}
+
+/// Read a config file, with its imports and the overrides for this machine merged into it.
+///
+/// All files which were read are added to `files`.
+fn read_config_value(
+    path: &Path,
+    files: &mut Vec<ConfigFile>,
+    recursion_limit: usize,
+) -> Result<Value> {
     let mut contents = fs::read_to_string(path)?;
 
     // Remove UTF-8 BOM
@@ -151,23 +224,174 @@ fn read_config(path: &PathBuf) -> Result<Config> {
         contents = contents.split_off(3);
     }
 
-    parse_config(&contents)
+    let mut value = parse_value(&contents)?;
+    files.push(ConfigFile::new(path, &contents));
+
+    let imports = match &mut value {
+        Value::Mapping(mapping) => mapping.remove(&Value::from("import")),
+        _ => None,
+    };
+
+    // Merge imports in order, so later ones override earlier ones
+    let mut merged = Value::Null;
+    for import in imports.map(import_paths).unwrap_or_default() {
+        if recursion_limit == 0 {
+            error!(target: LOG_TARGET_CONFIG, "Exceeded maximum config import depth");
+            break;
+        }
+
+        let import = match path.parent() {
+            Some(parent) => parent.join(import),
+            None => import,
+        };
+        match read_config_value(&import, files, recursion_limit - 1) {
+            Ok(value) => merged = merge(merged, value),
+            Err(err) => {
+                error!(target: LOG_TARGET_CONFIG, "Unable to import config {:?}: {}", import, err)
+            },
+        }
+    }
+
+    Ok(merge(merged, apply_overrides(value)))
 }
 
-fn parse_config(contents: &str) -> Result<Config> {
+fn parse_value(contents: &str) -> Result<Value> {
     match serde_yaml::from_str(contents) {
-        Err(error) => {
-            // Prevent parsing error with an empty string and commented out file.
-            if error.to_string() == "EOF while parsing a value" {
-                Ok(Config::default())
-            } else {
-                Err(Error::Yaml(error))
-            }
+        // Prevent parsing error with an empty string and commented out file
+        Err(error) if error.to_string() == "EOF while parsing a value" => Ok(Value::Null),
+        result => Ok(result?),
+    }
+}
+
+/// Paths of the `import` field, with `~` expanded to the home directory.
+///
+/// Environment variables are expanded before the home directory.
+fn import_paths(imports: Value) -> Vec<PathBuf> {
+    let imports = match imports {
+        Value::Sequence(imports) => imports,
+        _ => {
+            error!(target: LOG_TARGET_CONFIG, "Problem with config: import is not a list");
+            return Vec::new();
         },
-        Ok(config) => {
-            print_deprecation_warnings(&config);
-            Ok(config)
+    };
+
+    let home = env::var("HOME").ok();
+    imports
+        .into_iter()
+        .filter_map(|import| match import {
+            Value::String(import) => match (&home, expand_env(&import)) {
+                (Some(home), import) if import.starts_with("~/") => {
+                    Some(Path::new(home).join(&import[2..]))
+                },
+                (_, import) => Some(PathBuf::from(import)),
+            },
+            import => {
+                error!(target: LOG_TARGET_CONFIG, "Problem with config: invalid import {:?}", import);
+                None
+            },
+        })
+        .collect()
+}
+
+/// Expand environment variables in the shell and its working directory.
+fn expand_env_vars(config: &mut Config) {
+    if let Some(shell) = &mut config.shell {
+        shell.program = expand_env(&shell.program).into();
+        for arg in &mut shell.args {
+            *arg = expand_env(arg);
+        }
+    }
+
+    let working_directory = config.working_directory.as_ref().and_then(|dir| dir.to_str());
+    if let Some(dir) = working_directory.map(expand_env) {
+        config.working_directory = Some(PathBuf::from(dir));
+    }
+}
+
+/// Replace every `${NAME}` with the value of the environment variable `NAME`.
+///
+/// References to unset variables are kept unchanged.
+fn expand_env(input: &str) -> String {
+    let mut expanded = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        expanded.push_str(&rest[..start]);
+
+        let name = &rest[start + 2..end];
+        match env::var(name) {
+            Ok(value) => expanded.push_str(&value),
+            Err(_) => {
+                warn!(
+                    target: LOG_TARGET_CONFIG,
+                    "Problem with config: environment variable {:?} is not set", name
+                );
+                expanded.push_str(&rest[start..=end]);
+            },
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Merge the `os` section for this operating system and the `hosts` section for this hostname
+/// into the config, with the host taking precedence.
+fn apply_overrides(mut value: Value) -> Value {
+    let (os, hosts) = match &mut value {
+        Value::Mapping(mapping) => {
+            (mapping.remove(&Value::from("os")), mapping.remove(&Value::from("hosts")))
         },
+        _ => return value,
+    };
+
+    if let Some(Value::Mapping(os)) = os {
+        if let Some(section) = os.get(&Value::from(env::consts::OS)) {
+            value = merge(value, section.clone());
+        }
+    }
+
+    if let (Some(Value::Mapping(hosts)), Some(hostname)) = (hosts, util::hostname()) {
+        let section = hosts.into_iter().find(|(host, _)| {
+            host.as_str().map_or(false, |host| host.eq_ignore_ascii_case(&hostname))
+        });
+
+        if let Some((_, section)) = section {
+            value = merge(value, section);
+        }
+    }
+
+    value
+}
+
+/// Merge two config values.
+///
+/// Mappings are merged recursively, lists are concatenated and all other values are replaced.
+fn merge(base: Value, replacement: Value) -> Value {
+    match (base, replacement) {
+        (base, Value::Null) => base,
+        (Value::Sequence(mut base), Value::Sequence(replacement)) => {
+            base.extend(replacement);
+            Value::Sequence(base)
+        },
+        (Value::Mapping(mut base), Value::Mapping(replacement)) => {
+            for (key, value) in replacement {
+                let value = match base.remove(&key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => value,
+                };
+                base.insert(key, value);
+            }
+            Value::Mapping(base)
+        },
+        (_, replacement) => replacement,
     }
 }
 
@@ -196,9 +420,8 @@ fn print_deprecation_warnings(config: &Config) {
     if config.scrolling.faux_multiplier().is_some() {
         warn!(
             target: LOG_TARGET_CONFIG,
-            "Config scrolling.faux_multiplier is deprecated; the alternate scroll escape can now \
-             be used to disable it and `scrolling.multiplier` controls the number of scrolled \
-             lines"
+            "Config scrolling.faux_multiplier is deprecated; please use \
+             scrolling.alternate_multiplier instead"
         );
     }
 
@@ -224,10 +447,110 @@ mod tests {
     static DEFAULT_ALACRITTY_CONFIG: &str =
         include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../alacritty.yml"));
 
-    use super::Config;
+    use std::env;
+    use std::fs;
+
+    use serde_yaml::Value;
+
+    use super::*;
 
     #[test]
     fn config_read_eof() {
         assert_eq!(super::parse_config(DEFAULT_ALACRITTY_CONFIG).unwrap(), Config::default());
     }
+
+    #[test]
+    fn subpixel_follows_opacity() {
+        let config = Config::default();
+        assert_eq!(config.subpixel(1.), font::Subpixel::Auto);
+        assert_eq!(config.subpixel(0.9), font::Subpixel::None);
+
+        let config = parse_config("font: {subpixel: Rgb}").unwrap();
+        assert_eq!(config.subpixel(0.9), font::Subpixel::Rgb);
+    }
+
+    #[test]
+    fn merge_values() {
+        let base: Value = serde_yaml::from_str("{a: 1, b: {c: 2, d: 3}, e: [1]}").unwrap();
+        let replacement: Value = serde_yaml::from_str("{b: {d: 4}, e: [2], f: 5}").unwrap();
+        let merged: Value =
+            serde_yaml::from_str("{a: 1, b: {c: 2, d: 4}, e: [1, 2], f: 5}").unwrap();
+
+        assert_eq!(merge(base, replacement), merged);
+    }
+
+    #[test]
+    fn os_overrides() {
+        let yaml =
+            format!("{{a: 1, b: 2, os: {{{}: {{b: 3}}, other: {{a: 4}}}}}}", env::consts::OS);
+        let value = apply_overrides(serde_yaml::from_str(&yaml).unwrap());
+
+        assert_eq!(value, serde_yaml::from_str::<Value>("{a: 1, b: 3}").unwrap());
+    }
+
+    #[test]
+    fn env_expansion() {
+        env::set_var("ALACRITTY_TEST_EXPANSION", "value");
+        env::remove_var("ALACRITTY_TEST_UNSET");
+
+        assert_eq!(expand_env("a/${ALACRITTY_TEST_EXPANSION}/b"), "a/value/b");
+        assert_eq!(
+            expand_env("${ALACRITTY_TEST_EXPANSION}${ALACRITTY_TEST_EXPANSION}"),
+            "valuevalue"
+        );
+        assert_eq!(expand_env("${ALACRITTY_TEST_UNSET}/$HOME"), "${ALACRITTY_TEST_UNSET}/$HOME");
+        assert_eq!(expand_env("${ALACRITTY_TEST_EXPANSION"), "${ALACRITTY_TEST_EXPANSION");
+    }
+
+    #[test]
+    fn ui_config_diagnostics() {
+        // Invalid values of the flattened UI config are reported at their own key
+        let value: Value = serde_yaml::from_str("{bell: 5, paste: 5, mouse: {}}").unwrap();
+        let (config, problems) =
+            diagnostics::collect(|| Config::deserialize(TrackedValue(value.clone())));
+
+        assert!(config.is_ok());
+        let paths: Vec<_> =
+            problems.iter().map(|problem| diagnostics::display_path(&problem.path)).collect();
+        assert_eq!(paths, ["bell", "paste"]);
+    }
+
+    #[test]
+    fn scrolling_multipliers() {
+        let config = parse_config("scrolling: {multiplier: 5}").unwrap();
+        assert_eq!(config.scrolling.alternate_multiplier(), 5);
+        assert_eq!(config.scrolling.touchpad_multiplier(), None);
+
+        let config = parse_config("scrolling: {multiplier: 5, faux_multiplier: 2}").unwrap();
+        assert_eq!(config.scrolling.alternate_multiplier(), 2);
+
+        let yaml =
+            "scrolling: {alternate_multiplier: 1, faux_multiplier: 2, touchpad_multiplier: 0.5}";
+        let config = parse_config(yaml).unwrap();
+        assert_eq!(config.scrolling.multiplier(), 3);
+        assert_eq!(config.scrolling.alternate_multiplier(), 1);
+        assert_eq!(config.scrolling.touchpad_multiplier(), Some(0.5));
+
+        let config = parse_config("scrolling: {touchpad_multiplier: -1}").unwrap();
+        assert_eq!(config.scrolling.touchpad_multiplier(), None);
+    }
+
+    #[test]
+    fn imports() {
+        let dir = env::temp_dir().join(format!("alacritty-config-imports-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("base.yml"), "{a: 1, b: 1, c: 1}").unwrap();
+        fs::write(dir.join("colors.yml"), "{b: 2, c: 2}").unwrap();
+        fs::write(dir.join("main.yml"), "{import: [base.yml, colors.yml, missing.yml], c: 3}")
+            .unwrap();
+
+        let mut files = Vec::new();
+        let value = read_config_value(&dir.join("main.yml"), &mut files, 1).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let paths: Vec<_> = files.into_iter().map(|file| file.path).collect();
+
+        assert_eq!(value, serde_yaml::from_str::<Value>("{a: 1, b: 2, c: 3}").unwrap());
+        assert_eq!(paths, vec![dir.join("main.yml"), dir.join("base.yml"), dir.join("colors.yml")]);
+    }
 }
@@ -1,8 +1,11 @@
+use std::fs;
 use std::path::PathBuf;
 use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
+use log::warn;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
 
 use alacritty_terminal::event::{Event, EventListener};
 use alacritty_terminal::util;
@@ -10,49 +13,98 @@ use alacritty_terminal::util;
 use crate::event::EventProxy;
 
 pub struct Monitor {
+    watcher: RecommendedWatcher,
+
+    /// Config file and its imports, as they were passed to `watch`.
+    config_paths: Vec<PathBuf>,
+
+    /// Canonical paths of all watched files, starting with the config file.
+    paths: Arc<Mutex<Vec<PathBuf>>>,
+
+    /// Directories of all watched files.
+    parents: Vec<PathBuf>,
+
     _thread: ::std::thread::JoinHandle<()>,
 }
 
 impl Monitor {
-    pub fn new<P>(path: P, event_proxy: EventProxy) -> Monitor
-    where
-        P: Into<PathBuf>,
-    {
-        let path = path.into();
-
-        Monitor {
-            _thread: util::thread::spawn_named("config watcher", move || {
-                let (tx, rx) = mpsc::channel();
-                // The Duration argument is a debouncing period.
-                let mut watcher =
-                    watcher(tx, Duration::from_millis(10)).expect("Unable to spawn file watcher");
-                let config_path = ::std::fs::canonicalize(path).expect("canonicalize config path");
-
-                // Get directory of config
-                let mut parent = config_path.clone();
-                parent.pop();
-
-                // Watch directory
-                watcher
-                    .watch(&parent, RecursiveMode::NonRecursive)
-                    .expect("watch alacritty.yml dir");
-
-                loop {
-                    match rx.recv().expect("watcher event") {
-                        DebouncedEvent::Rename(..) => continue,
-                        DebouncedEvent::Write(path)
-                        | DebouncedEvent::Create(path)
-                        | DebouncedEvent::Chmod(path) => {
-                            if path != config_path {
-                                continue;
-                            }
-
-                            event_proxy.send_event(Event::ConfigReload(path));
-                        },
-                        _ => {},
-                    }
+    /// Watch the config file and its imports, which follow it in `paths`.
+    ///
+    /// A change to any of them reloads the config file.
+    pub fn new(paths: Vec<PathBuf>, event_proxy: EventProxy) -> Monitor {
+        let (tx, rx) = mpsc::channel();
+        // The Duration argument is a debouncing period.
+        let watcher = watcher(tx, Duration::from_millis(10)).expect("Unable to spawn file watcher");
+
+        let watched_paths = Arc::new(Mutex::new(Vec::new()));
+        let thread_paths = watched_paths.clone();
+
+        let mut monitor = Monitor {
+            watcher,
+            config_paths: Vec::new(),
+            paths: watched_paths,
+            parents: Vec::new(),
+            _thread: util::thread::spawn_named("config watcher", move || loop {
+                match rx.recv().expect("watcher event") {
+                    DebouncedEvent::Rename(..) => continue,
+                    DebouncedEvent::Write(path)
+                    | DebouncedEvent::Create(path)
+                    | DebouncedEvent::Chmod(path) => {
+                        let paths = thread_paths.lock().unwrap();
+                        if !paths.contains(&path) {
+                            continue;
+                        }
+
+                        event_proxy.send_event(Event::ConfigReload(paths[0].clone()));
+                    },
+                    _ => {},
                 }
             }),
+        };
+
+        monitor.watch(&paths);
+
+        monitor
+    }
+
+    /// Replace the watched files after the config file has been reloaded.
+    ///
+    /// Imports can be added or removed by every reload, so their directories are watched again.
+    pub fn watch(&mut self, config_paths: &[PathBuf]) {
+        if self.config_paths == config_paths {
+            return;
+        }
+        self.config_paths = config_paths.to_vec();
+
+        // Skip files which have been removed since they were read
+        let paths: Vec<PathBuf> =
+            config_paths.iter().filter_map(|path| fs::canonicalize(path).ok()).collect();
+
+        // Get directories of all config files
+        let mut parents: Vec<PathBuf> =
+            paths.iter().filter_map(|path| path.parent()).map(PathBuf::from).collect();
+        parents.sort();
+        parents.dedup();
+
+        // Stop watching directories which no longer contain any config file
+        for parent in &self.parents {
+            if !parents.contains(parent) {
+                let _ = self.watcher.unwatch(parent);
+            }
         }
+
+        // Watch new directories
+        for parent in &parents {
+            if self.parents.contains(parent) {
+                continue;
+            }
+
+            if let Err(err) = self.watcher.watch(parent, RecursiveMode::NonRecursive) {
+                warn!("Unable to watch config directory {:?}: {}", parent, err);
+            }
+        }
+
+        self.parents = parents;
+        *self.paths.lock().unwrap() = paths;
     }
 }
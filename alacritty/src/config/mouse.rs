@@ -1,10 +1,9 @@
 use std::time::Duration;
 
 use glutin::event::ModifiersState;
-use log::error;
 use serde::{Deserialize, Deserializer};
 
-use alacritty_terminal::config::{failure_default, LOG_TARGET_CONFIG};
+use alacritty_terminal::config::{diagnostics, failure_default};
 
 use crate::config::bindings::{CommandWrapper, ModsWrapper};
 
@@ -58,12 +57,11 @@ where
     match <Option<CommandWrapper>>::deserialize(val) {
         Ok(launcher) => Ok(launcher),
         Err(err) => {
-            error!(
-                target: LOG_TARGET_CONFIG,
-                "Problem with config: {}; using {}",
+            diagnostics::invalid_value(format_args!(
+                "{}; using {}",
                 err,
                 default.clone().unwrap().program()
-            );
+            ));
             Ok(default)
         },
     }
@@ -108,7 +106,7 @@ where
     match u64::deserialize(value) {
         Ok(threshold_ms) => Ok(Duration::from_millis(threshold_ms)),
         Err(err) => {
-            error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; using default value", err);
+            diagnostics::invalid_value(format_args!("{}; using default value", err));
             Ok(default_threshold_ms())
         },
     }
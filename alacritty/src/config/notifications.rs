@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+use alacritty_terminal::config::failure_default;
+
+use crate::config::bindings::CommandWrapper;
+
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Notifications {
+    /// When notifications requested by applications are shown
+    #[serde(deserialize_with = "failure_default")]
+    pub mode: NotificationMode,
+
+    /// Program showing the notification, its title and body are appended to the arguments
+    #[serde(deserialize_with = "failure_default")]
+    pub command: Option<CommandWrapper>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications {
+            mode: Default::default(),
+            #[cfg(not(any(target_os = "macos", windows)))]
+            command: Some(CommandWrapper::WithArgs {
+                program: String::from("notify-send"),
+                args: vec![String::from("--app-name=Alacritty"), String::from("--")],
+            }),
+            #[cfg(target_os = "macos")]
+            command: Some(CommandWrapper::WithArgs {
+                program: String::from("osascript"),
+                args: vec![
+                    String::from("-e"),
+                    String::from("on run argv"),
+                    String::from("-e"),
+                    String::from(
+                        "display notification (item 2 of argv) with title (item 1 of argv)",
+                    ),
+                    String::from("-e"),
+                    String::from("end run"),
+                ],
+            }),
+            #[cfg(windows)]
+            command: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum NotificationMode {
+    /// Always show notifications
+    Enabled,
+    /// Only show notifications while the window is not focused
+    Unfocused,
+    /// Never show notifications
+    Disabled,
+}
+
+impl Default for NotificationMode {
+    fn default() -> Self {
+        NotificationMode::Unfocused
+    }
+}
@@ -0,0 +1,25 @@
+use serde::Deserialize;
+
+use alacritty_terminal::config::failure_default;
+
+#[serde(default)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Paste {
+    /// Remove control characters which could be used to run commands
+    #[serde(deserialize_with = "failure_default")]
+    pub filter_control_characters: bool,
+
+    /// Send line breaks as carriage returns without bracketed paste
+    #[serde(deserialize_with = "failure_default")]
+    pub convert_newlines: bool,
+
+    /// Ask before pasting multiple lines or large text without bracketed paste
+    #[serde(deserialize_with = "failure_default")]
+    pub confirm: bool,
+}
+
+impl Default for Paste {
+    fn default() -> Self {
+        Paste { filter_control_characters: true, convert_newlines: true, confirm: true }
+    }
+}
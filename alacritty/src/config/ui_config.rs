@@ -1,16 +1,52 @@
-use log::error;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use serde::{Deserialize, Deserializer};
 
-use alacritty_terminal::config::{failure_default, LOG_TARGET_CONFIG};
+use alacritty_terminal::config::{diagnostics, failure_default};
 
+use crate::config::appearance::Appearance;
+use crate::config::bell::Bell;
 use crate::config::bindings::{self, Binding, KeyBinding, MouseBinding};
+use crate::config::color_schemes::ColorSchemes;
+use crate::config::hints::Hints;
 use crate::config::mouse::Mouse;
+use crate::config::notifications::Notifications;
+use crate::config::paste::Paste;
 
 #[derive(Debug, PartialEq, Deserialize)]
 pub struct UIConfig {
     #[serde(default, deserialize_with = "failure_default")]
     pub mouse: Mouse,
 
+    /// Paste filtering and confirmation
+    #[serde(default, deserialize_with = "failure_default")]
+    pub paste: Paste,
+
+    /// Desktop notifications requested by applications
+    #[serde(default, deserialize_with = "failure_default")]
+    pub notifications: Notifications,
+
+    /// Actions performed when the bell rings, besides the visual bell
+    #[serde(default, deserialize_with = "failure_default")]
+    pub bell: Bell,
+
+    /// Regex hints for selecting visible text with the keyboard
+    #[serde(default, deserialize_with = "failure_default")]
+    pub hints: Hints,
+
+    /// Named alternatives to the `colors`
+    #[serde(default, deserialize_with = "failure_default")]
+    pub color_schemes: ColorSchemes,
+
+    /// Name of the active color scheme
+    #[serde(default, deserialize_with = "failure_default")]
+    pub color_scheme: Option<String>,
+
+    /// Switching between a dark and a light color scheme
+    #[serde(default, deserialize_with = "failure_default")]
+    pub appearance: Appearance,
+
     /// Keybindings
     #[serde(default = "default_key_bindings", deserialize_with = "deserialize_key_bindings")]
     pub key_bindings: Vec<KeyBinding>,
@@ -18,14 +54,48 @@ pub struct UIConfig {
     /// Bindings for the mouse
     #[serde(default = "default_mouse_bindings", deserialize_with = "deserialize_mouse_bindings")]
     pub mouse_bindings: Vec<MouseBinding>,
+
+    /// Time for pressing the next key of a chord binding
+    #[serde(default = "default_chord_timeout", deserialize_with = "deserialize_chord_timeout")]
+    pub chord_timeout: Duration,
+
+    /// Time for completing a Compose sequence
+    #[serde(default = "default_compose_timeout", deserialize_with = "deserialize_compose_timeout")]
+    pub compose_timeout: Duration,
+
+    /// Paths of the config file and all files it imports
+    #[serde(skip)]
+    pub config_paths: Vec<PathBuf>,
 }
 
 impl Default for UIConfig {
     fn default() -> Self {
-        UIConfig {
+        let mut ui_config = UIConfig {
             mouse: Mouse::default(),
+            paste: Paste::default(),
+            notifications: Notifications::default(),
+            bell: Bell::default(),
+            hints: Hints::default(),
+            color_schemes: ColorSchemes::default(),
+            color_scheme: None,
+            appearance: Appearance::default(),
             key_bindings: default_key_bindings(),
             mouse_bindings: default_mouse_bindings(),
+            chord_timeout: default_chord_timeout(),
+            compose_timeout: default_compose_timeout(),
+            config_paths: Vec::new(),
+        };
+        ui_config.add_hint_bindings();
+        ui_config
+    }
+}
+
+impl UIConfig {
+    /// Add the bindings of all hints, replacing bindings with the same trigger.
+    pub fn add_hint_bindings(&mut self) {
+        for binding in self.hints.key_bindings() {
+            self.key_bindings.retain(|b| !b.triggers_match(&binding));
+            self.key_bindings.push(binding);
         }
     }
 }
@@ -38,6 +108,43 @@ fn default_mouse_bindings() -> Vec<MouseBinding> {
     bindings::default_mouse_bindings()
 }
 
+fn default_chord_timeout() -> Duration {
+    Duration::from_millis(1000)
+}
+
+fn default_compose_timeout() -> Duration {
+    Duration::from_millis(2000)
+}
+
+fn deserialize_chord_timeout<'a, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    deserialize_timeout(deserializer, default_chord_timeout())
+}
+
+fn deserialize_compose_timeout<'a, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    deserialize_timeout(deserializer, default_compose_timeout())
+}
+
+/// Deserialize a timeout in milliseconds, falling back to its default.
+fn deserialize_timeout<'a, D>(deserializer: D, default: Duration) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    let value = serde_yaml::Value::deserialize(deserializer)?;
+    match u64::deserialize(value) {
+        Ok(timeout_ms) => Ok(Duration::from_millis(timeout_ms)),
+        Err(err) => {
+            diagnostics::invalid_value(format_args!("{}; using default value", err));
+            Ok(default)
+        },
+    }
+}
+
 fn deserialize_key_bindings<'a, D>(deserializer: D) -> Result<Vec<KeyBinding>, D::Error>
 where
     D: Deserializer<'a>,
@@ -69,7 +176,7 @@ where
         match Binding::<T>::deserialize(value) {
             Ok(binding) => bindings.push(binding),
             Err(err) => {
-                error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; ignoring binding", err);
+                diagnostics::invalid_value(format_args!("{}; ignoring binding", err));
             },
         }
     }
@@ -0,0 +1,215 @@
+//! Reporting of config problems with their location in the config files.
+
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use log::{error, warn};
+use serde_yaml::Value;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+use alacritty_terminal::config::diagnostics::{self, Diagnostic, DiagnosticKind};
+use alacritty_terminal::config::{CONFIG_FIELDS, LOG_TARGET_CONFIG};
+use alacritty_terminal::util;
+
+use crate::config::ui_config::UIConfig;
+
+/// Config file with the line and column of every key in it.
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    keys: HashMap<Vec<String>, (usize, usize)>,
+}
+
+impl ConfigFile {
+    pub fn new(path: &Path, contents: &str) -> Self {
+        let mut locator = KeyLocator::default();
+
+        // Syntax errors are already reported when the config is parsed
+        let _ = Parser::new(contents.chars()).load(&mut locator, false);
+
+        Self { path: path.to_owned(), keys: locator.keys }
+    }
+
+    /// Location of a key in this file, considering its `os` and `hosts` overrides.
+    fn location(&self, path: &[String]) -> Option<(usize, usize)> {
+        let hostname = util::hostname().unwrap_or_default();
+        let host = self.keys.keys().find(|key| {
+            key.len() == path.len() + 2
+                && key[0] == "hosts"
+                && key[1].eq_ignore_ascii_case(&hostname)
+                && key[2..] == *path
+        });
+
+        let mut os = vec![String::from("os"), String::from(env::consts::OS)];
+        os.extend_from_slice(path);
+
+        host.and_then(|key| self.keys.get(key))
+            .or_else(|| self.keys.get(&os))
+            .or_else(|| self.keys.get(path))
+            .copied()
+    }
+}
+
+/// Log the problems with a config, pointing at the file they originate from.
+///
+/// The `value` is the merged config of all `files`, ordered like the imports were read.
+pub fn report(value: &Value, mut diagnostics: Vec<Diagnostic>, files: &[ConfigFile]) {
+    diagnostics.extend(unknown_root_fields(value));
+
+    for diagnostic in diagnostics {
+        // The main file takes precedence over its imports, later imports over earlier ones
+        let location = files
+            .first()
+            .into_iter()
+            .chain(files.iter().skip(1).rev())
+            .find_map(|file| Some((file, file.location(&diagnostic.path)?)));
+
+        let location = match location {
+            Some((file, (line, column))) => {
+                format!(" at {}:{}:{}", file.path.display(), line, column + 1)
+            },
+            None => String::new(),
+        };
+
+        match diagnostic.kind {
+            DiagnosticKind::UnknownField => {
+                warn!(target: LOG_TARGET_CONFIG, "Problem with config{}: {}", location, diagnostic)
+            },
+            DiagnosticKind::InvalidValue => {
+                error!(target: LOG_TARGET_CONFIG, "Problem with config{}: {}", location, diagnostic)
+            },
+        }
+    }
+}
+
+/// Top-level fields which are neither part of the terminal nor the UI config.
+///
+/// These are not caught while deserializing, since the UI config is flattened into the top level.
+fn unknown_root_fields(value: &Value) -> Vec<Diagnostic> {
+    let mapping = match value.as_mapping() {
+        Some(mapping) => mapping,
+        None => return Vec::new(),
+    };
+
+    let ui_fields = diagnostics::struct_fields::<UIConfig>();
+    let fields = || CONFIG_FIELDS.iter().chain(ui_fields).copied();
+
+    mapping
+        .iter()
+        .filter_map(|(key, _)| key.as_str())
+        .filter(|key| !fields().any(|field| field == *key))
+        .map(|key| Diagnostic {
+            kind: DiagnosticKind::UnknownField,
+            path: vec![key.to_owned()],
+            message: format!("unknown field{}", diagnostics::did_you_mean(key, fields())),
+        })
+        .collect()
+}
+
+/// YAML nodes containing other nodes.
+enum Container {
+    /// Mapping with the key whose value is parsed next.
+    Mapping(Option<String>),
+
+    /// Sequence with the index of the next element.
+    Sequence(usize),
+}
+
+/// Receiver of YAML events, recording the position of every key.
+#[derive(Default)]
+struct KeyLocator {
+    /// Containers enclosing the current node, with the key leading to them.
+    containers: Vec<(Option<String>, Container)>,
+
+    keys: HashMap<Vec<String>, (usize, usize)>,
+}
+
+impl KeyLocator {
+    /// Key leading to the current node.
+    fn key(&self) -> Option<String> {
+        match self.containers.last() {
+            Some((_, Container::Mapping(key))) => key.clone(),
+            Some((_, Container::Sequence(index))) => Some(format!("[{}]", index)),
+            None => None,
+        }
+    }
+
+    /// Move on to the next node of the current container.
+    fn node_done(&mut self) {
+        match self.containers.last_mut() {
+            Some((_, Container::Mapping(key))) => *key = None,
+            Some((_, Container::Sequence(index))) => *index += 1,
+            None => (),
+        }
+    }
+
+    /// Check if the next node is a key of a mapping.
+    fn expects_key(&self) -> bool {
+        match self.containers.last() {
+            Some((_, Container::Mapping(key))) => key.is_none(),
+            _ => false,
+        }
+    }
+}
+
+impl MarkedEventReceiver for KeyLocator {
+    fn on_event(&mut self, event: Event, marker: Marker) {
+        match event {
+            Event::Scalar(key, ..) if self.expects_key() => {
+                let mut path: Vec<String> =
+                    self.containers.iter().filter_map(|(key, _)| key.clone()).collect();
+                path.push(key.clone());
+                self.keys.entry(path).or_insert((marker.line(), marker.col()));
+
+                if let Some((_, Container::Mapping(next))) = self.containers.last_mut() {
+                    *next = Some(key);
+                }
+            },
+            Event::Scalar(..) | Event::Alias(_) => self.node_done(),
+            Event::MappingStart(_) => {
+                let key = self.key();
+                self.containers.push((key, Container::Mapping(None)));
+            },
+            Event::SequenceStart(_) => {
+                let key = self.key();
+                self.containers.push((key, Container::Sequence(0)));
+            },
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.containers.pop();
+                self.node_done();
+            },
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_locations() {
+        let yaml = "window:\n  padding: {x: 1}\nhints:\n  enabled:\n    - regex: a\n      \
+                    command: b\nos:\n  linux: {cursor: {style: Beam}}\n";
+        let file = ConfigFile::new(Path::new("alacritty.yml"), yaml);
+        let path = |path: &str| -> Vec<String> { path.split('.').map(String::from).collect() };
+
+        assert_eq!(file.location(&path("window")), Some((1, 0)));
+        assert_eq!(file.location(&path("window.padding.x")), Some((2, 12)));
+        assert_eq!(file.location(&path("hints.enabled.[0].command")), Some((6, 6)));
+        assert_eq!(file.location(&path("window.missing")), None);
+
+        let cursor = file.location(&path("cursor.style"));
+        assert_eq!(cursor.is_some(), env::consts::OS == "linux");
+    }
+
+    #[test]
+    fn unknown_root_field() {
+        let value: Value = serde_yaml::from_str("{windw: {}, mouse: {}, scrolling: {}}").unwrap();
+        let diagnostics = unknown_root_fields(&value);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].to_string(), "windw: unknown field, did you mean `window`?");
+    }
+}
@@ -16,6 +16,7 @@
 //! GPU drawing.
 use std::f64;
 use std::fmt::{self, Formatter};
+use std::mem;
 use std::time::Instant;
 
 use glutin::dpi::{PhysicalPosition, PhysicalSize};
@@ -24,27 +25,37 @@ use glutin::event_loop::EventLoop;
 #[cfg(not(any(target_os = "macos", windows)))]
 use glutin::platform::unix::EventLoopWindowTargetExtUnix;
 use glutin::window::CursorIcon;
-use log::{debug, info};
+use glutin::Rect;
+use log::{debug, error, info, warn};
 use parking_lot::MutexGuard;
 
 use font::{self, Rasterize};
 
 use alacritty_terminal::config::{Font, StartupMode};
 use alacritty_terminal::event::{Event, OnResize};
-use alacritty_terminal::index::Line;
+use alacritty_terminal::index::{Column, Line, Point};
 use alacritty_terminal::message_bar::MessageBuffer;
 use alacritty_terminal::meter::Meter;
 use alacritty_terminal::selection::Selection;
+use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::color::Rgb;
+use alacritty_terminal::term::damage::{LineDamageBounds, TermDamage};
 use alacritty_terminal::term::{RenderableCell, SizeInfo, Term, TermMode};
+use alacritty_terminal::util;
 
+use crate::chord::ChordState;
 use crate::config::Config;
 use crate::event::{DisplayUpdate, Mouse};
+use crate::hint::HintState;
+use crate::renderer::graphics::RenderGraphic;
 use crate::renderer::rects::{RenderLines, RenderRect};
 use crate::renderer::{self, GlyphCache, QuadRenderer};
 use crate::url::{Url, Urls};
 use crate::window::{self, Window};
 
+/// Background color of hint labels.
+const HINT_LABEL_COLOR: Rgb = Rgb { r: 0xf4, g: 0xbf, b: 0x75 };
+
 #[derive(Debug)]
 pub enum Error {
     /// Error with window management
@@ -112,9 +123,24 @@ pub struct Display {
     pub window: Window,
     pub urls: Urls,
 
+    /// Hint waiting for the label of one of its matches.
+    pub hint_state: HintState,
+
+    /// Keys of the chord binding which is being typed.
+    pub chord_state: ChordState,
+
     /// Currently highlighted URL.
     pub highlighted_url: Option<Url>,
 
+    /// Next time the visibility of blinking text on screen changes.
+    pub text_blink_deadline: Option<Instant>,
+
+    /// Next time the blinking cursor is shown or hidden.
+    pub cursor_blink_deadline: Option<Instant>,
+
+    /// Whether the last frame contained elements which are not tracked by the terminal's damage.
+    overlay_damage: bool,
+
     renderer: QuadRenderer,
     glyph_cache: GlyphCache,
     meter: Meter,
@@ -124,9 +150,21 @@ pub struct Display {
 
 impl Display {
     pub fn new(config: &Config, event_loop: &EventLoop<Event>) -> Result<Display, Error> {
-        // Guess DPR based on first monitor
-        let estimated_dpr =
-            event_loop.available_monitors().next().map(|m| m.scale_factor()).unwrap_or(1.);
+        // Monitor selected for the window
+        let monitor = config.window.monitor.as_ref().and_then(|name| {
+            let monitor = event_loop.available_monitors().find(|m| m.name().as_ref() == Some(name));
+            if monitor.is_none() {
+                warn!("Unable to find monitor {:?}", name);
+            }
+            monitor
+        });
+
+        // Guess DPR based on the selected or first monitor
+        let estimated_dpr = monitor
+            .clone()
+            .or_else(|| event_loop.available_monitors().next())
+            .map(|m| m.scale_factor())
+            .unwrap_or(1.);
 
         // Guess the target window dimensions
         let metrics = GlyphCache::static_metrics(config.font.clone(), estimated_dpr)?;
@@ -134,6 +172,8 @@ impl Display {
         let dimensions =
             GlyphCache::calculate_dimensions(config, estimated_dpr, cell_width, cell_height);
 
+        let monitors: Vec<_> = event_loop.available_monitors().filter_map(|m| m.name()).collect();
+        debug!("Available monitors: {:?}", monitors);
         debug!("Estimated DPR: {}", estimated_dpr);
         debug!("Estimated Cell Size: {} x {}", cell_width, cell_height);
         debug!("Estimated Dimensions: {:?}", dimensions);
@@ -197,7 +237,7 @@ impl Display {
         // Clear screen
         let background_color = config.colors.primary.background;
         renderer.with_api(&config, &size_info, |api| {
-            api.clear(background_color);
+            api.clear(background_color, window.background_opacity());
         });
 
         #[cfg(not(any(target_os = "macos", windows)))]
@@ -223,17 +263,27 @@ impl Display {
         //
         // TODO: replace `set_position` with `with_position` once available
         // Upstream issue: https://github.com/rust-windowing/winit/issues/806
-        if let Some(position) = config.window.position {
-            window.set_outer_position(PhysicalPosition::from((position.x, position.y)));
+        //
+        // With a selected monitor, the position is relative to its top left corner.
+        let origin = monitor.as_ref().map(|m| m.position());
+        let position = match (config.window.position, origin) {
+            (Some(position), Some(origin)) => Some((origin.x + position.x, origin.y + position.y)),
+            (Some(position), None) => Some((position.x, position.y)),
+            (None, Some(origin)) => Some((origin.x, origin.y)),
+            (None, None) => None,
+        };
+        if let Some(position) = position {
+            window.set_outer_position(PhysicalPosition::from(position));
         }
 
         #[allow(clippy::single_match)]
-        match config.window.startup_mode() {
-            StartupMode::Fullscreen => window.set_fullscreen(true),
+        match (config.window.startup_mode(), monitor) {
+            (StartupMode::Fullscreen, Some(monitor)) => window.set_fullscreen_on(monitor),
+            (StartupMode::Fullscreen, None) => window.set_fullscreen(true),
             #[cfg(target_os = "macos")]
-            StartupMode::SimpleFullscreen => window.set_simple_fullscreen(true),
+            (StartupMode::SimpleFullscreen, _) => window.set_simple_fullscreen(true),
             #[cfg(not(any(target_os = "macos", windows)))]
-            StartupMode::Maximized => window.set_maximized(true),
+            (StartupMode::Maximized, _) => window.set_maximized(true),
             _ => (),
         }
 
@@ -244,7 +294,12 @@ impl Display {
             meter: Meter::new(),
             size_info,
             urls: Urls::new(),
+            hint_state: HintState::default(),
+            chord_state: ChordState::default(),
             highlighted_url: None,
+            text_blink_deadline: None,
+            cursor_blink_deadline: None,
+            overlay_damage: true,
             #[cfg(not(any(target_os = "macos", windows)))]
             is_x11,
         })
@@ -256,7 +311,8 @@ impl Display {
         config: &Config,
     ) -> Result<(GlyphCache, f32, f32), Error> {
         let font = config.font.clone();
-        let rasterizer = font::Rasterizer::new(dpr as f32, config.font.use_thin_strokes())?;
+        let mut rasterizer = font::Rasterizer::new(dpr as f32, config.font.use_thin_strokes())?;
+        rasterizer.set_subpixel(config.subpixel(config.background_opacity()));
 
         // Initialize glyph cache
         let glyph_cache = {
@@ -282,12 +338,37 @@ impl Display {
     }
 
     /// Update font size and cell dimensions
-    fn update_glyph_cache(&mut self, config: &Config, font: Font) {
+    fn update_glyph_cache(&mut self, config: &Config, font: Font, reload_rasterizer: bool) {
         let size_info = &mut self.size_info;
         let cache = &mut self.glyph_cache;
+        let subpixel = config.subpixel(self.window.background_opacity());
+
+        // Use a new rasterizer after config changes, so no font state is carried over
+        let rasterizer = if reload_rasterizer {
+            match font::Rasterizer::new(size_info.dpr as f32, font.use_thin_strokes()) {
+                Ok(mut rasterizer) => {
+                    rasterizer.set_subpixel(subpixel);
+                    Some(rasterizer)
+                },
+                Err(err) => {
+                    error!("Unable to create rasterizer: {}", err);
+                    None
+                },
+            }
+        } else {
+            None
+        };
 
+        cache.set_subpixel(subpixel);
         self.renderer.with_loader(|mut api| {
-            let _ = cache.update_font_size(font, size_info.dpr, &mut api);
+            let result = match rasterizer {
+                Some(rasterizer) => cache.reload_font(rasterizer, font, size_info.dpr, &mut api),
+                None => cache.update_font_size(font, size_info.dpr, &mut api),
+            };
+
+            if let Err(err) = result {
+                error!("Unable to load font: {}", err);
+            }
         });
 
         // Update cell size
@@ -313,9 +394,11 @@ impl Display {
         config: &Config,
         update_pending: DisplayUpdate,
     ) {
+        terminal.mark_fully_damaged();
+
         // Update font size and cell dimensions
         if let Some(font) = update_pending.font {
-            self.update_glyph_cache(config, font);
+            self.update_glyph_cache(config, font, update_pending.rasterizer);
         } else if update_pending.cursor {
             self.clear_glyph_cache();
         }
@@ -370,14 +453,19 @@ impl Display {
     /// This call may block if vsync is enabled
     pub fn draw<T>(
         &mut self,
-        terminal: MutexGuard<'_, Term<T>>,
+        mut terminal: MutexGuard<'_, Term<T>>,
         message_buffer: &MessageBuffer,
         config: &Config,
         mouse: &Mouse,
         mods: ModifiersState,
     ) {
         let grid_cells: Vec<RenderableCell> = terminal.renderable_cells(config).collect();
+        let blink_flags = grid_cells.iter().fold(Flags::empty(), |flags, cell| flags | cell.flags);
+        self.text_blink_deadline = terminal.text_blink.next_toggle(blink_flags);
+        self.cursor_blink_deadline = terminal.cursor_blink_deadline();
+        let graphics_queues = terminal.graphics_take_queues();
         let visual_bell_intensity = terminal.visual_bell.intensity();
+        let damage = terminal.damage(config);
         let background_color = terminal.background_color();
         let metrics = self.glyph_cache.font_metrics();
         let glyph_cache = &mut self.glyph_cache;
@@ -387,6 +475,12 @@ impl Display {
         let mouse_mode = terminal.mode().intersects(TermMode::MOUSE_MODE)
             && !terminal.mode().contains(TermMode::VI);
 
+        let line_timestamps = if terminal.show_line_timestamps {
+            line_timestamps(&terminal, &size_info)
+        } else {
+            Vec::new()
+        };
+
         let vi_mode_cursor = if terminal.mode().contains(TermMode::VI) {
             Some(terminal.vi_mode_cursor)
         } else {
@@ -400,12 +494,40 @@ impl Display {
         // Drop terminal as early as possible to free lock
         drop(terminal);
 
+        // Elements drawn on top of the grid are always redrawn completely
+        let highlighted_url = self.urls.highlighted(config, mouse, mods, mouse_mode, selection);
+        let overlay = visual_bell_intensity != 0.
+            || message_buffer.message().is_some()
+            || config.render_timer()
+            || !line_timestamps.is_empty()
+            || self.text_blink_deadline.is_some()
+            || self.cursor_blink_deadline.is_some()
+            || graphics_queues.is_some()
+            || vi_mode_cursor.is_some()
+            || self.hint_state.active()
+            || self.chord_state.pending()
+            || highlighted_url != self.highlighted_url;
+        let full_damage = mem::replace(&mut self.overlay_damage, overlay) || overlay;
+
+        // Skip frames without any visible changes
+        let mut damage = match damage {
+            TermDamage::Partial(damage) if damage.is_empty() && !full_damage => return,
+            TermDamage::Partial(damage) if !full_damage => Some(damage),
+            _ => None,
+        };
+
+        let background_opacity = self.window.background_opacity();
         self.renderer.with_api(&config, &size_info, |api| {
-            api.clear(background_color);
+            api.clear(background_color, background_opacity);
         });
 
+        if let Some(graphics_queues) = graphics_queues {
+            self.renderer.update_graphics(graphics_queues);
+        }
+
         let mut lines = RenderLines::new();
         let mut urls = Urls::new();
+        let mut graphics = Vec::new();
 
         // Draw grid
         {
@@ -413,12 +535,21 @@ impl Display {
 
             self.renderer.with_api(&config, &size_info, |mut api| {
                 // Iterate over all non-empty cells in the grid
-                for cell in grid_cells {
+                for mut cell in grid_cells {
+                    // Collect graphics, they are drawn after the text
+                    if let Some(graphic) = cell.graphic.take() {
+                        graphics.push(RenderGraphic {
+                            line: cell.line,
+                            column: cell.column,
+                            graphic,
+                        });
+                    }
+
                     // Update URL underlines
-                    urls.update(size_info.cols().0, cell);
+                    urls.update(size_info.cols().0, &cell);
 
                     // Update underline/strikeout
-                    lines.update(cell);
+                    lines.update(&cell);
 
                     // Draw the cell
                     api.render_cell(cell, glyph_cache);
@@ -426,9 +557,12 @@ impl Display {
             });
         }
 
+        self.renderer.draw_graphics(&size_info, graphics);
+
         let mut rects = lines.rects(&metrics, &size_info);
 
         // Update visible URLs
+        let last_highlighted_url = self.highlighted_url.clone();
         self.urls = urls;
         if let Some(url) = self.urls.highlighted(config, mouse, mods, mouse_mode, selection) {
             rects.append(&mut url.rects(&metrics, &size_info));
@@ -446,6 +580,11 @@ impl Display {
             }
         }
 
+        // URL underlines are not part of the terminal's damage
+        if self.highlighted_url != last_highlighted_url {
+            damage = None;
+        }
+
         // Highlight URLs at the vi mode cursor position
         if let Some(vi_mode_cursor) = vi_mode_cursor {
             if let Some(url) = self.urls.find_at(vi_mode_cursor.point) {
@@ -487,7 +626,7 @@ impl Display {
                 self.renderer.with_api(&config, &size_info, |mut api| {
                     api.render_string(
                         &message_text,
-                        Line(size_info.lines().saturating_sub(offset)),
+                        Point::new(Line(size_info.lines().saturating_sub(offset)), Column(0)),
                         glyph_cache,
                         None,
                     );
@@ -499,16 +638,62 @@ impl Display {
             self.renderer.draw_rects(&size_info, rects);
         }
 
+        // Draw line timestamps above the grid, aligned to the right edge
+        if !line_timestamps.is_empty() {
+            let color = Rgb { r: 0x55, g: 0x55, b: 0x55 };
+            self.renderer.with_api(&config, &size_info, |mut api| {
+                for (point, timestamp) in line_timestamps {
+                    api.render_string(&timestamp, point, glyph_cache, Some(color));
+                }
+            });
+        }
+
+        // Draw hint labels on top of their matches
+        if self.hint_state.active() {
+            let num_cols = size_info.cols().0;
+            let hint_state = &self.hint_state;
+            self.renderer.with_api(&config, &size_info, |mut api| {
+                for (point, label) in hint_state.labels() {
+                    let label: String =
+                        label.chars().take(num_cols.saturating_sub(point.col.0)).collect();
+                    api.render_string(&label, point, glyph_cache, Some(HINT_LABEL_COLOR));
+                }
+            });
+        }
+
+        // Draw the keys of a pending chord in the bottom right corner, like the hint labels
+        if self.chord_state.pending() {
+            let num_cols = size_info.cols().0;
+            let indicator: String = self.chord_state.indicator().chars().take(num_cols).collect();
+            let col = Column(num_cols - indicator.chars().count());
+            self.renderer.with_api(&config, &size_info, |mut api| {
+                let point = Point::new(size_info.lines() - 1, col);
+                api.render_string(&indicator, point, glyph_cache, Some(HINT_LABEL_COLOR));
+            });
+        }
+
         // Draw render timer
         if config.render_timer() {
-            let timing = format!("{:.3} usec", self.meter.average());
+            let timing = format!(
+                "{:.3} usec, {} glyphs, {}/{} atlas pages",
+                self.meter.average(),
+                glyph_cache.len(),
+                self.renderer.atlas_pages(),
+                renderer::ATLAS_MAX_PAGES,
+            );
             let color = Rgb { r: 0xd5, g: 0x4e, b: 0x53 };
             self.renderer.with_api(&config, &size_info, |mut api| {
-                api.render_string(&timing[..], size_info.lines() - 2, glyph_cache, Some(color));
+                let point = Point::new(size_info.lines() - 2, Column(0));
+                api.render_string(&timing[..], point, glyph_cache, Some(color));
             });
         }
 
-        self.window.swap_buffers();
+        match damage {
+            Some(damage) => {
+                self.window.swap_buffers_with_damage(&damage_rects(&damage, &size_info))
+            },
+            None => self.window.swap_buffers(),
+        }
 
         #[cfg(not(any(target_os = "macos", windows)))]
         {
@@ -524,6 +709,42 @@ impl Display {
     }
 }
 
+/// Completion time of every visible line, with the position it should be rendered at.
+fn line_timestamps<T>(terminal: &Term<T>, size_info: &SizeInfo) -> Vec<(Point, String)> {
+    let num_lines = terminal.grid().num_lines().0;
+    let display_offset = terminal.grid().display_offset();
+
+    (0..num_lines)
+        .filter_map(|line| {
+            let time = terminal.line_timestamp(num_lines - 1 - line + display_offset)?;
+            let timestamp = format!(" {} ", util::format_time_of_day(time));
+            let col = Column(size_info.cols().0.saturating_sub(timestamp.len()));
+            Some((Point::new(Line(line), col), timestamp))
+        })
+        .collect()
+}
+
+/// Convert damaged cells to rectangles in window coordinates, starting at the bottom left.
+fn damage_rects(damage: &[LineDamageBounds], size_info: &SizeInfo) -> Vec<Rect> {
+    damage
+        .iter()
+        .map(|bounds| {
+            let x = size_info.padding_x + bounds.left.0 as f32 * size_info.cell_width;
+            let y = size_info.height
+                - size_info.padding_y
+                - (bounds.line.0 + 1) as f32 * size_info.cell_height;
+            let width = (bounds.right.0 - bounds.left.0 + 1) as f32 * size_info.cell_width;
+
+            Rect {
+                x: x as u32,
+                y: y.max(0.) as u32,
+                width: width.ceil() as u32,
+                height: size_info.cell_height.ceil() as u32,
+            }
+        })
+        .collect()
+}
+
 /// Calculate padding to spread it evenly around the terminal content
 #[inline]
 fn dynamic_padding(padding: f32, dimension: f32, cell_dimension: f32) -> f32 {
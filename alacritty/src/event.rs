@@ -1,27 +1,32 @@
 //! Process window events
 use std::borrow::Cow;
 use std::cmp::max;
+use std::collections::HashSet;
 use std::env;
 #[cfg(unix)]
 use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::mem;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use glutin::dpi::PhysicalSize;
-use glutin::event::{ElementState, Event as GlutinEvent, ModifiersState, WindowEvent};
+use glutin::event::{
+    ElementState, Event as GlutinEvent, ModifiersState, ScanCode, StartCause, WindowEvent,
+};
 use glutin::event_loop::{ControlFlow, EventLoop, EventLoopProxy, EventLoopWindowTarget};
 use glutin::platform::desktop::EventLoopExtDesktop;
 #[cfg(not(any(target_os = "macos", windows)))]
 use glutin::platform::unix::EventLoopWindowTargetExtUnix;
+use glutin::window::Theme;
 use log::{debug, info, warn};
 use serde_json as json;
 
 use font::Size;
 
+use alacritty_terminal::ansi::{Notification, Progress};
 use alacritty_terminal::clipboard::ClipboardType;
 use alacritty_terminal::config::Font;
 use alacritty_terminal::config::LOG_TARGET_CONFIG;
@@ -36,27 +41,46 @@ use alacritty_terminal::term::cell::Cell;
 use alacritty_terminal::term::{SizeInfo, Term, TermMode};
 #[cfg(not(windows))]
 use alacritty_terminal::tty;
-use alacritty_terminal::util::{limit, start_daemon};
+use alacritty_terminal::util::{limit, start_daemon, start_daemon_in};
+use alacritty_terminal::vi_mode::InlineSearch;
 
+use crate::chord::ChordState;
 use crate::cli::Options;
+use crate::compose::ComposeState;
 use crate::config;
-use crate::config::Config;
+use crate::config::monitor::Monitor;
+use crate::config::{Config, NotificationMode, ViAction, SOUND_PLAYER};
 use crate::display::Display;
+use crate::geometry::Geometry;
+use crate::hint::HintState;
 use crate::input::{self, ActionContext as _, FONT_SIZE_STEP};
 use crate::url::{Url, Urls};
 use crate::window::Window;
 
+/// Minimum time between two bells running the bell command and sound.
+const BELL_COMMAND_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Minimum time between two notifications running the notification command.
+const NOTIFICATION_COMMAND_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(Default, Clone, Debug, PartialEq)]
 pub struct DisplayUpdate {
     pub dimensions: Option<PhysicalSize<u32>>,
     pub message_buffer: bool,
     pub font: Option<Font>,
     pub cursor: bool,
+
+    /// Load the font with a new rasterizer instead of only changing its size.
+    pub rasterizer: bool,
 }
 
 impl DisplayUpdate {
     fn is_empty(&self) -> bool {
-        self.dimensions.is_none() && self.font.is_none() && !self.message_buffer && !self.cursor
+        self.dimensions.is_none()
+            && self.font.is_none()
+            && !self.message_buffer
+            && !self.cursor
+            && !self.rasterizer
     }
 }
 
@@ -67,6 +91,13 @@ pub struct ActionContext<'a, N, T> {
     pub mouse: &'a mut Mouse,
     pub received_count: &'a mut usize,
     pub suppress_chars: &'a mut bool,
+    pub compose_state: &'a mut ComposeState,
+    pub pending_paste: &'a mut Option<String>,
+    pub inline_search: &'a mut InlineSearchState,
+    pub appearance: &'a mut AppearanceState,
+    pub hint_state: &'a mut HintState,
+    pub chord_state: &'a mut ChordState,
+    pub pressed_keys: &'a mut HashSet<ScanCode>,
     pub modifiers: &'a mut ModifiersState,
     pub window: &'a mut Window,
     pub message_buffer: &'a mut MessageBuffer,
@@ -75,6 +106,8 @@ pub struct ActionContext<'a, N, T> {
     pub event_loop: &'a EventLoopWindowTarget<Event>,
     pub urls: &'a Urls,
     font_size: &'a mut Size,
+    last_bell: &'a mut Option<Instant>,
+    last_notification: &'a mut Option<Instant>,
 }
 
 impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionContext<'a, N, T> {
@@ -192,6 +225,32 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         &mut self.suppress_chars
     }
 
+    #[inline]
+    fn compose_state(&mut self) -> &mut ComposeState {
+        &mut self.compose_state
+    }
+
+    fn pending_paste(&mut self) -> &mut Option<String> {
+        &mut self.pending_paste
+    }
+
+    fn inline_search(&mut self) -> &mut InlineSearchState {
+        &mut self.inline_search
+    }
+
+    fn hint_state(&mut self) -> &mut HintState {
+        &mut self.hint_state
+    }
+
+    fn chord_state(&mut self) -> &mut ChordState {
+        &mut self.chord_state
+    }
+
+    #[inline]
+    fn pressed_keys(&mut self) -> &mut HashSet<ScanCode> {
+        &mut self.pressed_keys
+    }
+
     #[inline]
     fn modifiers(&mut self) -> &mut ModifiersState {
         &mut self.modifiers
@@ -217,24 +276,18 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.terminal
     }
 
+    fn working_directory(&self) -> Option<PathBuf> {
+        // Prefer the directory reported by the shell, since it is available on every platform
+        self.terminal.working_directory().map(Path::to_path_buf).or_else(child_working_directory)
+    }
+
     fn spawn_new_instance(&mut self) {
         let alacritty = env::args().next().unwrap();
 
-        #[cfg(unix)]
-        let args = {
-            #[cfg(not(target_os = "freebsd"))]
-            let proc_prefix = "";
-            #[cfg(target_os = "freebsd")]
-            let proc_prefix = "/compat/linux";
-            let link_path = format!("{}/proc/{}/cwd", proc_prefix, tty::child_pid());
-            if let Ok(path) = fs::read_link(link_path) {
-                vec!["--working-directory".into(), path]
-            } else {
-                Vec::new()
-            }
+        let args = match self.working_directory() {
+            Some(path) => vec!["--working-directory".into(), path],
+            None => Vec::new(),
         };
-        #[cfg(not(unix))]
-        let args: Vec<String> = Vec::new();
 
         match start_daemon(&alacritty, &args) {
             Ok(_) => debug!("Started new Alacritty process: {} {:?}", alacritty, args),
@@ -255,6 +308,54 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.terminal.dirty = true;
     }
 
+    fn change_opacity(&mut self, delta: f32) {
+        let opacity = self.window.background_opacity() + delta;
+        self.set_background_opacity(opacity);
+    }
+
+    fn reset_opacity(&mut self) {
+        self.set_background_opacity(self.config.background_opacity());
+    }
+
+    fn toggle_debug_overlay(&mut self) {
+        self.config.debug.render_timer = !self.config.render_timer();
+        self.config.render_timer = None;
+        self.terminal.dirty = true;
+    }
+
+    fn set_color_scheme(&mut self, name: Option<String>) {
+        let colors = match self.config.ui_config.color_schemes.get(name.as_deref()) {
+            Some(colors) => colors.clone(),
+            None => {
+                warn!("Unknown color scheme {:?}", name.unwrap_or_default());
+                return;
+            },
+        };
+
+        #[cfg(not(any(target_os = "macos", windows)))]
+        {
+            if self.event_loop.is_wayland() {
+                self.window.set_wayland_theme(&colors);
+            }
+        }
+
+        self.terminal.update_colors(&colors);
+        self.terminal.dirty = true;
+        self.config.colors = colors;
+        self.config.ui_config.color_scheme = name;
+
+        // Let applications adapt to the new palette
+        if self.terminal.mode().contains(TermMode::COLOR_PALETTE_UPDATES) {
+            let report = self.terminal.color_scheme_report();
+            self.write_to_pty(report.into_bytes());
+        }
+    }
+
+    fn push_message(&mut self, message: Message) {
+        self.display_update_pending.message_buffer = true;
+        self.message_buffer.push(message);
+    }
+
     fn pop_message(&mut self) {
         self.display_update_pending.message_buffer = true;
         self.message_buffer.pop();
@@ -276,6 +377,11 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
         self.urls
     }
 
+    fn hovered_url(&self) -> Option<String> {
+        let url = self.urls.find_at(self.mouse_coords()?)?;
+        self.url_text(&url)
+    }
+
     /// Spawn URL launcher when clicking on URLs.
     fn launch_url(&self, url: Url) {
         if self.mouse.block_url_launcher {
@@ -284,11 +390,14 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
 
         if let Some(ref launcher) = self.config.ui_config.mouse.url.launcher {
             let mut args = launcher.args().to_vec();
-            let start = self.terminal.visible_to_buffer(url.start());
-            let end = self.terminal.visible_to_buffer(url.end());
-            args.push(self.terminal.bounds_to_string(start, end));
+            match self.url_text(&url) {
+                Some(text) => args.push(text),
+                None => return,
+            }
 
-            match start_daemon(launcher.program(), &args) {
+            // Relative paths are resolved in the working directory of the shell
+            let working_directory = self.terminal.working_directory();
+            match start_daemon_in(launcher.program(), &args, working_directory) {
                 Ok(_) => debug!("Launched {} with args {:?}", launcher.program(), args),
                 Err(_) => warn!("Unable to launch {} with args {:?}", launcher.program(), args),
             }
@@ -296,12 +405,82 @@ impl<'a, N: Notify + 'a, T: EventListener> input::ActionContext<T> for ActionCon
     }
 }
 
+/// Check if an action ran less than `interval` ago, otherwise record that it runs now.
+fn throttled(last: &mut Option<Instant>, interval: Duration) -> bool {
+    let now = Instant::now();
+    match last {
+        Some(last) if now.duration_since(*last) < interval => true,
+        _ => {
+            *last = Some(now);
+            false
+        },
+    }
+}
+
+/// Background opacity after a config reload.
+///
+/// Opacity which has been changed at runtime is kept, otherwise the new config value is used.
+fn reloaded_opacity(opacity: f32, old_config: f32, new_config: f32) -> f32 {
+    if (opacity - old_config).abs() < std::f32::EPSILON {
+        new_config
+    } else {
+        opacity
+    }
+}
+
+/// Working directory of the shell process.
+#[cfg(unix)]
+fn child_working_directory() -> Option<PathBuf> {
+    #[cfg(not(target_os = "freebsd"))]
+    let proc_prefix = "";
+    #[cfg(target_os = "freebsd")]
+    let proc_prefix = "/compat/linux";
+    let link_path = format!("{}/proc/{}/cwd", proc_prefix, tty::child_pid());
+    fs::read_link(link_path).ok()
+}
+
+#[cfg(not(unix))]
+fn child_working_directory() -> Option<PathBuf> {
+    None
+}
+
+impl<'a, N, T> ActionContext<'a, N, T> {
+    /// Change the window opacity, reloading the font if its subpixel layout depends on it.
+    fn set_background_opacity(&mut self, opacity: f32) {
+        let subpixel = self.config.subpixel(self.window.background_opacity());
+        self.window.set_background_opacity(opacity);
+
+        if self.config.subpixel(self.window.background_opacity()) != subpixel
+            && self.display_update_pending.font.is_none()
+        {
+            let font = self.config.font.clone().with_size(*self.font_size);
+            self.display_update_pending.font = Some(font);
+        }
+
+        self.terminal.mark_fully_damaged();
+        self.terminal.dirty = true;
+    }
+
+    /// Target of a hyperlink, or the text of a URL detected in the grid.
+    fn url_text(&self, url: &Url) -> Option<String> {
+        match url.hyperlink() {
+            Some(id) => self.terminal.hyperlink_uri(id).map(str::to_owned),
+            None => {
+                let start = self.terminal.visible_to_buffer(url.start());
+                let end = self.terminal.visible_to_buffer(url.end());
+                Some(self.terminal.bounds_to_string(start, end))
+            },
+        }
+    }
+}
+
 #[derive(Debug, Eq, PartialEq)]
 pub enum ClickState {
     None,
     Click,
     DoubleClick,
     TripleClick,
+    QuadrupleClick,
 }
 
 /// State of the mouse
@@ -344,6 +523,40 @@ impl Default for Mouse {
     }
 }
 
+/// State of the vi mode inline character search
+#[derive(Debug, Default)]
+pub struct InlineSearchState {
+    /// Search waiting for the character it should jump to.
+    pub pending: Option<ViAction>,
+
+    /// Last completed search, used by the repeat motions.
+    pub last: Option<InlineSearch>,
+}
+
+/// State of the automatic switching between the dark and light color schemes
+#[derive(Debug)]
+pub struct AppearanceState {
+    /// Appearance last reported by the system.
+    system_theme: Option<Theme>,
+
+    /// Time at which the color scheme needs to be checked again.
+    pub deadline: Option<Instant>,
+}
+
+impl Default for AppearanceState {
+    fn default() -> Self {
+        // Pick the color scheme as soon as the first events are processed
+        AppearanceState { system_theme: None, deadline: Some(Instant::now()) }
+    }
+}
+
+impl AppearanceState {
+    /// Check if the color scheme needs to be picked again.
+    fn is_due(&self) -> bool {
+        self.deadline.map_or(false, |deadline| deadline <= Instant::now())
+    }
+}
+
 /// The event processor
 ///
 /// Stores some state from received events and dispatches actions when they are
@@ -353,11 +566,19 @@ pub struct Processor<N> {
     mouse: Mouse,
     received_count: usize,
     suppress_chars: bool,
+    compose_state: ComposeState,
+    pending_paste: Option<String>,
+    inline_search: InlineSearchState,
+    appearance: AppearanceState,
+    pressed_keys: HashSet<ScanCode>,
     modifiers: ModifiersState,
     config: Config,
     message_buffer: MessageBuffer,
     display: Display,
     font_size: Size,
+    last_bell: Option<Instant>,
+    last_notification: Option<Instant>,
+    monitor: Option<Monitor>,
 }
 
 impl<N: Notify + OnResize> Processor<N> {
@@ -370,14 +591,23 @@ impl<N: Notify + OnResize> Processor<N> {
         message_buffer: MessageBuffer,
         config: Config,
         display: Display,
+        monitor: Option<Monitor>,
     ) -> Processor<N> {
         Processor {
             notifier,
             mouse: Default::default(),
             received_count: 0,
             suppress_chars: false,
+            compose_state: Default::default(),
+            pending_paste: None,
+            inline_search: Default::default(),
+            appearance: Default::default(),
+            pressed_keys: HashSet::new(),
             modifiers: Default::default(),
             font_size: config.font.size,
+            last_bell: None,
+            last_notification: None,
+            monitor,
             config,
             message_buffer,
             display,
@@ -404,12 +634,30 @@ impl<N: Notify + OnResize> Processor<N> {
             match event {
                 // Check for shutdown
                 GlutinEvent::UserEvent(Event::Exit) => {
+                    if self.config.window.remember_geometry {
+                        let window = &self.display.window;
+                        if let Some(geometry) = Geometry::new(window, &self.display.size_info) {
+                            geometry.save();
+                        }
+                    }
+
                     *control_flow = ControlFlow::Exit;
                     return;
                 },
                 // Process events
                 GlutinEvent::RedrawEventsCleared => {
-                    *control_flow = ControlFlow::Wait;
+                    // Wake up again once blinking text or the cursor needs to be toggled, the
+                    // color scheme is scheduled to change or a pending chord times out
+                    let deadlines = [
+                        self.display.text_blink_deadline,
+                        self.display.cursor_blink_deadline,
+                        self.appearance.deadline,
+                        self.display.chord_state.deadline(),
+                    ];
+                    *control_flow = match deadlines.iter().flatten().min() {
+                        Some(&deadline) => ControlFlow::WaitUntil(deadline),
+                        None => ControlFlow::Wait,
+                    };
 
                     if event_queue.is_empty() {
                         return;
@@ -446,11 +694,20 @@ impl<N: Notify + OnResize> Processor<N> {
                 size_info: &mut self.display.size_info,
                 received_count: &mut self.received_count,
                 suppress_chars: &mut self.suppress_chars,
+                compose_state: &mut self.compose_state,
+                pending_paste: &mut self.pending_paste,
+                inline_search: &mut self.inline_search,
+                appearance: &mut self.appearance,
+                hint_state: &mut self.display.hint_state,
+                chord_state: &mut self.display.chord_state,
+                pressed_keys: &mut self.pressed_keys,
                 modifiers: &mut self.modifiers,
                 message_buffer: &mut self.message_buffer,
                 display_update_pending: &mut display_update_pending,
                 window: &mut self.display.window,
                 font_size: &mut self.font_size,
+                last_bell: &mut self.last_bell,
+                last_notification: &mut self.last_notification,
                 config: &mut self.config,
                 urls: &self.display.urls,
                 event_loop,
@@ -461,6 +718,21 @@ impl<N: Notify + OnResize> Processor<N> {
                 Processor::handle_event(event, &mut processor);
             }
 
+            if processor.ctx.appearance.is_due() {
+                Processor::update_appearance(&mut processor);
+            }
+
+            // Remove the indicator of a chord which has timed out
+            if processor.ctx.chord_state.is_expired() {
+                processor.ctx.chord_state.clear();
+                processor.ctx.terminal.dirty = true;
+            }
+
+            // Watch imports which have been changed by a config reload
+            if let Some(monitor) = &mut self.monitor {
+                monitor.watch(&self.config.ui_config.config_paths);
+            }
+
             // Process DisplayUpdate events
             if !display_update_pending.is_empty() {
                 self.display.handle_update(
@@ -520,10 +792,29 @@ impl<N: Notify + OnResize> Processor<N> {
                     processor.ctx.terminal.dirty = true;
                 },
                 Event::Title(title) => processor.ctx.window.set_title(&title),
-                Event::Wakeup => processor.ctx.terminal.dirty = true,
-                Event::Urgent => {
-                    processor.ctx.window.set_urgent(!processor.ctx.terminal.is_focused)
+                Event::Progress(progress) => {
+                    // Draw attention to tasks which failed in the background
+                    if let Progress::Error(_) = progress {
+                        processor.ctx.window.set_urgent(!processor.ctx.terminal.is_focused);
+                    }
+
+                    processor.ctx.window.set_progress(progress);
+                },
+                Event::TextAreaResize(width, height) => {
+                    // Add the configured padding around the requested text area
+                    let dpr = processor.ctx.size_info.dpr;
+                    let padding = &processor.ctx.config.window.padding;
+                    let padding_x = (f64::from(padding.x) * dpr).floor() as u32;
+                    let padding_y = (f64::from(padding.y) * dpr).floor() as u32;
+
+                    let width = width.saturating_add(padding_x.saturating_mul(2));
+                    let height = height.saturating_add(padding_y.saturating_mul(2));
+                    let size = PhysicalSize::new(width, height);
+                    processor.ctx.window.set_inner_size(size);
                 },
+                Event::Wakeup => processor.ctx.terminal.dirty = true,
+                Event::Bell => Self::ring_bell(processor),
+                Event::Notification(notification) => Self::notify(notification, processor),
                 Event::ConfigReload(path) => Self::reload_config(&path, processor),
                 Event::Message(message) => {
                     processor.ctx.message_buffer.push(message);
@@ -533,7 +824,13 @@ impl<N: Notify + OnResize> Processor<N> {
                 Event::MouseCursorDirty => processor.reset_mouse_cursor(),
                 Event::Exit => (),
             },
-            GlutinEvent::RedrawRequested(_) => processor.ctx.terminal.dirty = true,
+            GlutinEvent::RedrawRequested(_) => {
+                processor.ctx.terminal.mark_fully_damaged();
+                processor.ctx.terminal.dirty = true;
+            },
+            GlutinEvent::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                processor.ctx.terminal.dirty = true
+            },
             GlutinEvent::WindowEvent { event, window_id, .. } => {
                 match event {
                     WindowEvent::CloseRequested => processor.ctx.terminal.exit(),
@@ -555,6 +852,9 @@ impl<N: Notify + OnResize> Processor<N> {
                     WindowEvent::KeyboardInput { input, is_synthetic: false, .. } => {
                         processor.key_input(input);
                         if input.state == ElementState::Pressed {
+                            // Keep the cursor visible while typing
+                            processor.ctx.terminal.cursor_blink.reset();
+
                             // Hide cursor while typing
                             if processor.ctx.config.ui_config.mouse.hide_when_typing {
                                 processor.ctx.window.set_mouse_visible(false);
@@ -588,14 +888,20 @@ impl<N: Notify + OnResize> Processor<N> {
                             processor.ctx.terminal.dirty = true;
 
                             if is_focused {
+                                processor.ctx.terminal.cursor_blink.reset();
                                 processor.ctx.window.set_urgent(false);
                             } else {
                                 processor.ctx.window.set_mouse_visible(true);
+                                processor.ctx.pressed_keys.clear();
                             }
 
                             processor.on_focus_change(is_focused);
                         }
                     },
+                    WindowEvent::ThemeChanged(theme) => {
+                        processor.ctx.appearance.system_theme = Some(theme);
+                        processor.ctx.appearance.deadline = Some(Instant::now());
+                    },
                     WindowEvent::DroppedFile(path) => {
                         let path: String = path.to_string_lossy().into();
                         processor.ctx.write_to_pty(path.into_bytes());
@@ -614,7 +920,6 @@ impl<N: Notify + OnResize> Processor<N> {
                     | WindowEvent::AxisMotion { .. }
                     | WindowEvent::HoveredFileCancelled
                     | WindowEvent::Destroyed
-                    | WindowEvent::ThemeChanged(_)
                     | WindowEvent::HoveredFile(_)
                     | WindowEvent::Touch(_)
                     | WindowEvent::Moved(_) => (),
@@ -645,6 +950,7 @@ impl<N: Notify + OnResize> Processor<N> {
                 | WindowEvent::Moved(_) => true,
                 _ => false,
             },
+            GlutinEvent::NewEvents(StartCause::ResumeTimeReached { .. }) => false,
             GlutinEvent::Suspended { .. }
             | GlutinEvent::NewEvents { .. }
             | GlutinEvent::MainEventsCleared
@@ -653,6 +959,81 @@ impl<N: Notify + OnResize> Processor<N> {
         }
     }
 
+    /// Perform the actions configured for the bell.
+    fn ring_bell<T>(processor: &mut input::Processor<T, ActionContext<N, T>>)
+    where
+        T: EventListener,
+    {
+        let config = &processor.ctx.config.ui_config.bell;
+        if config.urgent {
+            processor.ctx.window.set_urgent(!processor.ctx.terminal.is_focused);
+        }
+
+        // Avoid spawning a process for every bell of a burst
+        if throttled(processor.ctx.last_bell, BELL_COMMAND_INTERVAL) {
+            return;
+        }
+
+        if let Some(command) = &config.command {
+            match start_daemon(command.program(), command.args()) {
+                Ok(_) => debug!("Launched {} with args {:?}", command.program(), command.args()),
+                Err(_) => {
+                    warn!("Unable to launch {} with args {:?}", command.program(), command.args())
+                },
+            }
+        }
+
+        match (&config.sound, SOUND_PLAYER) {
+            (Some(sound), Some(player)) => match start_daemon(player, &[sound]) {
+                Ok(_) => debug!("Playing bell sound {:?} with {}", sound, player),
+                Err(_) => warn!("Unable to play bell sound {:?} with {}", sound, player),
+            },
+            (Some(_), None) => warn!("Bell sounds are not supported on this platform"),
+            (None, _) => (),
+        }
+    }
+
+    /// Show a desktop notification requested by the terminal
+    fn notify<T>(
+        notification: Notification,
+        processor: &mut input::Processor<T, ActionContext<N, T>>,
+    ) where
+        T: EventListener,
+    {
+        let config = &processor.ctx.config.ui_config.notifications;
+        match config.mode {
+            NotificationMode::Enabled => (),
+            NotificationMode::Unfocused if !processor.ctx.terminal.is_focused => (),
+            _ => return,
+        }
+
+        let command = match &config.command {
+            Some(command) => command,
+            None => return,
+        };
+
+        // Applications can send notifications much faster than they could be read
+        if throttled(processor.ctx.last_notification, NOTIFICATION_COMMAND_INTERVAL) {
+            debug!("Dropping notification {:?}", notification);
+            return;
+        }
+
+        // Notifications without title show their body in its place
+        let Notification { mut title, mut body } = notification;
+        if title.is_empty() {
+            mem::swap(&mut title, &mut body);
+        }
+
+        let mut args = command.args().to_vec();
+        args.push(title);
+        args.push(body);
+
+        match start_daemon(command.program(), &args) {
+            Ok(_) => debug!("Launched {} with args {:?}", command.program(), args),
+            Err(_) => warn!("Unable to launch {} with args {:?}", command.program(), args),
+        }
+    }
+
     pub fn reload_config<T>(
         path: &PathBuf,
         processor: &mut input::Processor<T, ActionContext<N, T>>,
@@ -679,7 +1060,18 @@ impl<N: Notify + OnResize> Processor<N> {
             processor.ctx.display_update_pending.cursor = true;
         }
 
-        if processor.ctx.config.font != config.font {
+        let window = &mut processor.ctx.window;
+        let old_subpixel = processor.ctx.config.subpixel(window.background_opacity());
+        window.set_background_opacity(reloaded_opacity(
+            window.background_opacity(),
+            processor.ctx.config.background_opacity(),
+            config.background_opacity(),
+        ));
+        window.set_blur(config.window.blur);
+
+        if processor.ctx.config.font != config.font
+            || old_subpixel != config.subpixel(window.background_opacity())
+        {
             // Do not update font size if it has been changed at runtime
             if *processor.ctx.font_size == processor.ctx.config.font.size {
                 *processor.ctx.font_size = config.font.size;
@@ -687,6 +1079,7 @@ impl<N: Notify + OnResize> Processor<N> {
 
             let font = config.font.clone().with_size(*processor.ctx.font_size);
             processor.ctx.display_update_pending.font = Some(font);
+            processor.ctx.display_update_pending.rasterizer = true;
         }
 
         #[cfg(not(any(target_os = "macos", windows)))]
@@ -698,9 +1091,50 @@ impl<N: Notify + OnResize> Processor<N> {
 
         *processor.ctx.config = config;
 
+        // Reapply the automatic color scheme on top of the new colors
+        processor.ctx.appearance.deadline = Some(Instant::now());
+
         processor.ctx.terminal.dirty = true;
     }
 
+    /// Switch to the color scheme of the current dark or light appearance.
+    fn update_appearance<T>(processor: &mut input::Processor<T, ActionContext<N, T>>)
+    where
+        T: EventListener,
+    {
+        processor.ctx.appearance.deadline = None;
+
+        let appearance = &processor.ctx.config.ui_config.appearance;
+        if !appearance.enabled() {
+            return;
+        }
+
+        let theme = match appearance.schedule {
+            Some(schedule) => {
+                let now = time::now();
+                let minute = now.tm_hour as u32 * 60 + now.tm_min as u32;
+
+                // Check again at the start of the minute of the next switch
+                let minutes = schedule.minutes_until_switch(minute);
+                let secs = u64::from(minutes * 60).saturating_sub(now.tm_sec as u64);
+                processor.ctx.appearance.deadline =
+                    Some(Instant::now() + Duration::from_secs(secs));
+
+                schedule.theme(minute)
+            },
+            None => match processor.ctx.appearance.system_theme.clone() {
+                Some(theme) => theme,
+                None => return,
+            },
+        };
+
+        let scheme = appearance.color_scheme(theme).map(String::from);
+        if scheme != processor.ctx.config.ui_config.color_scheme {
+            processor.ctx.set_color_scheme(scheme);
+            processor.ctx.terminal.dirty = true;
+        }
+    }
+
     // Write the ref test results to the disk
     pub fn write_ref_test_results<T>(&self, terminal: &Term<T>) {
         if !self.config.debug.ref_test {
@@ -746,3 +1180,27 @@ impl EventListener for EventProxy {
         let _ = self.0.send_event(event);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reload_keeps_runtime_opacity() {
+        assert!((reloaded_opacity(1., 1., 0.5) - 0.5).abs() < std::f32::EPSILON);
+        assert!((reloaded_opacity(0.8, 1., 0.5) - 0.8).abs() < std::f32::EPSILON);
+    }
+
+    #[test]
+    fn throttle_within_interval() {
+        let mut last = None;
+        assert!(!throttled(&mut last, Duration::from_secs(60)));
+        assert!(last.is_some());
+        assert!(throttled(&mut last, Duration::from_secs(60)));
+
+        // Actions run again once the interval has passed
+        let previous = last;
+        assert!(!throttled(&mut last, Duration::from_secs(0)));
+        assert!(last >= previous);
+    }
+}
@@ -0,0 +1,136 @@
+//! Window geometry restored on the next startup.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+
+use alacritty_terminal::config::{Delta, Dimensions};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::SizeInfo;
+
+use crate::config::Config;
+use crate::window::Window;
+
+/// Name of the state file storing the geometry.
+const GEOMETRY_FILE: &str = "geometry.json";
+
+/// Size and position of the window when it was last closed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Geometry {
+    columns: usize,
+    lines: usize,
+
+    /// Position relative to the monitor
+    position: Option<(i32, i32)>,
+    monitor: Option<String>,
+}
+
+impl Geometry {
+    /// Geometry of the window, unless it is fullscreen or maximized.
+    ///
+    /// The size of a fullscreen or maximized window would open an oversized window.
+    pub fn new(window: &Window, size_info: &SizeInfo) -> Option<Self> {
+        if !window.is_windowed() {
+            return None;
+        }
+
+        let monitor = window.current_monitor();
+        let position = window.outer_position().map(|position| {
+            let origin = monitor.position();
+            (position.x - origin.x, position.y - origin.y)
+        });
+
+        Some(Geometry {
+            columns: size_info.cols().0,
+            lines: size_info.lines().0,
+            position,
+            monitor: monitor.name(),
+        })
+    }
+
+    /// Load the geometry stored by the last instance.
+    pub fn load() -> Option<Self> {
+        Self::load_from(&state_path()?)
+    }
+
+    fn load_from(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(geometry) => Some(geometry),
+            Err(err) => {
+                warn!("Unable to read window geometry from {:?}: {}", path, err);
+                None
+            },
+        }
+    }
+
+    /// Store the geometry for the next instance.
+    pub fn save(&self) {
+        match state_path() {
+            Some(path) => self.save_to(&path),
+            None => warn!("Unable to find a directory for the window geometry"),
+        }
+    }
+
+    fn save_to(&self, path: &Path) {
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(path, serde_json::to_string(self)?));
+        match result {
+            Ok(()) => debug!("Stored window geometry in {:?}", path),
+            Err(err) => warn!("Unable to store window geometry in {:?}: {}", path, err),
+        }
+    }
+
+    /// Use the geometry as the initial window size and position.
+    pub fn apply(self, config: &mut Config) {
+        config.window.dimensions = Dimensions::new(Column(self.columns), Line(self.lines));
+        config.window.position = self.position.map(|(x, y)| Delta { x, y });
+        config.window.monitor = self.monitor;
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn state_path() -> Option<PathBuf> {
+    let dirs = xdg::BaseDirectories::with_prefix("alacritty").ok()?;
+    Some(dirs.get_data_home().join(GEOMETRY_FILE))
+}
+
+#[cfg(any(target_os = "macos", windows))]
+fn state_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("alacritty").join(GEOMETRY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn restore_saved_geometry() {
+        let geometry = Geometry {
+            columns: 120,
+            lines: 40,
+            position: Some((-10, 20)),
+            monitor: Some(String::from("DP-1")),
+        };
+
+        let dir = env::temp_dir().join(format!("alacritty-geometry-{}", process::id()));
+        let path = dir.join(GEOMETRY_FILE);
+        geometry.save_to(&path);
+        let loaded = Geometry::load_from(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.as_ref(), Some(&geometry));
+
+        let mut config = Config::default();
+        loaded.unwrap().apply(&mut config);
+        assert_eq!(config.window.dimensions, Dimensions::new(Column(120), Line(40)));
+        assert_eq!(config.window.position, Some(Delta { x: -10, y: 20 }));
+        assert_eq!(config.window.monitor.as_deref(), Some("DP-1"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Keyboard-driven selection of visible text matching the regex of a hint.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use alacritty_terminal::index::{Column, Line, Point};
+use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::Term;
+
+use crate::config::Hint;
+
+/// Visible text matching a hint's regex.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HintMatch {
+    /// Matched text.
+    pub text: String,
+
+    /// First cell of the match in the viewport.
+    pub start: Point,
+
+    /// Last cell of the match in the viewport.
+    pub end: Point,
+}
+
+/// File location of compiler output, like `src/main.rs:10:5`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileLocation {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl FileLocation {
+    /// Parse a `path:line` or `path:line:column` location.
+    ///
+    /// Relative paths are resolved against the working directory, the column defaults to 1.
+    /// Without a working directory they start with `./`, so they are never taken for an option.
+    pub fn parse(text: &str, working_directory: Option<&Path>) -> Option<Self> {
+        let mut parts = text.splitn(3, ':');
+        let path = parts.next().filter(|path| !path.is_empty())?;
+        let line = parts.next()?.parse().ok()?;
+        let column = parts.next().map_or(Ok(1), str::parse).ok()?;
+
+        let path = match (path.starts_with("~/"), env::var_os("HOME")) {
+            (true, Some(home)) => Path::new(&home).join(&path[2..]),
+            _ => working_directory.unwrap_or_else(|| Path::new(".")).join(path),
+        };
+
+        Some(Self { path, line, column })
+    }
+
+    /// Substitute `{file}`, `{line}` and `{column}` in the arguments of the editor.
+    ///
+    /// The file is appended to the arguments if none of them contain it.
+    pub fn editor_args(&self, args: &[String]) -> Vec<String> {
+        let file = self.path.to_string_lossy();
+        let append_file = !args.iter().any(|arg| arg.contains("{file}"));
+
+        let mut args: Vec<String> = args
+            .iter()
+            .map(|arg| {
+                arg.replace("{file}", &file)
+                    .replace("{line}", &self.line.to_string())
+                    .replace("{column}", &self.column.to_string())
+            })
+            .collect();
+
+        if append_file {
+            args.push(file.into_owned());
+        }
+
+        args
+    }
+}
+
+/// Hint which is currently waiting for one of its labels to be typed.
+#[derive(Default)]
+pub struct HintState {
+    hint: Option<Hint>,
+
+    /// Visible matches of the hint.
+    matches: Vec<HintMatch>,
+
+    /// Label of each match.
+    labels: Vec<Vec<char>>,
+
+    /// Label characters typed so far.
+    keys: Vec<char>,
+}
+
+impl HintState {
+    /// Check if a hint is waiting for its label.
+    #[inline]
+    pub fn active(&self) -> bool {
+        self.hint.is_some()
+    }
+
+    /// Label all visible matches of a hint.
+    ///
+    /// The hint is not started if nothing on the screen matches.
+    pub fn start<T>(&mut self, term: &Term<T>, hint: Hint, alphabet: &str) {
+        self.matches = visible_matches(term, hint.regex.regex(), hint.anchored);
+        self.labels = labels(alphabet, self.matches.len());
+        self.keys.clear();
+        self.hint = Some(hint).filter(|_| !self.matches.is_empty());
+    }
+
+    /// Stop waiting for a label.
+    pub fn stop(&mut self) {
+        self.hint = None;
+        self.matches.clear();
+        self.labels.clear();
+        self.keys.clear();
+    }
+
+    /// Handle a typed character.
+    ///
+    /// Returns the hint and its selected match once a complete label has been typed.
+    pub fn keyboard_input(&mut self, c: char) -> Option<(Hint, HintMatch)> {
+        match c {
+            '\x1b' => self.stop(),
+            '\x08' | '\x7f' => {
+                self.keys.pop();
+            },
+            c => {
+                self.keys.push(c);
+
+                // Ignore characters which are not part of any remaining label
+                if !self.labels.iter().any(|label| label.starts_with(&self.keys)) {
+                    self.keys.pop();
+                    return None;
+                }
+
+                let index = self.labels.iter().position(|label| label == &self.keys)?;
+                let hint = self.hint.take()?;
+                let hint_match = self.matches.swap_remove(index);
+                self.stop();
+
+                return Some((hint, hint_match));
+            },
+        }
+
+        None
+    }
+
+    /// Untyped remainder of every label that is still possible, at the start of its match.
+    pub fn labels(&self) -> impl Iterator<Item = (Point, String)> + '_ {
+        self.matches
+            .iter()
+            .zip(&self.labels)
+            .filter(move |(_, label)| label.starts_with(&self.keys))
+            .map(move |(hint_match, label)| {
+                (hint_match.start, label[self.keys.len()..].iter().collect())
+            })
+    }
+}
+
+/// Find all matches of the regex in the viewport.
+///
+/// Wrapped lines are searched as a whole, so matches can span multiple rows. With `anchored`, only
+/// matches which are delimited like the words of semantic selection are kept, so they have to be
+/// surrounded by semantic escape characters or the start and end of the line.
+fn visible_matches<T>(term: &Term<T>, regex: &Regex, anchored: bool) -> Vec<HintMatch> {
+    let escape_chars = term.semantic_escape_chars();
+    let grid = term.grid();
+    let num_lines = grid.num_lines().0;
+    let num_cols = grid.num_cols();
+    let display_offset = grid.display_offset();
+
+    let mut matches = Vec::new();
+
+    // Text of the current line, with the position of the cell each character starts in
+    let mut text = String::new();
+    let mut offsets: Vec<(usize, Point)> = Vec::new();
+
+    for line in 0..num_lines {
+        let row = &grid[display_offset + num_lines - 1 - line];
+
+        for col in 0..num_cols.0 {
+            let cell = &row[Column(col)];
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            offsets.push((text.len(), Point::new(Line(line), Column(col))));
+            text.push(cell.c);
+            text.extend(cell.zerowidth().into_iter().flatten());
+        }
+
+        if line + 1 < num_lines && row[num_cols - 1].flags.contains(Flags::WRAPLINE) {
+            continue;
+        }
+
+        // Map byte offsets back to the cell containing them
+        let point_at = |offset: usize| match offsets.binary_search_by_key(&offset, |&(o, _)| o) {
+            Ok(index) => offsets[index].1,
+            Err(index) => offsets[index - 1].1,
+        };
+
+        let is_delimiter = |c: Option<char>| c.map_or(true, |c| escape_chars.contains(c));
+        for regex_match in regex.find_iter(&text).filter(|m| m.start() != m.end()) {
+            if anchored
+                && (!is_delimiter(text[..regex_match.start()].chars().next_back())
+                    || !is_delimiter(text[regex_match.end()..].chars().next()))
+            {
+                continue;
+            }
+
+            matches.push(HintMatch {
+                text: regex_match.as_str().to_owned(),
+                start: point_at(regex_match.start()),
+                end: point_at(regex_match.end() - 1),
+            });
+        }
+
+        text.clear();
+        offsets.clear();
+    }
+
+    matches
+}
+
+/// Generate unique labels of equal length for `count` matches.
+fn labels(alphabet: &str, count: usize) -> Vec<Vec<char>> {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+
+    let mut len = 1;
+    while alphabet.len().pow(len) < count {
+        len += 1;
+    }
+
+    (0..count)
+        .map(|mut index| {
+            let mut label = vec![alphabet[0]; len as usize];
+            for c in label.iter_mut().rev() {
+                *c = alphabet[index % alphabet.len()];
+                index /= alphabet.len();
+            }
+            label
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::ansi::Handler;
+    use alacritty_terminal::clipboard::Clipboard;
+    use alacritty_terminal::event::{Event, EventListener};
+    use alacritty_terminal::term::SizeInfo;
+
+    use crate::config::Config;
+
+    struct Mock;
+    impl EventListener for Mock {
+        fn send_event(&self, _event: Event) {}
+    }
+
+    fn term(text: &str) -> Term<Mock> {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&Config::default(), &size, Clipboard::new_nop(), Mock);
+        for c in text.chars() {
+            term.input(c);
+        }
+        term
+    }
+
+    #[test]
+    fn wrapped_match() {
+        // The first match wraps into the next row of the 7 column terminal
+        let term = term("ab 123456 78");
+        let matches = visible_matches(&term, &Regex::new("[0-9]+").unwrap(), false);
+
+        assert_eq!(matches, vec![
+            HintMatch {
+                text: String::from("123456"),
+                start: Point::new(Line(0), Column(3)),
+                end: Point::new(Line(1), Column(1)),
+            },
+            HintMatch {
+                text: String::from("78"),
+                start: Point::new(Line(1), Column(3)),
+                end: Point::new(Line(1), Column(4)),
+            },
+        ]);
+    }
+
+    #[test]
+    fn anchored_match() {
+        let term = term("ab 12 c3 (45)");
+        let regex = Regex::new("[0-9]+").unwrap();
+        assert_eq!(visible_matches(&term, &regex, false).len(), 3);
+
+        // Matches within a word are skipped
+        assert_eq!(visible_matches(&term, &regex, true), vec![
+            HintMatch {
+                text: String::from("12"),
+                start: Point::new(Line(0), Column(3)),
+                end: Point::new(Line(0), Column(4)),
+            },
+            HintMatch {
+                text: String::from("45"),
+                start: Point::new(Line(1), Column(3)),
+                end: Point::new(Line(1), Column(4)),
+            },
+        ]);
+    }
+
+    #[test]
+    fn url_ending_in_parenthesis() {
+        let url = "https://en.wikipedia.org/wiki/Rust_(programming_language)";
+        let term = term(url);
+        let hint = &Config::default().ui_config.hints.enabled[0];
+
+        let matches = visible_matches(&term, hint.regex.regex(), hint.anchored);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].text, url);
+    }
+
+    #[test]
+    fn select_by_label() {
+        let term = term("a b c");
+        let mut hint_state = HintState::default();
+        let mut hint = Config::default().ui_config.hints.enabled[0].clone();
+        hint.regex = serde_yaml::from_str("'[a-z]'").unwrap();
+
+        hint_state.start(&term, hint, "xy");
+        assert!(hint_state.active());
+        assert_eq!(hint_state.labels().map(|(_, label)| label).collect::<Vec<_>>(), vec![
+            "xx", "xy", "yx"
+        ]);
+
+        // Invalid characters are ignored, backspace removes the last character
+        assert!(hint_state.keyboard_input('y').is_none());
+        assert!(hint_state.keyboard_input('z').is_none());
+        assert!(hint_state.keyboard_input('\x7f').is_none());
+        assert!(hint_state.keyboard_input('x').is_none());
+        assert_eq!(hint_state.labels().count(), 2);
+
+        let (_, hint_match) = hint_state.keyboard_input('y').unwrap();
+        assert_eq!(hint_match.text, "b");
+        assert!(!hint_state.active());
+    }
+
+    #[test]
+    fn file_location() {
+        let cwd = Path::new("/home/user/project");
+
+        let location = FileLocation::parse("src/main.rs:10:5", Some(cwd)).unwrap();
+        assert_eq!(location, FileLocation {
+            path: PathBuf::from("/home/user/project/src/main.rs"),
+            line: 10,
+            column: 5,
+        });
+
+        let location = FileLocation::parse("/tmp/lib.c:3", Some(cwd)).unwrap();
+        assert_eq!(location.path, PathBuf::from("/tmp/lib.c"));
+        assert_eq!(location.column, 1);
+
+        let option = FileLocation::parse("-rf.c:1", None).unwrap();
+        assert_eq!(option.path, PathBuf::from("./-rf.c"));
+
+        assert_eq!(FileLocation::parse("lib.c", None), None);
+        assert_eq!(FileLocation::parse("lib.c:x", None), None);
+
+        let args = vec![String::from("--goto"), String::from("{file}:{line}:{column}")];
+        assert_eq!(location.editor_args(&args), vec!["--goto", "/tmp/lib.c:3:1"]);
+        assert_eq!(location.editor_args(&[String::from("+{line}")]), vec!["+3", "/tmp/lib.c"]);
+    }
+
+    #[test]
+    fn label_generation() {
+        assert_eq!(labels("ab", 2), vec![vec!['a'], vec!['b']]);
+        assert_eq!(labels("ab", 3), vec![vec!['a', 'a'], vec!['a', 'b'], vec!['b', 'a']]);
+        assert!(labels("ab", 0).is_empty());
+    }
+}
@@ -20,39 +20,64 @@
 //! determine what to do when a non-modifier key is pressed.
 use std::borrow::Cow;
 use std::cmp::{min, Ordering};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
 use std::marker::PhantomData;
-use std::time::Instant;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::{env, mem};
 
 use log::{debug, trace, warn};
 
 use glutin::event::{
-    ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, TouchPhase,
+    ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, ScanCode,
+    TouchPhase, VirtualKeyCode,
 };
 use glutin::event_loop::EventLoopWindowTarget;
 #[cfg(target_os = "macos")]
 use glutin::platform::macos::EventLoopWindowTargetExtMacOS;
 use glutin::window::CursorIcon;
 
-use alacritty_terminal::ansi::{ClearMode, Handler};
 use alacritty_terminal::clipboard::ClipboardType;
 use alacritty_terminal::event::{Event, EventListener};
 use alacritty_terminal::grid::Scroll;
 use alacritty_terminal::index::{Column, Line, Point, Side};
 use alacritty_terminal::message_bar::{self, Message};
-use alacritty_terminal::selection::SelectionType;
+use alacritty_terminal::selection::{Selection, SelectionType};
+use alacritty_terminal::term::color;
 use alacritty_terminal::term::mode::TermMode;
-use alacritty_terminal::term::{SizeInfo, Term};
-use alacritty_terminal::util::start_daemon;
-use alacritty_terminal::vi_mode::ViMotion;
-
-use crate::config::{Action, Binding, Config, Key, ViAction};
-use crate::event::{ClickState, Mouse};
+use alacritty_terminal::term::{SizeInfo, Term, CLIPBOARD_READ_TARGET};
+use alacritty_terminal::util::{start_daemon, start_daemon_in};
+use alacritty_terminal::vi_mode::{InlineSearch, ViMotion};
+
+use crate::chord::{self, ChordState};
+use crate::compose::ComposeState;
+use crate::config::{
+    Action, Binding, Config, Hint, HintAction, Key, KeyBinding, MouseEvent, ViAction,
+};
+use crate::event::{ClickState, InlineSearchState, Mouse};
+use crate::hint::{FileLocation, HintMatch, HintState};
+use crate::keyboard::{self, KeyEventType};
 use crate::url::{Url, Urls};
 use crate::window::Window;
 
 /// Font size change interval
 pub const FONT_SIZE_STEP: f32 = 0.5;
 
+/// Background opacity change interval
+pub const OPACITY_STEP: f32 = 0.1;
+
+/// Message target of the paste confirmation prompt.
+const PASTE_CONFIRMATION_TARGET: &str = "paste_confirmation";
+
+/// Size in bytes above which pasting without bracketed paste asks for confirmation.
+const LARGE_PASTE_SIZE: usize = 4096;
+
 /// Processes input from glutin.
 ///
 /// An escape sequence may be emitted in case specific keys or key combinations
@@ -77,6 +102,12 @@ pub trait ActionContext<T: EventListener> {
     fn mouse_coords(&self) -> Option<Point>;
     fn received_count(&mut self) -> &mut usize;
     fn suppress_chars(&mut self) -> &mut bool;
+    fn compose_state(&mut self) -> &mut ComposeState;
+    fn pending_paste(&mut self) -> &mut Option<String>;
+    fn inline_search(&mut self) -> &mut InlineSearchState;
+    fn hint_state(&mut self) -> &mut HintState;
+    fn chord_state(&mut self) -> &mut ChordState;
+    fn pressed_keys(&mut self) -> &mut HashSet<ScanCode>;
     fn modifiers(&mut self) -> &mut ModifiersState;
     fn scroll(&mut self, scroll: Scroll);
     fn window(&self) -> &Window;
@@ -84,14 +115,21 @@ pub trait ActionContext<T: EventListener> {
     fn terminal(&self) -> &Term<T>;
     fn terminal_mut(&mut self) -> &mut Term<T>;
     fn spawn_new_instance(&mut self);
+    fn working_directory(&self) -> Option<PathBuf>;
     fn change_font_size(&mut self, delta: f32);
     fn reset_font_size(&mut self);
+    fn change_opacity(&mut self, delta: f32);
+    fn reset_opacity(&mut self);
+    fn toggle_debug_overlay(&mut self);
+    fn set_color_scheme(&mut self, name: Option<String>);
+    fn push_message(&mut self, message: Message);
     fn pop_message(&mut self);
     fn message(&self) -> Option<&Message>;
     fn config(&self) -> &Config;
     fn event_loop(&self) -> &EventLoopWindowTarget<Event>;
     fn urls(&self) -> &Urls;
     fn launch_url(&self, url: Url);
+    fn hovered_url(&self) -> Option<String>;
     fn mouse_mode(&self) -> bool;
 }
 
@@ -143,10 +181,16 @@ impl<T: EventListener> Execute<T> for Action {
                 let text = ctx.terminal_mut().clipboard().load(ClipboardType::Selection);
                 paste(ctx, &text);
             },
+            Action::PasteBlock => {
+                let text = ctx.terminal_mut().clipboard().load(ClipboardType::Clipboard);
+                let col = ctx.terminal().cursor().point.col;
+                paste(ctx, &align_block(&text, col));
+            },
             Action::Command(ref program, ref args) => {
+                let args = command_args(ctx, args);
                 trace!("Running command {} with args {:?}", program, args);
 
-                match start_daemon(program, args) {
+                match start_daemon(program, &args) {
                     Ok(_) => debug!("Spawned new proc"),
                     Err(err) => warn!("Couldn't run command {}", err),
                 }
@@ -165,13 +209,39 @@ impl<T: EventListener> Execute<T> for Action {
             Action::ViAction(ViAction::ToggleSemanticSelection) => {
                 Self::toggle_selection(ctx, SelectionType::Semantic)
             },
+            Action::ViAction(ViAction::SwapSelectionEnds) => {
+                ctx.terminal_mut().vi_swap_selection_ends()
+            },
             Action::ViAction(ViAction::Open) => {
                 ctx.mouse_mut().block_url_launcher = false;
                 if let Some(url) = ctx.urls().find_at(ctx.terminal().vi_mode_cursor.point) {
                     ctx.launch_url(url);
                 }
             },
+            Action::ViAction(action @ ViAction::InlineSearchForward)
+            | Action::ViAction(action @ ViAction::InlineSearchBackward)
+            | Action::ViAction(action @ ViAction::InlineSearchForwardShort)
+            | Action::ViAction(action @ ViAction::InlineSearchBackwardShort) => {
+                ctx.inline_search().pending = Some(action);
+            },
+            Action::ViAction(ViAction::InlineSearchNext) => {
+                if let Some(search) = ctx.inline_search().last {
+                    ctx.terminal_mut().vi_inline_search(search);
+                }
+            },
+            Action::ViAction(ViAction::InlineSearchPrevious) => {
+                if let Some(search) = ctx.inline_search().last {
+                    ctx.terminal_mut().vi_inline_search(search.reversed());
+                }
+            },
             Action::ViMotion(motion) => ctx.terminal_mut().vi_motion(motion),
+            Action::Hint(ref hint) => {
+                let alphabet = ctx.config().ui_config.hints.alphabet().to_owned();
+                let mut hint_state = mem::take(ctx.hint_state());
+                hint_state.start(ctx.terminal(), hint.clone(), &alphabet);
+                *ctx.hint_state() = hint_state;
+                ctx.terminal_mut().dirty = true;
+            },
             Action::ToggleFullscreen => ctx.window_mut().toggle_fullscreen(),
             #[cfg(target_os = "macos")]
             Action::ToggleSimpleFullscreen => ctx.window_mut().toggle_simple_fullscreen(),
@@ -184,6 +254,10 @@ impl<T: EventListener> Execute<T> for Action {
             Action::IncreaseFontSize => ctx.change_font_size(FONT_SIZE_STEP),
             Action::DecreaseFontSize => ctx.change_font_size(FONT_SIZE_STEP * -1.),
             Action::ResetFontSize => ctx.reset_font_size(),
+            Action::IncreaseOpacity => ctx.change_opacity(OPACITY_STEP),
+            Action::DecreaseOpacity => ctx.change_opacity(OPACITY_STEP * -1.),
+            Action::ResetOpacity => ctx.reset_opacity(),
+            Action::ToggleDebugOverlay => ctx.toggle_debug_overlay(),
             Action::ScrollPageUp => {
                 // Move vi mode cursor
                 let term = ctx.terminal_mut();
@@ -252,7 +326,43 @@ impl<T: EventListener> Execute<T> for Action {
                 term.vi_mode_cursor.point.line = term.grid().num_lines() - 1;
                 term.vi_motion(ViMotion::FirstOccupied);
             },
-            Action::ClearHistory => ctx.terminal_mut().clear_screen(ClearMode::Saved),
+            Action::ScrollToPreviousPrompt => {
+                if let Some(line) = ctx.terminal().previous_prompt() {
+                    scroll_to_line(ctx, line);
+                }
+            },
+            Action::ScrollToNextPrompt => match ctx.terminal().next_prompt() {
+                Some(line) => scroll_to_line(ctx, line),
+                None => ctx.scroll(Scroll::Bottom),
+            },
+            Action::SelectLastCommandOutput => {
+                if let Some(output) = ctx.terminal().last_command_output() {
+                    select_range(ctx, output);
+                }
+            },
+            Action::CopyLastCommandOutput => {
+                let term = ctx.terminal_mut();
+                if let Some((start, end)) = term.last_command_output() {
+                    let output = term.bounds_to_string(start, end);
+                    term.clipboard().store(ClipboardType::Clipboard, output);
+                }
+            },
+            Action::ClearHistory => ctx.terminal_mut().clear_history(),
+            Action::ClearScreen => ctx.terminal_mut().clear_to_prompt(),
+            Action::ExportScrollback => export_scrollback(ctx, false),
+            Action::ExportScrollbackHtml => export_scrollback(ctx, true),
+            Action::ToggleLineTimestamps => {
+                let term = ctx.terminal_mut();
+                term.show_line_timestamps = !term.show_line_timestamps;
+                term.dirty = true;
+            },
+            Action::ColorScheme(ref name) => ctx.set_color_scheme(Some(name.clone())),
+            Action::NextColorScheme => {
+                let ui_config = &ctx.config().ui_config;
+                let next = ui_config.color_schemes.next(ui_config.color_scheme.as_deref());
+                let next = next.map(str::to_owned);
+                ctx.set_color_scheme(next);
+            },
             Action::ClearLogNotice => ctx.pop_message(),
             Action::SpawnNewInstance => ctx.spawn_new_instance(),
             Action::ReceiveChar | Action::None => (),
@@ -260,20 +370,215 @@ impl<T: EventListener> Execute<T> for Action {
     }
 }
 
+/// Write history and visible area to a file in the temporary directory.
+fn export_scrollback<T: EventListener, A: ActionContext<T>>(ctx: &mut A, html: bool) {
+    let timestamps = ctx.terminal().show_line_timestamps;
+    let (contents, extension) = if html {
+        (ctx.terminal().history_to_html(timestamps), "html")
+    } else {
+        (ctx.terminal().history_to_string(timestamps), "txt")
+    };
+
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let prefix = format!("alacritty-scrollback-{}", timestamp);
+
+    let result = create_private_file(&env::temp_dir(), &prefix, extension)
+        .and_then(|(path, mut file)| file.write_all(contents.as_bytes()).map(|_| path));
+    match result {
+        Ok(path) => {
+            let text = format!("Scrollback exported to {}", path.display());
+            ctx.push_message(Message::new(text, color::YELLOW));
+        },
+        Err(err) => warn!("Unable to export scrollback: {}", err),
+    }
+}
+
+/// Create a new file which is only accessible by the current user.
+///
+/// The name ends in a random suffix and existing files are never opened, so other users can
+/// neither predict the path nor redirect the contents through a symlink they placed there.
+fn create_private_file(dir: &Path, prefix: &str, extension: &str) -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+
+    let mut attempts = 0;
+    loop {
+        let suffix = RandomState::new().build_hasher().finish();
+        let path = dir.join(format!("{}-{:016x}.{}", prefix, suffix, extension));
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempts < 8 => attempts += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Scroll the viewport so the buffer line is at its top.
+fn scroll_to_line<T: EventListener, A: ActionContext<T>>(ctx: &mut A, line: usize) {
+    let term = ctx.terminal_mut();
+    let top = term.grid().display_offset() + term.grid().num_lines().0 - 1;
+    let scroll_lines = line as isize - top as isize;
+    term.vi_mode_cursor = term.vi_mode_cursor.scroll(term, scroll_lines);
+
+    ctx.scroll(Scroll::Lines(scroll_lines));
+}
+
+/// Select the text between two buffer points.
+fn select_range<T: EventListener, A: ActionContext<T>>(
+    ctx: &mut A,
+    (start, end): (Point<usize>, Point<usize>),
+) {
+    let mut selection = Selection::new(SelectionType::Simple, start, Side::Left);
+    selection.update(end, Side::Right);
+
+    let term = ctx.terminal_mut();
+    *term.selection_mut() = Some(selection);
+    term.dirty = true;
+}
+
+/// Arguments of a command with its placeholders replaced by the terminal state.
+///
+/// Values are only computed for placeholders which are actually used.
+fn command_args<T: EventListener, A: ActionContext<T>>(ctx: &A, args: &[String]) -> Vec<String> {
+    let mut selection = None;
+    let mut url = None;
+    let mut cwd = None;
+
+    let mut placeholder = |name: &str| -> Option<String> {
+        let value = match name {
+            "selection" => selection
+                .get_or_insert_with(|| ctx.terminal().selection_to_string().unwrap_or_default()),
+            "url" => url.get_or_insert_with(|| ctx.hovered_url().unwrap_or_default()),
+            "cwd" => cwd.get_or_insert_with(|| {
+                let cwd = ctx.working_directory();
+                cwd.map(|dir| dir.to_string_lossy().into_owned()).unwrap_or_default()
+            }),
+            "window_id" => {
+                // Matches `$WINDOWID`, which is only available on X11
+                #[cfg(not(any(target_os = "macos", windows)))]
+                let window_id = ctx.window().x11_window_id();
+                #[cfg(any(target_os = "macos", windows))]
+                let window_id: Option<usize> = None;
+
+                return Some(window_id.map(|id| id.to_string()).unwrap_or_default());
+            },
+            _ => return None,
+        };
+
+        Some(value.clone())
+    };
+
+    args.iter().map(|arg| replace_placeholders(arg, &mut placeholder)).collect()
+}
+
+/// Replace all placeholders in a single pass, so values are never substituted again.
+///
+/// The `value` of a placeholder is requested by its name without braces, unknown placeholders
+/// are kept as they are. A literal `{` can be escaped as `{{`.
+fn replace_placeholders<F>(text: &str, mut value: F) -> String
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut replaced = String::with_capacity(text.len());
+
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        replaced.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        if rest.starts_with('{') {
+            replaced.push('{');
+            rest = &rest[1..];
+            continue;
+        }
+
+        let replacement = rest.find('}').and_then(|end| Some((end, value(&rest[..end])?)));
+        match replacement {
+            Some((end, replacement)) => {
+                replaced.push_str(&replacement);
+                rest = &rest[end + 1..];
+            },
+            None => replaced.push('{'),
+        }
+    }
+    replaced.push_str(rest);
+
+    replaced
+}
+
 fn paste<T: EventListener, A: ActionContext<T>>(ctx: &mut A, contents: &str) {
+    let config = &ctx.config().ui_config.paste;
+    let mut contents = if config.filter_control_characters {
+        filter_control_characters(contents)
+    } else {
+        contents.to_owned()
+    };
+
     if ctx.terminal().mode().contains(TermMode::BRACKETED_PASTE) {
+        // Escapes are always removed, so the pasted text cannot end the bracketed paste
         ctx.write_to_pty(&b"\x1b[200~"[..]);
         ctx.write_to_pty(contents.replace("\x1b", "").into_bytes());
         ctx.write_to_pty(&b"\x1b[201~"[..]);
-    } else {
-        // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
-        // pasted data from keystrokes.
-        // In theory, we should construct the keystrokes needed to produce the data we are
-        // pasting... since that's neither practical nor sensible (and probably an impossible
-        // task to solve in a general way), we'll just replace line breaks (windows and unix
-        // style) with a single carriage return (\r, which is what the Enter key produces).
-        ctx.write_to_pty(contents.replace("\r\n", "\r").replace("\n", "\r").into_bytes());
+        return;
+    }
+
+    // In non-bracketed (ie: normal) mode, terminal applications cannot distinguish
+    // pasted data from keystrokes.
+    // In theory, we should construct the keystrokes needed to produce the data we are
+    // pasting... since that's neither practical nor sensible (and probably an impossible
+    // task to solve in a general way), we'll just replace line breaks (windows and unix
+    // style) with a single carriage return (\r, which is what the Enter key produces).
+    if config.convert_newlines {
+        contents = contents.replace("\r\n", "\r").replace("\n", "\r");
+    }
+
+    // Every line break is executed right away by a shell, so make sure it was intended
+    if config.confirm
+        && (contents.contains(|c| c == '\r' || c == '\n') || contents.len() > LARGE_PASTE_SIZE)
+    {
+        let text = String::from(
+            "The application did not enable bracketed paste and the text contains line breaks or \
+             is very large. Press Y to paste or N to cancel.",
+        );
+        let mut message = Message::new(text, color::YELLOW);
+        message.set_target(PASTE_CONFIRMATION_TARGET.to_owned());
+
+        *ctx.pending_paste() = Some(contents);
+        ctx.push_message(message);
+        return;
+    }
+
+    ctx.write_to_pty(contents.into_bytes());
+}
+
+/// Remove control characters which are not part of regular text.
+///
+/// Tabs and line breaks are kept, all other C0 and C1 controls and DEL could be used to
+/// execute or hide commands when pasted.
+fn filter_control_characters(text: &str) -> String {
+    text.chars().filter(|&c| c == '\t' || c == '\n' || c == '\r' || !c.is_control()).collect()
+}
+
+/// Indent all lines after the first to `col`, so a pasted block stays aligned with the cursor.
+///
+/// Empty lines are not indented.
+fn align_block(text: &str, col: Column) -> String {
+    let indent = " ".repeat(col.0);
+
+    let mut aligned = String::with_capacity(text.len());
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            aligned.push('\n');
+            if !line.trim_end_matches('\r').is_empty() {
+                aligned.push_str(&indent);
+            }
+        }
+        aligned.push_str(line);
     }
+
+    aligned
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -304,6 +609,10 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
     pub fn mouse_moved(&mut self, x: usize, y: usize) {
         let size_info = self.ctx.size_info();
 
+        // Pixel reports include every movement, not only changes of the cell
+        let pixel_changed = self.ctx.terminal().mode().contains(TermMode::SGR_PIXELS_MOUSE)
+            && (x != self.ctx.mouse().x || y != self.ctx.mouse().y);
+
         self.ctx.mouse_mut().x = x;
         self.ctx.mouse_mut().y = y;
 
@@ -316,6 +625,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
 
         // If the mouse hasn't changed cells, do nothing
         if !cell_changed
+            && !pixel_changed
             && self.ctx.mouse().cell_side == cell_side
             && self.ctx.mouse().inside_grid == inside_grid
         {
@@ -347,9 +657,12 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                 self.ctx.terminal_mut().vi_mode_cursor.point = point;
             }
 
-            self.ctx.update_selection(Point { line, col: point.col }, cell_side);
+            // Command output selections cannot be extended
+            if self.ctx.mouse().click_state != ClickState::QuadrupleClick {
+                self.ctx.update_selection(Point { line, col: point.col }, cell_side);
+            }
         } else if inside_grid
-            && cell_changed
+            && (cell_changed || pixel_changed)
             && point.line <= last_term_line
             && self.ctx.terminal().mode().intersects(TermMode::MOUSE_MOTION | TermMode::MOUSE_DRAG)
         {
@@ -421,13 +734,22 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
     }
 
     fn sgr_mouse_report(&mut self, button: u8, state: ElementState) {
-        let (line, column) = (self.ctx.mouse().line, self.ctx.mouse().column);
+        let mouse = self.ctx.mouse();
+        let (x, y) = if self.ctx.terminal().mode().contains(TermMode::SGR_PIXELS_MOUSE) {
+            // Pixel positions are relative to the top left corner of the grid
+            let size_info = self.ctx.size_info();
+            let x = mouse.x.saturating_sub(size_info.padding_x as usize);
+            let y = mouse.y.saturating_sub(size_info.padding_y as usize);
+            (x, y)
+        } else {
+            (mouse.column.0, mouse.line.0)
+        };
         let c = match state {
             ElementState::Pressed => 'M',
             ElementState::Released => 'm',
         };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, column + 1, line + 1, c);
+        let msg = format!("\x1b[<{};{};{}{}", button, x + 1, y + 1, c);
         self.ctx.write_to_pty(msg.into_bytes());
     }
 
@@ -446,7 +768,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         }
 
         // Report mouse events
-        if self.ctx.terminal().mode().contains(TermMode::SGR_MOUSE) {
+        if self.ctx.terminal().mode().intersects(TermMode::SGR_MOUSE | TermMode::SGR_PIXELS_MOUSE) {
             self.sgr_mouse_report(button + mods, state);
         } else if let ElementState::Released = state {
             self.normal_mouse_report(3 + mods);
@@ -498,14 +820,23 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                 self.ctx.mouse_mut().block_url_launcher = true;
                 self.ctx.start_selection(SelectionType::Semantic, point, side);
                 ClickState::DoubleClick
-            }
+            },
             ClickState::DoubleClick
                 if elapsed < self.ctx.config().ui_config.mouse.triple_click.threshold =>
             {
                 self.ctx.mouse_mut().block_url_launcher = true;
                 self.ctx.start_selection(SelectionType::Lines, point, side);
                 ClickState::TripleClick
-            }
+            },
+            ClickState::TripleClick
+                if elapsed < self.ctx.config().ui_config.mouse.triple_click.threshold =>
+            {
+                let line = self.ctx.terminal().visible_to_buffer(point).line;
+                if let Some(output) = self.ctx.terminal().command_output_at(line) {
+                    select_range(&mut self.ctx, output);
+                }
+                ClickState::QuadrupleClick
+            },
             _ => {
                 // Don't launch URLs if this click cleared the selection
                 self.ctx.mouse_mut().block_url_launcher = !self.ctx.selection_is_empty();
@@ -552,7 +883,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         match delta {
             MouseScrollDelta::LineDelta(_columns, lines) => {
                 let new_scroll_px = lines * self.ctx.size_info().cell_height;
-                self.scroll_terminal(f64::from(new_scroll_px));
+                self.scroll_terminal(f64::from(new_scroll_px), false);
             },
             MouseScrollDelta::PixelDelta(lpos) => {
                 match phase {
@@ -561,7 +892,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                         self.ctx.mouse_mut().scroll_px = 0.;
                     },
                     TouchPhase::Moved => {
-                        self.scroll_terminal(lpos.y);
+                        self.scroll_terminal(lpos.y, true);
                     },
                     _ => (),
                 }
@@ -569,10 +900,29 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         }
     }
 
-    fn scroll_terminal(&mut self, new_scroll_px: f64) {
+    /// Scroll by a distance in pixels, which is `precise` when it was reported by a touchpad.
+    fn scroll_terminal(&mut self, new_scroll_px: f64, precise: bool) {
         let height = f64::from(self.ctx.size_info().cell_height);
+        let scrolling = self.ctx.config().scrolling;
+        let multiplier = |lines: u8| match scrolling.touchpad_multiplier() {
+            Some(multiplier) if precise => multiplier,
+            _ => f64::from(lines),
+        };
 
-        if self.ctx.mouse_mode() {
+        let event = if new_scroll_px > 0. { MouseEvent::WheelUp } else { MouseEvent::WheelDown };
+        let bindings = self.triggered_mouse_bindings(event);
+
+        if !bindings.is_empty() {
+            // Bindings replace scrolling, once for every line which would have been scrolled
+            self.ctx.mouse_mut().scroll_px += new_scroll_px * multiplier(scrolling.multiplier());
+
+            let lines = (self.ctx.mouse().scroll_px / height).abs() as i32;
+            for _ in 0..lines {
+                for binding in &bindings {
+                    binding.execute(&mut self.ctx);
+                }
+            }
+        } else if self.ctx.mouse_mode() {
             self.ctx.mouse_mut().scroll_px += new_scroll_px;
 
             let code = if new_scroll_px > 0. { 64 } else { 65 };
@@ -586,15 +936,10 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             .terminal()
             .mode()
             .contains(TermMode::ALT_SCREEN | TermMode::ALTERNATE_SCROLL)
+            && scrolling.alternate_multiplier() != 0
             && !self.ctx.modifiers().shift()
         {
-            let multiplier = f64::from(
-                self.ctx
-                    .config()
-                    .scrolling
-                    .faux_multiplier()
-                    .unwrap_or_else(|| self.ctx.config().scrolling.multiplier()),
-            );
+            let multiplier = multiplier(scrolling.alternate_multiplier());
             self.ctx.mouse_mut().scroll_px += new_scroll_px * multiplier;
 
             let cmd = if new_scroll_px > 0. { b'A' } else { b'B' };
@@ -608,8 +953,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
             }
             self.ctx.write_to_pty(content);
         } else {
-            let multiplier = f64::from(self.ctx.config().scrolling.multiplier());
-            self.ctx.mouse_mut().scroll_px += new_scroll_px * multiplier;
+            self.ctx.mouse_mut().scroll_px += new_scroll_px * multiplier(scrolling.multiplier());
 
             let lines = self.ctx.mouse().scroll_px / height;
 
@@ -679,7 +1023,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         } else {
             match state {
                 ElementState::Pressed => {
-                    self.process_mouse_bindings(button);
+                    self.process_mouse_bindings(MouseEvent::Button(button));
                     self.on_mouse_press(button);
                 },
                 ElementState::Released => self.on_mouse_release(button),
@@ -692,9 +1036,89 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         match input.state {
             ElementState::Pressed => {
                 *self.ctx.received_count() = 0;
-                self.process_key_bindings(input);
+
+                let event_type = if self.ctx.pressed_keys().insert(input.scancode) {
+                    KeyEventType::Press
+                } else {
+                    KeyEventType::Repeat
+                };
+
+                // Keys continuing a Compose sequence only produce its text, while keys without
+                // text abandon it and keep their usual behavior
+                let produces_text = keyboard::produces_text(input.virtual_keycode);
+                let composing = self.composing();
+                if composing && !produces_text && !chord::is_modifier(input) {
+                    self.ctx.compose_state().finish();
+                }
+
+                let composing = composing && produces_text;
+                if composing {
+                    self.ctx.compose_state().swallow(input.scancode);
+                }
+
+                let sequence = if composing { None } else { self.key_sequence(input, event_type) };
+
+                // The next character completes a pending inline search or hint instead
+                if composing || self.inline_search_pending() || self.ctx.hint_state().active() {
+                    *self.ctx.suppress_chars() = false;
+                } else {
+                    self.process_key_bindings(input, sequence.is_some());
+                }
+
+                if input.virtual_keycode == Some(VirtualKeyCode::Compose) {
+                    let timeout = self.ctx.config().ui_config.compose_timeout;
+                    self.ctx.compose_state().start(timeout);
+                }
+
+                // Send the key's escape sequence unless a binding has handled it
+                if let Some(sequence) = sequence {
+                    if !*self.ctx.suppress_chars() {
+                        self.ctx.scroll(Scroll::Bottom);
+                        self.ctx.clear_selection();
+                        self.ctx.write_to_pty(sequence);
+                        *self.ctx.suppress_chars() = true;
+                    }
+                }
+            },
+            ElementState::Released => {
+                *self.ctx.suppress_chars() = false;
+                self.ctx.pressed_keys().remove(&input.scancode);
+
+                // Keys swallowed by a Compose sequence are not reported on release either
+                if self.ctx.compose_state().release(input.scancode) {
+                    return;
+                }
+
+                if let Some(sequence) = self.key_sequence(input, KeyEventType::Release) {
+                    self.ctx.write_to_pty(sequence);
+                }
             },
-            ElementState::Released => *self.ctx.suppress_chars() = false,
+        }
+    }
+
+    /// Escape sequence for a key event when an enhanced keyboard mode is enabled.
+    ///
+    /// The kitty keyboard protocol takes precedence over xterm's modifyOtherKeys.
+    fn key_sequence(&mut self, input: KeyboardInput, event_type: KeyEventType) -> Option<Vec<u8>> {
+        let mode = *self.ctx.terminal().mode();
+        if mode.contains(TermMode::VI)
+            || self.clipboard_read_prompt()
+            || self.paste_prompt()
+            || self.ctx.hint_state().active()
+        {
+            return None;
+        }
+
+        let key = input.virtual_keycode?;
+        let mods = *self.ctx.modifiers();
+        if mode.intersects(TermMode::KITTY_KEYBOARD_PROTOCOL) {
+            keyboard::kitty_sequence(mode, key, mods, event_type)
+        } else if mode.intersects(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS)
+            && event_type != KeyEventType::Release
+        {
+            keyboard::modify_other_keys_sequence(mode, key, mods)
+        } else {
+            None
         }
     }
 
@@ -708,8 +1132,40 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         self.ctx.window_mut().set_mouse_cursor(mouse_state.into());
     }
 
+    /// Check if a Compose sequence is waiting for its next key.
+    ///
+    /// Keys held with Control or Super never continue the sequence, so they can still trigger
+    /// their bindings.
+    fn composing(&mut self) -> bool {
+        let mods = *self.ctx.modifiers();
+        !mods.ctrl() && !mods.logo() && self.ctx.compose_state().active()
+    }
+
     /// Process a received character.
     pub fn received_char(&mut self, c: char) {
+        // Characters complete any pending compose sequence
+        self.ctx.compose_state().finish();
+
+        if self.clipboard_read_prompt() {
+            self.answer_clipboard_read(c);
+            return;
+        }
+
+        if self.paste_prompt() {
+            self.answer_paste(c);
+            return;
+        }
+
+        if !*self.ctx.suppress_chars() && self.ctx.hint_state().active() {
+            self.hint_input(c);
+            return;
+        }
+
+        if !*self.ctx.suppress_chars() && self.inline_search_pending() {
+            self.complete_inline_search(c);
+            return;
+        }
+
         if *self.ctx.suppress_chars() || self.ctx.terminal().mode().contains(TermMode::VI) {
             return;
         }
@@ -737,6 +1193,160 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
         *self.ctx.received_count() += 1;
     }
 
+    /// Check if the clipboard read confirmation prompt is shown.
+    fn clipboard_read_prompt(&self) -> bool {
+        let target = self.ctx.message().and_then(|message| message.target());
+        target.map(String::as_str) == Some(CLIPBOARD_READ_TARGET)
+    }
+
+    /// Answer the clipboard read confirmation prompt.
+    fn answer_clipboard_read(&mut self, c: char) {
+        let allow = match c {
+            'y' | 'Y' => true,
+            'n' | 'N' | '\x1b' => false,
+            _ => return,
+        };
+
+        self.ctx.pop_message();
+
+        if let Some(response) = self.ctx.terminal_mut().answer_clipboard_read(allow) {
+            self.ctx.write_to_pty(response.into_bytes());
+        }
+    }
+
+    /// Check if the paste confirmation prompt is shown.
+    fn paste_prompt(&self) -> bool {
+        let target = self.ctx.message().and_then(|message| message.target());
+        target.map(String::as_str) == Some(PASTE_CONFIRMATION_TARGET)
+    }
+
+    /// Answer the paste confirmation prompt.
+    fn answer_paste(&mut self, c: char) {
+        let allow = match c {
+            'y' | 'Y' => true,
+            'n' | 'N' | '\x1b' => false,
+            _ => return,
+        };
+
+        self.ctx.pop_message();
+
+        let contents = self.ctx.pending_paste().take();
+        if let Some(contents) = contents.filter(|_| allow) {
+            self.ctx.scroll(Scroll::Bottom);
+            self.ctx.write_to_pty(contents.into_bytes());
+        }
+    }
+
+    /// Select a hint match through its label.
+    fn hint_input(&mut self, c: char) {
+        self.ctx.terminal_mut().dirty = true;
+
+        if let Some((hint, hint_match)) = self.ctx.hint_state().keyboard_input(c) {
+            self.hint_action(&hint, hint_match);
+        }
+    }
+
+    /// Handle the text selected through a hint.
+    fn hint_action(&mut self, hint: &Hint, hint_match: HintMatch) {
+        if let Some(command) = &hint.command {
+            let mut args = command.args().to_vec();
+            args.push(hint_match.text);
+
+            match start_daemon(command.program(), &args) {
+                Ok(_) => debug!("Launched {} with args {:?}", command.program(), args),
+                Err(_) => warn!("Unable to launch {} with args {:?}", command.program(), args),
+            }
+
+            return;
+        }
+
+        match hint.action {
+            HintAction::Copy => {
+                let clipboard = self.ctx.terminal_mut().clipboard();
+                clipboard.store(ClipboardType::Clipboard, hint_match.text);
+            },
+            HintAction::Paste => paste(&mut self.ctx, &hint_match.text),
+            HintAction::Select => {
+                let start = self.ctx.terminal().visible_to_buffer(hint_match.start);
+                let end = self.ctx.terminal().visible_to_buffer(hint_match.end);
+                select_range(&mut self.ctx, (start, end));
+            },
+            HintAction::MoveViModeCursor => {
+                let term = self.ctx.terminal_mut();
+                if !term.mode().contains(TermMode::VI) {
+                    term.toggle_vi_mode();
+                }
+
+                term.vi_mode_cursor.point = hint_match.start;
+            },
+            HintAction::Edit => self.open_in_editor(&hint_match.text),
+        }
+    }
+
+    /// Open the file of a `path:line:column` location in the editor.
+    ///
+    /// Without an editor, the file is opened with the URL launcher instead.
+    fn open_in_editor(&mut self, text: &str) {
+        let working_directory = self.ctx.terminal().working_directory();
+        let location = match FileLocation::parse(text, working_directory) {
+            Some(location) => location,
+            None => return,
+        };
+
+        let config = &self.ctx.config().ui_config;
+        let (program, args) = match (&config.hints.editor, &config.mouse.url.launcher) {
+            (Some(editor), _) => (editor.program(), location.editor_args(editor.args())),
+            (None, Some(launcher)) => {
+                let mut args = launcher.args().to_vec();
+                args.push(location.path.to_string_lossy().into_owned());
+                (launcher.program(), args)
+            },
+            (None, None) => return,
+        };
+
+        match start_daemon_in(program, &args, working_directory) {
+            Ok(_) => debug!("Launched {} with args {:?}", program, args),
+            Err(_) => warn!("Unable to launch {} with args {:?}", program, args),
+        }
+    }
+
+    /// Check if a vi mode inline search is waiting for its character.
+    fn inline_search_pending(&mut self) -> bool {
+        let vi_mode = self.ctx.terminal().mode().contains(TermMode::VI);
+        let state = self.ctx.inline_search();
+
+        // Drop searches which were interrupted by leaving vi mode
+        if !vi_mode {
+            state.pending = None;
+        }
+
+        state.pending.is_some()
+    }
+
+    /// Jump to the next occurrence of `c` for the pending inline search.
+    fn complete_inline_search(&mut self, c: char) {
+        let action = match self.ctx.inline_search().pending.take() {
+            Some(action) => action,
+            None => return,
+        };
+
+        // Escape aborts the search
+        if c == '\x1b' {
+            return;
+        }
+
+        let (left, stop_short) = match action {
+            ViAction::InlineSearchBackward => (true, false),
+            ViAction::InlineSearchForwardShort => (false, true),
+            ViAction::InlineSearchBackwardShort => (true, true),
+            _ => (false, false),
+        };
+
+        let search = InlineSearch { c, left, stop_short };
+        self.ctx.inline_search().last = Some(search);
+        self.ctx.terminal_mut().vi_inline_search(search);
+    }
+
     /// Reset mouse cursor based on modifier and terminal state.
     #[inline]
     pub fn reset_mouse_cursor(&mut self) {
@@ -748,12 +1358,52 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
-    fn process_key_bindings(&mut self, input: KeyboardInput) {
+    fn process_key_bindings(&mut self, input: KeyboardInput, skip_input: bool) {
+        let mode = self.binding_mode();
         let mods = *self.ctx.modifiers();
-        let mut suppress_chars = None;
+
+        // Keys are sent as escape sequences by the keyboard protocol instead
+        let skipped = |binding: &KeyBinding| {
+            skip_input
+                && match binding.action {
+                    Action::Esc(_) | Action::ReceiveChar => true,
+                    _ => false,
+                }
+        };
+
+        // Modifiers are pressed on their own while typing the next key of a chord
+        let chord = self.ctx.chord_state().keys().to_vec();
+        if !chord.is_empty() && chord::is_modifier(input) {
+            *self.ctx.suppress_chars() = true;
+            return;
+        }
+
+        // Keys starting or continuing a chord take precedence over other bindings
+        let bindings = &self.ctx.config().ui_config.key_bindings;
+        let continues_chord = bindings.iter().any(|binding| {
+            !skipped(binding) && binding.is_chord_continued_by(mode, &chord, input, mods)
+        });
+        if continues_chord {
+            let timeout = self.ctx.config().ui_config.chord_timeout;
+            self.ctx.chord_state().push(input, mods, timeout);
+            self.ctx.terminal_mut().dirty = true;
+            *self.ctx.suppress_chars() = true;
+            return;
+        }
+
+        if self.ctx.chord_state().deadline().is_some() {
+            self.ctx.chord_state().clear();
+            self.ctx.terminal_mut().dirty = true;
+        }
+
+        // Keys which complete no chord are dropped
+        let mut suppress_chars = if chord.is_empty() { None } else { Some(true) };
 
         for i in 0..self.ctx.config().ui_config.key_bindings.len() {
             let binding = &self.ctx.config().ui_config.key_bindings[i];
+            if skipped(binding) || !binding.chord_matches(&chord) {
+                continue;
+            }
 
             let key = match (binding.trigger, input.virtual_keycode) {
                 (Key::Scancode(_), _) => Key::Scancode(input.scancode),
@@ -761,7 +1411,7 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
                 _ => continue,
             };
 
-            if binding.is_triggered_by(*self.ctx.terminal().mode(), mods, &key) {
+            if binding.is_triggered_by(mode, mods, &key) {
                 // Binding was triggered; run the action
                 let binding = binding.clone();
                 binding.execute(&mut self.ctx);
@@ -779,23 +1429,48 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
     ///
     /// The provided mode, mods, and key must match what is allowed by a binding
     /// for its action to be executed.
-    fn process_mouse_bindings(&mut self, button: MouseButton) {
+    fn process_mouse_bindings(&mut self, event: MouseEvent) {
+        for binding in self.triggered_mouse_bindings(event) {
+            binding.execute(&mut self.ctx);
+        }
+    }
+
+    /// Mouse bindings matching the current mode and modifiers.
+    fn triggered_mouse_bindings(&mut self, event: MouseEvent) -> Vec<Binding<MouseEvent>> {
         let mods = *self.ctx.modifiers();
-        let mode = *self.ctx.terminal().mode();
+        let mode = self.binding_mode();
         let mouse_mode = self.ctx.mouse_mode();
 
-        for i in 0..self.ctx.config().ui_config.mouse_bindings.len() {
-            let mut binding = self.ctx.config().ui_config.mouse_bindings[i].clone();
+        let mut bindings = Vec::new();
+        for binding in &self.ctx.config().ui_config.mouse_bindings {
+            let mut binding = binding.clone();
 
-            // Require shift for all modifiers when mouse mode is active
-            if mouse_mode {
+            // Require shift for all modifiers when mouse mode is active, unless the binding is
+            // meant for it
+            if mouse_mode && !binding.mode.intersects(TermMode::MOUSE_MODE) {
                 binding.mods |= ModifiersState::SHIFT;
             }
 
-            if binding.is_triggered_by(mode, mods, &button) {
-                binding.execute(&mut self.ctx);
+            if binding.is_triggered_by(mode, mods, &event) {
+                bindings.push(binding);
             }
         }
+
+        bindings
+    }
+
+    /// Terminal mode which bindings are matched against.
+    ///
+    /// Any mouse reporting mode enables all of them, so the `Mouse` mode of bindings matches
+    /// each one.
+    fn binding_mode(&mut self) -> TermMode {
+        let mut mode = *self.ctx.terminal().mode();
+        if self.ctx.mouse_mode() {
+            mode.insert(TermMode::MOUSE_MODE);
+        } else {
+            mode.remove(TermMode::MOUSE_MODE);
+        }
+        mode
     }
 
     /// Check if the cursor is hovering above the message bar.
@@ -868,27 +1543,38 @@ impl<'a, T: EventListener, A: ActionContext<T>> Processor<'a, T, A> {
 #[cfg(test)]
 mod tests {
     use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
     use std::time::Duration;
+    use std::{env, fs};
 
     use glutin::event::{
-        ElementState, Event, ModifiersState, MouseButton, VirtualKeyCode, WindowEvent,
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, ScanCode, VirtualKeyCode,
+        WindowEvent,
     };
     use glutin::event_loop::EventLoopWindowTarget;
 
+    use alacritty_terminal::ansi::{Handler, KeyboardModes};
     use alacritty_terminal::clipboard::{Clipboard, ClipboardType};
     use alacritty_terminal::event::{Event as TerminalEvent, EventListener};
     use alacritty_terminal::grid::Scroll;
-    use alacritty_terminal::index::{Point, Side};
+    use alacritty_terminal::index::{Column, Point, Side};
     use alacritty_terminal::message_bar::{Message, MessageBuffer};
     use alacritty_terminal::selection::{Selection, SelectionType};
     use alacritty_terminal::term::{SizeInfo, Term, TermMode};
 
+    use crate::chord::ChordState;
+    use crate::compose::ComposeState;
     use crate::config::{ClickHandler, Config};
-    use crate::event::{ClickState, Mouse};
+    use crate::event::{ClickState, InlineSearchState, Mouse};
+    use crate::hint::HintState;
     use crate::url::{Url, Urls};
     use crate::window::Window;
 
-    use super::{Action, Binding, Processor};
+    use super::{
+        align_block, create_private_file, filter_control_characters, paste, replace_placeholders,
+        Action, Binding, Processor,
+    };
 
     const KEY: VirtualKeyCode = VirtualKeyCode::Key0;
 
@@ -906,6 +1592,12 @@ mod tests {
         pub message_buffer: &'a mut MessageBuffer,
         pub received_count: usize,
         pub suppress_chars: bool,
+        pub compose_state: ComposeState,
+        pub pending_paste: Option<String>,
+        pub inline_search: InlineSearchState,
+        pub hint_state: HintState,
+        pub chord_state: ChordState,
+        pub pressed_keys: HashSet<ScanCode>,
         pub modifiers: ModifiersState,
         config: &'a Config,
     }
@@ -925,10 +1617,22 @@ mod tests {
 
         fn spawn_new_instance(&mut self) {}
 
+        fn working_directory(&self) -> Option<PathBuf> {
+            None
+        }
+
         fn change_font_size(&mut self, _delta: f32) {}
 
         fn reset_font_size(&mut self) {}
 
+        fn change_opacity(&mut self, _delta: f32) {}
+
+        fn reset_opacity(&mut self) {}
+
+        fn toggle_debug_overlay(&mut self) {}
+
+        fn set_color_scheme(&mut self, _name: Option<String>) {}
+
         fn terminal(&self) -> &Term<T> {
             &self.terminal
         }
@@ -982,6 +1686,30 @@ mod tests {
             &mut self.suppress_chars
         }
 
+        fn compose_state(&mut self) -> &mut ComposeState {
+            &mut self.compose_state
+        }
+
+        fn pending_paste(&mut self) -> &mut Option<String> {
+            &mut self.pending_paste
+        }
+
+        fn inline_search(&mut self) -> &mut InlineSearchState {
+            &mut self.inline_search
+        }
+
+        fn hint_state(&mut self) -> &mut HintState {
+            &mut self.hint_state
+        }
+
+        fn chord_state(&mut self) -> &mut ChordState {
+            &mut self.chord_state
+        }
+
+        fn pressed_keys(&mut self) -> &mut HashSet<ScanCode> {
+            &mut self.pressed_keys
+        }
+
         fn modifiers(&mut self) -> &mut ModifiersState {
             &mut self.modifiers
         }
@@ -994,6 +1722,10 @@ mod tests {
             unimplemented!();
         }
 
+        fn push_message(&mut self, message: Message) {
+            self.message_buffer.push(message);
+        }
+
         fn pop_message(&mut self) {
             self.message_buffer.pop();
         }
@@ -1017,6 +1749,10 @@ mod tests {
         fn launch_url(&self, _: Url) {
             unimplemented!();
         }
+
+        fn hovered_url(&self) -> Option<String> {
+            None
+        }
     }
 
     macro_rules! test_clickstate {
@@ -1067,6 +1803,12 @@ mod tests {
                     size_info: &size,
                     received_count: 0,
                     suppress_chars: false,
+                    compose_state: Default::default(),
+                    pending_paste: None,
+                    inline_search: Default::default(),
+                    hint_state: Default::default(),
+                    chord_state: Default::default(),
+                    pressed_keys: HashSet::new(),
                     modifiers: Default::default(),
                     message_buffer: &mut message_buffer,
                     config: &cfg,
@@ -1209,7 +1951,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_shiftmod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState::SHIFT,
@@ -1217,7 +1959,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_shift,
-        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::SHIFT, action: Action::from("\x1b[1;2D"), mode: TermMode::NONE, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState::empty(),
@@ -1225,7 +1967,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_controlmod,
-        binding: Binding { trigger: KEY, mods: ModifiersState::CTRL, action: Action::from("\x1b[1;5D"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::CTRL, action: Action::from("\x1b[1;5D"), mode: TermMode::NONE, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState::CTRL,
@@ -1233,7 +1975,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_not_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1b[D"), mode: TermMode::NONE, notmode: TermMode::APP_CURSOR },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1b[D"), mode: TermMode::NONE, notmode: TermMode::APP_CURSOR, chord: Vec::new() },
         triggers: true,
         mode: TermMode::NONE,
         mods: ModifiersState::empty(),
@@ -1241,7 +1983,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: true,
         mode: TermMode::APP_CURSOR,
         mods: ModifiersState::empty(),
@@ -1249,7 +1991,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_nomode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState::empty(),
@@ -1257,7 +1999,7 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_appcursormode_appkeypadmode_nomod_require_appcursor,
-        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::empty(), action: Action::from("\x1bOD"), mode: TermMode::APP_CURSOR, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: true,
         mode: TermMode::APP_CURSOR | TermMode::APP_KEYPAD,
         mods: ModifiersState::empty(),
@@ -1265,9 +2007,194 @@ mod tests {
 
     test_process_binding! {
         name: process_binding_fail_with_extra_mods,
-        binding: Binding { trigger: KEY, mods: ModifiersState::LOGO, action: Action::from("arst"), mode: TermMode::NONE, notmode: TermMode::NONE },
+        binding: Binding { trigger: KEY, mods: ModifiersState::LOGO, action: Action::from("arst"), mode: TermMode::NONE, notmode: TermMode::NONE, chord: Vec::new() },
         triggers: false,
         mode: TermMode::NONE,
         mods: ModifiersState::ALT | ModifiersState::LOGO,
     }
+
+    #[test]
+    fn filter_pasted_control_characters() {
+        let text = "echo a\tb\x03\x1b[201~\r\n\x7f\u{9b}c\n";
+        assert_eq!(filter_control_characters(text), "echo a\tb[201~\r\nc\n");
+    }
+
+    #[test]
+    fn command_placeholders() {
+        let mut requested = Vec::new();
+        let mut value = |name: &str| {
+            requested.push(name.to_owned());
+            match name {
+                "url" => Some(String::from("a {selection}")),
+                "selection" => Some(String::new()),
+                _ => None,
+            }
+        };
+
+        let text = "open {url} {{selection} {selection}{cwd} {";
+        let replaced = replace_placeholders(text, &mut value);
+        assert_eq!(replaced, "open a {selection} {selection} {cwd} {");
+        assert_eq!(requested, ["url", "selection", "cwd"]);
+    }
+
+    #[test]
+    fn private_export_file() {
+        let dir = env::temp_dir();
+        let (first, _) = create_private_file(&dir, "alacritty-test-export", "txt").unwrap();
+        let (second, _) = create_private_file(&dir, "alacritty-test-export", "txt").unwrap();
+        assert_ne!(first, second);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn align_pasted_block() {
+        let text = "ab\ncd\r\n\r\nef\n";
+        assert_eq!(align_block(text, Column(2)), "ab\n  cd\r\n\r\n  ef\n");
+        assert_eq!(align_block(text, Column(0)), text);
+    }
+
+    /// State of a terminal with 7 columns and 17 lines, borrowed by the test's action context.
+    struct TestState {
+        size: SizeInfo,
+        terminal: Term<MockEventProxy>,
+        mouse: Mouse,
+        selection: Option<Selection>,
+        message_buffer: MessageBuffer,
+    }
+
+    impl TestState {
+        fn new(cfg: &Config) -> Self {
+            let size = SizeInfo {
+                width: 21.0,
+                height: 51.0,
+                cell_width: 3.0,
+                cell_height: 3.0,
+                padding_x: 0.,
+                padding_y: 0.,
+                dpr: 1.0,
+            };
+
+            Self {
+                terminal: Term::new(cfg, &size, Clipboard::new_nop(), MockEventProxy),
+                size,
+                mouse: Mouse::default(),
+                selection: None,
+                message_buffer: MessageBuffer::new(),
+            }
+        }
+
+        fn context<'a>(&'a mut self, cfg: &'a Config) -> ActionContext<'a, MockEventProxy> {
+            ActionContext {
+                terminal: &mut self.terminal,
+                selection: &mut self.selection,
+                mouse: &mut self.mouse,
+                size_info: &self.size,
+                received_count: 0,
+                suppress_chars: false,
+                compose_state: Default::default(),
+                pending_paste: None,
+                inline_search: Default::default(),
+                hint_state: Default::default(),
+                chord_state: Default::default(),
+                pressed_keys: HashSet::new(),
+                modifiers: Default::default(),
+                message_buffer: &mut self.message_buffer,
+                config: cfg,
+            }
+        }
+    }
+
+    #[test]
+    fn confirm_multiline_paste() {
+        let cfg = Config::default();
+        let mut state = TestState::new(&cfg);
+        let mut context = state.context(&cfg);
+
+        paste(&mut context, "single line");
+        assert_eq!(context.pending_paste, None);
+
+        paste(&mut context, "rm -rf ~\n");
+        assert_eq!(context.pending_paste, Some(String::from("rm -rf ~\r")));
+
+        let mut processor = Processor::new(context, &None);
+        processor.received_char('x');
+        assert!(processor.ctx.pending_paste.is_some());
+
+        processor.received_char('n');
+        assert_eq!(processor.ctx.pending_paste, None);
+        assert!(processor.ctx.message_buffer.is_empty());
+    }
+
+    #[allow(deprecated)]
+    fn key(
+        scancode: ScanCode,
+        keycode: Option<VirtualKeyCode>,
+        state: ElementState,
+    ) -> KeyboardInput {
+        KeyboardInput { scancode, state, virtual_keycode: keycode, modifiers: Default::default() }
+    }
+
+    /// Press and release a key, returning whether its text is suppressed.
+    fn type_key<T: EventListener, A: super::ActionContext<T>>(
+        processor: &mut Processor<'_, T, A>,
+        scancode: ScanCode,
+        keycode: Option<VirtualKeyCode>,
+    ) -> bool {
+        processor.key_input(key(scancode, keycode, ElementState::Pressed));
+        let suppress_chars = *processor.ctx.suppress_chars();
+        processor.key_input(key(scancode, keycode, ElementState::Released));
+        suppress_chars
+    }
+
+    #[test]
+    fn dead_key_sequence() {
+        let cfg = Config::default();
+        let mut no_compose = Config::default();
+        no_compose.ui_config.compose_timeout = Duration::from_millis(0);
+
+        let mut state = TestState::new(&cfg);
+        state.terminal.push_keyboard_mode(KeyboardModes::REPORT_ALL_KEYS_AS_ESC);
+        let mut processor = Processor::new(state.context(&cfg), &None);
+
+        let e = Some(VirtualKeyCode::E);
+        let compose = Some(VirtualKeyCode::Compose);
+
+        // Keys are reported as escape sequences instead of their text
+        assert!(type_key(&mut processor, 18, e));
+
+        // Keys following the Compose key produce the composed text
+        assert!(!type_key(&mut processor, 135, compose));
+        assert!(processor.ctx.compose_state.active());
+        assert!(!type_key(&mut processor, 40, Some(VirtualKeyCode::Apostrophe)));
+        processor.key_input(key(18, e, ElementState::Pressed));
+        assert!(!processor.ctx.suppress_chars);
+        processor.received_char('\u{e9}');
+        assert!(!processor.ctx.compose_state.active());
+        assert!(processor.ctx.compose_state.release(18));
+        assert!(type_key(&mut processor, 18, e));
+
+        // Keys without text abandon the sequence
+        type_key(&mut processor, 135, compose);
+        assert!(type_key(&mut processor, 103, Some(VirtualKeyCode::Up)));
+        assert!(!processor.ctx.compose_state.active());
+        assert!(type_key(&mut processor, 18, e));
+
+        // Keys producing no keycode do not start a sequence
+        type_key(&mut processor, 48, None);
+        assert!(type_key(&mut processor, 18, e));
+
+        // The Compose key is ignored without a compose timeout
+        processor.ctx.config = &no_compose;
+        type_key(&mut processor, 135, compose);
+        assert!(type_key(&mut processor, 18, e));
+    }
 }
@@ -0,0 +1,417 @@
+//! Key encoding of the kitty keyboard protocol and xterm's modifyOtherKeys
+//!
+//! Applications enable the kitty protocol by pushing its flags with `CSI > flags u`, or
+//! modifyOtherKeys with `CSI > 4 ; level m`. While either is active, keys which are ambiguous in
+//! the legacy encoding are sent as `CSI` escape sequences instead.
+use glutin::event::{ModifiersState, VirtualKeyCode};
+
+use alacritty_terminal::term::TermMode;
+
+/// Kind of key event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyEventType {
+    Press,
+    Repeat,
+    Release,
+}
+
+/// Key as it is identified by the kitty keyboard protocol.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum KittyKey {
+    /// Key producing text, with its unshifted character.
+    ///
+    /// Only the shifted form of letters is known, since other keys differ between layouts.
+    Text(char),
+
+    /// Key with a legacy escape sequence, sent as `CSI number ; modifiers terminator`.
+    Functional(u32, char),
+
+    /// Escape key.
+    Escape,
+
+    /// Enter, Tab and Backspace, which keep their legacy encoding while unmodified.
+    Control(u32),
+
+    /// Modifier key, which is only reported when all keys are sent as escape sequences.
+    Modifier(u32),
+}
+
+impl KittyKey {
+    fn from_keycode(key: VirtualKeyCode) -> Option<KittyKey> {
+        use KittyKey::*;
+
+        let key = match key {
+            VirtualKeyCode::Key1 => Text('1'),
+            VirtualKeyCode::Key2 => Text('2'),
+            VirtualKeyCode::Key3 => Text('3'),
+            VirtualKeyCode::Key4 => Text('4'),
+            VirtualKeyCode::Key5 => Text('5'),
+            VirtualKeyCode::Key6 => Text('6'),
+            VirtualKeyCode::Key7 => Text('7'),
+            VirtualKeyCode::Key8 => Text('8'),
+            VirtualKeyCode::Key9 => Text('9'),
+            VirtualKeyCode::Key0 => Text('0'),
+            VirtualKeyCode::A => Text('a'),
+            VirtualKeyCode::B => Text('b'),
+            VirtualKeyCode::C => Text('c'),
+            VirtualKeyCode::D => Text('d'),
+            VirtualKeyCode::E => Text('e'),
+            VirtualKeyCode::F => Text('f'),
+            VirtualKeyCode::G => Text('g'),
+            VirtualKeyCode::H => Text('h'),
+            VirtualKeyCode::I => Text('i'),
+            VirtualKeyCode::J => Text('j'),
+            VirtualKeyCode::K => Text('k'),
+            VirtualKeyCode::L => Text('l'),
+            VirtualKeyCode::M => Text('m'),
+            VirtualKeyCode::N => Text('n'),
+            VirtualKeyCode::O => Text('o'),
+            VirtualKeyCode::P => Text('p'),
+            VirtualKeyCode::Q => Text('q'),
+            VirtualKeyCode::R => Text('r'),
+            VirtualKeyCode::S => Text('s'),
+            VirtualKeyCode::T => Text('t'),
+            VirtualKeyCode::U => Text('u'),
+            VirtualKeyCode::V => Text('v'),
+            VirtualKeyCode::W => Text('w'),
+            VirtualKeyCode::X => Text('x'),
+            VirtualKeyCode::Y => Text('y'),
+            VirtualKeyCode::Z => Text('z'),
+            VirtualKeyCode::Space => Text(' '),
+            VirtualKeyCode::Apostrophe => Text('\''),
+            VirtualKeyCode::Backslash => Text('\\'),
+            VirtualKeyCode::Comma => Text(','),
+            VirtualKeyCode::Equals => Text('='),
+            VirtualKeyCode::Grave => Text('`'),
+            VirtualKeyCode::LBracket => Text('['),
+            VirtualKeyCode::Minus => Text('-'),
+            VirtualKeyCode::Period => Text('.'),
+            VirtualKeyCode::RBracket => Text(']'),
+            VirtualKeyCode::Semicolon => Text(';'),
+            VirtualKeyCode::Slash => Text('/'),
+            VirtualKeyCode::Escape => Escape,
+            VirtualKeyCode::Return => Control(13),
+            VirtualKeyCode::Tab => Control(9),
+            VirtualKeyCode::Back => Control(127),
+            VirtualKeyCode::Insert => Functional(2, '~'),
+            VirtualKeyCode::Delete => Functional(3, '~'),
+            VirtualKeyCode::PageUp => Functional(5, '~'),
+            VirtualKeyCode::PageDown => Functional(6, '~'),
+            VirtualKeyCode::Up => Functional(1, 'A'),
+            VirtualKeyCode::Down => Functional(1, 'B'),
+            VirtualKeyCode::Right => Functional(1, 'C'),
+            VirtualKeyCode::Left => Functional(1, 'D'),
+            VirtualKeyCode::End => Functional(1, 'F'),
+            VirtualKeyCode::Home => Functional(1, 'H'),
+            VirtualKeyCode::F1 => Functional(1, 'P'),
+            VirtualKeyCode::F2 => Functional(1, 'Q'),
+            VirtualKeyCode::F3 => Functional(13, '~'),
+            VirtualKeyCode::F4 => Functional(1, 'S'),
+            VirtualKeyCode::F5 => Functional(15, '~'),
+            VirtualKeyCode::F6 => Functional(17, '~'),
+            VirtualKeyCode::F7 => Functional(18, '~'),
+            VirtualKeyCode::F8 => Functional(19, '~'),
+            VirtualKeyCode::F9 => Functional(20, '~'),
+            VirtualKeyCode::F10 => Functional(21, '~'),
+            VirtualKeyCode::F11 => Functional(23, '~'),
+            VirtualKeyCode::F12 => Functional(24, '~'),
+            VirtualKeyCode::F13 => Functional(57376, 'u'),
+            VirtualKeyCode::F14 => Functional(57377, 'u'),
+            VirtualKeyCode::F15 => Functional(57378, 'u'),
+            VirtualKeyCode::F16 => Functional(57379, 'u'),
+            VirtualKeyCode::F17 => Functional(57380, 'u'),
+            VirtualKeyCode::F18 => Functional(57381, 'u'),
+            VirtualKeyCode::F19 => Functional(57382, 'u'),
+            VirtualKeyCode::F20 => Functional(57383, 'u'),
+            VirtualKeyCode::F21 => Functional(57384, 'u'),
+            VirtualKeyCode::F22 => Functional(57385, 'u'),
+            VirtualKeyCode::F23 => Functional(57386, 'u'),
+            VirtualKeyCode::F24 => Functional(57387, 'u'),
+            VirtualKeyCode::LShift => Modifier(57441),
+            VirtualKeyCode::LControl => Modifier(57442),
+            VirtualKeyCode::LAlt => Modifier(57443),
+            VirtualKeyCode::LWin => Modifier(57444),
+            VirtualKeyCode::RShift => Modifier(57447),
+            VirtualKeyCode::RControl => Modifier(57448),
+            VirtualKeyCode::RAlt => Modifier(57449),
+            VirtualKeyCode::RWin => Modifier(57450),
+            _ => return None,
+        };
+
+        Some(key)
+    }
+}
+
+/// Check if a key produces text, so it can continue a dead key or Compose sequence.
+///
+/// Keys without a keycode are specific to the keyboard layout and always produce text.
+pub fn produces_text(key: Option<VirtualKeyCode>) -> bool {
+    let key = match key {
+        Some(key) => key,
+        None => return true,
+    };
+
+    match key {
+        VirtualKeyCode::Numpad0
+        | VirtualKeyCode::Numpad1
+        | VirtualKeyCode::Numpad2
+        | VirtualKeyCode::Numpad3
+        | VirtualKeyCode::Numpad4
+        | VirtualKeyCode::Numpad5
+        | VirtualKeyCode::Numpad6
+        | VirtualKeyCode::Numpad7
+        | VirtualKeyCode::Numpad8
+        | VirtualKeyCode::Numpad9
+        | VirtualKeyCode::Add
+        | VirtualKeyCode::Subtract
+        | VirtualKeyCode::Multiply
+        | VirtualKeyCode::Divide
+        | VirtualKeyCode::Decimal
+        | VirtualKeyCode::NumpadComma
+        | VirtualKeyCode::NumpadEquals
+        | VirtualKeyCode::At
+        | VirtualKeyCode::Caret
+        | VirtualKeyCode::Colon
+        | VirtualKeyCode::Underline
+        | VirtualKeyCode::Yen
+        | VirtualKeyCode::OEM102 => true,
+        _ => match KittyKey::from_keycode(key) {
+            Some(KittyKey::Text(..)) => true,
+            _ => false,
+        },
+    }
+}
+
+/// Escape sequence for a key event in the active kitty keyboard protocol mode.
+///
+/// Returns `None` when the key is sent the same way as without the protocol. Since key releases
+/// have no legacy encoding, nothing is sent for them in that case.
+pub fn kitty_sequence(
+    mode: TermMode,
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+    event_type: KeyEventType,
+) -> Option<Vec<u8>> {
+    let key = KittyKey::from_keycode(key)?;
+
+    let all_keys = mode.contains(TermMode::REPORT_ALL_KEYS_AS_ESC);
+    let disambiguate = all_keys || mode.contains(TermMode::DISAMBIGUATE_ESC_CODES);
+    let event_types = mode.contains(TermMode::REPORT_EVENT_TYPES);
+    let event_type = match event_type {
+        KeyEventType::Release if !event_types => return None,
+        _ if !event_types => KeyEventType::Press,
+        event_type => event_type,
+    };
+
+    let escape = match key {
+        KittyKey::Text(..) => {
+            all_keys || (disambiguate && (mods.ctrl() || mods.alt() || mods.logo()))
+        },
+        KittyKey::Escape => disambiguate || event_type != KeyEventType::Press,
+        KittyKey::Control(_) if event_type == KeyEventType::Release => all_keys,
+        KittyKey::Control(_) => all_keys || (disambiguate && !mods.is_empty()),
+        KittyKey::Functional(..) => {
+            all_keys || !mods.is_empty() || event_type != KeyEventType::Press
+        },
+        KittyKey::Modifier(_) => all_keys,
+    };
+
+    if !escape {
+        return None;
+    }
+
+    let modifiers = modifier_parameter(mods);
+
+    let event_type = match event_type {
+        KeyEventType::Press => "",
+        KeyEventType::Repeat => ":2",
+        KeyEventType::Release => ":3",
+    };
+
+    // Modifiers can be omitted for unmodified key presses
+    let modifiers = if modifiers == 1 && event_type.is_empty() {
+        String::new()
+    } else {
+        format!(";{}{}", modifiers, event_type)
+    };
+
+    let sequence = match key {
+        KittyKey::Functional(1, terminator) if modifiers.is_empty() => {
+            format!("\x1b[{}", terminator)
+        },
+        KittyKey::Functional(number, terminator) => {
+            format!("\x1b[{}{}{}", number, modifiers, terminator)
+        },
+        KittyKey::Text(c) => {
+            let mut code = (c as u32).to_string();
+            let shifted = c.to_ascii_uppercase();
+            if mode.contains(TermMode::REPORT_ALTERNATE_KEYS) && mods.shift() && shifted != c {
+                code.push_str(&format!(":{}", shifted as u32));
+            }
+            format!("\x1b[{}{}u", code, modifiers)
+        },
+        KittyKey::Escape => format!("\x1b[27{}u", modifiers),
+        KittyKey::Control(code) | KittyKey::Modifier(code) => {
+            format!("\x1b[{}{}u", code, modifiers)
+        },
+    };
+
+    Some(sequence.into_bytes())
+}
+
+/// Escape sequence for a key press in xterm's modifyOtherKeys mode.
+///
+/// Modified keys are sent as `CSI 27 ; modifiers ; code ~`. Returns `None` when the key is sent
+/// the same way as without the mode.
+pub fn modify_other_keys_sequence(
+    mode: TermMode,
+    key: VirtualKeyCode,
+    mods: ModifiersState,
+) -> Option<Vec<u8>> {
+    let all_keys = mode.contains(TermMode::MODIFY_ALL_KEYS);
+
+    let code = match KittyKey::from_keycode(key)? {
+        KittyKey::Text(c) => {
+            let modified = if all_keys {
+                mods.ctrl() || mods.alt() || mods.logo()
+            } else {
+                // Control characters of letters and brackets are well known
+                let control_char = c.is_ascii_lowercase() || ['[', '\\', ']', ' '].contains(&c);
+                mods.ctrl() && (mods.shift() || !control_char)
+            };
+
+            if !modified {
+                return None;
+            }
+
+            if mods.shift() {
+                c.to_ascii_uppercase() as u32
+            } else {
+                c as u32
+            }
+        },
+        KittyKey::Escape | KittyKey::Control(_) if mods.is_empty() => return None,
+        KittyKey::Escape => 27,
+        KittyKey::Control(code) => {
+            // Alt sends an escape prefix and Shift+Tab has its own sequence
+            let well_known = !mods.ctrl() && (!mods.shift() || code == 9);
+            if !all_keys && well_known {
+                return None;
+            }
+
+            code
+        },
+        KittyKey::Functional(..) | KittyKey::Modifier(_) => return None,
+    };
+
+    Some(format!("\x1b[27;{};{}~", modifier_parameter(mods), code).into_bytes())
+}
+
+/// Modifier parameter shared by all escape sequences of modified keys.
+fn modifier_parameter(mods: ModifiersState) -> u8 {
+    let mut modifiers = 1;
+    if mods.shift() {
+        modifiers += 1;
+    }
+    if mods.alt() {
+        modifiers += 2;
+    }
+    if mods.ctrl() {
+        modifiers += 4;
+    }
+    if mods.logo() {
+        modifiers += 8;
+    }
+    modifiers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disambiguate_escape_codes() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES;
+        let press = KeyEventType::Press;
+        let none = ModifiersState::empty();
+
+        let escape = kitty_sequence(mode, VirtualKeyCode::Escape, none, press);
+        assert_eq!(escape, Some(b"\x1b[27u".to_vec()));
+
+        // Text is sent as is, unless modified by more than shift
+        assert_eq!(kitty_sequence(mode, VirtualKeyCode::A, none, press), None);
+        assert_eq!(kitty_sequence(mode, VirtualKeyCode::A, ModifiersState::SHIFT, press), None);
+        let ctrl_i = kitty_sequence(mode, VirtualKeyCode::I, ModifiersState::CTRL, press);
+        assert_eq!(ctrl_i, Some(b"\x1b[105;5u".to_vec()));
+
+        assert_eq!(kitty_sequence(mode, VirtualKeyCode::Tab, none, press), None);
+        let shift_tab = kitty_sequence(mode, VirtualKeyCode::Tab, ModifiersState::SHIFT, press);
+        assert_eq!(shift_tab, Some(b"\x1b[9;2u".to_vec()));
+
+        // Releases are only reported when requested
+        assert_eq!(kitty_sequence(mode, VirtualKeyCode::Escape, none, KeyEventType::Release), None);
+    }
+
+    #[test]
+    fn report_event_types_and_alternate_keys() {
+        let mode = TermMode::DISAMBIGUATE_ESC_CODES
+            | TermMode::REPORT_EVENT_TYPES
+            | TermMode::REPORT_ALTERNATE_KEYS;
+        let none = ModifiersState::empty();
+
+        let up = kitty_sequence(mode, VirtualKeyCode::Up, none, KeyEventType::Press);
+        assert_eq!(up, None);
+        let up = kitty_sequence(mode, VirtualKeyCode::Up, none, KeyEventType::Repeat);
+        assert_eq!(up, Some(b"\x1b[1;1:2A".to_vec()));
+        let f5 = kitty_sequence(mode, VirtualKeyCode::F5, none, KeyEventType::Release);
+        assert_eq!(f5, Some(b"\x1b[15;1:3~".to_vec()));
+
+        let mods = ModifiersState::CTRL | ModifiersState::SHIFT;
+        let ctrl_shift_a = kitty_sequence(mode, VirtualKeyCode::A, mods, KeyEventType::Press);
+        assert_eq!(ctrl_shift_a, Some(b"\x1b[97:65;6u".to_vec()));
+
+        // Shifted symbols depend on the keyboard layout
+        let ctrl_shift_2 = kitty_sequence(mode, VirtualKeyCode::Key2, mods, KeyEventType::Press);
+        assert_eq!(ctrl_shift_2, Some(b"\x1b[50;6u".to_vec()));
+    }
+
+    #[test]
+    fn report_all_keys_as_escape_codes() {
+        let mode = TermMode::REPORT_ALL_KEYS_AS_ESC;
+        let press = KeyEventType::Press;
+        let none = ModifiersState::empty();
+
+        assert_eq!(
+            kitty_sequence(mode, VirtualKeyCode::A, none, press),
+            Some(b"\x1b[97u".to_vec())
+        );
+        let enter = kitty_sequence(mode, VirtualKeyCode::Return, none, press);
+        assert_eq!(enter, Some(b"\x1b[13u".to_vec()));
+        let shift = kitty_sequence(mode, VirtualKeyCode::LShift, ModifiersState::SHIFT, press);
+        assert_eq!(shift, Some(b"\x1b[57441;2u".to_vec()));
+    }
+
+    #[test]
+    fn modify_other_keys() {
+        let ctrl = ModifiersState::CTRL;
+        let ctrl_shift = ModifiersState::CTRL | ModifiersState::SHIFT;
+
+        // Well-known combinations are only encoded in the second level
+        let mode = TermMode::MODIFY_OTHER_KEYS;
+        assert_eq!(modify_other_keys_sequence(mode, VirtualKeyCode::A, ctrl), None);
+        let ctrl_shift_a = modify_other_keys_sequence(mode, VirtualKeyCode::A, ctrl_shift);
+        assert_eq!(ctrl_shift_a, Some(b"\x1b[27;6;65~".to_vec()));
+        let ctrl_enter = modify_other_keys_sequence(mode, VirtualKeyCode::Return, ctrl);
+        assert_eq!(ctrl_enter, Some(b"\x1b[27;5;13~".to_vec()));
+        let shift_tab =
+            modify_other_keys_sequence(mode, VirtualKeyCode::Tab, ModifiersState::SHIFT);
+        assert_eq!(shift_tab, None);
+
+        let mode = TermMode::MODIFY_ALL_KEYS;
+        let ctrl_a = modify_other_keys_sequence(mode, VirtualKeyCode::A, ctrl);
+        assert_eq!(ctrl_a, Some(b"\x1b[27;5;97~".to_vec()));
+        let shift_a = modify_other_keys_sequence(mode, VirtualKeyCode::A, ModifiersState::SHIFT);
+        assert_eq!(shift_a, None);
+        assert_eq!(modify_other_keys_sequence(mode, VirtualKeyCode::Up, ctrl), None);
+    }
+}
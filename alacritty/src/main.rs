@@ -47,12 +47,17 @@ use alacritty_terminal::sync::FairMutex;
 use alacritty_terminal::term::Term;
 use alacritty_terminal::tty;
 
+mod chord;
 mod cli;
+mod compose;
 mod config;
 mod cursor;
 mod display;
 mod event;
+mod geometry;
+mod hint;
 mod input;
+mod keyboard;
 mod logging;
 mod renderer;
 mod url;
@@ -71,6 +76,7 @@ use crate::config::monitor::Monitor;
 use crate::config::Config;
 use crate::display::Display;
 use crate::event::{EventProxy, Processor};
+use crate::geometry::Geometry;
 
 fn main() {
     panic::attach_handler();
@@ -95,7 +101,15 @@ fn main() {
 
     // Load configuration file
     let config_path = options.config_path().or_else(config::installed_config);
-    let config = config_path.map(config::load_from).unwrap_or_else(Config::default);
+    let mut config = config_path.map(config::load_from).unwrap_or_else(Config::default);
+
+    // Restore the last window geometry, command line options take precedence
+    if config.window.remember_geometry {
+        if let Some(geometry) = Geometry::load() {
+            geometry.apply(&mut config);
+        }
+    }
+
     let config = options.into_config(config);
 
     // Update the log level from config
@@ -187,18 +201,25 @@ fn run(window_event_loop: GlutinEventLoop<Event>, config: Config) -> Result<(),
 
     // Create a config monitor when config was loaded from path
     //
-    // The monitor watches the config file for changes and reloads it. Pending
+    // The monitor watches the config file and its imports for changes and reloads it. Pending
     // config changes are processed in the main loop.
-    if config.live_config_reload() {
-        config.config_path.as_ref().map(|path| Monitor::new(path, event_proxy.clone()));
-    }
+    let monitor = if config.live_config_reload() && config.config_path.is_some() {
+        Some(Monitor::new(config.ui_config.config_paths.clone(), event_proxy))
+    } else {
+        None
+    };
 
     // Setup storage for message UI
     let message_buffer = MessageBuffer::new();
 
     // Event processor
-    let mut processor =
-        Processor::new(event_loop::Notifier(loop_tx.clone()), message_buffer, config, display);
+    let mut processor = Processor::new(
+        event_loop::Notifier(loop_tx.clone()),
+        message_buffer,
+        config,
+        display,
+        monitor,
+    );
 
     // Kick off the I/O thread
     let io_thread = event_loop.spawn();
@@ -0,0 +1,544 @@
+// Copyright 2020 The Alacritty Project Contributors
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Glyphs for box drawing, block element and powerline characters
+//!
+//! Fonts rarely size these glyphs to fill the cell exactly, which leaves gaps
+//! between lines that should connect. Drawing them from the cell metrics
+//! instead makes them tile seamlessly with any font.
+
+use std::cmp;
+
+use font::{BitmapBuffer, Metrics, RasterizedGlyph};
+
+use alacritty_terminal::config::Delta;
+
+/// Number of sub-samples per axis used to antialias curves and diagonals
+const SAMPLES: i32 = 4;
+
+/// Rasterize `c` if it is one of the characters drawn without the font.
+pub fn builtin_glyph(c: char, metrics: &Metrics, offset: Delta<i8>) -> Option<RasterizedGlyph> {
+    let width = cmp::max(metrics.average_advance as i32 + i32::from(offset.x), 1);
+    let height = cmp::max(metrics.line_height as i32 + i32::from(offset.y), 1);
+    let stroke = cmp::max(metrics.underline_thickness.round() as i32, 1);
+
+    let mut canvas = Canvas::new(width, height, stroke);
+    match c {
+        '\u{2500}'..='\u{257f}' => canvas.draw_box(c),
+        '\u{2580}'..='\u{259f}' => canvas.draw_block(c),
+        '\u{e0b0}'..='\u{e0bf}' => canvas.draw_powerline(c),
+        _ => return None,
+    }
+
+    Some(canvas.into_glyph(c))
+}
+
+/// Stroke weight of one arm of a box drawing character
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Weight {
+    None,
+    Light,
+    Heavy,
+    Double,
+}
+
+/// Arm weights of the line characters in U+2500..U+257F, as `[up, right, down, left]`
+///
+/// Dashed lines, arcs and diagonals are listed with their plain shape but drawn separately.
+#[rustfmt::skip]
+const BOX_LINES: [[Weight; 4]; 128] = {
+    use Weight::{Double as D, Heavy as H, Light as L, None as N};
+    [
+        [N, L, N, L], [N, H, N, H], [L, N, L, N], [H, N, H, N], // ─ ━ │ ┃
+        [N, L, N, L], [N, H, N, H], [L, N, L, N], [H, N, H, N], // ┄ ┅ ┆ ┇
+        [N, L, N, L], [N, H, N, H], [L, N, L, N], [H, N, H, N], // ┈ ┉ ┊ ┋
+        [N, L, L, N], [N, H, L, N], [N, L, H, N], [N, H, H, N], // ┌ ┍ ┎ ┏
+        [N, N, L, L], [N, N, L, H], [N, N, H, L], [N, N, H, H], // ┐ ┑ ┒ ┓
+        [L, L, N, N], [L, H, N, N], [H, L, N, N], [H, H, N, N], // └ ┕ ┖ ┗
+        [L, N, N, L], [L, N, N, H], [H, N, N, L], [H, N, N, H], // ┘ ┙ ┚ ┛
+        [L, L, L, N], [L, H, L, N], [H, L, L, N], [L, L, H, N], // ├ ┝ ┞ ┟
+        [H, L, H, N], [H, H, L, N], [L, H, H, N], [H, H, H, N], // ┠ ┡ ┢ ┣
+        [L, N, L, L], [L, N, L, H], [H, N, L, L], [L, N, H, L], // ┤ ┥ ┦ ┧
+        [H, N, H, L], [H, N, L, H], [L, N, H, H], [H, N, H, H], // ┨ ┩ ┪ ┫
+        [N, L, L, L], [N, L, L, H], [N, H, L, L], [N, H, L, H], // ┬ ┭ ┮ ┯
+        [N, L, H, L], [N, L, H, H], [N, H, H, L], [N, H, H, H], // ┰ ┱ ┲ ┳
+        [L, L, N, L], [L, L, N, H], [L, H, N, L], [L, H, N, H], // ┴ ┵ ┶ ┷
+        [H, L, N, L], [H, L, N, H], [H, H, N, L], [H, H, N, H], // ┸ ┹ ┺ ┻
+        [L, L, L, L], [L, L, L, H], [L, H, L, L], [L, H, L, H], // ┼ ┽ ┾ ┿
+        [H, L, L, L], [L, L, H, L], [H, L, H, L], [H, L, L, H], // ╀ ╁ ╂ ╃
+        [H, H, L, L], [L, L, H, H], [L, H, H, L], [H, H, L, H], // ╄ ╅ ╆ ╇
+        [L, H, H, H], [H, L, H, H], [H, H, H, L], [H, H, H, H], // ╈ ╉ ╊ ╋
+        [N, L, N, L], [N, H, N, H], [L, N, L, N], [H, N, H, N], // ╌ ╍ ╎ ╏
+        [N, D, N, D], [D, N, D, N], [N, D, L, N], [N, L, D, N], // ═ ║ ╒ ╓
+        [N, D, D, N], [N, N, L, D], [N, N, D, L], [N, N, D, D], // ╔ ╕ ╖ ╗
+        [L, D, N, N], [D, L, N, N], [D, D, N, N], [L, N, N, D], // ╘ ╙ ╚ ╛
+        [D, N, N, L], [D, N, N, D], [L, D, L, N], [D, L, D, N], // ╜ ╝ ╞ ╟
+        [D, D, D, N], [L, N, L, D], [D, N, D, L], [D, N, D, D], // ╠ ╡ ╢ ╣
+        [N, D, L, D], [N, L, D, L], [N, D, D, D], [L, D, N, D], // ╤ ╥ ╦ ╧
+        [D, L, N, L], [D, D, N, D], [L, D, L, D], [D, L, D, L], // ╨ ╩ ╪ ╫
+        [D, D, D, D], [N, L, L, N], [N, N, L, L], [L, N, N, L], // ╬ ╭ ╮ ╯
+        [L, L, N, N], [N, N, N, N], [N, N, N, N], [N, N, N, N], // ╰ ╱ ╲ ╳
+        [N, N, N, L], [L, N, N, N], [N, L, N, N], [N, N, L, N], // ╴ ╵ ╶ ╷
+        [N, N, N, H], [H, N, N, N], [N, H, N, N], [N, N, H, N], // ╸ ╹ ╺ ╻
+        [N, H, N, L], [L, N, H, N], [N, L, N, H], [H, N, L, N], // ╼ ╽ ╾ ╿
+    ]
+};
+
+/// Grayscale coverage buffer the size of a single cell
+struct Canvas {
+    width: i32,
+    height: i32,
+
+    /// Thickness of light lines in pixels
+    stroke: i32,
+
+    buffer: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: i32, height: i32, stroke: i32) -> Self {
+        Self { width, height, stroke, buffer: vec![0; (width * height) as usize] }
+    }
+
+    /// Convert the coverage into a glyph covering the entire cell.
+    fn into_glyph(self, c: char) -> RasterizedGlyph {
+        let buf = self.buffer.iter().flat_map(|&alpha| vec![alpha; 3]).collect();
+
+        RasterizedGlyph {
+            c,
+            top: self.height,
+            left: 0,
+            width: self.width,
+            height: self.height,
+            buf: BitmapBuffer::RGB(buf),
+        }
+    }
+
+    /// Fill the rectangle `x0..x1` x `y0..y1`, clamped to the canvas.
+    fn fill_rect(&mut self, x0: i32, x1: i32, y0: i32, y1: i32, alpha: u8) {
+        let (x0, x1) = (cmp::max(x0, 0), cmp::min(x1, self.width));
+        let (y0, y1) = (cmp::max(y0, 0), cmp::min(y1, self.height));
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let pixel = &mut self.buffer[(y * self.width + x) as usize];
+                *pixel = cmp::max(*pixel, alpha);
+            }
+        }
+    }
+
+    /// Fill a rectangle given by its extent along and across a line direction.
+    fn fill_line(&mut self, horizontal: bool, along: (i32, i32), across: (i32, i32)) {
+        if horizontal {
+            self.fill_rect(along.0, along.1, across.0, across.1, 255);
+        } else {
+            self.fill_rect(across.0, across.1, along.0, along.1, 255);
+        }
+    }
+
+    /// Antialiased fill of every point for which `inside` holds.
+    fn fill_shape<F: Fn(f32, f32) -> bool>(&mut self, inside: F) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut hits = 0;
+                for sy in 0..SAMPLES {
+                    for sx in 0..SAMPLES {
+                        let px = x as f32 + (sx as f32 + 0.5) / SAMPLES as f32;
+                        let py = y as f32 + (sy as f32 + 0.5) / SAMPLES as f32;
+                        if inside(px, py) {
+                            hits += 1;
+                        }
+                    }
+                }
+
+                let alpha = (hits * 255 / (SAMPLES * SAMPLES)) as u8;
+                self.fill_rect(x, x + 1, y, y + 1, alpha);
+            }
+        }
+    }
+
+    /// Thickness of a line with the specified weight.
+    ///
+    /// For double lines this is the distance between the outer edges of both lines.
+    fn thickness(&self, weight: Weight) -> i32 {
+        match weight {
+            Weight::None => 0,
+            Weight::Light => self.stroke,
+            Weight::Heavy => self.stroke * 2,
+            Weight::Double => self.stroke * 3,
+        }
+    }
+
+    /// Distance between the center of a double line and each of its strokes.
+    fn double_gap(&self) -> i32 {
+        self.stroke
+    }
+
+    fn draw_box(&mut self, c: char) {
+        match c {
+            // Dashed lines
+            '\u{2504}'..='\u{250b}' | '\u{254c}'..='\u{254f}' => {
+                let [up, right, _, _] = BOX_LINES[c as usize - 0x2500];
+                let dashes = match c {
+                    '\u{2504}'..='\u{2507}' => 3,
+                    '\u{2508}'..='\u{250b}' => 4,
+                    _ => 2,
+                };
+                if right == Weight::None {
+                    self.draw_dashes(false, self.thickness(up), dashes);
+                } else {
+                    self.draw_dashes(true, self.thickness(right), dashes);
+                }
+            },
+            // Rounded corners
+            '\u{256d}' => self.draw_arc(1., 1.),
+            '\u{256e}' => self.draw_arc(-1., 1.),
+            '\u{256f}' => self.draw_arc(-1., -1.),
+            '\u{2570}' => self.draw_arc(1., -1.),
+            // Diagonals
+            '\u{2571}'..='\u{2573}' => {
+                let (width, height) = (self.width as f32, self.height as f32);
+                let radius = self.stroke as f32 / 2.;
+                let rising = c != '\u{2572}';
+                let falling = c != '\u{2571}';
+                self.fill_shape(|x, y| {
+                    (rising && line_distance((0., height), (width, 0.), (x, y)) <= radius)
+                        || (falling && line_distance((0., 0.), (width, height), (x, y)) <= radius)
+                });
+            },
+            _ => {
+                let [up, right, down, left] = BOX_LINES[c as usize - 0x2500];
+                self.draw_arm(true, true, (right, left), (up, down));
+                self.draw_arm(true, false, (left, right), (up, down));
+                self.draw_arm(false, true, (down, up), (left, right));
+                self.draw_arm(false, false, (up, down), (left, right));
+            },
+        }
+    }
+
+    /// Draw one arm of a line character, from the center to the edge of the cell.
+    ///
+    /// Positive arms point right or down. The `arms` are the weights of the drawn arm and the
+    /// one opposite to it, while `perpendicular` is ordered up/down for horizontal arms and
+    /// left/right for vertical ones.
+    fn draw_arm(
+        &mut self,
+        horizontal: bool,
+        positive: bool,
+        arms: (Weight, Weight),
+        perpendicular: (Weight, Weight),
+    ) {
+        let (weight, opposite) = arms;
+        if weight == Weight::None {
+            return;
+        }
+
+        let (center, center_across) = if horizontal {
+            (self.width / 2, self.height / 2)
+        } else {
+            (self.height / 2, self.width / 2)
+        };
+        let length = if horizontal { self.width } else { self.height };
+        let light = self.stroke;
+        let gap = self.double_gap();
+
+        // Map an anchor, given as offset from the center and line thickness, to the arm extent
+        let extent = |(offset, thickness): (i32, i32)| {
+            if positive {
+                (center + offset - thickness / 2, length)
+            } else {
+                (0, center - offset - thickness / 2 + thickness)
+            }
+        };
+
+        let has_solid_perpendicular = [perpendicular.0, perpendicular.1]
+            .iter()
+            .any(|weight| *weight == Weight::Light || *weight == Weight::Heavy);
+        let perpendicular_thickness =
+            cmp::max(self.thickness(perpendicular.0), self.thickness(perpendicular.1));
+
+        if weight == Weight::Double {
+            for &(side, same_side, other_side) in
+                &[(-1, perpendicular.0, perpendicular.1), (1, perpendicular.1, perpendicular.0)]
+            {
+                let anchor = if same_side == Weight::Double {
+                    // Stop at the closer stroke of the perpendicular double line
+                    (gap, light)
+                } else if has_solid_perpendicular {
+                    (0, perpendicular_thickness)
+                } else if other_side == Weight::Double && opposite == Weight::None {
+                    // Outer stroke of a corner extends to the farther perpendicular stroke
+                    (-gap, light)
+                } else {
+                    (0, light)
+                };
+
+                let across = center_across + side * gap - light / 2;
+                self.fill_line(horizontal, extent(anchor), (across, across + light));
+            }
+        } else {
+            let thickness = self.thickness(weight);
+            let perpendicular_double =
+                perpendicular.0 == Weight::Double || perpendicular.1 == Weight::Double;
+
+            let anchor = if !perpendicular_double {
+                (0, cmp::max(perpendicular_thickness, thickness))
+            } else if opposite != Weight::None {
+                // Pass straight through the double line
+                (0, thickness)
+            } else if perpendicular.0 == Weight::Double && perpendicular.1 == Weight::Double {
+                // Attach to the closer stroke of the double line
+                (gap, light)
+            } else {
+                // Corner joining the farther stroke of the double line
+                (-gap, light)
+            };
+
+            let across = center_across - thickness / 2;
+            self.fill_line(horizontal, extent(anchor), (across, across + thickness));
+        }
+    }
+
+    fn draw_dashes(&mut self, horizontal: bool, thickness: i32, dashes: i32) {
+        let (length, across) = if horizontal {
+            (self.width, self.height / 2 - thickness / 2)
+        } else {
+            (self.height, self.width / 2 - thickness / 2)
+        };
+
+        let segment = length as f32 / dashes as f32;
+        let gap = (segment / 4.).max(1.);
+        for dash in 0..dashes {
+            let start = dash as f32 * segment + gap / 2.;
+            let end = (dash + 1) as f32 * segment - gap / 2.;
+            let along = (start.round() as i32, end.round() as i32);
+            self.fill_line(horizontal, along, (across, across + thickness));
+        }
+    }
+
+    /// Draw a rounded corner, connecting the center of the edges in the `x` and `y` direction.
+    fn draw_arc(&mut self, x: f32, y: f32) {
+        let stroke = self.stroke;
+        let radius = stroke as f32 / 2.;
+
+        // Center of the pixels covered by straight lines through the cell center
+        let line_x = (self.width / 2 - stroke / 2) as f32 + radius;
+        let line_y = (self.height / 2 - stroke / 2) as f32 + radius;
+
+        let arc_radius =
+            line_x.min(self.width as f32 - line_x).min(line_y).min(self.height as f32 - line_y);
+        let arc_x = line_x + x * arc_radius;
+        let arc_y = line_y + y * arc_radius;
+
+        self.fill_shape(|px, py| {
+            let in_quadrant = (px - arc_x) * x <= 0. && (py - arc_y) * y <= 0.;
+            let distance = ((px - arc_x).powi(2) + (py - arc_y).powi(2)).sqrt();
+            in_quadrant && (distance - arc_radius).abs() <= radius
+        });
+
+        // Extend the arc to the edges of the cell
+        let across_y = self.height / 2 - stroke / 2;
+        let across_x = self.width / 2 - stroke / 2;
+        let arc_x = arc_x.round() as i32;
+        let arc_y = arc_y.round() as i32;
+        if x > 0. {
+            self.fill_rect(arc_x, self.width, across_y, across_y + stroke, 255);
+        } else {
+            self.fill_rect(0, arc_x, across_y, across_y + stroke, 255);
+        }
+        if y > 0. {
+            self.fill_rect(across_x, across_x + stroke, arc_y, self.height, 255);
+        } else {
+            self.fill_rect(across_x, across_x + stroke, 0, arc_y, 255);
+        }
+    }
+
+    fn draw_block(&mut self, c: char) {
+        let (width, height) = (self.width, self.height);
+
+        // Split point after `eighths` of `length`
+        let split =
+            |length: i32, eighths: i32| (length as f32 * eighths as f32 / 8.).round() as i32;
+        let (mid_x, mid_y) = (split(width, 4), split(height, 4));
+
+        match c {
+            '\u{2580}' => self.fill_rect(0, width, 0, mid_y, 255),
+            '\u{2581}'..='\u{2588}' => {
+                let eighths = c as i32 - 0x2580;
+                self.fill_rect(0, width, split(height, 8 - eighths), height, 255);
+            },
+            '\u{2589}'..='\u{258f}' => {
+                let eighths = 0x2590 - c as i32;
+                self.fill_rect(0, split(width, eighths), 0, height, 255);
+            },
+            '\u{2590}' => self.fill_rect(mid_x, width, 0, height, 255),
+            '\u{2591}' => self.fill_rect(0, width, 0, height, 64),
+            '\u{2592}' => self.fill_rect(0, width, 0, height, 128),
+            '\u{2593}' => self.fill_rect(0, width, 0, height, 192),
+            '\u{2594}' => self.fill_rect(0, width, 0, split(height, 1), 255),
+            '\u{2595}' => self.fill_rect(split(width, 7), width, 0, height, 255),
+            _ => {
+                // Quadrants as upper left, upper right, lower left and lower right
+                let quadrants = match c {
+                    '\u{2596}' => [false, false, true, false],
+                    '\u{2597}' => [false, false, false, true],
+                    '\u{2598}' => [true, false, false, false],
+                    '\u{2599}' => [true, false, true, true],
+                    '\u{259a}' => [true, false, false, true],
+                    '\u{259b}' => [true, true, true, false],
+                    '\u{259c}' => [true, true, false, true],
+                    '\u{259d}' => [false, true, false, false],
+                    '\u{259e}' => [false, true, true, false],
+                    _ => [false, true, true, true],
+                };
+
+                let columns = [(0, mid_x), (mid_x, width)];
+                let rows = [(0, mid_y), (mid_y, height)];
+                for (i, _) in quadrants.iter().enumerate().filter(|(_, filled)| **filled) {
+                    let (x0, x1) = columns[i % 2];
+                    let (y0, y1) = rows[i / 2];
+                    self.fill_rect(x0, x1, y0, y1, 255);
+                }
+            },
+        }
+    }
+
+    fn draw_powerline(&mut self, c: char) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        let radius = self.stroke as f32 / 2.;
+        let half = height / 2.;
+
+        // Symbols pointing to the left are mirrored versions of the right ones
+        let mirror = match c {
+            '\u{e0b2}' | '\u{e0b3}' | '\u{e0b6}' | '\u{e0b7}' => true,
+            _ => false,
+        };
+        let flip = |x: f32| if mirror { width - x } else { x };
+
+        match c {
+            // Solid and thin arrows
+            '\u{e0b0}' | '\u{e0b2}' => {
+                self.fill_shape(|x, y| flip(x) * half <= width * (half - (y - half).abs()))
+            },
+            '\u{e0b1}' | '\u{e0b3}' => self.fill_shape(|x, y| {
+                let point = (flip(x), y);
+                segment_distance((0., 0.), (width, half), point) <= radius
+                    || segment_distance((width, half), (0., height), point) <= radius
+            }),
+            // Solid and thin half circles
+            '\u{e0b4}' | '\u{e0b6}' => self
+                .fill_shape(|x, y| (flip(x) / width).powi(2) + ((y - half) / half).powi(2) <= 1.),
+            '\u{e0b5}' | '\u{e0b7}' => {
+                let stroke = self.stroke as f32;
+                self.fill_shape(|x, y| {
+                    let x = flip(x);
+                    let outer = (x / width).powi(2) + ((y - half) / half).powi(2);
+                    let inner =
+                        (x / (width - stroke)).powi(2) + ((y - half) / (half - stroke)).powi(2);
+                    outer <= 1. && inner >= 1.
+                })
+            },
+            // Triangles filling half the cell
+            '\u{e0b8}' => self.fill_shape(|x, y| x / width <= y / height),
+            '\u{e0ba}' => self.fill_shape(|x, y| x / width >= 1. - y / height),
+            '\u{e0bc}' => self.fill_shape(|x, y| x / width <= 1. - y / height),
+            '\u{e0be}' => self.fill_shape(|x, y| x / width >= y / height),
+            // Diagonal separators
+            '\u{e0b9}' | '\u{e0bf}' => {
+                self.fill_shape(|x, y| line_distance((0., 0.), (width, height), (x, y)) <= radius)
+            },
+            _ => self.fill_shape(|x, y| line_distance((0., height), (width, 0.), (x, y)) <= radius),
+        }
+    }
+}
+
+/// Distance of `point` to the infinite line through `start` and `end`.
+fn line_distance(start: (f32, f32), end: (f32, f32), point: (f32, f32)) -> f32 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    (dy * (point.0 - start.0) - dx * (point.1 - start.1)).abs() / (dx * dx + dy * dy).sqrt()
+}
+
+/// Distance of `point` to the line segment between `start` and `end`.
+fn segment_distance(start: (f32, f32), end: (f32, f32), point: (f32, f32)) -> f32 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let t = ((point.0 - start.0) * dx + (point.1 - start.1) * dy) / (dx * dx + dy * dy);
+    let t = t.max(0.).min(1.);
+    let (x, y) = (start.0 + t * dx, start.1 + t * dy);
+    ((point.0 - x).powi(2) + (point.1 - y).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> Metrics {
+        Metrics {
+            average_advance: 9.6,
+            line_height: 17.,
+            descent: -4.,
+            underline_position: -2.,
+            underline_thickness: 1.,
+            strikeout_position: 5.,
+            strikeout_thickness: 1.,
+        }
+    }
+
+    fn coverage(glyph: &RasterizedGlyph, x: i32, y: i32) -> u8 {
+        match &glyph.buf {
+            BitmapBuffer::RGB(buf) => buf[((y * glyph.width + x) * 3) as usize],
+            BitmapBuffer::RGBA(buf) => buf[((y * glyph.width + x) * 4 + 3) as usize],
+        }
+    }
+
+    #[test]
+    fn glyph_fills_cell() {
+        let offset = Delta { x: 1, y: 2 };
+        let glyph = builtin_glyph('\u{2588}', &metrics(), offset).unwrap();
+
+        assert_eq!((glyph.width, glyph.height, glyph.top, glyph.left), (10, 19, 19, 0));
+        for y in 0..glyph.height {
+            for x in 0..glyph.width {
+                assert_eq!(coverage(&glyph, x, y), 255);
+            }
+        }
+    }
+
+    #[test]
+    fn lines_reach_cell_edges() {
+        let glyph = builtin_glyph('\u{253c}', &metrics(), Delta::default()).unwrap();
+
+        assert_eq!(coverage(&glyph, 4, 0), 255);
+        assert_eq!(coverage(&glyph, 4, glyph.height - 1), 255);
+        assert_eq!(coverage(&glyph, 0, 8), 255);
+        assert_eq!(coverage(&glyph, glyph.width - 1, 8), 255);
+    }
+
+    #[test]
+    fn double_corner() {
+        let glyph = builtin_glyph('\u{2554}', &metrics(), Delta::default()).unwrap();
+
+        // Outer stroke turns the corner, inner stroke starts at the inner corner
+        assert_eq!(coverage(&glyph, 3, 7), 255);
+        assert_eq!(coverage(&glyph, 4, 7), 255);
+        assert_eq!(coverage(&glyph, 4, 8), 0);
+        assert_eq!(coverage(&glyph, 5, 9), 255);
+        assert_eq!(coverage(&glyph, 4, 9), 0);
+    }
+
+    #[test]
+    fn unsupported_characters() {
+        assert!(builtin_glyph('a', &metrics(), Delta::default()).is_none());
+        assert!(builtin_glyph('\u{25a0}', &metrics(), Delta::default()).is_none());
+    }
+}
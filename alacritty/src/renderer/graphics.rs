@@ -0,0 +1,309 @@
+//! Rendering of graphics embedded in the terminal grid
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::ptr;
+
+use alacritty_terminal::graphics::{GraphicCell, GraphicId, UpdateQueues};
+use alacritty_terminal::index::{Column, Line};
+use alacritty_terminal::term::SizeInfo;
+
+use crate::gl;
+use crate::gl::types::*;
+use crate::renderer::{create_program, create_shader, ShaderCreationError};
+
+// Shader paths for live reload
+static GRAPHICS_SHADER_F_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.f.glsl");
+static GRAPHICS_SHADER_V_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.v.glsl");
+
+// Shader source which is used when live-shader-reload feature is disable
+static GRAPHICS_SHADER_F: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.f.glsl"));
+static GRAPHICS_SHADER_V: &str =
+    include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/../res/graphics.v.glsl"));
+
+/// Number of floats per vertex; position followed by texture coordinates.
+const VERTEX_SIZE: usize = 4;
+
+/// Part of a graphic which is drawn in a single cell.
+#[derive(Debug)]
+pub struct RenderGraphic {
+    pub line: Line,
+    pub column: Column,
+    pub graphic: GraphicCell,
+}
+
+/// Graphic uploaded to the GPU.
+#[derive(Debug)]
+struct GraphicTexture {
+    id: GLuint,
+    width: f32,
+    height: f32,
+
+    /// Cell dimensions when the graphic was inserted.
+    cell_width: f32,
+    cell_height: f32,
+}
+
+/// Graphic drawing program
+#[derive(Debug)]
+pub struct GraphicsShaderProgram {
+    // Program id
+    id: GLuint,
+}
+
+impl GraphicsShaderProgram {
+    pub fn new() -> Result<Self, ShaderCreationError> {
+        let (vertex_src, fragment_src) = if cfg!(feature = "live-shader-reload") {
+            (None, None)
+        } else {
+            (Some(GRAPHICS_SHADER_V), Some(GRAPHICS_SHADER_F))
+        };
+        let vertex_shader = create_shader(GRAPHICS_SHADER_V_PATH, gl::VERTEX_SHADER, vertex_src)?;
+        let fragment_shader =
+            create_shader(GRAPHICS_SHADER_F_PATH, gl::FRAGMENT_SHADER, fragment_src)?;
+        let program = create_program(vertex_shader, fragment_shader)?;
+
+        unsafe {
+            gl::DeleteShader(fragment_shader);
+            gl::DeleteShader(vertex_shader);
+        }
+
+        Ok(Self { id: program })
+    }
+}
+
+impl Drop for GraphicsShaderProgram {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.id);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GraphicsRenderer {
+    pub program: GraphicsShaderProgram,
+    vao: GLuint,
+    vbo: GLuint,
+    textures: HashMap<GraphicId, GraphicTexture>,
+}
+
+impl GraphicsRenderer {
+    pub fn new() -> Result<Self, ShaderCreationError> {
+        let program = GraphicsShaderProgram::new()?;
+
+        let mut vao: GLuint = 0;
+        let mut vbo: GLuint = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            // Position
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (VERTEX_SIZE * size_of::<f32>()) as _,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Texture coordinates
+            gl::VertexAttribPointer(
+                1,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (VERTEX_SIZE * size_of::<f32>()) as _,
+                (2 * size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+
+        Ok(Self { program, vao, vbo, textures: HashMap::new() })
+    }
+
+    /// Upload new graphics and release the textures which are no longer used.
+    ///
+    /// This leaves the texture binding of the active texture unit in an undefined state.
+    pub fn update(&mut self, queues: UpdateQueues) {
+        for id in queues.remove_queue {
+            if let Some(texture) = self.textures.remove(&id) {
+                unsafe {
+                    gl::DeleteTextures(1, &texture.id);
+                }
+            }
+        }
+
+        for data in queues.pending {
+            let graphic = &data.graphic;
+
+            let mut id: GLuint = 0;
+            unsafe {
+                gl::GenTextures(1, &mut id);
+                gl::BindTexture(gl::TEXTURE_2D, id);
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA as i32,
+                    graphic.width as i32,
+                    graphic.height as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    graphic.pixels.as_ptr() as *const _,
+                );
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                gl::BindTexture(gl::TEXTURE_2D, 0);
+            }
+
+            self.textures.insert(data.id, GraphicTexture {
+                id,
+                width: graphic.width as f32,
+                height: graphic.height as f32,
+                cell_width: data.cell_width as f32,
+                cell_height: data.cell_height as f32,
+            });
+        }
+    }
+
+    /// Draw the visible parts of all graphics.
+    ///
+    /// This leaves the texture binding of the active texture unit in an undefined state.
+    pub fn draw(&mut self, mut graphics: Vec<RenderGraphic>, size: &SizeInfo) {
+        if graphics.is_empty() {
+            return;
+        }
+
+        // Draw all cells of a graphic with a single call
+        graphics.sort_by_key(|graphic| graphic.graphic.texture.id);
+
+        unsafe {
+            gl::UseProgram(self.program.id);
+
+            // Remove padding from viewport
+            gl::Viewport(0, 0, size.width as i32, size.height as i32);
+
+            // Change blending strategy
+            gl::BlendFuncSeparate(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA, gl::SRC_ALPHA, gl::ONE);
+
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+
+        let mut vertices = Vec::new();
+        for (index, graphic) in graphics.iter().enumerate() {
+            let texture = match self.textures.get(&graphic.graphic.texture.id) {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            push_vertices(&mut vertices, graphic, texture, size);
+
+            // Flush once all cells of this graphic have been added
+            let next_id = graphics.get(index + 1).map(|next| next.graphic.texture.id);
+            if next_id != Some(graphic.graphic.texture.id) {
+                unsafe {
+                    gl::BindTexture(gl::TEXTURE_2D, texture.id);
+                    gl::BufferData(
+                        gl::ARRAY_BUFFER,
+                        (vertices.len() * size_of::<f32>()) as isize,
+                        vertices.as_ptr() as *const _,
+                        gl::STREAM_DRAW,
+                    );
+                    gl::DrawArrays(gl::TRIANGLES, 0, (vertices.len() / VERTEX_SIZE) as i32);
+                }
+
+                vertices.clear();
+            }
+        }
+
+        unsafe {
+            // Reset blending strategy
+            gl::BlendFunc(gl::SRC1_COLOR, gl::ONE_MINUS_SRC1_COLOR);
+
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+
+            let padding_x = size.padding_x as i32;
+            let padding_y = size.padding_y as i32;
+            let width = size.width as i32;
+            let height = size.height as i32;
+            gl::Viewport(padding_x, padding_y, width - 2 * padding_x, height - 2 * padding_y);
+
+            gl::UseProgram(0);
+        }
+    }
+}
+
+impl Drop for GraphicsRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            for texture in self.textures.values() {
+                gl::DeleteTextures(1, &texture.id);
+            }
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+/// Add the two triangles covering a cell's part of a graphic.
+fn push_vertices(
+    vertices: &mut Vec<f32>,
+    graphic: &RenderGraphic,
+    texture: &GraphicTexture,
+    size: &SizeInfo,
+) {
+    let offset_x = f32::from(graphic.graphic.offset_x);
+    let offset_y = f32::from(graphic.graphic.offset_y);
+
+    // Cells on the right and bottom edge are only partially covered
+    let width = texture.cell_width.min(texture.width - offset_x);
+    let height = texture.cell_height.min(texture.height - offset_y);
+    if width <= 0. || height <= 0. {
+        return;
+    }
+
+    // Scale to the current cell dimensions, in case the font size has changed
+    let x = size.padding_x + graphic.column.0 as f32 * size.cell_width;
+    let y = size.padding_y + graphic.line.0 as f32 * size.cell_height;
+    let right = x + width / texture.cell_width * size.cell_width;
+    let bottom = y + height / texture.cell_height * size.cell_height;
+
+    // Convert to OpenGL coordinates
+    let ndc_x = |x: f32| x / size.width * 2. - 1.;
+    let ndc_y = |y: f32| 1. - y / size.height * 2.;
+    let (left, right) = (ndc_x(x), ndc_x(right));
+    let (top, bottom) = (ndc_y(y), ndc_y(bottom));
+
+    let uv_left = offset_x / texture.width;
+    let uv_right = (offset_x + width) / texture.width;
+    let uv_top = offset_y / texture.height;
+    let uv_bottom = (offset_y + height) / texture.height;
+
+    #[rustfmt::skip]
+    let quad = [
+        left,  top,    uv_left,  uv_top,
+        right, top,    uv_right, uv_top,
+        left,  bottom, uv_left,  uv_bottom,
+        right, top,    uv_right, uv_top,
+        right, bottom, uv_right, uv_bottom,
+        left,  bottom, uv_left,  uv_bottom,
+    ];
+    vertices.extend_from_slice(&quad);
+}
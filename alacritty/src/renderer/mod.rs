@@ -15,7 +15,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::hash::BuildHasherDefault;
 use std::io;
-use std::mem::size_of;
+use std::mem::{self, size_of};
 use std::path::PathBuf;
 use std::ptr;
 use std::sync::mpsc;
@@ -31,15 +31,19 @@ use notify::{watcher, DebouncedEvent, RecursiveMode, Watcher};
 use crate::cursor;
 use crate::gl;
 use crate::gl::types::*;
+use crate::renderer::graphics::{GraphicsRenderer, GraphicsShaderProgram, RenderGraphic};
 use crate::renderer::rects::RenderRect;
 use alacritty_terminal::config::{self, Config, Delta, Font, StartupMode};
-use alacritty_terminal::index::{Column, Line};
-use alacritty_terminal::term::cell::{self, Flags};
+use alacritty_terminal::graphics::UpdateQueues;
+use alacritty_terminal::index::Point;
+use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::term::color::Rgb;
 use alacritty_terminal::term::{self, CursorKey, RenderableCell, RenderableCellContent, SizeInfo};
 use alacritty_terminal::util;
 use std::fmt::{self, Display, Formatter};
 
+mod builtin_font;
+pub mod graphics;
 pub mod rects;
 
 // Shader paths for live reload
@@ -61,7 +65,9 @@ static RECT_SHADER_V: &str =
 /// `LoadGlyph` allows for copying a rasterized glyph into graphics memory
 pub trait LoadGlyph {
     /// Load the rasterized glyph into GPU memory
-    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph;
+    ///
+    /// Fails with `AtlasInsertError::Full` once all atlas pages are exhausted.
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Result<Glyph, AtlasInsertError>;
 
     /// Clear any state accumulated from previous loaded glyphs
     ///
@@ -133,7 +139,7 @@ pub struct RectShaderProgram {
     u_color: GLint,
 }
 
-#[derive(Copy, Debug, Clone)]
+#[derive(Copy, Debug, Clone, Default)]
 pub struct Glyph {
     tex_id: GLuint,
     colored: bool,
@@ -179,6 +185,12 @@ pub struct GlyphCache {
     /// glyph offset
     glyph_offset: Delta<i8>,
 
+    /// extra spacing per character
+    font_offset: Delta<i8>,
+
+    /// draw box drawing characters without the font
+    builtin_box_drawing: bool,
+
     metrics: font::Metrics,
 }
 
@@ -191,6 +203,8 @@ impl GlyphCache {
     where
         L: LoadGlyph,
     {
+        rasterizer.set_style_synthesis(font.builtin_style_synthesis());
+
         let (regular, bold, italic, bold_italic) = Self::compute_font_keys(font, &mut rasterizer)?;
 
         // Need to load at least one glyph for the face before calling metrics.
@@ -198,7 +212,7 @@ impl GlyphCache {
         // meaning.
         rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
 
-        let metrics = rasterizer.metrics(regular, font.size)?;
+        let metrics = spaced_metrics(font, rasterizer.metrics(regular, font.size)?);
 
         let mut cache = Self {
             cache: HashMap::default(),
@@ -210,6 +224,8 @@ impl GlyphCache {
             italic_key: italic,
             bold_italic_key: bold_italic,
             glyph_offset: font.glyph_offset,
+            font_offset: font.offset,
+            builtin_box_drawing: font.builtin_box_drawing(),
             metrics,
         };
 
@@ -278,25 +294,69 @@ impl GlyphCache {
             font::Style::Description { slant, weight }
         };
         FontDesc::new(desc.family.clone(), style)
+            .with_hinting(desc.hinting.map(Into::into), desc.autohint)
     }
 
-    pub fn get<L>(&mut self, glyph_key: GlyphKey, loader: &mut L) -> &Glyph
+    pub fn get<L>(&mut self, glyph_key: GlyphKey, loader: &mut L) -> Glyph
     where
         L: LoadGlyph,
     {
-        let glyph_offset = self.glyph_offset;
-        let rasterizer = &mut self.rasterizer;
-        let metrics = &self.metrics;
-        self.cache.entry(glyph_key).or_insert_with(|| {
+        if let Some(glyph) = self.cache.get(&glyph_key) {
+            return *glyph;
+        }
+
+        let builtin = if self.builtin_box_drawing {
+            builtin_font::builtin_glyph(glyph_key.c, &self.metrics, self.font_offset)
+        } else {
+            None
+        };
+
+        let rasterized = builtin.unwrap_or_else(|| {
             let mut rasterized =
-                rasterizer.get_glyph(glyph_key).unwrap_or_else(|_| Default::default());
+                self.rasterizer.get_glyph(glyph_key).unwrap_or_else(|_| Default::default());
 
-            rasterized.left += i32::from(glyph_offset.x);
-            rasterized.top += i32::from(glyph_offset.y);
-            rasterized.top -= metrics.descent as i32;
+            rasterized.left += i32::from(self.glyph_offset.x);
+            rasterized.top += i32::from(self.glyph_offset.y);
+            rasterized.top -= self.metrics.descent as i32;
 
-            loader.load_glyph(&rasterized)
-        })
+            rasterized
+        });
+
+        let glyph = self.load_glyph(&rasterized, loader);
+        self.cache.insert(glyph_key, glyph);
+
+        glyph
+    }
+
+    /// Get the glyph for a cursor, rasterizing it if it isn't cached yet.
+    pub fn get_cursor<L, F>(&mut self, cursor_key: CursorKey, loader: &mut L, rasterize: F) -> Glyph
+    where
+        L: LoadGlyph,
+        F: FnOnce(font::Metrics) -> RasterizedGlyph,
+    {
+        if let Some(glyph) = self.cursor_cache.get(&cursor_key) {
+            return *glyph;
+        }
+
+        let glyph = self.load_glyph(&rasterize(self.metrics), loader);
+        self.cursor_cache.insert(cursor_key, glyph);
+
+        glyph
+    }
+
+    /// Upload a glyph to the atlas.
+    ///
+    /// When every atlas page is full, all cached glyphs are evicted and the atlas is repacked
+    /// starting with the common glyphs, which keeps GPU memory bounded for long sessions.
+    fn load_glyph<L: LoadGlyph>(&mut self, rasterized: &RasterizedGlyph, loader: &mut L) -> Glyph {
+        match loader.load_glyph(rasterized) {
+            Ok(glyph) => glyph,
+            Err(_) => {
+                info!("Glyph atlas is full; evicting cached glyphs");
+                self.clear_glyph_cache(loader);
+                loader.load_glyph(rasterized).unwrap_or_default()
+            },
+        }
     }
 
     /// Clear currently cached data in both GL and the registry.
@@ -308,6 +368,11 @@ impl GlyphCache {
         self.load_common_glyphs(loader);
     }
 
+    /// Change the subpixel layout, taking effect once the fonts are reloaded.
+    pub fn set_subpixel(&mut self, subpixel: font::Subpixel) {
+        self.rasterizer.set_subpixel(subpixel);
+    }
+
     pub fn update_font_size<L: LoadGlyph>(
         &mut self,
         font: config::Font,
@@ -316,13 +381,14 @@ impl GlyphCache {
     ) -> Result<(), font::Error> {
         // Update dpi scaling
         self.rasterizer.update_dpr(dpr as f32);
+        self.rasterizer.set_style_synthesis(font.builtin_style_synthesis());
 
         // Recompute font keys
         let (regular, bold, italic, bold_italic) =
             Self::compute_font_keys(&font, &mut self.rasterizer)?;
 
         self.rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
-        let metrics = self.rasterizer.metrics(regular, font.size)?;
+        let metrics = spaced_metrics(&font, self.rasterizer.metrics(regular, font.size)?);
 
         info!("Font size changed to {:?} with DPR of {}", font.size, dpr);
 
@@ -331,6 +397,9 @@ impl GlyphCache {
         self.bold_key = bold;
         self.italic_key = italic;
         self.bold_italic_key = bold_italic;
+        self.glyph_offset = font.glyph_offset;
+        self.font_offset = font.offset;
+        self.builtin_box_drawing = font.builtin_box_drawing();
         self.metrics = metrics;
 
         self.clear_glyph_cache(loader);
@@ -338,6 +407,31 @@ impl GlyphCache {
         Ok(())
     }
 
+    /// Load the fonts with a new rasterizer, which picks up changed font files and options.
+    ///
+    /// The previous rasterizer is kept if the fonts cannot be loaded.
+    pub fn reload_font<L: LoadGlyph>(
+        &mut self,
+        rasterizer: Rasterizer,
+        font: config::Font,
+        dpr: f64,
+        loader: &mut L,
+    ) -> Result<(), font::Error> {
+        let previous = mem::replace(&mut self.rasterizer, rasterizer);
+
+        let result = self.update_font_size(font, dpr, loader);
+        if result.is_err() {
+            self.rasterizer = previous;
+        }
+
+        result
+    }
+
+    /// Number of glyphs currently stored in the atlas.
+    pub fn len(&self) -> usize {
+        self.cache.len() + self.cursor_cache.len()
+    }
+
     pub fn font_metrics(&self) -> font::Metrics {
         self.metrics
     }
@@ -358,7 +452,7 @@ impl GlyphCache {
         let regular = rasterizer.load_font(&regular_desc, font.size)?;
         rasterizer.get_glyph(GlyphKey { font_key: regular, c: 'm', size: font.size })?;
 
-        rasterizer.metrics(regular, font.size)
+        Ok(spaced_metrics(&font, rasterizer.metrics(regular, font.size)?))
     }
 
     pub fn calculate_dimensions<C>(
@@ -428,6 +522,7 @@ pub struct QuadRenderer {
     vbo_instance: GLuint,
     rect_vao: GLuint,
     rect_vbo: GLuint,
+    graphics: GraphicsRenderer,
     atlas: Vec<Atlas>,
     current_atlas: usize,
     active_tex: GLuint,
@@ -464,18 +559,18 @@ impl Batch {
         Self { tex: 0, instances: Vec::with_capacity(BATCH_MAX) }
     }
 
-    pub fn add_item(&mut self, mut cell: RenderableCell, glyph: &Glyph) {
+    pub fn add_item(&mut self, cell: &RenderableCell, glyph: &Glyph) {
         if self.is_empty() {
             self.tex = glyph.tex_id;
         }
 
-        if glyph.colored {
+        let fg = if glyph.colored {
             // XXX Temporary workaround to prevent emojis being rendered with a wrong colors on, at
             // least, dark backgrounds. For more info see #1864.
-            cell.fg.r = 255;
-            cell.fg.g = 255;
-            cell.fg.b = 255;
-        }
+            Rgb { r: 255, g: 255, b: 255 }
+        } else {
+            cell.fg
+        };
 
         self.instances.push(InstanceData {
             col: cell.column.0 as f32,
@@ -491,9 +586,9 @@ impl Batch {
             uv_width: glyph.uv_width,
             uv_height: glyph.uv_height,
 
-            r: f32::from(cell.fg.r),
-            g: f32::from(cell.fg.g),
-            b: f32::from(cell.fg.b),
+            r: f32::from(fg.r),
+            g: f32::from(fg.g),
+            b: f32::from(fg.b),
 
             bg_r: f32::from(cell.bg.r),
             bg_g: f32::from(cell.bg.g),
@@ -537,10 +632,14 @@ impl Batch {
 const BATCH_MAX: usize = 0x1_0000;
 const ATLAS_SIZE: i32 = 1024;
 
+/// Maximum number of atlas pages before cached glyphs are evicted
+pub const ATLAS_MAX_PAGES: usize = 8;
+
 impl QuadRenderer {
     pub fn new() -> Result<QuadRenderer, Error> {
         let program = TextShaderProgram::new()?;
         let rect_program = RectShaderProgram::new()?;
+        let graphics = GraphicsRenderer::new()?;
 
         let mut vao: GLuint = 0;
         let mut ebo: GLuint = 0;
@@ -702,6 +801,7 @@ impl QuadRenderer {
             vbo_instance,
             rect_vao,
             rect_vbo,
+            graphics,
             atlas: Vec::new(),
             current_atlas: 0,
             active_tex: 0,
@@ -826,9 +926,10 @@ impl QuadRenderer {
 
     pub fn reload_shaders(&mut self, props: &term::SizeInfo) {
         info!("Reloading shaders...");
-        let result = (TextShaderProgram::new(), RectShaderProgram::new());
-        let (program, rect_program) = match result {
-            (Ok(program), Ok(rect_program)) => {
+        let result =
+            (TextShaderProgram::new(), RectShaderProgram::new(), GraphicsShaderProgram::new());
+        let (program, rect_program, graphics_program) = match result {
+            (Ok(program), Ok(rect_program), Ok(graphics_program)) => {
                 unsafe {
                     gl::UseProgram(program.id);
                     program.update_projection(
@@ -841,9 +942,9 @@ impl QuadRenderer {
                 }
 
                 info!("... successfully reloaded shaders");
-                (program, rect_program)
+                (program, rect_program, graphics_program)
             },
-            (Err(err), _) | (_, Err(err)) => {
+            (Err(err), ..) | (_, Err(err), _) | (.., Err(err)) => {
                 error!("{}", err);
                 return;
             },
@@ -852,6 +953,24 @@ impl QuadRenderer {
         self.active_tex = 0;
         self.program = program;
         self.rect_program = rect_program;
+        self.graphics.program = graphics_program;
+    }
+
+    /// Upload new graphics and release the ones no longer visible in the grid.
+    pub fn update_graphics(&mut self, queues: UpdateQueues) {
+        self.graphics.update(queues);
+        self.active_tex = 0;
+    }
+
+    /// Draw the graphics on top of the grid.
+    pub fn draw_graphics(&mut self, props: &term::SizeInfo, graphics: Vec<RenderGraphic>) {
+        self.graphics.draw(graphics, props);
+        self.active_tex = 0;
+    }
+
+    /// Number of atlas pages with glyphs in them.
+    pub fn atlas_pages(&self) -> usize {
+        self.current_atlas + 1
     }
 
     pub fn resize(&mut self, size: &SizeInfo) {
@@ -909,10 +1028,20 @@ impl QuadRenderer {
     }
 }
 
+/// Scale the cell of the font metrics by the configured line height and letter spacing.
+///
+/// Additional line height is split evenly above and below the baseline.
+fn spaced_metrics(font: &Font, mut metrics: font::Metrics) -> font::Metrics {
+    let extra_height = metrics.line_height * (font.line_height.as_f64() - 1.);
+    metrics.line_height += extra_height;
+    metrics.descent -= (extra_height / 2.).round() as f32;
+    metrics.average_advance *= font.letter_spacing.as_f64();
+    metrics
+}
+
 impl<'a, C> RenderApi<'a, C> {
-    pub fn clear(&self, color: Rgb) {
+    pub fn clear(&self, color: Rgb, alpha: f32) {
         unsafe {
-            let alpha = self.config.background_opacity();
             gl::ClearColor(
                 (f32::from(color.r) / 255.0).min(1.0) * alpha,
                 (f32::from(color.g) / 255.0).min(1.0) * alpha,
@@ -974,12 +1103,12 @@ impl<'a, C> RenderApi<'a, C> {
     pub fn render_string(
         &mut self,
         string: &str,
-        line: Line,
+        point: Point,
         glyph_cache: &mut GlyphCache,
         color: Option<Rgb>,
     ) {
         let bg_alpha = color.map(|_| 1.0).unwrap_or(0.0);
-        let col = Column(0);
+        let Point { line, col } = point;
 
         let cells = string
             .chars()
@@ -987,15 +1116,14 @@ impl<'a, C> RenderApi<'a, C> {
             .map(|(i, c)| RenderableCell {
                 line,
                 column: col + i,
-                inner: RenderableCellContent::Chars({
-                    let mut chars = [' '; cell::MAX_ZEROWIDTH_CHARS + 1];
-                    chars[0] = c;
-                    chars
-                }),
+                inner: RenderableCellContent::Chars((c, None)),
                 bg: color.unwrap_or(Rgb { r: 0, g: 0, b: 0 }),
                 fg: Rgb { r: 0, g: 0, b: 0 },
+                underline: Rgb { r: 0, g: 0, b: 0 },
                 flags: Flags::empty(),
                 bg_alpha,
+                graphic: None,
+                hyperlink: None,
             })
             .collect::<Vec<_>>();
 
@@ -1005,7 +1133,7 @@ impl<'a, C> RenderApi<'a, C> {
     }
 
     #[inline]
-    fn add_render_item(&mut self, cell: RenderableCell, glyph: &Glyph) {
+    fn add_render_item(&mut self, cell: &RenderableCell, glyph: &Glyph) {
         // Flush batch if tex changing
         if !self.batch.is_empty() && self.batch.tex != glyph.tex_id {
             self.render_batch();
@@ -1019,25 +1147,25 @@ impl<'a, C> RenderApi<'a, C> {
         }
     }
 
-    pub fn render_cell(&mut self, cell: RenderableCell, glyph_cache: &mut GlyphCache) {
-        let chars = match cell.inner {
+    pub fn render_cell(&mut self, mut cell: RenderableCell, glyph_cache: &mut GlyphCache) {
+        let (c, zerowidth) = match cell.inner {
             RenderableCellContent::Cursor(cursor_key) => {
                 // Raw cell pixel buffers like cursors don't need to go through font lookup
-                let metrics = glyph_cache.metrics;
-                let glyph = glyph_cache.cursor_cache.entry(cursor_key).or_insert_with(|| {
-                    self.load_glyph(&cursor::get_cursor_glyph(
+                let config = self.config;
+                let glyph = glyph_cache.get_cursor(cursor_key, self, |metrics| {
+                    cursor::get_cursor_glyph(
                         cursor_key.style,
                         metrics,
-                        self.config.font.offset.x,
-                        self.config.font.offset.y,
+                        config.font.offset.x,
+                        config.font.offset.y,
                         cursor_key.is_wide,
-                        self.config.cursor.thickness(),
-                    ))
+                        config.cursor.thickness(),
+                    )
                 });
-                self.add_render_item(cell, glyph);
+                self.add_render_item(&cell, &glyph);
                 return;
             },
-            RenderableCellContent::Chars(chars) => chars,
+            RenderableCellContent::Chars((c, ref mut zerowidth)) => (c, zerowidth.take()),
         };
 
         // Get font key for cell
@@ -1049,27 +1177,24 @@ impl<'a, C> RenderApi<'a, C> {
         };
 
         // Don't render text of HIDDEN cells
-        let mut chars = if cell.flags.contains(Flags::HIDDEN) {
-            [' '; cell::MAX_ZEROWIDTH_CHARS + 1]
-        } else {
-            chars
-        };
+        let (mut c, zerowidth) =
+            if cell.flags.contains(Flags::HIDDEN) { (' ', None) } else { (c, zerowidth) };
 
         // Render tabs as spaces in case the font doesn't support it
-        if chars[0] == '\t' {
-            chars[0] = ' ';
+        if c == '\t' {
+            c = ' ';
         }
 
-        let mut glyph_key = GlyphKey { font_key, size: glyph_cache.font_size, c: chars[0] };
+        let mut glyph_key = GlyphKey { font_key, size: glyph_cache.font_size, c };
 
         // Add cell to batch
         let glyph = glyph_cache.get(glyph_key, self);
-        self.add_render_item(cell, glyph);
+        self.add_render_item(&cell, &glyph);
 
         // Render zero-width characters
-        for c in (&chars[1..]).iter().filter(|c| **c != ' ') {
+        for c in zerowidth.iter().flatten() {
             glyph_key.c = *c;
-            let mut glyph = *glyph_cache.get(glyph_key, self);
+            let mut glyph = glyph_cache.get(glyph_key, self);
 
             // The metrics of zero-width characters are based on rendering
             // the character after the current cell, with the anchor at the
@@ -1078,26 +1203,30 @@ impl<'a, C> RenderApi<'a, C> {
             // anchor has been moved to the right by one cell.
             glyph.left += glyph_cache.metrics.average_advance as f32;
 
-            self.add_render_item(cell, &glyph);
+            self.add_render_item(&cell, &glyph);
         }
     }
 }
 
 /// Load a glyph into a texture atlas
 ///
-/// If the current atlas is full, a new one will be created.
+/// If the current atlas is full, a new one will be created until `ATLAS_MAX_PAGES` is reached.
 #[inline]
 fn load_glyph(
     active_tex: &mut GLuint,
     atlas: &mut Vec<Atlas>,
     current_atlas: &mut usize,
     rasterized: &RasterizedGlyph,
-) -> Glyph {
+) -> Result<Glyph, AtlasInsertError> {
     // At least one atlas is guaranteed to be in the `self.atlas` list; thus
     // the unwrap.
     match atlas[*current_atlas].insert(rasterized, active_tex) {
-        Ok(glyph) => glyph,
+        Ok(glyph) => Ok(glyph),
         Err(AtlasInsertError::Full) => {
+            if *current_atlas + 1 == ATLAS_MAX_PAGES {
+                return Err(AtlasInsertError::Full);
+            }
+
             *current_atlas += 1;
             if *current_atlas == atlas.len() {
                 let new = Atlas::new(ATLAS_SIZE);
@@ -1106,17 +1235,8 @@ fn load_glyph(
             }
             load_glyph(active_tex, atlas, current_atlas, rasterized)
         },
-        Err(AtlasInsertError::GlyphTooLarge) => Glyph {
-            tex_id: atlas[*current_atlas].id,
-            colored: false,
-            top: 0.0,
-            left: 0.0,
-            width: 0.0,
-            height: 0.0,
-            uv_bot: 0.0,
-            uv_left: 0.0,
-            uv_width: 0.0,
-            uv_height: 0.0,
+        Err(AtlasInsertError::GlyphTooLarge) => {
+            Ok(Glyph { tex_id: atlas[*current_atlas].id, ..Default::default() })
         },
     }
 }
@@ -1130,7 +1250,7 @@ fn clear_atlas(atlas: &mut Vec<Atlas>, current_atlas: &mut usize) {
 }
 
 impl<'a> LoadGlyph for LoaderApi<'a> {
-    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Result<Glyph, AtlasInsertError> {
         load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
     }
 
@@ -1140,11 +1260,16 @@ impl<'a> LoadGlyph for LoaderApi<'a> {
 }
 
 impl<'a, C> LoadGlyph for RenderApi<'a, C> {
-    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Glyph {
+    fn load_glyph(&mut self, rasterized: &RasterizedGlyph) -> Result<Glyph, AtlasInsertError> {
         load_glyph(self.active_tex, self.atlas, self.current_atlas, rasterized)
     }
 
     fn clear(&mut self) {
+        // Draw pending glyphs before their atlas content is overwritten
+        if !self.batch.is_empty() {
+            self.render_batch();
+        }
+
         clear_atlas(self.atlas, self.current_atlas)
     }
 }
@@ -1497,7 +1622,8 @@ struct Atlas {
 }
 
 /// Error that can happen when inserting a texture to the Atlas
-enum AtlasInsertError {
+#[derive(Debug)]
+pub enum AtlasInsertError {
     /// Texture atlas is full
     Full,
 
@@ -1657,3 +1783,110 @@ impl Atlas {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loader with room for a fixed number of glyphs.
+    struct MockLoader {
+        capacity: usize,
+        loaded: usize,
+        clears: usize,
+    }
+
+    impl LoadGlyph for MockLoader {
+        fn load_glyph(&mut self, _: &RasterizedGlyph) -> Result<Glyph, AtlasInsertError> {
+            if self.loaded == self.capacity {
+                return Err(AtlasInsertError::Full);
+            }
+
+            self.loaded += 1;
+            Ok(Glyph::default())
+        }
+
+        fn clear(&mut self) {
+            self.loaded = 0;
+            self.clears += 1;
+        }
+    }
+
+    /// Glyph cache of the default font, with room for all glyphs.
+    fn glyph_cache() -> (GlyphCache, MockLoader) {
+        let font = Font::default();
+        let rasterizer = Rasterizer::new(1., font.use_thin_strokes()).unwrap();
+        let mut loader = MockLoader { capacity: usize::max_value(), loaded: 0, clears: 0 };
+        let cache = GlyphCache::new(rasterizer, &font, &mut loader).unwrap();
+        (cache, loader)
+    }
+
+    #[test]
+    fn evict_glyphs_from_full_atlas() {
+        let (mut cache, mut loader) = glyph_cache();
+
+        // Leave room for a few glyphs besides the common ones
+        loader.capacity = cache.len() + 20;
+
+        for c in (0x100..0x200).filter_map(std::char::from_u32) {
+            cache.get(GlyphKey { font_key: cache.font_key, c, size: cache.font_size }, &mut loader);
+        }
+
+        assert!(loader.clears > 0);
+        assert_eq!(cache.len(), loader.loaded);
+        assert!(cache.len() <= loader.capacity);
+    }
+
+    #[test]
+    fn count_cached_glyphs() {
+        let (mut cache, mut loader) = glyph_cache();
+        assert_eq!(cache.len(), loader.loaded);
+
+        let key = GlyphKey { font_key: cache.font_key, c: '\u{2603}', size: cache.font_size };
+        cache.get(key, &mut loader);
+        assert_eq!(cache.len(), loader.loaded);
+
+        // Cached glyphs are not loaded again
+        let len = cache.len();
+        cache.get(key, &mut loader);
+        assert_eq!(cache.len(), len);
+    }
+
+    #[test]
+    fn reload_font_with_new_rasterizer() {
+        let (mut cache, mut loader) = glyph_cache();
+
+        let mut font = Font::default();
+        font.glyph_offset = Delta { x: 1, y: 2 };
+        let rasterizer = Rasterizer::new(1., font.use_thin_strokes()).unwrap();
+        cache.reload_font(rasterizer, font.clone(), 1., &mut loader).unwrap();
+
+        assert_eq!(cache.glyph_offset, font.glyph_offset);
+        assert_eq!(loader.clears, 1);
+        assert_eq!(cache.len(), loader.loaded);
+    }
+
+    #[test]
+    fn spaced_metrics_scale_cell() {
+        let metrics = font::Metrics {
+            average_advance: 10.,
+            line_height: 20.,
+            descent: -4.,
+            underline_position: -2.,
+            underline_thickness: 1.,
+            strikeout_position: 6.,
+            strikeout_thickness: 1.,
+        };
+
+        let font: Font = serde_yaml::from_str("{line_height: 1.5, letter_spacing: 1.25}").unwrap();
+        let spaced = spaced_metrics(&font, metrics);
+        assert!((spaced.line_height - 30.).abs() < std::f64::EPSILON);
+        assert!((spaced.average_advance - 12.5).abs() < std::f64::EPSILON);
+        assert!((spaced.descent + 9.).abs() < std::f32::EPSILON);
+
+        // Default multipliers leave the metrics unchanged
+        let spaced = spaced_metrics(&Font::default(), metrics);
+        assert!((spaced.line_height - metrics.line_height).abs() < std::f64::EPSILON);
+        assert!((spaced.average_advance - metrics.average_advance).abs() < std::f64::EPSILON);
+        assert!((spaced.descent - metrics.descent).abs() < std::f32::EPSILON);
+    }
+}
@@ -11,6 +11,7 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::cmp;
 use std::collections::HashMap;
 
 use font::Metrics;
@@ -51,48 +52,136 @@ impl RenderLine {
         while start.line < self.end.line {
             let mut end = start;
             end.col = size.cols() - 1;
-            rects.push(Self::create_rect(metrics, size, flag, start, end, self.color));
+            Self::push_rects(&mut rects, metrics, size, flag, start, end, self.color);
 
             start.col = Column(0);
             start.line += 1;
         }
 
-        rects.push(Self::create_rect(metrics, size, flag, start, self.end, self.color));
+        Self::push_rects(&mut rects, metrics, size, flag, start, self.end, self.color);
 
         rects
     }
 
-    fn create_rect(
+    /// Push the rects for a line which does not wrap to the next terminal line.
+    fn push_rects(
+        rects: &mut Vec<RenderRect>,
         metrics: &Metrics,
         size: &SizeInfo,
         flag: Flags,
         start: Point,
         end: Point,
         color: Rgb,
-    ) -> RenderRect {
+    ) {
         let start_x = start.col.0 as f32 * size.cell_width;
         let end_x = (end.col.0 + 1) as f32 * size.cell_width;
         let width = end_x - start_x;
 
-        let (position, mut height) = match flag {
-            Flags::UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
+        let (position, thickness) = match flag {
+            Flags::UNDERLINE
+            | Flags::DOUBLE_UNDERLINE
+            | Flags::UNDERCURL
+            | Flags::DOTTED_UNDERLINE
+            | Flags::DASHED_UNDERLINE => (metrics.underline_position, metrics.underline_thickness),
             Flags::STRIKEOUT => (metrics.strikeout_position, metrics.strikeout_thickness),
             _ => unimplemented!("Invalid flag for cell line drawing specified"),
         };
 
         // Make sure lines are always visible
-        height = height.max(1.);
+        let thickness = thickness.max(1.);
+
+        // Amplitude of the undercurl wave
+        let amplitude = (thickness * 1.5).ceil();
+
+        // Vertical space required by the line
+        let height = match flag {
+            Flags::DOUBLE_UNDERLINE => thickness * 3.,
+            Flags::UNDERCURL => amplitude * 2. + thickness,
+            _ => thickness,
+        };
 
         let line_bottom = (start.line.0 as f32 + 1.) * size.cell_height;
         let baseline = line_bottom + metrics.descent;
 
-        let mut y = (baseline - position - height / 2.).ceil();
+        let mut y = (baseline - position - thickness / 2.).ceil();
         let max_y = line_bottom - height;
         if y > max_y {
             y = max_y;
         }
 
-        RenderRect::new(start_x + size.padding_x, y + size.padding_y, width, height, color, 1.)
+        let mut push = |x: f32, y: f32, width: f32, height: f32| {
+            rects.push(RenderRect::new(
+                x + size.padding_x,
+                y + size.padding_y,
+                width,
+                height,
+                color,
+                1.,
+            ));
+        };
+
+        match flag {
+            Flags::DOUBLE_UNDERLINE => {
+                push(start_x, y, width, thickness);
+                push(start_x, y + thickness * 2., width, thickness);
+            },
+            Flags::DOTTED_UNDERLINE => {
+                // Align dots to the grid, so separate lines continue the same pattern
+                let period = thickness * 2.;
+                let mut x = (start_x / period).ceil() * period;
+                while x < end_x {
+                    push(x, y, thickness.min(end_x - x), thickness);
+                    x += period;
+                }
+            },
+            Flags::DASHED_UNDERLINE => {
+                // One dash centered in every cell
+                let dash_width = (size.cell_width / 2.).round();
+                let dash_offset = ((size.cell_width - dash_width) / 2.).floor();
+                for col in start.col.0..=end.col.0 {
+                    push(col as f32 * size.cell_width + dash_offset, y, dash_width, thickness);
+                }
+            },
+            Flags::UNDERCURL => {
+                // Wave with one period per cell, so adjacent cells connect seamlessly
+                let wave_offset = |x: usize| {
+                    let phase = (x as f32 + 0.5) / size.cell_width * 2. * std::f32::consts::PI;
+                    (amplitude * (1. - phase.cos())).round() as i32
+                };
+
+                // Vertical extent of a pixel column, reaching to the next column's offset
+                let column = |x: usize| {
+                    let (current, next) = (wave_offset(x), wave_offset(x + 1));
+                    (cmp::min(current, next), cmp::max(current, next))
+                };
+
+                // Merge neighboring columns with the same extent into a single rect
+                let (start_x, end_x) = (start_x as usize, end_x as usize);
+                let mut segment_start = start_x;
+                let mut extent = column(start_x);
+                for x in start_x + 1..=end_x {
+                    let next = if x < end_x { Some(column(x)) } else { None };
+                    if next == Some(extent) {
+                        continue;
+                    }
+
+                    let (top, bottom) = extent;
+                    let width = (x - segment_start) as f32;
+                    push(
+                        segment_start as f32,
+                        y + top as f32,
+                        width,
+                        (bottom - top) as f32 + thickness,
+                    );
+
+                    if let Some(next) = next {
+                        segment_start = x;
+                        extent = next;
+                    }
+                }
+            },
+            _ => push(start_x, y, width, thickness),
+        }
     }
 }
 
@@ -118,15 +207,24 @@ impl RenderLines {
     }
 
     /// Update the stored lines with the next cell info.
-    pub fn update(&mut self, cell: RenderableCell) {
-        for flag in &[Flags::UNDERLINE, Flags::STRIKEOUT] {
+    pub fn update(&mut self, cell: &RenderableCell) {
+        for flag in &[
+            Flags::UNDERLINE,
+            Flags::DOUBLE_UNDERLINE,
+            Flags::UNDERCURL,
+            Flags::DOTTED_UNDERLINE,
+            Flags::DASHED_UNDERLINE,
+            Flags::STRIKEOUT,
+        ] {
             if !cell.flags.contains(*flag) {
                 continue;
             }
 
+            let color = if *flag == Flags::STRIKEOUT { cell.fg } else { cell.underline };
+
             // Check if there's an active line
             if let Some(line) = self.inner.get_mut(flag).and_then(|lines| lines.last_mut()) {
-                if cell.fg == line.color
+                if color == line.color
                     && cell.column == line.end.col + 1
                     && cell.line == line.end.line
                 {
@@ -137,7 +235,7 @@ impl RenderLines {
             }
 
             // Start new line if there currently is none
-            let line = RenderLine { start: cell.into(), end: cell.into(), color: cell.fg };
+            let line = RenderLine { start: cell.into(), end: cell.into(), color };
             match self.inner.get_mut(flag) {
                 Some(lines) => lines.push(line),
                 None => {
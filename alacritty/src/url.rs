@@ -7,7 +7,7 @@ use urlocator::{UrlLocation, UrlLocator};
 use font::Metrics;
 
 use alacritty_terminal::index::Point;
-use alacritty_terminal::term::cell::Flags;
+use alacritty_terminal::term::cell::{Flags, HyperlinkId};
 use alacritty_terminal::term::color::Rgb;
 use alacritty_terminal::term::{RenderableCell, RenderableCellContent, SizeInfo};
 
@@ -20,6 +20,7 @@ pub struct Url {
     lines: Vec<RenderLine>,
     end_offset: u16,
     num_cols: usize,
+    hyperlink: Option<HyperlinkId>,
 }
 
 impl Url {
@@ -44,11 +45,29 @@ impl Url {
     pub fn end(&self) -> Point {
         self.lines[self.lines.len() - 1].end.sub(self.num_cols, self.end_offset as usize)
     }
+
+    /// Hyperlink set by the application, if the URL was not detected from the text.
+    pub fn hyperlink(&self) -> Option<HyperlinkId> {
+        self.hyperlink
+    }
+
+    fn extend(&mut self, start: Point, end: Point, color: Rgb, end_offset: u16) {
+        // If color changed, we need to insert a new line
+        if self.lines.last().map(|last| last.color) == Some(color) {
+            self.lines.last_mut().unwrap().end = end;
+        } else {
+            self.lines.push(RenderLine { color, start, end });
+        }
+
+        // Update excluded cells at the end of the URL
+        self.end_offset = end_offset;
+    }
 }
 
 pub struct Urls {
     locator: UrlLocator,
     urls: Vec<Url>,
+    hyperlinks: Vec<Url>,
     scheme_buffer: Vec<RenderableCell>,
     last_point: Option<Point>,
     state: UrlLocation,
@@ -60,6 +79,7 @@ impl Default for Urls {
             locator: UrlLocator::new(),
             scheme_buffer: Vec::new(),
             urls: Vec::new(),
+            hyperlinks: Vec::new(),
             state: UrlLocation::Reset,
             last_point: None,
         }
@@ -72,16 +92,20 @@ impl Urls {
     }
 
     // Update tracked URLs
-    pub fn update(&mut self, num_cols: usize, cell: RenderableCell) {
+    pub fn update(&mut self, num_cols: usize, cell: &RenderableCell) {
         // Convert cell to character
         let c = match cell.inner {
-            RenderableCellContent::Chars(chars) => chars[0],
+            RenderableCellContent::Chars((c, _)) => c,
             RenderableCellContent::Cursor(_) => return,
         };
 
         let point: Point = cell.into();
         let end = point;
 
+        if let Some(hyperlink) = cell.hyperlink {
+            self.update_hyperlink(num_cols, point, cell.fg, hyperlink);
+        }
+
         // Reset URL when empty cells have been skipped
         if point != Point::default() && Some(point.sub(num_cols, 1)) != self.last_point {
             self.reset();
@@ -107,11 +131,11 @@ impl Urls {
         match (self.state, last_state) {
             (UrlLocation::Url(_length, end_offset), UrlLocation::Scheme) => {
                 // Create empty URL
-                self.urls.push(Url { lines: Vec::new(), end_offset, num_cols });
+                self.urls.push(Url { lines: Vec::new(), end_offset, num_cols, hyperlink: None });
 
                 // Push schemes into URL
                 for scheme_cell in self.scheme_buffer.split_off(0) {
-                    let point = scheme_cell.into();
+                    let point = (&scheme_cell).into();
                     self.extend_url(point, point, scheme_cell.fg, end_offset);
                 }
 
@@ -121,7 +145,7 @@ impl Urls {
             (UrlLocation::Url(_length, end_offset), UrlLocation::Url(..)) => {
                 self.extend_url(point, end, cell.fg, end_offset);
             },
-            (UrlLocation::Scheme, _) => self.scheme_buffer.push(cell),
+            (UrlLocation::Scheme, _) => self.scheme_buffer.push(cell.clone()),
             (UrlLocation::Reset, _) => self.reset(),
             _ => (),
         }
@@ -134,17 +158,25 @@ impl Urls {
 
     // Extend the last URL
     fn extend_url(&mut self, start: Point, end: Point, color: Rgb, end_offset: u16) {
-        let url = self.urls.last_mut().unwrap();
+        self.urls.last_mut().unwrap().extend(start, end, color, end_offset);
+    }
 
-        // If color changed, we need to insert a new line
-        if url.lines.last().map(|last| last.color) == Some(color) {
-            url.lines.last_mut().unwrap().end = end;
-        } else {
-            url.lines.push(RenderLine { color, start, end });
+    // Add a cell to the hyperlink it is part of
+    fn update_hyperlink(&mut self, num_cols: usize, point: Point, color: Rgb, id: HyperlinkId) {
+        match self.hyperlinks.last_mut() {
+            Some(url) if url.hyperlink == Some(id) && url.end().add(num_cols, 1) == point => {
+                url.extend(point, point, color, 0);
+            },
+            _ => {
+                let line = RenderLine { color, start: point, end: point };
+                self.hyperlinks.push(Url {
+                    lines: vec![line],
+                    end_offset: 0,
+                    num_cols,
+                    hyperlink: Some(id),
+                });
+            },
         }
-
-        // Update excluded cells at the end of the URL
-        url.end_offset = end_offset;
     }
 
     /// Find URL below the mouse cursor.
@@ -176,8 +208,10 @@ impl Urls {
     }
 
     /// Find URL at location.
+    ///
+    /// Hyperlinks take precedence over URLs detected in the text.
     pub fn find_at(&self, point: Point) -> Option<Url> {
-        for url in &self.urls {
+        for url in self.hyperlinks.iter().chain(&self.urls) {
             if (url.start()..=url.end()).contains(&point) {
                 return Some(url.clone());
             }
@@ -197,19 +231,21 @@ mod tests {
     use super::*;
 
     use alacritty_terminal::index::{Column, Line};
-    use alacritty_terminal::term::cell::MAX_ZEROWIDTH_CHARS;
 
     fn text_to_cells(text: &str) -> Vec<RenderableCell> {
         text.chars()
             .enumerate()
             .map(|(i, c)| RenderableCell {
-                inner: RenderableCellContent::Chars([c; MAX_ZEROWIDTH_CHARS + 1]),
+                inner: RenderableCellContent::Chars((c, None)),
                 line: Line(0),
                 column: Column(i),
                 fg: Default::default(),
                 bg: Default::default(),
                 bg_alpha: 0.,
+                underline: Default::default(),
                 flags: Flags::empty(),
+                graphic: None,
+                hyperlink: None,
             })
             .collect()
     }
@@ -224,7 +260,7 @@ mod tests {
         let mut urls = Urls::new();
 
         for cell in input {
-            urls.update(num_cols, cell);
+            urls.update(num_cols, &cell);
         }
 
         let url = urls.urls.first().unwrap();
@@ -240,7 +276,7 @@ mod tests {
         let mut urls = Urls::new();
 
         for cell in input {
-            urls.update(num_cols, cell);
+            urls.update(num_cols, &cell);
         }
 
         assert_eq!(urls.urls.len(), 3);
@@ -254,4 +290,33 @@ mod tests {
         assert_eq!(urls.urls[2].start().col, Column(17));
         assert_eq!(urls.urls[2].end().col, Column(21));
     }
+
+    #[test]
+    fn hyperlinks() {
+        let mut input = text_to_cells("a link b another");
+        let num_cols = input.len();
+
+        for cell in &mut input[2..6] {
+            cell.hyperlink = Some(HyperlinkId(0));
+        }
+        for cell in &mut input[9..] {
+            cell.hyperlink = Some(HyperlinkId(1));
+        }
+
+        let mut urls = Urls::new();
+
+        for cell in input {
+            urls.update(num_cols, &cell);
+        }
+
+        assert_eq!(urls.hyperlinks.len(), 2);
+
+        let url = urls.find_at(Point::new(Line(0), Column(4))).unwrap();
+        assert_eq!(url.hyperlink(), Some(HyperlinkId(0)));
+        assert_eq!(url.start().col, Column(2));
+        assert_eq!(url.end().col, Column(5));
+
+        assert_eq!(urls.hyperlinks[1].start().col, Column(9));
+        assert!(urls.find_at(Point::new(Line(0), Column(7))).is_none());
+    }
 }
@@ -11,34 +11,55 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+#[cfg(not(windows))]
+use std::cmp::min;
 use std::convert::From;
 #[cfg(not(any(target_os = "macos", windows)))]
 use std::ffi::c_void;
 use std::fmt::{self, Display, Formatter};
 #[cfg(not(any(target_os = "macos", windows)))]
 use std::os::raw::c_ulong;
+#[cfg(not(target_os = "macos"))]
+use std::ptr;
 
 use glutin::dpi::{PhysicalPosition, PhysicalSize};
 use glutin::event_loop::EventLoop;
+use glutin::monitor::MonitorHandle;
 #[cfg(target_os = "macos")]
 use glutin::platform::macos::{RequestUserAttentionType, WindowBuilderExtMacOS, WindowExtMacOS};
 #[cfg(not(any(target_os = "macos", windows)))]
 use glutin::platform::unix::{EventLoopWindowTargetExtUnix, WindowBuilderExtUnix, WindowExtUnix};
 #[cfg(windows)]
-use glutin::platform::windows::IconExtWindows;
+use glutin::platform::windows::{IconExtWindows, WindowExtWindows};
 #[cfg(not(target_os = "macos"))]
 use glutin::window::Icon;
 use glutin::window::{CursorIcon, Fullscreen, Window as GlutinWindow, WindowBuilder, WindowId};
-use glutin::{self, ContextBuilder, PossiblyCurrent, WindowedContext};
+use glutin::{self, ContextBuilder, PossiblyCurrent, Rect, WindowedContext};
 #[cfg(not(any(target_os = "macos", windows)))]
 use image::ImageFormat;
 #[cfg(not(any(target_os = "macos", windows)))]
 use log::error;
 #[cfg(windows)]
-use winapi::shared::minwindef::WORD;
+use winapi::shared::minwindef::{LPVOID, WORD};
+#[cfg(windows)]
+use winapi::shared::windef::HWND;
+#[cfg(windows)]
+use winapi::shared::winerror::SUCCEEDED;
+#[cfg(windows)]
+use winapi::shared::wtypesbase::CLSCTX_INPROC_SERVER;
+#[cfg(windows)]
+use winapi::um::combaseapi::CoCreateInstance;
+#[cfg(windows)]
+use winapi::um::shobjidl_core::{
+    CLSID_TaskbarList, ITaskbarList3, TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL,
+    TBPF_PAUSED,
+};
+#[cfg(windows)]
+use winapi::Interface;
 #[cfg(not(any(target_os = "macos", windows)))]
-use x11_dl::xlib::{Display as XDisplay, PropModeReplace, XErrorEvent, Xlib};
+use x11_dl::xlib::{Display as XDisplay, PropModeReplace, XErrorEvent, Xlib, XA_CARDINAL};
 
+use alacritty_terminal::ansi::Progress;
 #[cfg(not(any(target_os = "macos", windows)))]
 use alacritty_terminal::config::Colors;
 use alacritty_terminal::config::{Decorations, StartupMode, WindowConfig};
@@ -142,6 +163,23 @@ pub struct Window {
     windowed_context: WindowedContext<PossiblyCurrent>,
     current_mouse_cursor: CursorIcon,
     mouse_visible: bool,
+
+    /// Background opacity, which can be changed at runtime.
+    background_opacity: f32,
+
+    /// Compositor blur behind the transparent background.
+    blur: bool,
+
+    /// Window has been maximized by the startup mode or an action.
+    maximized: bool,
+
+    /// Window title without the progress of a running task.
+    #[cfg(not(windows))]
+    title: String,
+
+    /// Progress of a running task shown in the window title.
+    #[cfg(not(windows))]
+    progress: Progress,
 }
 
 impl Window {
@@ -178,7 +216,44 @@ impl Window {
             }
         }
 
-        Ok(Self { current_mouse_cursor, mouse_visible: true, windowed_context })
+        let window = Self {
+            current_mouse_cursor,
+            mouse_visible: true,
+            windowed_context,
+            background_opacity: config.background_opacity(),
+            blur: config.window.blur,
+            maximized: config.window.startup_mode() == StartupMode::Maximized,
+            #[cfg(not(windows))]
+            title: config.window.title.clone(),
+            #[cfg(not(windows))]
+            progress: Progress::Hidden,
+        };
+        window.update_blur();
+
+        Ok(window)
+    }
+
+    #[inline]
+    pub fn background_opacity(&self) -> f32 {
+        self.background_opacity
+    }
+
+    /// Change the background opacity, clamped between fully transparent and opaque.
+    pub fn set_background_opacity(&mut self, opacity: f32) {
+        self.background_opacity = opacity.max(0.).min(1.);
+        self.update_blur();
+    }
+
+    /// Request or remove compositor blur behind the window.
+    pub fn set_blur(&mut self, blur: bool) {
+        self.blur = blur;
+        self.update_blur();
+    }
+
+    /// Blur the background only while it is actually transparent.
+    fn update_blur(&self) {
+        #[cfg(not(any(target_os = "macos", windows)))]
+        x_set_blur(self.window(), self.blur && self.background_opacity < 1.);
     }
 
     pub fn set_inner_size(&mut self, size: PhysicalSize<u32>) {
@@ -199,11 +274,60 @@ impl Window {
     }
 
     /// Set the window title
+    #[cfg(not(windows))]
+    pub fn set_title(&mut self, title: &str) {
+        self.title = title.to_owned();
+        self.window().set_title(&progress_title(&self.title, self.progress));
+    }
+
+    /// Set the window title
+    #[cfg(windows)]
     #[inline]
-    pub fn set_title(&self, title: &str) {
+    pub fn set_title(&mut self, title: &str) {
         self.window().set_title(title);
     }
 
+    /// Show the progress of a running task after the window title
+    #[cfg(not(windows))]
+    pub fn set_progress(&mut self, progress: Progress) {
+        self.progress = progress;
+        self.window().set_title(&progress_title(&self.title, progress));
+    }
+
+    /// Show the progress of a running task in the taskbar
+    #[cfg(windows)]
+    pub fn set_progress(&mut self, progress: Progress) {
+        let (state, percentage) = match progress {
+            Progress::Hidden => (TBPF_NOPROGRESS, None),
+            Progress::Normal(percentage) => (TBPF_NORMAL, Some(percentage)),
+            Progress::Error(percentage) => (TBPF_ERROR, percentage),
+            Progress::Indeterminate => (TBPF_INDETERMINATE, None),
+            Progress::Paused(percentage) => (TBPF_PAUSED, percentage),
+        };
+
+        unsafe {
+            let mut taskbar: *mut ITaskbarList3 = ptr::null_mut();
+            let result = CoCreateInstance(
+                &CLSID_TaskbarList,
+                ptr::null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &ITaskbarList3::uuidof(),
+                &mut taskbar as *mut *mut ITaskbarList3 as *mut LPVOID,
+            );
+            if !SUCCEEDED(result) {
+                return;
+            }
+
+            let hwnd = self.window().hwnd() as HWND;
+            (*taskbar).HrInit();
+            (*taskbar).SetProgressState(hwnd, state);
+            if let Some(percentage) = percentage {
+                (*taskbar).SetProgressValue(hwnd, u64::from(percentage), 100);
+            }
+            (*taskbar).Release();
+        }
+    }
+
     #[inline]
     pub fn set_mouse_cursor(&mut self, cursor: CursorIcon) {
         if cursor != self.current_mouse_cursor {
@@ -316,6 +440,15 @@ impl Window {
         self.window().set_outer_position(pos);
     }
 
+    /// Position of the window's top left corner, if the platform exposes it.
+    pub fn outer_position(&self) -> Option<PhysicalPosition<i32>> {
+        self.window().outer_position().ok()
+    }
+
+    pub fn current_monitor(&self) -> MonitorHandle {
+        self.window().current_monitor()
+    }
+
     #[cfg(not(any(target_os = "macos", windows)))]
     pub fn x11_window_id(&self) -> Option<usize> {
         self.window().xlib_window().map(|xlib_window| xlib_window as usize)
@@ -326,10 +459,23 @@ impl Window {
     }
 
     #[cfg(not(any(target_os = "macos", windows)))]
-    pub fn set_maximized(&self, maximized: bool) {
+    pub fn set_maximized(&mut self, maximized: bool) {
+        self.maximized = maximized;
         self.window().set_maximized(maximized);
     }
 
+    /// Check if the window is neither fullscreen nor maximized.
+    pub fn is_windowed(&self) -> bool {
+        #[cfg(target_os = "macos")]
+        {
+            if self.window().simple_fullscreen() {
+                return false;
+            }
+        }
+
+        !self.maximized && self.window().fullscreen().is_none()
+    }
+
     pub fn set_minimized(&self, minimized: bool) {
         self.window().set_minimized(minimized);
     }
@@ -347,12 +493,17 @@ impl Window {
     pub fn set_fullscreen(&mut self, fullscreen: bool) {
         if fullscreen {
             let current_monitor = self.window().current_monitor();
-            self.window().set_fullscreen(Some(Fullscreen::Borderless(current_monitor)));
+            self.set_fullscreen_on(current_monitor);
         } else {
             self.window().set_fullscreen(None);
         }
     }
 
+    /// Enter fullscreen on a specific monitor
+    pub fn set_fullscreen_on(&mut self, monitor: MonitorHandle) {
+        self.window().set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+    }
+
     #[cfg(target_os = "macos")]
     pub fn set_simple_fullscreen(&mut self, simple_fullscreen: bool) {
         self.window().set_simple_fullscreen(simple_fullscreen);
@@ -369,21 +520,29 @@ impl Window {
     }
 
     /// Adjust the IME editor position according to the new location of the cursor
+    ///
+    /// The candidate window is placed below the cursor cell in the viewport, or at the bottom of
+    /// the window while the cursor is scrolled out of view.
     #[cfg(not(windows))]
     pub fn update_ime_position<T>(&mut self, terminal: &Term<T>, size_info: &SizeInfo) {
-        let point = terminal.cursor().point;
-        let SizeInfo { cell_width, cell_height, padding_x, padding_y, .. } = size_info;
-
-        let nspot_x = f64::from(padding_x + point.col.0 as f32 * cell_width);
-        let nspot_y = f64::from(padding_y + (point.line.0 + 1) as f32 * cell_height);
-
-        self.window().set_ime_position(PhysicalPosition::new(nspot_x, nspot_y));
+        self.window().set_ime_position(ime_position(terminal, size_info));
     }
 
     pub fn swap_buffers(&self) {
         self.windowed_context.swap_buffers().expect("swap buffers");
     }
 
+    /// Swap buffers, informing the compositor about the regions which changed.
+    ///
+    /// Falls back to swapping the entire window if damage is not supported.
+    pub fn swap_buffers_with_damage(&self, damage: &[Rect]) {
+        if self.windowed_context.swap_buffers_with_damage_supported() {
+            self.windowed_context.swap_buffers_with_damage(damage).expect("swap buffers");
+        } else {
+            self.swap_buffers();
+        }
+    }
+
     pub fn resize(&self, size: PhysicalSize<u32>) {
         self.windowed_context.resize(size);
     }
@@ -427,8 +586,126 @@ fn x_embed_window(window: &GlutinWindow, parent_id: c_ulong) {
     }
 }
 
+/// Request blur from KDE compositors, an empty region covers the whole window.
+#[cfg(not(any(target_os = "macos", windows)))]
+fn x_set_blur(window: &GlutinWindow, blur: bool) {
+    let (xlib_display, xlib_window) = match (window.xlib_display(), window.xlib_window()) {
+        (Some(display), Some(window)) => (display, window),
+        _ => return,
+    };
+
+    let xlib = match Xlib::open() {
+        Ok(xlib) => xlib,
+        Err(_) => return,
+    };
+
+    unsafe {
+        let atom = (xlib.XInternAtom)(
+            xlib_display as _,
+            b"_KDE_NET_WM_BLUR_BEHIND_REGION\0".as_ptr() as *const _,
+            0,
+        );
+
+        if blur {
+            (xlib.XChangeProperty)(
+                xlib_display as _,
+                xlib_window as _,
+                atom,
+                XA_CARDINAL,
+                32,
+                PropModeReplace,
+                ptr::null(),
+                0,
+            );
+        } else {
+            (xlib.XDeleteProperty)(xlib_display as _, xlib_window as _, atom);
+        }
+
+        (xlib.XFlush)(xlib_display as _);
+    }
+}
+
 #[cfg(not(any(target_os = "macos", windows)))]
 unsafe extern "C" fn xembed_error_handler(_: *mut XDisplay, _: *mut XErrorEvent) -> i32 {
     error!("Could not embed into specified window.");
     std::process::exit(1);
 }
+
+/// Window title followed by the progress of a running task.
+#[cfg(not(windows))]
+fn progress_title(title: &str, progress: Progress) -> String {
+    match progress {
+        Progress::Hidden => title.to_owned(),
+        Progress::Normal(percentage) => format!("{} ({}%)", title, percentage),
+        Progress::Error(Some(percentage)) => format!("{} ({}%, failed)", title, percentage),
+        Progress::Error(None) => format!("{} (failed)", title),
+        Progress::Indeterminate => format!("{} (running)", title),
+        Progress::Paused(Some(percentage)) => format!("{} ({}%, paused)", title, percentage),
+        Progress::Paused(None) => format!("{} (paused)", title),
+    }
+}
+
+/// Position of the IME editor below the cursor cell, kept inside the viewport.
+#[cfg(not(windows))]
+fn ime_position<T>(terminal: &Term<T>, size_info: &SizeInfo) -> PhysicalPosition<f64> {
+    let point = terminal.cursor().point;
+    let SizeInfo { cell_width, cell_height, padding_x, padding_y, .. } = size_info;
+
+    let num_lines = terminal.grid().num_lines().0;
+    let line = min(point.line.0 + terminal.grid().display_offset(), num_lines - 1);
+
+    let nspot_x = f64::from(padding_x + point.col.0 as f32 * cell_width);
+    let nspot_y = f64::from(padding_y + (line + 1) as f32 * cell_height);
+
+    PhysicalPosition::new(nspot_x, nspot_y)
+}
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use super::*;
+
+    use alacritty_terminal::ansi::Handler;
+    use alacritty_terminal::clipboard::Clipboard;
+    use alacritty_terminal::event::{Event, EventListener};
+    use alacritty_terminal::grid::Scroll;
+    use alacritty_terminal::index::{Column, Line};
+
+    use crate::config::Config;
+
+    struct Mock;
+    impl EventListener for Mock {
+        fn send_event(&self, _event: Event) {}
+    }
+
+    #[test]
+    fn ime_position_stays_in_viewport() {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        let mut term = Term::new(&Config::default(), &size, Clipboard::new_nop(), Mock);
+
+        // Fill the history and leave the cursor on the last line
+        for _ in 0..20 {
+            term.input('a');
+            term.carriage_return();
+            term.newline();
+        }
+        term.input('a');
+        assert_eq!(ime_position(&term, &size), PhysicalPosition::new(3., 51.));
+
+        // The cursor is scrolled below the viewport
+        term.scroll_display(Scroll::Lines(2));
+        assert_eq!(ime_position(&term, &size), PhysicalPosition::new(3., 51.));
+
+        term.scroll_display(Scroll::Bottom);
+        term.goto(Line(2), Column(1));
+        term.scroll_display(Scroll::Lines(3));
+        assert_eq!(ime_position(&term, &size), PhysicalPosition::new(3., 18.));
+    }
+}
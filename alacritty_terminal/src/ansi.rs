@@ -13,12 +13,18 @@
 // limitations under the License.
 //
 //! ANSI Terminal Stream Parsing
+use std::convert::TryFrom;
 use std::io;
+use std::mem;
+use std::path::PathBuf;
 use std::str;
+use std::time::{Duration, Instant};
 
+use bitflags::bitflags;
 use log::{debug, trace};
 use serde::{Deserialize, Serialize};
 
+use crate::graphics::{sixel, Graphic};
 use crate::index::{Column, Line};
 use crate::term::color::Rgb;
 
@@ -87,6 +93,128 @@ fn parse_number(input: &[u8]) -> Option<u8> {
     Some(num)
 }
 
+/// Parse a hex encoded UTF-8 string
+fn parse_hex_string(input: &[u8]) -> Option<String> {
+    if input.len() % 2 != 0 {
+        return None;
+    }
+
+    let bytes = input
+        .chunks(2)
+        .map(|byte| u8::from_str_radix(str::from_utf8(byte).ok()?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Parse a `file://host/path` URI into its host and percent-decoded path
+///
+/// The URI is passed as OSC parameters, since it might contain semicolons.
+fn parse_file_uri(params: &[&[u8]]) -> Option<(String, PathBuf)> {
+    let uri = params.iter().flat_map(|x| str::from_utf8(x)).collect::<Vec<&str>>().join(";");
+    if !uri.starts_with("file://") {
+        return None;
+    }
+
+    let uri = &uri["file://".len()..];
+    let (host, path) = uri.split_at(uri.find('/')?);
+
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut iter = path.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+
+    // The drive letter follows the root on Windows, like `file:///C:/Users`
+    #[cfg(windows)]
+    let path = if path.get(2..3) == Some(":") { path[1..].to_owned() } else { path };
+
+    Some((host.to_owned(), PathBuf::from(path)))
+}
+
+/// Maximum length of the title and body of a notification sent in multiple chunks
+const MAX_NOTIFICATION_LEN: usize = 0x1_0000;
+
+/// Add a chunk of an `OSC 99` notification, returning the notification once it is complete
+///
+/// Chunks are joined while their metadata sets `d=0`, as long as they share the same `i`. The
+/// pending notification is `None` if it has been dropped, ignoring all of its remaining chunks.
+fn parse_kitty_notification(
+    pending: &mut Option<(Vec<u8>, Option<Notification>)>,
+    metadata: &[u8],
+    payload: &[&[u8]],
+) -> Option<Notification> {
+    let mut id: &[u8] = b"";
+    let mut done = true;
+    let mut base64 = false;
+    let mut body = false;
+    for option in metadata.split(|&b| b == b':') {
+        let mut key_value = option.splitn(2, |&b| b == b'=');
+        match (key_value.next(), key_value.next()) {
+            (Some(b"i"), Some(value)) => id = value,
+            (Some(b"d"), Some(value)) => done = value != b"0",
+            (Some(b"e"), Some(value)) => base64 = value == b"1",
+            (Some(b"p"), Some(b"title")) => body = false,
+            (Some(b"p"), Some(b"body")) => body = true,
+            // Other payloads, like requests to close notifications, are not supported
+            (Some(b"p"), Some(_)) => return None,
+            _ => (),
+        }
+    }
+
+    let payload = payload.join(&b';');
+    let payload = if base64 { base64::decode(&payload).ok()? } else { payload };
+    let text = String::from_utf8_lossy(&payload);
+
+    // Chunks of a different notification discard the pending one
+    let mut notification = match pending.take() {
+        Some((pending_id, notification)) if pending_id == id => notification,
+        _ => Some(Notification::default()),
+    };
+
+    let len = notification
+        .as_ref()
+        .map(|notification| notification.title.len() + notification.body.len() + text.len());
+    if len.map_or(false, |len| len > MAX_NOTIFICATION_LEN) {
+        debug!("Dropping notification exceeding {} bytes", MAX_NOTIFICATION_LEN);
+        notification = None;
+    }
+
+    match &mut notification {
+        Some(notification) if body => notification.body.push_str(&text),
+        Some(notification) => notification.title.push_str(&text),
+        None => (),
+    }
+
+    if done {
+        notification
+    } else {
+        *pending = Some((id.to_vec(), notification));
+        None
+    }
+}
+
+/// Maximum length of the capability names in a single XTGETTCAP request
+const MAX_TERMCAP_QUERY_LEN: usize = 1024;
+
+/// Maximum length of a SOS, PM or APC string, longer strings are dropped
+const MAX_CONTROL_STRING_LEN: usize = 0x10_0000;
+
+/// Maximum length of the parameters of an SGR sequence with sub-parameters
+const MAX_SGR_PARAMS_LEN: usize = 256;
+
+/// Maximum time before a synchronized update is aborted
+const SYNC_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Maximum number of bytes buffered during a synchronized update
+const SYNC_BUFFER_SIZE: usize = 0x20_0000;
+
 /// The processor wraps a `vte::Parser` to ultimately call methods on a Handler
 pub struct Processor {
     state: ProcessorState,
@@ -96,6 +224,77 @@ pub struct Processor {
 /// Internal state for VTE processor
 struct ProcessorState {
     preceding_char: Option<char>,
+
+    /// Sixel decoder of the active DCS sequence
+    sixel_parser: Option<sixel::Parser>,
+
+    /// Hex encoded capability names of the active XTGETTCAP request
+    termcap_query: Option<Vec<u8>>,
+
+    /// State of the active synchronized update
+    sync_state: SyncState,
+
+    /// Whether the last byte started an escape sequence
+    escape: bool,
+
+    /// Kind and content of the active SOS, PM or APC string
+    control_string: Option<(ControlStringKind, Vec<u8>)>,
+
+    /// Parameters of the active CSI, while it could still be an SGR sequence
+    sgr_params: Option<Vec<u8>>,
+
+    /// Identifier and content of an `OSC 99` notification which is still being received
+    notification: Option<(Vec<u8>, Option<Notification>)>,
+}
+
+/// Bytes received during a synchronized update
+#[derive(Default)]
+struct SyncState {
+    /// Expiration of the update, if one is active
+    timeout: Option<Instant>,
+
+    /// Bytes which will be processed once the update ends
+    buffer: Vec<u8>,
+
+    /// Parser looking for the end of the update in the buffered bytes
+    parser: vte::Parser,
+}
+
+impl SyncState {
+    fn start(&mut self) {
+        self.timeout = Some(Instant::now() + SYNC_UPDATE_TIMEOUT);
+        self.parser = vte::Parser::new();
+    }
+}
+
+/// Performer recognizing the sequences which end a synchronized update
+#[derive(Default)]
+struct SyncEnd {
+    ended: bool,
+}
+
+impl vte::Perform for SyncEnd {
+    fn print(&mut self, _: char) {}
+
+    fn execute(&mut self, _: u8) {}
+
+    fn hook(&mut self, params: &[i64], intermediates: &[u8], _: bool, action: char) {
+        // DCS = 2 s
+        self.ended |= action == 's' && intermediates == [b'='] && params == [2];
+    }
+
+    fn put(&mut self, _: u8) {}
+
+    fn unhook(&mut self) {}
+
+    fn osc_dispatch(&mut self, _: &[&[u8]], _: bool) {}
+
+    fn csi_dispatch(&mut self, params: &[i64], intermediates: &[u8], _: bool, action: char) {
+        // CSI ? 2026 l, possibly along with other private modes
+        self.ended |= action == 'l' && intermediates == [b'?'] && params.contains(&2026);
+    }
+
+    fn esc_dispatch(&mut self, _: &[u8], _: bool, _: u8) {}
 }
 
 /// Helper type that implements `vte::Perform`.
@@ -122,7 +321,19 @@ impl<'a, H: Handler + TermInfo + 'a, W: io::Write> Performer<'a, H, W> {
 
 impl Default for Processor {
     fn default() -> Processor {
-        Processor { state: ProcessorState { preceding_char: None }, parser: vte::Parser::new() }
+        Processor {
+            state: ProcessorState {
+                preceding_char: None,
+                sixel_parser: None,
+                termcap_query: None,
+                sync_state: Default::default(),
+                escape: false,
+                control_string: None,
+                sgr_params: None,
+                notification: None,
+            },
+            parser: vte::Parser::new(),
+        }
     }
 }
 
@@ -137,8 +348,120 @@ impl Processor {
         H: Handler + TermInfo,
         W: io::Write,
     {
-        let mut performer = Performer::new(&mut self.state, handler, writer);
-        self.parser.advance(&mut performer, byte);
+        if self.state.sync_state.timeout.is_some() {
+            self.advance_sync(handler, byte, writer);
+        } else {
+            self.advance_sgr_params(handler, byte);
+            self.advance_control_string(handler, byte);
+
+            let mut performer = Performer::new(&mut self.state, handler, writer);
+            self.parser.advance(&mut performer, byte);
+        }
+    }
+
+    /// Collect SOS, PM and APC strings, which are ignored by `vte::Parser`
+    ///
+    /// This follows the parser's state transitions, so strings start and end on the same bytes.
+    #[inline]
+    fn advance_control_string<H: Handler>(&mut self, handler: &mut H, byte: u8) {
+        let state = &mut self.state;
+
+        if let Some((kind, mut data)) = state.control_string.take() {
+            match byte {
+                // ESC and ST end the string, CAN and SUB abort it
+                0x1b | 0x9c => handler.control_string(kind, &data),
+                0x18 | 0x1a => (),
+                _ if data.len() >= MAX_CONTROL_STRING_LEN => {
+                    debug!("Dropping {:?} string exceeding {} bytes", kind, MAX_CONTROL_STRING_LEN);
+                },
+                _ => {
+                    data.push(byte);
+                    state.control_string = Some((kind, data));
+                },
+            }
+        } else if state.escape {
+            let kind = match byte {
+                b'X' => Some(ControlStringKind::Sos),
+                b'^' => Some(ControlStringKind::Pm),
+                b'_' => Some(ControlStringKind::Apc),
+                _ => None,
+            };
+            state.control_string = kind.map(|kind| (kind, Vec::new()));
+        }
+
+        state.escape = byte == 0x1b;
+    }
+
+    /// Collect SGR parameters with sub-parameters, which are ignored by `vte::Parser`
+    ///
+    /// Sequences without any `:` are left to the parser.
+    #[inline]
+    fn advance_sgr_params<H: Handler>(&mut self, handler: &mut H, byte: u8) {
+        let state = &mut self.state;
+
+        if let Some(mut params) = state.sgr_params.take() {
+            match byte {
+                b'0'..=b'9' | b';' | b':' if params.len() < MAX_SGR_PARAMS_LEN => {
+                    params.push(byte);
+                    state.sgr_params = Some(params);
+                },
+                b'm' if params.contains(&b':') => {
+                    for attr in attrs_from_sgr_subparameters(&params) {
+                        match attr {
+                            Some(attr) => handler.terminal_attribute(attr),
+                            None => debug!(
+                                "[Unhandled SGR] params={:?}",
+                                String::from_utf8_lossy(&params)
+                            ),
+                        }
+                    }
+                },
+                _ => (),
+            }
+        } else if state.escape && byte == b'[' {
+            state.sgr_params = Some(Vec::new());
+        }
+    }
+
+    /// Expiration of the active synchronized update
+    ///
+    /// Once this is reached, the update should be ended with `stop_sync`.
+    #[inline]
+    pub fn sync_timeout(&self) -> Option<Instant> {
+        self.state.sync_state.timeout
+    }
+
+    /// End the synchronized update, processing all bytes received since it started
+    pub fn stop_sync<H, W>(&mut self, handler: &mut H, writer: &mut W)
+    where
+        H: Handler + TermInfo,
+        W: io::Write,
+    {
+        let buffer = mem::replace(&mut self.state.sync_state.buffer, Vec::new());
+        self.state.sync_state.timeout = None;
+
+        // Updates started by the buffered bytes are buffered again
+        for byte in buffer {
+            self.advance(handler, byte, writer);
+        }
+    }
+
+    /// Buffer a byte received during a synchronized update
+    #[inline]
+    fn advance_sync<H, W>(&mut self, handler: &mut H, byte: u8, writer: &mut W)
+    where
+        H: Handler + TermInfo,
+        W: io::Write,
+    {
+        let sync_state = &mut self.state.sync_state;
+        sync_state.buffer.push(byte);
+
+        let mut sync_end = SyncEnd::default();
+        sync_state.parser.advance(&mut sync_end, byte);
+
+        if sync_state.buffer.len() >= SYNC_BUFFER_SIZE || sync_end.ended {
+            self.stop_sync(handler, writer);
+        }
     }
 }
 
@@ -159,6 +482,9 @@ pub trait Handler {
     /// Set the cursor style
     fn set_cursor_style(&mut self, _: Option<CursorStyle>) {}
 
+    /// Set whether the cursor blinks, `None` restores the configured default
+    fn set_cursor_blinking(&mut self, _: Option<bool>) {}
+
     /// A character to be displayed
     fn input(&mut self, _c: char) {}
 
@@ -182,12 +508,20 @@ pub trait Handler {
 
     /// Identify the terminal (should write back to the pty stream)
     ///
+    /// The intermediate is `>` for the secondary device attributes request.
+    ///
     /// TODO this should probably return an io::Result
-    fn identify_terminal<W: io::Write>(&mut self, _: &mut W) {}
+    fn identify_terminal<W: io::Write>(&mut self, _: &mut W, _intermediate: Option<char>) {}
+
+    /// ENQ - Send the answerback message
+    fn answerback<W: io::Write>(&mut self, _: &mut W) {}
 
     // Report device status
     fn device_status<W: io::Write>(&mut self, _: &mut W, _: usize) {}
 
+    /// Report whether the color scheme is dark or light
+    fn report_color_scheme<W: io::Write>(&mut self, _: &mut W) {}
+
     /// Move cursor forward `cols`
     fn move_forward(&mut self, _: Column) {}
 
@@ -271,9 +605,15 @@ pub trait Handler {
     /// Clear tab stops
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
+    /// Reset tab stops to every 8 columns (DECST8C)
+    fn set_default_tabstops(&mut self) {}
+
     /// Reset terminal state
     fn reset_state(&mut self) {}
 
+    /// Reset modes, margins, character sets and text attributes (DECSTR)
+    fn soft_reset(&mut self) {}
+
     /// Reverse Index
     ///
     /// Move the active position to the same horizontal position on the
@@ -293,6 +633,29 @@ pub trait Handler {
     /// DECSTBM - Set the terminal scrolling region
     fn set_scrolling_region(&mut self, _top: usize, _bottom: usize) {}
 
+    /// DECSLRM - Set the left and right margins
+    ///
+    /// This shares its sequence with saving the cursor position, which is what it does unless
+    /// DECLRMM is set.
+    fn set_left_and_right_margins(&mut self, _left: usize, _right: usize) {
+        self.save_cursor_position();
+    }
+
+    /// DECCRA - Copy a rectangular area to the given top left corner
+    fn copy_rect(&mut self, _src: Rect, _top: usize, _left: usize) {}
+
+    /// DECFRA - Fill a rectangular area with a character
+    fn fill_rect(&mut self, _: Rect, _: char) {}
+
+    /// DECERA - Erase a rectangular area
+    fn erase_rect(&mut self, _: Rect) {}
+
+    /// DECCARA - Change a text attribute in an area
+    fn change_rect_attribute(&mut self, _: Rect, _: Attr) {}
+
+    /// DECSACE - Select the area affected by DECCARA
+    fn set_attribute_change_extent(&mut self, _: AttributeChangeExtent) {}
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits)
     fn set_keypad_application_mode(&mut self) {}
 
@@ -311,12 +674,18 @@ pub trait Handler {
     /// later be 'invoked' by `set_active_charset`
     fn configure_charset(&mut self, _: CharsetIndex, _: StandardCharset) {}
 
+    /// SS2/SS3 - Use G2 or G3 for the next character only
+    fn set_single_shift(&mut self, _: CharsetIndex) {}
+
     /// Set an indexed color value
     fn set_color(&mut self, _: usize, _: Rgb) {}
 
     /// Write a foreground/background color escape sequence with the current color
     fn dynamic_color_sequence<W: io::Write>(&mut self, _: &mut W, _: u8, _: usize, _: &str) {}
 
+    /// Write an indexed color escape sequence with the current color
+    fn color_sequence<W: io::Write>(&mut self, _: &mut W, _: usize, _: &str) {}
+
     /// Reset an indexed color to original value
     fn reset_color(&mut self, _: usize) {}
 
@@ -329,11 +698,73 @@ pub trait Handler {
     /// Run the decaln routine.
     fn decaln(&mut self) {}
 
+    /// Insert a graphic at the cursor position
+    fn insert_graphic(&mut self, _graphic: Graphic) {}
+
+    /// Set the hyperlink of the characters written after the cursor
+    fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
+
+    /// Mark the start of a shell integration zone at the cursor
+    fn set_prompt_mark(&mut self, _: PromptMark) {}
+
+    /// Report the progress of a long running task
+    fn set_progress(&mut self, _: Progress) {}
+
+    /// Set the working directory of the shell on the given host
+    fn set_working_directory(&mut self, _host: &str, _path: PathBuf) {}
+
+    /// SOS, PM or APC string which is not handled by the parser
+    fn control_string(&mut self, _: ControlStringKind, _: &[u8]) {}
+
+    /// Show a desktop notification
+    fn desktop_notification(&mut self, _: Notification) {}
+
     /// Push a title onto the stack
     fn push_title(&mut self) {}
 
     /// Pop the last title from the stack
     fn pop_title(&mut self) {}
+
+    /// Push keyboard protocol flags onto the stack
+    fn push_keyboard_mode(&mut self, _mode: KeyboardModes) {}
+
+    /// Pop the given number of keyboard protocol flags from the stack
+    fn pop_keyboard_modes(&mut self, _to_pop: u16) {}
+
+    /// Modify the active keyboard protocol flags
+    fn set_keyboard_mode(&mut self, _mode: KeyboardModes, _behavior: KeyboardModesApplyBehavior) {}
+
+    /// Report the active keyboard protocol flags
+    fn report_keyboard_mode<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Set xterm's modifyOtherKeys mode
+    fn set_modify_other_keys(&mut self, _mode: ModifyOtherKeys) {}
+
+    /// Report xterm's modifyOtherKeys mode
+    fn report_modify_other_keys<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// State of a mode, as reported in response to DECRQM
+    fn mode_state(&self, _mode: Mode) -> ModeState {
+        ModeState::NotRecognized
+    }
+
+    /// Report the value of a terminfo capability (XTGETTCAP)
+    fn report_terminfo_capability<W: io::Write>(&mut self, _: &mut W, _name: &str) {}
+
+    /// Report the size of the text area in pixels
+    fn text_area_size_pixels<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Report the size of the text area in characters
+    fn text_area_size_chars<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Report the size of a cell in pixels
+    fn cell_size_pixels<W: io::Write>(&mut self, _: &mut W) {}
+
+    /// Resize the text area to the given size in pixels, `None` keeps the current size
+    fn resize_text_area_pixels(&mut self, _height: Option<usize>, _width: Option<usize>) {}
+
+    /// Resize the text area to the given size in characters, `None` keeps the current size
+    fn resize_text_area_chars(&mut self, _lines: Option<usize>, _cols: Option<usize>) {}
 }
 
 /// Describes shape of cursor
@@ -364,7 +795,7 @@ impl Default for CursorStyle {
 }
 
 /// Terminal modes
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Mode {
     /// ?1
     CursorKeys = 1,
@@ -400,6 +831,11 @@ pub enum Mode {
     LineFeedNewLine = 20,
     /// ?25
     ShowCursor = 25,
+    /// DECLRMM - Left and right margin mode
+    ///
+    /// * `CSI ? 69 h` allow setting the left and right margins with DECSLRM
+    /// * `CSI ? 69 l` reset the margins, `CSI s` saves the cursor position again
+    LeftRightMargin = 69,
     /// ?1000
     ReportMouseClicks = 1000,
     /// ?1002
@@ -414,10 +850,16 @@ pub enum Mode {
     SgrMouse = 1006,
     /// ?1007
     AlternateScroll = 1007,
+    /// ?1016
+    SgrPixelsMouse = 1016,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2026
+    SyncUpdate = 2026,
+    /// ?2031
+    ColorPaletteUpdates = 2031,
 }
 
 impl Mode {
@@ -439,6 +881,7 @@ impl Mode {
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
+                69 => Mode::LeftRightMargin,
                 1000 => Mode::ReportMouseClicks,
                 1002 => Mode::ReportCellMouseMotion,
                 1003 => Mode::ReportAllMouseMotion,
@@ -446,8 +889,11 @@ impl Mode {
                 1005 => Mode::Utf8Mouse,
                 1006 => Mode::SgrMouse,
                 1007 => Mode::AlternateScroll,
+                1016 => Mode::SgrPixelsMouse,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
+                2026 => Mode::SyncUpdate,
+                2031 => Mode::ColorPaletteUpdates,
                 _ => {
                     trace!("[unimplemented] primitive mode: {}", num);
                     return None;
@@ -463,6 +909,73 @@ impl Mode {
     }
 }
 
+/// State of a mode, as reported in response to DECRQM
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ModeState {
+    NotRecognized = 0,
+    Set = 1,
+    Reset = 2,
+    PermanentlySet = 3,
+    PermanentlyReset = 4,
+}
+
+impl From<bool> for ModeState {
+    fn from(set: bool) -> Self {
+        if set {
+            ModeState::Set
+        } else {
+            ModeState::Reset
+        }
+    }
+}
+
+/// Hyperlink target set with `OSC 8`
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Hyperlink {
+    /// Identifier used to join hyperlinks which are not adjacent
+    pub id: Option<String>,
+
+    /// Target of the hyperlink
+    pub uri: String,
+}
+
+/// Shell integration marks set with `OSC 133`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PromptMark {
+    /// Start of the prompt
+    PromptStart,
+    /// Start of the command typed by the user
+    CommandStart,
+    /// Start of the command's output
+    OutputStart,
+    /// Command has finished
+    CommandEnd,
+}
+
+/// Progress of a long running task reported with `OSC 9 ; 4`
+///
+/// Percentages are omitted when the state was changed without reporting the progress again.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Progress {
+    /// No task is running
+    Hidden,
+    /// Percentage of the task which has been completed
+    Normal(u8),
+    /// Task has failed
+    Error(Option<u8>),
+    /// Task is running without a known progress
+    Indeterminate,
+    /// Task has been paused
+    Paused(Option<u8>),
+}
+
+/// Desktop notification requested with `OSC 777` or `OSC 99`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+}
+
 /// Mode for clearing line
 ///
 /// Relative to cursor
@@ -491,6 +1004,64 @@ pub enum ClearMode {
     Saved,
 }
 
+bitflags! {
+    /// Progressive enhancements of the kitty keyboard protocol
+    pub struct KeyboardModes: u8 {
+        const NO_MODE                 = 0b0000_0000;
+        const DISAMBIGUATE_ESC_CODES  = 0b0000_0001;
+        const REPORT_EVENT_TYPES      = 0b0000_0010;
+        const REPORT_ALTERNATE_KEYS   = 0b0000_0100;
+        const REPORT_ALL_KEYS_AS_ESC  = 0b0000_1000;
+        const REPORT_ASSOCIATED_TEXT  = 0b0001_0000;
+    }
+}
+
+/// Way new keyboard protocol flags are combined with the active ones
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyboardModesApplyBehavior {
+    /// Replace the active flags
+    Replace,
+    /// Set the new flags in addition to the active ones
+    Union,
+    /// Clear the new flags from the active ones
+    Difference,
+}
+
+/// Encoding of modified keys requested through xterm's modifyOtherKeys resource
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ModifyOtherKeys {
+    /// Send modified keys like any other key
+    Reset,
+    /// Encode modified keys, unless the combination has a well-known legacy encoding
+    EnableExceptWellDefined,
+    /// Encode all modified keys
+    EnableAll,
+}
+
+/// Rectangular area of the screen with inclusive, 1-based coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub top: usize,
+    pub left: usize,
+    pub bottom: usize,
+    pub right: usize,
+}
+
+/// Area affected by attribute changes, selected with DECSACE
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttributeChangeExtent {
+    /// All characters from the start to the end position, wrapping at line ends
+    Stream,
+    /// Only characters inside the rectangle between the start and end position
+    Rectangle,
+}
+
+impl Default for AttributeChangeExtent {
+    fn default() -> AttributeChangeExtent {
+        AttributeChangeExtent::Stream
+    }
+}
+
 /// Mode for clearing tab stops
 #[derive(Debug)]
 pub enum TabulationClearMode {
@@ -636,6 +1207,14 @@ pub enum Attr {
     Italic,
     /// Underline text
     Underline,
+    /// Underline text twice
+    DoubleUnderline,
+    /// Undercurl text
+    Undercurl,
+    /// Dotted underline text
+    DottedUnderline,
+    /// Dashed underline text
+    DashedUnderline,
     /// Blink cursor slowly
     BlinkSlow,
     /// Blink cursor fast
@@ -666,6 +1245,8 @@ pub enum Attr {
     Foreground(Color),
     /// Set indexed background color
     Background(Color),
+    /// Set underline color, `None` draws it in the foreground color
+    UnderlineColor(Option<Color>),
 }
 
 /// Identifiers which can be assigned to a graphic character set
@@ -688,6 +1269,8 @@ impl Default for CharsetIndex {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum StandardCharset {
     Ascii,
+    /// British national replacement set, replacing `#` with `£`
+    UnitedKingdom,
     SpecialCharacterAndLineDrawing,
 }
 
@@ -697,6 +1280,17 @@ impl Default for StandardCharset {
     }
 }
 
+/// Introducer of a control string without a meaning defined by the terminal
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ControlStringKind {
+    /// Start of string (`ESC X`)
+    Sos,
+    /// Privacy message (`ESC ^`)
+    Pm,
+    /// Application program command (`ESC _`)
+    Apc,
+}
+
 impl<'a, H, W> vte::Perform for Performer<'a, H, W>
 where
     H: Handler + TermInfo + 'a,
@@ -715,6 +1309,7 @@ where
             C0::BS => self.handler.backspace(),
             C0::CR => self.handler.carriage_return(),
             C0::LF | C0::VT | C0::FF => self.handler.linefeed(),
+            C0::ENQ => self.handler.answerback(self.writer),
             C0::BEL => self.handler.bell(),
             C0::SUB => self.handler.substitute(),
             C0::SI => self.handler.set_active_charset(CharsetIndex::G0),
@@ -724,24 +1319,53 @@ where
     }
 
     #[inline]
-    fn hook(&mut self, params: &[i64], intermediates: &[u8], ignore: bool, _c: char) {
-        debug!(
-            "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}",
-            params, intermediates, ignore
-        );
+    fn hook(&mut self, params: &[i64], intermediates: &[u8], ignore: bool, c: char) {
+        match (c, intermediates) {
+            ('q', []) if !ignore => self.state.sixel_parser = Some(sixel::Parser::new(params)),
+            ('q', [b'+']) if !ignore => self.state.termcap_query = Some(Vec::new()),
+            ('s', [b'=']) if params == [1] => self.state.sync_state.start(),
+            // Updates are ended before the buffered sequence is processed
+            ('s', [b'=']) if params == [2] => (),
+            _ => debug!(
+                "[unhandled hook] params={:?}, ints: {:?}, ignore: {:?}, action: {:?}",
+                params, intermediates, ignore, c
+            ),
+        }
     }
 
     #[inline]
     fn put(&mut self, byte: u8) {
-        debug!("[unhandled put] byte={:?}", byte);
+        if let Some(parser) = &mut self.state.sixel_parser {
+            parser.put(byte);
+        } else if let Some(query) = &mut self.state.termcap_query {
+            if query.len() < MAX_TERMCAP_QUERY_LEN {
+                query.push(byte);
+            }
+        } else {
+            debug!("[unhandled put] byte={:?}", byte);
+        }
     }
 
     #[inline]
     fn unhook(&mut self) {
-        debug!("[unhandled unhook]");
+        if let Some(parser) = self.state.sixel_parser.take() {
+            if let Some(graphic) = parser.finish() {
+                self.handler.insert_graphic(graphic);
+            }
+        } else if let Some(query) = self.state.termcap_query.take() {
+            for name in query.split(|&byte| byte == b';') {
+                match parse_hex_string(name) {
+                    Some(name) => self.handler.report_terminfo_capability(self.writer, &name),
+                    None => debug!("Invalid XTGETTCAP capability name: {:?}", name),
+                }
+            }
+        } else {
+            debug!("[unhandled unhook]");
+        }
     }
 
     // TODO replace OSC parsing with parser combinators
+    #[allow(clippy::cognitive_complexity)]
     #[inline]
     fn osc_dispatch(&mut self, params: &[&[u8]], bell_terminated: bool) {
         let writer = &mut self.writer;
@@ -784,19 +1408,30 @@ where
             // This is ignored, since alacritty has no concept of tabs
             b"1" => (),
 
-            // Set color index
+            // Get/set color index
             b"4" => {
-                if params.len() > 1 && params.len() % 2 != 0 {
-                    for chunk in params[1..].chunks(2) {
-                        let index = parse_number(chunk[0]);
-                        let color = xparse_color(chunk[1]);
-                        if let (Some(i), Some(c)) = (index, color) {
-                            self.handler.set_color(i as usize, c);
-                            return;
-                        }
+                if params.len() <= 1 || params.len() % 2 == 0 {
+                    unhandled(params);
+                    return;
+                }
+
+                for chunk in params[1..].chunks(2) {
+                    let index = match parse_number(chunk[0]) {
+                        Some(index) => index as usize,
+                        None => {
+                            unhandled(params);
+                            continue;
+                        },
+                    };
+
+                    if let Some(color) = xparse_color(chunk[1]) {
+                        self.handler.set_color(index, color);
+                    } else if chunk[1] == b"?" {
+                        self.handler.color_sequence(writer, index, terminator);
+                    } else {
+                        unhandled(params);
                     }
                 }
-                unhandled(params);
             },
 
             // Get/set Foreground, Background, Cursor colors
@@ -834,6 +1469,66 @@ where
                 unhandled(params);
             },
 
+            // Current working directory
+            b"7" if params.len() > 1 => match parse_file_uri(&params[1..]) {
+                Some((host, path)) => self.handler.set_working_directory(&host, path),
+                None => unhandled(params),
+            },
+
+            // Hyperlink
+            b"8" if params.len() > 2 => {
+                let link_params = params[1];
+                let uri = params[2..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+
+                // An empty URI closes the hyperlink
+                if uri.is_empty() {
+                    self.handler.set_hyperlink(None);
+                    return;
+                }
+
+                let id = link_params
+                    .split(|&b| b == b':')
+                    .find(|kv| kv.starts_with(b"id="))
+                    .and_then(|kv| str::from_utf8(&kv[3..]).ok())
+                    .map(String::from);
+
+                self.handler.set_hyperlink(Some(Hyperlink { id, uri }));
+            },
+
+            // ConEmu progress report
+            b"9" if params.len() > 2 && params[1] == b"4" => {
+                let percentage = params
+                    .get(3)
+                    .and_then(|param| str::from_utf8(param).ok())
+                    .and_then(|param| param.parse::<usize>().ok())
+                    .map(|percentage| percentage.min(100) as u8);
+                let progress = match params[2] {
+                    b"0" => Progress::Hidden,
+                    b"1" => Progress::Normal(percentage.unwrap_or(0)),
+                    b"2" => Progress::Error(percentage),
+                    b"3" => Progress::Indeterminate,
+                    b"4" => Progress::Paused(percentage),
+                    _ => return unhandled(params),
+                };
+                self.handler.set_progress(progress);
+            },
+
+            // Shell integration marks
+            b"133" if params.len() > 1 => {
+                let mark = match params[1] {
+                    b"A" => PromptMark::PromptStart,
+                    b"B" => PromptMark::CommandStart,
+                    b"C" => PromptMark::OutputStart,
+                    b"D" => PromptMark::CommandEnd,
+                    _ => return unhandled(params),
+                };
+                self.handler.set_prompt_mark(mark);
+            },
+
             // Set cursor style
             b"50" => {
                 if params.len() >= 2
@@ -893,6 +1588,26 @@ where
             // Reset text cursor color
             b"112" => self.handler.reset_color(NamedColor::Cursor as usize),
 
+            // Kitty desktop notification
+            b"99" if params.len() > 2 => {
+                let notification =
+                    parse_kitty_notification(&mut self.state.notification, params[1], &params[2..]);
+                if let Some(notification) = notification {
+                    self.handler.desktop_notification(notification);
+                }
+            },
+
+            // Desktop notification
+            b"777" if params.len() > 2 && params[1] == b"notify" => {
+                let title = String::from_utf8_lossy(params[2]).into_owned();
+                let body = params[3..]
+                    .iter()
+                    .flat_map(|x| str::from_utf8(x))
+                    .collect::<Vec<&str>>()
+                    .join(";");
+                self.handler.desktop_notification(Notification { title, body });
+            },
+
             _ => unhandled(params),
         }
     }
@@ -923,12 +1638,46 @@ where
             };
         }
 
+        // DECRQM is the only sequence with both a private marker and an intermediate
+        match (action, intermediates) {
+            ('p', [b'$']) | ('p', [b'?', b'$']) if !has_ignored_intermediates => {
+                let private = intermediates.len() == 2;
+                let num = args.get(0).copied().unwrap_or(0);
+                let mode = Mode::from_primitive(intermediates.get(0).filter(|_| private), num);
+                let state = match mode {
+                    Some(Mode::SyncUpdate) => {
+                        ModeState::from(self.state.sync_state.timeout.is_some())
+                    },
+                    Some(mode) => self.handler.mode_state(mode),
+                    None => ModeState::NotRecognized,
+                };
+
+                let marker = if private { "?" } else { "" };
+                let _ = write!(self.writer, "\x1b[{}{};{}$y", marker, num, state as u8);
+                return;
+            },
+            _ => (),
+        }
+
         if has_ignored_intermediates || intermediates.len() > 1 {
             unhandled!();
             return;
         }
 
         let handler = &mut self.handler;
+
+        // Rectangle given by four arguments starting at `idx`, defaulting to the whole screen
+        macro_rules! rect {
+            (idx: $idx:expr) => {
+                Rect {
+                    top: arg_or_default!(idx: $idx, default: 1) as usize,
+                    left: arg_or_default!(idx: $idx + 1, default: 1) as usize,
+                    bottom: arg_or_default!(idx: $idx + 2, default: handler.lines().0 as _)
+                        as usize,
+                    right: arg_or_default!(idx: $idx + 3, default: handler.cols().0 as _) as usize,
+                }
+            };
+        }
         let writer = &mut self.writer;
 
         match (action, intermediates.get(0)) {
@@ -950,8 +1699,10 @@ where
             ('B', None) | ('e', None) => {
                 handler.move_down(Line(arg_or_default!(idx: 0, default: 1) as usize))
             },
-            ('c', None) if arg_or_default!(idx: 0, default: 0) == 0 => {
-                handler.identify_terminal(writer)
+            ('c', intermediate @ None) | ('c', intermediate @ Some(b'>'))
+                if arg_or_default!(idx: 0, default: 0) == 0 =>
+            {
+                handler.identify_terminal(writer, intermediate.map(|&i| i as char))
             },
             ('C', None) | ('a', None) => {
                 handler.move_forward(Column(arg_or_default!(idx: 0, default: 1) as usize))
@@ -968,7 +1719,7 @@ where
             ('g', None) => {
                 let mode = match arg_or_default!(idx: 0, default: 0) {
                     0 => TabulationClearMode::Current,
-                    3 => TabulationClearMode::All,
+                    3 | 5 => TabulationClearMode::All,
                     _ => {
                         unhandled!();
                         return;
@@ -1014,10 +1765,26 @@ where
                 handler.clear_line(mode);
             },
             ('S', None) => handler.scroll_up(Line(arg_or_default!(idx: 0, default: 1) as usize)),
-            ('t', None) => match arg_or_default!(idx: 0, default: 1) as usize {
-                22 => handler.push_title(),
-                23 => handler.pop_title(),
-                _ => unhandled!(),
+            ('t', None) => {
+                // Omitted and zero sizes both keep the current dimension
+                let size = |idx: usize| match args.get(idx) {
+                    Some(&arg) if arg > 0 => {
+                        Some(usize::try_from(arg).unwrap_or(usize::max_value()))
+                    },
+                    _ => None,
+                };
+
+                match arg_or_default!(idx: 0, default: 1) as usize {
+                    4 => handler.resize_text_area_pixels(size(1), size(2)),
+                    8 => handler.resize_text_area_chars(size(1), size(2)),
+                    14 => handler.text_area_size_pixels(writer),
+                    16 => handler.cell_size_pixels(writer),
+                    18 => handler.text_area_size_chars(writer),
+                    // Icon titles are not supported, so their stack operations are ignored
+                    22 if args.get(1) != Some(&1) => handler.push_title(),
+                    23 if args.get(1) != Some(&1) => handler.pop_title(),
+                    _ => unhandled!(),
+                }
             },
             ('T', None) => handler.scroll_down(Line(arg_or_default!(idx: 0, default: 1) as usize)),
             ('L', None) => {
@@ -1026,6 +1793,8 @@ where
             ('l', intermediate) => {
                 for arg in args {
                     match Mode::from_primitive(intermediate, *arg) {
+                        // Updates are ended before the buffered sequence is processed
+                        Some(Mode::SyncUpdate) => (),
                         Some(mode) => handler.unset_mode(mode),
                         None => unhandled!(),
                     }
@@ -1038,6 +1807,14 @@ where
             ('P', None) => {
                 handler.delete_chars(Column(arg_or_default!(idx: 0, default: 1) as usize))
             },
+            ('W', None) => match arg_or_default!(idx: 0, default: 0) {
+                // CTC (CSI Ps W) -- Cursor Tabulation Control
+                0 => handler.set_horizontal_tabstop(),
+                2 => handler.clear_tabs(TabulationClearMode::Current),
+                5 => handler.clear_tabs(TabulationClearMode::All),
+                _ => unhandled!(),
+            },
+            ('W', Some(b'?')) if args == [5] => handler.set_default_tabstops(),
             ('Z', None) => handler.move_backward_tabs(arg_or_default!(idx: 0, default: 1)),
             ('d', None) => {
                 handler.goto_line(Line(arg_or_default!(idx: 0, default: 1) as usize - 1))
@@ -1045,6 +1822,7 @@ where
             ('h', intermediate) => {
                 for arg in args {
                     match Mode::from_primitive(intermediate, *arg) {
+                        Some(Mode::SyncUpdate) => self.state.sync_state.start(),
                         Some(mode) => handler.set_mode(mode),
                         None => unhandled!(),
                     }
@@ -1062,12 +1840,38 @@ where
                     }
                 }
             },
+            ('m', Some(b'>')) => {
+                // Only the modifyOtherKeys resource is supported
+                if args.get(0) != Some(&4) {
+                    unhandled!();
+                    return;
+                }
+
+                let mode = match args.get(1).copied().unwrap_or(0) {
+                    0 => ModifyOtherKeys::Reset,
+                    1 => ModifyOtherKeys::EnableExceptWellDefined,
+                    2 => ModifyOtherKeys::EnableAll,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_modify_other_keys(mode);
+            },
+            ('m', Some(b'?')) if args == [4] => handler.report_modify_other_keys(writer),
             ('n', None) => {
                 handler.device_status(writer, arg_or_default!(idx: 0, default: 0) as usize)
             },
+            ('n', Some(b'?')) if args == [996] => handler.report_color_scheme(writer),
+            ('n', Some(b'>')) if args == [4] => {
+                handler.set_modify_other_keys(ModifyOtherKeys::Reset)
+            },
+            ('p', Some(b'!')) => handler.soft_reset(),
             ('q', Some(b' ')) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style
-                let style = match arg_or_default!(idx: 0, default: 0) {
+                let arg = arg_or_default!(idx: 0, default: 0);
+                let style = match arg {
                     0 => None,
                     1 | 2 => Some(CursorStyle::Block),
                     3 | 4 => Some(CursorStyle::Underline),
@@ -1078,7 +1882,11 @@ where
                     },
                 };
 
+                // Odd styles blink, even ones are steady
+                let blinking = if arg == 0 { None } else { Some(arg % 2 == 1) };
+
                 handler.set_cursor_style(style);
+                handler.set_cursor_blinking(blinking);
             },
             ('r', None) => {
                 let top = arg_or_default!(idx: 0, default: 1) as usize;
@@ -1086,8 +1894,81 @@ where
 
                 handler.set_scrolling_region(top, bottom);
             },
-            ('s', None) => handler.save_cursor_position(),
+            ('r', Some(b'$')) => {
+                let rect = rect!(idx: 0);
+                if args.len() <= 4 {
+                    handler.change_rect_attribute(rect, Attr::Reset);
+                    return;
+                }
+
+                for attr in attrs_from_sgr_parameters(&args[4..]) {
+                    match attr {
+                        Some(attr) => handler.change_rect_attribute(rect, attr),
+                        None => unhandled!(),
+                    }
+                }
+            },
+            ('s', None) => {
+                let left = arg_or_default!(idx: 0, default: 1) as usize;
+                let right = arg_or_default!(idx: 1, default: handler.cols().0 as _) as usize;
+
+                handler.set_left_and_right_margins(left, right);
+            },
             ('u', None) => handler.restore_cursor_position(),
+            ('u', Some(b'?')) => handler.report_keyboard_mode(writer),
+            ('u', Some(b'=')) => {
+                let mode =
+                    KeyboardModes::from_bits_truncate(arg_or_default!(idx: 0, default: 0) as u8);
+                let behavior = match arg_or_default!(idx: 1, default: 1) {
+                    1 => KeyboardModesApplyBehavior::Replace,
+                    2 => KeyboardModesApplyBehavior::Union,
+                    3 => KeyboardModesApplyBehavior::Difference,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+
+                handler.set_keyboard_mode(mode, behavior);
+            },
+            ('u', Some(b'>')) => {
+                let mode =
+                    KeyboardModes::from_bits_truncate(arg_or_default!(idx: 0, default: 0) as u8);
+                handler.push_keyboard_mode(mode);
+            },
+            ('u', Some(b'<')) => {
+                handler.pop_keyboard_modes(arg_or_default!(idx: 0, default: 1) as u16)
+            },
+            ('v', Some(b'$')) => {
+                // Page arguments are ignored, since there is only a single page
+                let src = rect!(idx: 0);
+                let top = arg_or_default!(idx: 5, default: 1) as usize;
+                let left = arg_or_default!(idx: 6, default: 1) as usize;
+                handler.copy_rect(src, top, left);
+            },
+            ('x', Some(b'$')) => {
+                // Only printable characters of the GL and GR sets can be used for filling
+                let c = match args.get(0) {
+                    Some(&c @ 32..=126) | Some(&c @ 160..=255) => c as u8 as char,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+                handler.fill_rect(rect!(idx: 1), c);
+            },
+            ('x', Some(b'*')) => {
+                let extent = match arg_or_default!(idx: 0, default: 0) {
+                    0 | 1 => AttributeChangeExtent::Stream,
+                    2 => AttributeChangeExtent::Rectangle,
+                    _ => {
+                        unhandled!();
+                        return;
+                    },
+                };
+                handler.set_attribute_change_extent(extent);
+            },
+            ('z', Some(b'$')) => handler.erase_rect(rect!(idx: 0)),
             _ => unhandled!(),
         }
     }
@@ -1120,6 +2001,9 @@ where
         }
 
         match (byte, intermediates.get(0)) {
+            (b'A', intermediate) => {
+                configure_charset!(StandardCharset::UnitedKingdom, intermediate)
+            },
             (b'B', intermediate) => configure_charset!(StandardCharset::Ascii, intermediate),
             (b'D', None) => self.handler.linefeed(),
             (b'E', None) => {
@@ -1128,8 +2012,13 @@ where
             },
             (b'H', None) => self.handler.set_horizontal_tabstop(),
             (b'M', None) => self.handler.reverse_index(),
-            (b'Z', None) => self.handler.identify_terminal(self.writer),
-            (b'c', None) => self.handler.reset_state(),
+            (b'N', None) => self.handler.set_single_shift(CharsetIndex::G2),
+            (b'O', None) => self.handler.set_single_shift(CharsetIndex::G3),
+            (b'Z', None) => self.handler.identify_terminal(self.writer, None),
+            (b'c', None) => {
+                self.state.preceding_char = None;
+                self.handler.reset_state();
+            },
             (b'0', intermediate) => {
                 configure_charset!(StandardCharset::SpecialCharacterAndLineDrawing, intermediate)
             },
@@ -1138,6 +2027,8 @@ where
             (b'8', None) => self.handler.restore_cursor_position(),
             (b'=', None) => self.handler.set_keypad_application_mode(),
             (b'>', None) => self.handler.unset_keypad_application_mode(),
+            (b'n', None) => self.handler.set_active_charset(CharsetIndex::G2),
+            (b'o', None) => self.handler.set_active_charset(CharsetIndex::G3),
             // String terminator, do nothing (parser handles as string terminator)
             (b'\\', None) => (),
             _ => unhandled!(),
@@ -1166,7 +2057,7 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
             7 => Some(Attr::Reverse),
             8 => Some(Attr::Hidden),
             9 => Some(Attr::Strike),
-            21 => Some(Attr::CancelBold),
+            21 => Some(Attr::DoubleUnderline),
             22 => Some(Attr::CancelBoldDim),
             23 => Some(Attr::CancelItalic),
             24 => Some(Attr::CancelUnderline),
@@ -1210,6 +2101,16 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
                 }
             },
             49 => Some(Attr::Background(Color::Named(NamedColor::Background))),
+            58 => {
+                let mut start = 0;
+                if let Some(color) = parse_sgr_color(&parameters[i..], &mut start) {
+                    i += start;
+                    Some(Attr::UnderlineColor(Some(color)))
+                } else {
+                    None
+                }
+            },
+            59 => Some(Attr::UnderlineColor(None)),
             90 => Some(Attr::Foreground(Color::Named(NamedColor::BrightBlack))),
             91 => Some(Attr::Foreground(Color::Named(NamedColor::BrightRed))),
             92 => Some(Attr::Foreground(Color::Named(NamedColor::BrightGreen))),
@@ -1236,6 +2137,65 @@ fn attrs_from_sgr_parameters(parameters: &[i64]) -> Vec<Option<Attr>> {
     attrs
 }
 
+/// Parse SGR parameters in which some are split into `:` separated sub-parameters
+fn attrs_from_sgr_subparameters(params: &[u8]) -> Vec<Option<Attr>> {
+    let mut attrs = Vec::new();
+
+    // Parameters without sub-parameters can still form a single attribute, like `38;5;1`
+    let mut plain = Vec::new();
+    for param in params.split(|&byte| byte == b';') {
+        let subparams: Vec<i64> = param
+            .split(|&byte| byte == b':')
+            .map(|subparam| {
+                subparam.iter().fold(0i64, |num, &digit| {
+                    num.saturating_mul(10).saturating_add(i64::from(digit - b'0'))
+                })
+            })
+            .collect();
+
+        if subparams.len() == 1 {
+            plain.push(subparams[0]);
+        } else {
+            attrs.append(&mut attrs_from_sgr_parameters(&plain));
+            plain.clear();
+            attrs.push(attr_from_sgr_subparameters(&subparams));
+        }
+    }
+    attrs.append(&mut attrs_from_sgr_parameters(&plain));
+
+    attrs
+}
+
+/// Parse a single SGR parameter with its sub-parameters, like `4:3` or `38:2::255:0:0`
+fn attr_from_sgr_subparameters(subparams: &[i64]) -> Option<Attr> {
+    match (subparams[0], subparams.len()) {
+        (4, 2) => match subparams[1] {
+            0 => Some(Attr::CancelUnderline),
+            1 => Some(Attr::Underline),
+            2 => Some(Attr::DoubleUnderline),
+            3 => Some(Attr::Undercurl),
+            4 => Some(Attr::DottedUnderline),
+            5 => Some(Attr::DashedUnderline),
+            _ => None,
+        },
+        (38, _) | (48, _) | (58, _) => {
+            // Truecolor may include a color space identifier, which is ignored
+            let mut subparams = subparams.to_vec();
+            if subparams[1] == 2 && subparams.len() == 6 {
+                subparams.remove(2);
+            }
+
+            let color = parse_sgr_color(&subparams, &mut 0)?;
+            match subparams[0] {
+                38 => Some(Attr::Foreground(color)),
+                48 => Some(Attr::Background(color)),
+                _ => Some(Attr::UnderlineColor(Some(color))),
+            }
+        },
+        _ => None,
+    }
+}
+
 /// Parse a color specifier from list of attributes
 fn parse_sgr_color(attrs: &[i64], i: &mut usize) -> Option<Color> {
     if attrs.len() < 2 {
@@ -1364,18 +2324,40 @@ pub mod C0 {
 #[cfg(test)]
 mod tests {
     use super::{
-        parse_number, xparse_color, Attr, CharsetIndex, Color, Handler, Processor, StandardCharset,
-        TermInfo,
+        parse_number, xparse_color, Attr, CharsetIndex, Color, ControlStringKind, Graphic, Handler,
+        Hyperlink, KeyboardModes, KeyboardModesApplyBehavior, Mode, ModeState, ModifyOtherKeys,
+        Notification, Processor, Progress, PromptMark, Rect, StandardCharset, TermInfo,
+        MAX_NOTIFICATION_LEN,
     };
     use crate::index::{Column, Line};
     use crate::term::color::Rgb;
     use std::io;
+    use std::path::PathBuf;
 
     struct MockHandler {
         index: CharsetIndex,
         charset: StandardCharset,
+        single_shift: Option<CharsetIndex>,
         attr: Option<Attr>,
         identity_reported: bool,
+        identity_intermediate: Option<char>,
+        graphic: Option<Graphic>,
+        hyperlink: Option<Hyperlink>,
+        keyboard_mode: Option<(KeyboardModes, KeyboardModesApplyBehavior)>,
+        modify_other_keys: Option<ModifyOtherKeys>,
+        terminfo_capabilities: Vec<String>,
+        colors: Vec<(usize, Rgb)>,
+        color_queries: Vec<usize>,
+        text_area_resize: Option<(Option<usize>, Option<usize>)>,
+        prompt_marks: Vec<PromptMark>,
+        progress: Vec<Progress>,
+        rect_fill: Option<(Rect, char)>,
+        margins: Option<(usize, usize)>,
+        working_directory: Option<(String, PathBuf)>,
+        control_strings: Vec<(ControlStringKind, Vec<u8>)>,
+        title_stack_depth: usize,
+        notifications: Vec<Notification>,
+        cursor_blinking: Vec<Option<bool>>,
     }
 
     impl Handler for MockHandler {
@@ -1392,13 +2374,94 @@ mod tests {
             self.index = index;
         }
 
-        fn identify_terminal<W: io::Write>(&mut self, _: &mut W) {
+        fn set_single_shift(&mut self, index: CharsetIndex) {
+            self.single_shift = Some(index);
+        }
+
+        fn identify_terminal<W: io::Write>(&mut self, _: &mut W, intermediate: Option<char>) {
             self.identity_reported = true;
+            self.identity_intermediate = intermediate;
         }
 
         fn reset_state(&mut self) {
             *self = Self::default();
         }
+
+        fn insert_graphic(&mut self, graphic: Graphic) {
+            self.graphic = Some(graphic);
+        }
+
+        fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+            self.hyperlink = hyperlink;
+        }
+
+        fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+            self.keyboard_mode = Some((mode, behavior));
+        }
+
+        fn set_modify_other_keys(&mut self, mode: ModifyOtherKeys) {
+            self.modify_other_keys = Some(mode);
+        }
+
+        fn mode_state(&self, mode: Mode) -> ModeState {
+            ModeState::from(mode == Mode::BracketedPaste)
+        }
+
+        fn report_terminfo_capability<W: io::Write>(&mut self, _: &mut W, name: &str) {
+            self.terminfo_capabilities.push(name.to_owned());
+        }
+
+        fn set_color(&mut self, index: usize, color: Rgb) {
+            self.colors.push((index, color));
+        }
+
+        fn color_sequence<W: io::Write>(&mut self, _: &mut W, index: usize, _: &str) {
+            self.color_queries.push(index);
+        }
+
+        fn resize_text_area_chars(&mut self, lines: Option<usize>, cols: Option<usize>) {
+            self.text_area_resize = Some((lines, cols));
+        }
+
+        fn set_prompt_mark(&mut self, mark: PromptMark) {
+            self.prompt_marks.push(mark);
+        }
+
+        fn set_progress(&mut self, progress: Progress) {
+            self.progress.push(progress);
+        }
+
+        fn set_working_directory(&mut self, host: &str, path: PathBuf) {
+            self.working_directory = Some((host.to_owned(), path));
+        }
+
+        fn control_string(&mut self, kind: ControlStringKind, data: &[u8]) {
+            self.control_strings.push((kind, data.to_vec()));
+        }
+
+        fn push_title(&mut self) {
+            self.title_stack_depth += 1;
+        }
+
+        fn desktop_notification(&mut self, notification: Notification) {
+            self.notifications.push(notification);
+        }
+
+        fn pop_title(&mut self) {
+            self.title_stack_depth = self.title_stack_depth.saturating_sub(1);
+        }
+
+        fn fill_rect(&mut self, rect: Rect, c: char) {
+            self.rect_fill = Some((rect, c));
+        }
+
+        fn set_left_and_right_margins(&mut self, left: usize, right: usize) {
+            self.margins = Some((left, right));
+        }
+
+        fn set_cursor_blinking(&mut self, blinking: Option<bool>) {
+            self.cursor_blinking.push(blinking);
+        }
     }
 
     impl TermInfo for MockHandler {
@@ -1416,8 +2479,27 @@ mod tests {
             MockHandler {
                 index: CharsetIndex::G0,
                 charset: StandardCharset::Ascii,
+                single_shift: None,
                 attr: None,
                 identity_reported: false,
+                identity_intermediate: None,
+                graphic: None,
+                hyperlink: None,
+                keyboard_mode: None,
+                modify_other_keys: None,
+                terminfo_capabilities: Vec::new(),
+                colors: Vec::new(),
+                color_queries: Vec::new(),
+                text_area_resize: None,
+                prompt_marks: Vec::new(),
+                progress: Vec::new(),
+                rect_fill: None,
+                margins: None,
+                working_directory: None,
+                control_strings: Vec::new(),
+                title_stack_depth: 0,
+                notifications: Vec::new(),
+                cursor_blinking: Vec::new(),
             }
         }
     }
@@ -1436,6 +2518,372 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Bold));
     }
 
+    #[test]
+    fn parse_sixel_graphic() {
+        let bytes: &[u8] = b"\x1bP0;1q\"1;1;2;6#1~~\x1b\\";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let graphic = handler.graphic.unwrap();
+        assert_eq!((graphic.width, graphic.height), (2, 6));
+        assert!(graphic.pixels.chunks(4).all(|pixel| pixel == [51, 51, 204, 255]));
+    }
+
+    #[test]
+    fn parse_hyperlink() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b]8;foo=bar:id=link;https://example.org/?a=1;b=2\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = Hyperlink {
+            id: Some(String::from("link")),
+            uri: String::from("https://example.org/?a=1;b=2"),
+        };
+        assert_eq!(handler.hyperlink, Some(expected));
+
+        for byte in &b"\x1b]8;;\x1b\\"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.hyperlink, None);
+    }
+
+    #[test]
+    fn parse_keyboard_mode() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[=5;2u"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let mode = KeyboardModes::DISAMBIGUATE_ESC_CODES | KeyboardModes::REPORT_ALTERNATE_KEYS;
+        assert_eq!(handler.keyboard_mode, Some((mode, KeyboardModesApplyBehavior::Union)));
+
+        for byte in &b"\x1b[=1u"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let mode = KeyboardModes::DISAMBIGUATE_ESC_CODES;
+        assert_eq!(handler.keyboard_mode, Some((mode, KeyboardModesApplyBehavior::Replace)));
+    }
+
+    #[test]
+    fn parse_modify_other_keys() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[>4;2m"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.modify_other_keys, Some(ModifyOtherKeys::EnableAll));
+
+        for byte in &b"\x1b[>4m"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.modify_other_keys, Some(ModifyOtherKeys::Reset));
+
+        // Other resources are ignored
+        handler.modify_other_keys = None;
+        for byte in &b"\x1b[>1;2m"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.modify_other_keys, None);
+    }
+
+    #[test]
+    fn parse_terminfo_capability_request() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        // Request for `RGB`, `Smulx` and an invalid name
+        for byte in &b"\x1bP+q524742;536d756c78;5\x1b\\"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.terminfo_capabilities, vec![String::from("RGB"), String::from("Smulx")]);
+    }
+
+    #[test]
+    fn parse_color_index() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b]4;1;#ff0000;2;?;3;rgb:00/ff/00\x07\x1b]4;4;invalid;5;?\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let red = Rgb { r: 0xff, g: 0x00, b: 0x00 };
+        let green = Rgb { r: 0x00, g: 0xff, b: 0x00 };
+        assert_eq!(handler.colors, vec![(1, red), (3, green)]);
+        assert_eq!(handler.color_queries, vec![2, 5]);
+    }
+
+    #[test]
+    fn parse_text_area_resize() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[8;24;80t"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.text_area_resize, Some((Some(24), Some(80))));
+
+        for byte in &b"\x1b[8;;100t"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.text_area_resize, Some((None, Some(100))));
+    }
+
+    #[test]
+    fn parse_prompt_marks() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07\x1b]133;D;0\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = vec![
+            PromptMark::PromptStart,
+            PromptMark::CommandStart,
+            PromptMark::OutputStart,
+            PromptMark::CommandEnd,
+        ];
+        assert_eq!(handler.prompt_marks, expected);
+    }
+
+    #[test]
+    fn parse_progress() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes =
+            &b"\x1b]9;4;1;42\x07\x1b]9;4;2\x07\x1b]9;4;4;250\x07\x1b]9;4;3\x07\x1b]9;4;0\x07"[..];
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = vec![
+            Progress::Normal(42),
+            Progress::Error(None),
+            Progress::Paused(Some(100)),
+            Progress::Indeterminate,
+            Progress::Hidden,
+        ];
+        assert_eq!(handler.progress, expected);
+    }
+
+    #[test]
+    fn parse_working_directory() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b]7;file://host/home/user/a%20b;c\x1b\\"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = (String::from("host"), PathBuf::from("/home/user/a b;c"));
+        assert_eq!(handler.working_directory, Some(expected));
+
+        handler.working_directory = None;
+        for byte in &b"\x1b]7;/home/user\x07\x1b]7;file://host/%2\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.working_directory, None);
+    }
+
+    #[test]
+    fn parse_control_strings() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes = &b"\x1b_Gf=100;AAAA\x1b\\\x1b^pm\x1b\\\x1bXab\x18\x1b]2;_\x07\x1b(_"[..];
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = vec![
+            (ControlStringKind::Apc, b"Gf=100;AAAA".to_vec()),
+            (ControlStringKind::Pm, b"pm".to_vec()),
+        ];
+        assert_eq!(handler.control_strings, expected);
+    }
+
+    #[test]
+    fn parse_title_stack_operations() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[22t\x1b[22;1t\x1b[22;2t"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.title_stack_depth, 2);
+
+        for byte in &b"\x1b[23;1t\x1b[23;0t"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.title_stack_depth, 1);
+    }
+
+    #[test]
+    fn parse_notifications() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let bytes = &b"\x1b]777;notify;Done;exit 0; took 5s\x07\
+                       \x1b]99;i=1:d=0;Build\x1b\\\x1b]99;i=1:p=body:e=1;ZmluaXNoZWQ=\x1b\\\
+                       \x1b]99;i=2:d=0;Lost\x07\x1b]99;;Test\x07\x1b]99;p=close;\x07"[..];
+        for byte in bytes {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let notification = |title: &str, body: &str| Notification {
+            title: title.to_owned(),
+            body: body.to_owned(),
+        };
+        let expected = vec![
+            notification("Done", "exit 0; took 5s"),
+            notification("Build", "finished"),
+            notification("Test", ""),
+        ];
+        assert_eq!(handler.notifications, expected);
+    }
+
+    #[test]
+    fn drop_oversized_notification() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        let chunk = format!("\x1b]99;i=1:d=0;{}\x07", "a".repeat(1000));
+        for _ in 0..=MAX_NOTIFICATION_LEN / 1000 {
+            for byte in chunk.as_bytes() {
+                parser.advance(&mut handler, *byte, &mut io::sink());
+            }
+        }
+        for byte in &b"\x1b]99;i=1;b\x07\x1b]99;;Test\x07"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let expected = Notification { title: String::from("Test"), body: String::new() };
+        assert_eq!(handler.notifications, vec![expected]);
+    }
+
+    #[test]
+    fn parse_fill_rect() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[120;2;3$x"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        let rect = Rect { top: 2, left: 3, bottom: 200, right: 90 };
+        assert_eq!(handler.rect_fill, Some((rect, 'x')));
+    }
+
+    #[test]
+    fn parse_left_and_right_margins() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[5;20s"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.margins, Some((5, 20)));
+
+        for byte in &b"\x1b[s"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.margins, Some((1, 90)));
+    }
+
+    #[test]
+    fn parse_cursor_blinking() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[1 q\x1b[6 q\x1b[ q"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.cursor_blinking, vec![Some(true), Some(false), None]);
+    }
+
+    #[test]
+    fn parse_mode_request() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+        let mut writer = Vec::new();
+
+        for byte in &b"\x1b[?2004$p\x1b[?1$p\x1b[?2026$p\x1b[?9999$p\x1b[4$p"[..] {
+            parser.advance(&mut handler, *byte, &mut writer);
+        }
+
+        let expected = &b"\x1b[?2004;1$y\x1b[?1;2$y\x1b[?2026;2$y\x1b[?9999;0$y\x1b[4;2$y"[..];
+        assert_eq!(writer, expected);
+    }
+
+    #[test]
+    fn parse_sync_update() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b[?2026h\x1b[1m"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(parser.sync_timeout().is_some());
+        assert_eq!(handler.attr, None);
+
+        for byte in &b"\x1b[?2026l"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(parser.sync_timeout().is_none());
+        assert_eq!(handler.attr, Some(Attr::Bold));
+
+        // Equivalent forms of the sequence end the update too
+        for end in &[&b"\x1b[?2026;l"[..], b"\x1b[?25;2026l", b"\x1bP=2s\x1b\\"] {
+            for byte in b"\x1b[?2026h\x1b[3m".iter().chain(end.iter()) {
+                parser.advance(&mut handler, *byte, &mut io::sink());
+            }
+
+            assert!(parser.sync_timeout().is_none());
+            assert_eq!(handler.attr, Some(Attr::Italic));
+            handler.attr = None;
+        }
+    }
+
+    #[test]
+    fn parse_sync_update_timeout() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1bP=1s\x1b\\\x1b[1m"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(parser.sync_timeout().is_some());
+        assert_eq!(handler.attr, None);
+
+        parser.stop_sync(&mut handler, &mut io::sink());
+
+        assert!(parser.sync_timeout().is_none());
+        assert_eq!(handler.attr, Some(Attr::Bold));
+    }
+
     #[test]
     fn parse_terminal_identity_csi() {
         let bytes: &[u8] = &[0x1b, b'[', b'1', b'c'];
@@ -1466,6 +2914,15 @@ mod tests {
         }
 
         assert!(handler.identity_reported);
+        assert_eq!(handler.identity_intermediate, None);
+        handler.reset_state();
+
+        for byte in &b"\x1b[>c"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert!(handler.identity_reported);
+        assert_eq!(handler.identity_intermediate, Some('>'));
     }
 
     #[test]
@@ -1514,6 +2971,65 @@ mod tests {
         assert_eq!(handler.attr, Some(Attr::Foreground(Color::Spec(spec))));
     }
 
+    #[test]
+    fn parse_underline_color_attr() {
+        static BYTES: &[u8] = b"\x1b[58;5;196m";
+
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &BYTES[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Indexed(196)))));
+
+        for byte in b"\x1b[59m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(None)));
+    }
+
+    #[test]
+    fn parse_underline_style_attr() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in b"\x1b[21m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.attr, Some(Attr::DoubleUnderline));
+
+        for byte in b"\x1b[4:3m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.attr, Some(Attr::Undercurl));
+
+        for byte in b"\x1b[1;4:4m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.attr, Some(Attr::DottedUnderline));
+
+        for byte in b"\x1b[4:5;38;5;1m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.attr, Some(Attr::Foreground(Color::Indexed(1))));
+
+        for byte in b"\x1b[58:2::1:2:3m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        let spec = Rgb { r: 1, g: 2, b: 3 };
+        assert_eq!(handler.attr, Some(Attr::UnderlineColor(Some(Color::Spec(spec)))));
+
+        // Private sequences are never SGR
+        handler.attr = None;
+        for byte in b"\x1b[?4:3m" {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+        assert_eq!(handler.attr, None);
+    }
+
     /// No exactly a test; useful for debugging
     #[test]
     fn parse_zsh_startup() {
@@ -1574,6 +3090,32 @@ mod tests {
         assert_eq!(handler.index, CharsetIndex::G1);
     }
 
+    #[test]
+    fn parse_locking_and_single_shifts() {
+        let mut parser = Processor::new();
+        let mut handler = MockHandler::default();
+
+        for byte in &b"\x1b+A\x1bo"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G3);
+        assert_eq!(handler.charset, StandardCharset::UnitedKingdom);
+
+        for byte in &b"\x1bn\x1bN"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.index, CharsetIndex::G2);
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G2));
+
+        for byte in &b"\x1bO"[..] {
+            parser.advance(&mut handler, *byte, &mut io::sink());
+        }
+
+        assert_eq!(handler.single_shift, Some(CharsetIndex::G3));
+    }
+
     #[test]
     fn parse_valid_rgb_colors() {
         assert_eq!(xparse_color(b"rgb:f/e/d"), Some(Rgb { r: 0xff, g: 0xee, b: 0xdd }));
@@ -1,11 +1,10 @@
-use log::error;
 use serde::{Deserialize, Deserializer};
 
-use crate::config::{failure_default, LOG_TARGET_CONFIG};
+use crate::config::{diagnostics, failure_default};
 use crate::term::color::Rgb;
 
 #[serde(default)]
-#[derive(Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct Colors {
     #[serde(deserialize_with = "failure_default")]
     pub primary: PrimaryColors,
@@ -23,6 +22,8 @@ pub struct Colors {
     pub dim: Option<AnsiColors>,
     #[serde(deserialize_with = "failure_default")]
     pub indexed_colors: Vec<IndexedColor>,
+    #[serde(deserialize_with = "failure_default")]
+    minimum_contrast: f64,
 }
 
 impl Colors {
@@ -33,6 +34,11 @@ impl Colors {
     pub fn bright(&self) -> &AnsiColors {
         &self.bright.0
     }
+
+    /// Contrast ratio the foreground is adjusted to, between 1 (disabled) and 21.
+    pub fn minimum_contrast(&self) -> f64 {
+        self.minimum_contrast.max(1.).min(21.)
+    }
 }
 
 #[serde(default)]
@@ -52,12 +58,11 @@ where
     match u8::deserialize(value) {
         Ok(index) => {
             if index < 16 {
-                error!(
-                    target: LOG_TARGET_CONFIG,
-                    "Problem with config: indexed_color's index is {}, but a value bigger than 15 \
-                     was expected; ignoring setting",
+                diagnostics::invalid_value(format_args!(
+                    "indexed_color's index is {}, but a value bigger than 15 was expected; \
+                     ignoring setting",
                     index
-                );
+                ));
 
                 // Return value out of range to ignore this color
                 Ok(0)
@@ -66,7 +71,7 @@ where
             }
         },
         Err(err) => {
-            error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; ignoring setting", err);
+            diagnostics::invalid_value(format_args!("{}; ignoring setting", err));
 
             // Return value out of range to ignore this color
             Ok(0)
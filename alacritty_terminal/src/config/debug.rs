@@ -1,7 +1,7 @@
-use log::{error, LevelFilter};
+use log::LevelFilter;
 use serde::{Deserialize, Deserializer};
 
-use crate::config::{failure_default, LOG_TARGET_CONFIG};
+use crate::config::{diagnostics, failure_default};
 
 /// Debugging options
 #[serde(default)]
@@ -54,10 +54,10 @@ where
         "debug" => LevelFilter::Debug,
         "trace" => LevelFilter::Trace,
         level => {
-            error!(
-                target: LOG_TARGET_CONFIG,
-                "Problem with config: invalid log level {}; using level Warn", level
-            );
+            diagnostics::invalid_value(format_args!(
+                "invalid log level {}; using level Warn",
+                level
+            ));
             default_log_level()
         },
     })
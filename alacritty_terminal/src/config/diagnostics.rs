@@ -0,0 +1,531 @@
+//! Problems with config values, with the path of keys leading to them.
+//!
+//! Values are deserialized through [`TrackedValue`], which keeps track of the keys leading to the
+//! value being deserialized and reports fields that are not part of the struct they appear in.
+
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::mem;
+use std::vec;
+
+use log::{error, warn};
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+use serde_yaml::{Mapping, Value};
+
+use crate::config::LOG_TARGET_CONFIG;
+
+/// Minimum similarity of a name to be suggested in place of an unknown one.
+const SUGGESTION_SIMILARITY: f64 = 0.8;
+
+thread_local! {
+    static STATE: RefCell<State> = RefCell::new(State::default());
+}
+
+/// Problem with a config value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+
+    /// Keys leading to the value.
+    pub path: Vec<String>,
+
+    /// Description of the problem and how it was handled.
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", display_path(&self.path), self.message)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// Field which is not part of the config and is ignored.
+    UnknownField,
+
+    /// Value which could not be deserialized and is replaced by its default.
+    InvalidValue,
+}
+
+#[derive(Default)]
+struct State {
+    /// Keys leading to the value which is currently deserialized.
+    path: Vec<String>,
+
+    /// Path and suggested fix of the deepest error which has not been reported yet.
+    pending: Option<(Vec<String>, String)>,
+
+    /// Diagnostics of the current `collect` call, they're logged directly without one.
+    diagnostics: Option<Vec<Diagnostic>>,
+}
+
+/// Collect the diagnostics of all values deserialized by `f`, instead of logging them.
+pub fn collect<T, F>(f: F) -> (T, Vec<Diagnostic>)
+where
+    F: FnOnce() -> T,
+{
+    let previous = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.pending = None;
+        state.diagnostics.replace(Vec::new())
+    });
+
+    let result = f();
+
+    let diagnostics =
+        STATE.with(|state| mem::replace(&mut state.borrow_mut().diagnostics, previous));
+
+    (result, diagnostics.unwrap_or_default())
+}
+
+/// Deserialize a value, replacing it by its default if it is invalid.
+pub fn deserialize_or_default<'de, T>(value: Value) -> T
+where
+    T: Deserialize<'de> + Default,
+{
+    STATE.with(|state| state.borrow_mut().pending = None);
+
+    T::deserialize(TrackedValue(value)).unwrap_or_else(|err| {
+        invalid_value(format_args!("{}; using default value", err));
+        T::default()
+    })
+}
+
+/// Deserialize a struct which is flattened into its parent, tracking the keys of its fields.
+///
+/// Serde passes the fields of flattened structs without any of the keys leading to them, so they
+/// are collected into a map first. Unknown fields are not reported, since they are shared with
+/// the parent.
+pub fn deserialize_flattened<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let mapping = Mapping::deserialize(deserializer)?;
+    T::deserialize(FlattenedMap(mapping)).map_err(de::Error::custom)
+}
+
+/// Report a value which could not be used, with its consequence like `...; using default value`.
+///
+/// The value is located at the deepest key which failed to deserialize since the last report.
+pub fn invalid_value(message: impl Display) {
+    let (path, suggestion) = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let path = state.path.clone();
+        state.pending.take().unwrap_or((path, String::new()))
+    });
+
+    // Suggestions belong to the error, in front of its consequence
+    let mut message = message.to_string();
+    let index = message.find("; ").unwrap_or_else(|| message.len());
+    message.insert_str(index, &suggestion);
+
+    report(Diagnostic { kind: DiagnosticKind::InvalidValue, path, message });
+}
+
+/// Suggestion like ``, did you mean `window`?`` for the candidate most similar to a name.
+pub fn did_you_mean<'a, I>(name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let name = name.to_lowercase();
+    let best = candidates
+        .into_iter()
+        .map(|candidate| (strsim::jaro_winkler(&name, &candidate.to_lowercase()), candidate))
+        .filter(|(similarity, _)| *similarity >= SUGGESTION_SIMILARITY)
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+    match best {
+        Some((_, candidate)) => format!(", did you mean `{}`?", candidate),
+        None => String::new(),
+    }
+}
+
+/// Names of the fields of a struct.
+pub fn struct_fields<'de, T>() -> &'static [&'static str]
+where
+    T: Deserialize<'de>,
+{
+    let fields = Cell::new(&[][..]);
+    let _ = T::deserialize(FieldsProbe(&fields));
+    fields.get()
+}
+
+/// Display a path of keys like `hints.enabled[0].regex`.
+pub fn display_path(path: &[String]) -> String {
+    let mut text = String::new();
+    for key in path {
+        if !text.is_empty() && !key.starts_with('[') {
+            text.push('.');
+        }
+        text.push_str(key);
+    }
+    text
+}
+
+fn report(diagnostic: Diagnostic) {
+    let diagnostic = STATE.with(|state| match &mut state.borrow_mut().diagnostics {
+        Some(diagnostics) => {
+            diagnostics.push(diagnostic);
+            None
+        },
+        None => Some(diagnostic),
+    });
+
+    match diagnostic {
+        Some(diagnostic) if diagnostic.kind == DiagnosticKind::UnknownField => {
+            warn!(target: LOG_TARGET_CONFIG, "Problem with config: {}", diagnostic);
+        },
+        Some(diagnostic) => {
+            error!(target: LOG_TARGET_CONFIG, "Problem with config: {}", diagnostic)
+        },
+        None => (),
+    }
+}
+
+/// Deserialize the value of a key, remembering where the deepest error occurred.
+fn with_key<T, E, F>(key: String, f: F) -> Result<T, E>
+where
+    F: FnOnce() -> Result<T, E>,
+{
+    STATE.with(|state| state.borrow_mut().path.push(key));
+
+    let result = f();
+
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        if result.is_err() && state.pending.is_none() {
+            state.pending = Some((state.path.clone(), String::new()));
+        }
+        state.path.pop();
+    });
+
+    result
+}
+
+fn key_name(key: &Value) -> String {
+    match key {
+        Value::String(key) => key.clone(),
+        Value::Number(key) => key.to_string(),
+        Value::Bool(key) => key.to_string(),
+        key => format!("{:?}", key),
+    }
+}
+
+/// Config value which tracks the keys leading to the values inside of it.
+pub struct TrackedValue(pub Value);
+
+impl<'de> Deserializer<'de> for TrackedValue {
+    type Error = serde_yaml::Error;
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct tuple tuple_struct identifier ignored_any
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Mapping(mapping) => visitor.visit_map(TrackedMap::new(mapping, None)),
+            Value::Sequence(sequence) => visitor.visit_seq(TrackedSeq::new(sequence)),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Sequence(sequence) => visitor.visit_seq(TrackedSeq::new(sequence)),
+            value => value.deserialize_seq(visitor),
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Mapping(mapping) => visitor.visit_map(TrackedMap::new(mapping, None)),
+            value => value.deserialize_map(visitor),
+        }
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Mapping(mapping) => visitor.visit_map(TrackedMap::new(mapping, Some(fields))),
+            value => value.deserialize_struct(name, fields, visitor),
+        }
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        let suggestion = match &self.0 {
+            Value::String(variant) => did_you_mean(variant, variants.iter().copied()),
+            _ => String::new(),
+        };
+
+        let result = self.0.deserialize_enum(name, variants, visitor);
+
+        if result.is_err() {
+            STATE.with(|state| {
+                let mut state = state.borrow_mut();
+                state.pending = Some((state.path.clone(), suggestion));
+            });
+        }
+
+        result
+    }
+}
+
+/// Fields of a flattened struct.
+struct FlattenedMap(Mapping);
+
+impl<'de> Deserializer<'de> for FlattenedMap {
+    type Error = serde_yaml::Error;
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(TrackedMap::new(self.0, None))
+    }
+}
+
+struct TrackedMap {
+    iter: vec::IntoIter<(Value, Value)>,
+    value: Option<(String, Value)>,
+
+    /// Valid fields, if the map is a struct.
+    fields: Option<&'static [&'static str]>,
+}
+
+impl TrackedMap {
+    fn new(mapping: Mapping, fields: Option<&'static [&'static str]>) -> Self {
+        let entries: Vec<_> = mapping.into_iter().collect();
+        Self { iter: entries.into_iter(), value: None, fields }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for TrackedMap {
+    type Error = serde_yaml::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let (key, value) = match self.iter.next() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        let name = key_name(&key);
+        if let Some(fields) = self.fields.filter(|fields| !fields.contains(&name.as_str())) {
+            let mut path = STATE.with(|state| state.borrow().path.clone());
+            path.push(name.clone());
+            let message = format!("unknown field{}", did_you_mean(&name, fields.iter().copied()));
+            report(Diagnostic { kind: DiagnosticKind::UnknownField, path, message });
+        }
+
+        self.value = Some((name, value));
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some((name, value)) => with_key(name, || seed.deserialize(TrackedValue(value))),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct TrackedSeq {
+    iter: vec::IntoIter<Value>,
+    index: usize,
+}
+
+impl TrackedSeq {
+    fn new(sequence: Vec<Value>) -> Self {
+        Self { iter: sequence.into_iter(), index: 0 }
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for TrackedSeq {
+    type Error = serde_yaml::Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = match self.iter.next() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+
+        let key = format!("[{}]", self.index);
+        self.index += 1;
+        with_key(key, || seed.deserialize(TrackedValue(value))).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializer which only records the fields of a struct.
+struct FieldsProbe<'a>(&'a Cell<&'static [&'static str]>);
+
+impl<'a, 'de> Deserializer<'de> for FieldsProbe<'a> {
+    type Error = de::value::Error;
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.set(fields);
+        Err(de::Error::custom("fields probed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::{MockConfig, Scrolling, CONFIG_FIELDS};
+
+    #[test]
+    fn collect_diagnostics() {
+        let yaml = "window: {padding: {x: a, y: 2}, startup_mode: Fulscreen, dynamic_paddin: \
+                    true}\nscrolling: {history: 10}";
+        let value: Value = serde_yaml::from_str(yaml).unwrap();
+        let (config, diagnostics) =
+            collect(|| MockConfig::deserialize(TrackedValue(value.clone())).unwrap());
+
+        assert_eq!(config.scrolling.history(), 10);
+        assert_eq!(diagnostics.len(), 3);
+
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::InvalidValue);
+        assert_eq!(display_path(&diagnostics[0].path), "window.padding.x");
+
+        assert_eq!(display_path(&diagnostics[1].path), "window.startup_mode");
+        assert!(diagnostics[1]
+            .message
+            .ends_with("did you mean `Fullscreen`?; using default value"));
+
+        assert_eq!(diagnostics[2].kind, DiagnosticKind::UnknownField);
+        assert_eq!(
+            diagnostics[2].to_string(),
+            "window.dynamic_paddin: unknown field, did you mean `dynamic_padding`?"
+        );
+    }
+
+    #[test]
+    fn suggestions() {
+        let candidates = ["Windowed", "Fullscreen", "SimpleFullscreen"];
+        assert_eq!(
+            did_you_mean("fulscreen", candidates.iter().copied()),
+            ", did you mean `Fullscreen`?"
+        );
+        assert_eq!(did_you_mean("maximized", candidates.iter().copied()), "");
+        assert_eq!(did_you_mean("window", Vec::new()), "");
+    }
+
+    #[test]
+    fn terminal_config_fields() {
+        // Fields which are not part of the terminal config end up in the flattened map
+        let mut mapping = Mapping::new();
+        for field in CONFIG_FIELDS.iter().chain(&["unknown"]) {
+            mapping.insert(Value::from(*field), Value::Null);
+        }
+        let value = Value::Mapping(mapping);
+        let (config, _) = collect(|| serde_yaml::from_value::<MockConfig>(value.clone()));
+
+        // The WinPTY backend is only part of the config on Windows
+        let mut remaining: Vec<_> = config.unwrap().ui_config.into_iter().map(|(k, _)| k).collect();
+        remaining.retain(|key| cfg!(windows) || key != "winpty_backend");
+        assert_eq!(remaining, vec!["unknown"]);
+    }
+
+    #[test]
+    fn probe_fields() {
+        assert!(struct_fields::<Scrolling>().contains(&"history"));
+        assert!(struct_fields::<u8>().is_empty());
+        assert_eq!(display_path(&["a".into(), "[0]".into(), "b".into()]), "a[0].b");
+    }
+}
@@ -1,13 +1,10 @@
 use std::fmt;
 
 use font::Size;
-use log::error;
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer};
 
-#[cfg(target_os = "macos")]
-use crate::config::DefaultTrueBool;
-use crate::config::{failure_default, Delta, LOG_TARGET_CONFIG};
+use crate::config::{diagnostics, failure_default, DefaultTrueBool, Delta};
 
 /// Font config
 ///
@@ -16,7 +13,7 @@ use crate::config::{failure_default, Delta, LOG_TARGET_CONFIG};
 /// each value independently. Alternatively, maybe erroring when the user
 /// doesn't provide complete config is Ok.
 #[serde(default)]
-#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
 pub struct Font {
     /// Normal font face
     #[serde(deserialize_with = "failure_default")]
@@ -46,6 +43,26 @@ pub struct Font {
     #[serde(deserialize_with = "failure_default")]
     pub glyph_offset: Delta<i8>,
 
+    /// Scale of the line height, applied before the offset
+    #[serde(deserialize_with = "failure_default")]
+    pub line_height: Multiplier,
+
+    /// Scale of the character width, applied before the offset
+    #[serde(deserialize_with = "failure_default")]
+    pub letter_spacing: Multiplier,
+
+    /// Synthesize bold and italic faces when the family lacks them
+    #[serde(deserialize_with = "failure_default")]
+    builtin_style_synthesis: DefaultTrueBool,
+
+    /// Draw box drawing, block and powerline characters instead of using the font
+    #[serde(deserialize_with = "failure_default")]
+    builtin_box_drawing: DefaultTrueBool,
+
+    /// Subpixel layout used for antialiasing
+    #[serde(deserialize_with = "failure_default")]
+    pub subpixel: Subpixel,
+
     #[cfg(target_os = "macos")]
     #[serde(deserialize_with = "failure_default")]
     use_thin_strokes: DefaultTrueBool,
@@ -61,6 +78,11 @@ impl Default for Font {
             bold_italic: Default::default(),
             glyph_offset: Default::default(),
             offset: Default::default(),
+            line_height: Default::default(),
+            letter_spacing: Default::default(),
+            builtin_style_synthesis: Default::default(),
+            builtin_box_drawing: Default::default(),
+            subpixel: Default::default(),
             #[cfg(target_os = "macos")]
             use_thin_strokes: Default::default(),
         }
@@ -93,6 +115,14 @@ impl Font {
         self.bold_italic.desc(&self.normal)
     }
 
+    pub fn builtin_style_synthesis(&self) -> bool {
+        self.builtin_style_synthesis.0
+    }
+
+    pub fn builtin_box_drawing(&self) -> bool {
+        self.builtin_box_drawing.0
+    }
+
     #[cfg(target_os = "macos")]
     pub fn use_thin_strokes(&self) -> bool {
         self.use_thin_strokes.0
@@ -104,6 +134,72 @@ impl Font {
     }
 }
 
+/// Positive scale factor of a font metric.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Multiplier(f32);
+
+impl Multiplier {
+    #[inline]
+    pub fn as_f64(self) -> f64 {
+        f64::from(self.0)
+    }
+}
+
+impl Default for Multiplier {
+    fn default() -> Self {
+        Multiplier(1.)
+    }
+}
+
+impl<'a> Deserialize<'a> for Multiplier {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'a>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        if value > 0. && value.is_finite() {
+            Ok(Multiplier(value))
+        } else {
+            Err(serde::de::Error::custom(format!(
+                "invalid multiplier {}, expected a positive number",
+                value
+            )))
+        }
+    }
+}
+
+/// Subpixel layout of the display
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Subpixel {
+    /// Use the system's layout
+    Auto,
+    /// Grayscale antialiasing
+    None,
+    Rgb,
+    Bgr,
+    Vrgb,
+    Vbgr,
+}
+
+impl Default for Subpixel {
+    fn default() -> Self {
+        Subpixel::Auto
+    }
+}
+
+impl From<Subpixel> for font::Subpixel {
+    fn from(subpixel: Subpixel) -> Self {
+        match subpixel {
+            Subpixel::Auto => font::Subpixel::Auto,
+            Subpixel::None => font::Subpixel::None,
+            Subpixel::Rgb => font::Subpixel::Rgb,
+            Subpixel::Bgr => font::Subpixel::Bgr,
+            Subpixel::Vrgb => font::Subpixel::Vrgb,
+            Subpixel::Vbgr => font::Subpixel::Vbgr,
+        }
+    }
+}
+
 fn default_font_size() -> Size {
     Size::new(11.)
 }
@@ -116,6 +212,10 @@ pub struct FontDescription {
     pub family: String,
     #[serde(deserialize_with = "failure_default")]
     pub style: Option<String>,
+    #[serde(deserialize_with = "failure_default")]
+    pub hinting: Option<Hinting>,
+    #[serde(deserialize_with = "failure_default")]
+    pub autohint: Option<bool>,
 }
 
 impl Default for FontDescription {
@@ -128,6 +228,8 @@ impl Default for FontDescription {
             #[cfg(windows)]
             family: "Consolas".into(),
             style: None,
+            hinting: None,
+            autohint: None,
         }
     }
 }
@@ -140,6 +242,10 @@ pub struct SecondaryFontDescription {
     family: Option<String>,
     #[serde(deserialize_with = "failure_default")]
     style: Option<String>,
+    #[serde(deserialize_with = "failure_default")]
+    hinting: Option<Hinting>,
+    #[serde(deserialize_with = "failure_default")]
+    autohint: Option<bool>,
 }
 
 impl SecondaryFontDescription {
@@ -147,6 +253,26 @@ impl SecondaryFontDescription {
         FontDescription {
             family: self.family.clone().unwrap_or_else(|| fallback.family.clone()),
             style: self.style.clone(),
+            hinting: self.hinting.or(fallback.hinting),
+            autohint: self.autohint.or(fallback.autohint),
+        }
+    }
+}
+
+/// Hinting strength for glyph outlines
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hinting {
+    None,
+    Slight,
+    Full,
+}
+
+impl From<Hinting> for font::Hinting {
+    fn from(hinting: Hinting) -> Self {
+        match hinting {
+            Hinting::None => font::Hinting::None,
+            Hinting::Slight => font::Hinting::Slight,
+            Hinting::Full => font::Hinting::Full,
         }
     }
 }
@@ -203,14 +329,32 @@ impl DeserializeSize for Size {
             Ok(size) => Ok(size),
             Err(err) => {
                 let size = default_font_size();
-                error!(
-                    target: LOG_TARGET_CONFIG,
-                    "Problem with config: {}; using size {}",
+                diagnostics::invalid_value(format_args!(
+                    "{}; using size {}",
                     err,
                     size.as_f32_pts()
-                );
+                ));
                 Ok(size)
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secondary_hinting_fallback() {
+        let yaml = "{normal: {hinting: Slight, autohint: false}, bold: {hinting: Full}, italic: \
+                    {autohint: true}}";
+        let font: Font = serde_yaml::from_str(yaml).unwrap();
+
+        assert_eq!(font.bold().hinting, Some(Hinting::Full));
+        assert_eq!(font.bold().autohint, Some(false));
+        assert_eq!(font.italic().hinting, Some(Hinting::Slight));
+        assert_eq!(font.italic().autohint, Some(true));
+        assert_eq!(font.bold_italic().hinting, Some(Hinting::Slight));
+        assert_eq!(font.bold_italic().autohint, Some(false));
+    }
+}
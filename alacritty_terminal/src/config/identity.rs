@@ -0,0 +1,63 @@
+use serde::Deserialize;
+
+use crate::config::failure_default;
+
+/// Version reported in place of `{version}`
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TerminalIdentity {
+    /// Response to ENQ
+    #[serde(deserialize_with = "failure_default")]
+    answerback: String,
+
+    /// Response to the primary device attributes request (DA)
+    #[serde(deserialize_with = "failure_default")]
+    primary_device_attributes: String,
+
+    /// Response to the secondary device attributes request (DA2)
+    #[serde(deserialize_with = "failure_default")]
+    secondary_device_attributes: String,
+}
+
+impl Default for TerminalIdentity {
+    fn default() -> Self {
+        TerminalIdentity {
+            answerback: String::new(),
+            primary_device_attributes: String::from("\x1b[?6c"),
+            secondary_device_attributes: String::from("\x1b[>0;{version_number};1c"),
+        }
+    }
+}
+
+impl TerminalIdentity {
+    /// Response to ENQ
+    pub fn answerback(&self) -> String {
+        expand_version(&self.answerback)
+    }
+
+    /// Response to the primary device attributes request (DA)
+    pub fn primary_device_attributes(&self) -> String {
+        expand_version(&self.primary_device_attributes)
+    }
+
+    /// Response to the secondary device attributes request (DA2)
+    pub fn secondary_device_attributes(&self) -> String {
+        expand_version(&self.secondary_device_attributes)
+    }
+}
+
+/// Replace `{version}` with the version string and `{version_number}` with its numeric form.
+///
+/// The numeric form encodes `major.minor.patch` as `major * 10000 + minor * 100 + patch`.
+fn expand_version(template: &str) -> String {
+    template
+        .replace("{version_number}", &version_number(VERSION).to_string())
+        .replace("{version}", VERSION)
+}
+
+fn version_number(version: &str) -> u32 {
+    let release = version.split('-').next().unwrap_or_default();
+    release.split('.').take(3).fold(0, |number, part| number * 100 + part.parse().unwrap_or(0))
+}
@@ -14,17 +14,19 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fmt::Display;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use log::error;
 use serde::{Deserialize, Deserializer};
 use serde_yaml::Value;
 
 mod colors;
 mod debug;
+pub mod diagnostics;
 mod font;
+mod identity;
 mod scrolling;
+mod text_blink;
 mod visual_bell;
 mod window;
 
@@ -32,8 +34,10 @@ use crate::ansi::{CursorStyle, NamedColor};
 
 pub use crate::config::colors::Colors;
 pub use crate::config::debug::Debug;
-pub use crate::config::font::{Font, FontDescription};
+pub use crate::config::font::{Font, FontDescription, Subpixel};
+pub use crate::config::identity::TerminalIdentity;
 pub use crate::config::scrolling::Scrolling;
+pub use crate::config::text_blink::{TextBlinkConfig, TextBlinkStyle};
 pub use crate::config::visual_bell::{VisualBellAnimation, VisualBellConfig};
 pub use crate::config::window::{Decorations, Dimensions, StartupMode, WindowConfig, DEFAULT_NAME};
 use crate::term::color::Rgb;
@@ -44,6 +48,35 @@ const DEFAULT_CURSOR_THICKNESS: f32 = 0.15;
 
 pub type MockConfig = Config<HashMap<String, serde_yaml::Value>>;
 
+/// Names of the top-level fields of the terminal config, without the flattened `ui_config`.
+pub const CONFIG_FIELDS: &[&str] = &[
+    "padding",
+    "env",
+    "font",
+    "draw_bold_text_with_bright_colors",
+    "colors",
+    "background_opacity",
+    "window",
+    "selection",
+    "shell",
+    "config_path",
+    "visual_bell",
+    "text_blink",
+    "dynamic_title",
+    "live_config_reload",
+    "scrolling",
+    "cursor",
+    "winpty_backend",
+    "alt_send_esc",
+    "identity",
+    "clipboard_read",
+    "working_directory",
+    "debug",
+    "tabspaces",
+    "render_timer",
+    "persistent_logging",
+];
+
 /// Top-level config type
 #[derive(Debug, PartialEq, Default, Deserialize)]
 pub struct Config<T> {
@@ -89,6 +122,10 @@ pub struct Config<T> {
     #[serde(default, deserialize_with = "failure_default")]
     pub visual_bell: VisualBellConfig,
 
+    /// Blinking text configuration
+    #[serde(default, deserialize_with = "failure_default")]
+    pub text_blink: TextBlinkConfig,
+
     /// Use dynamic title
     #[serde(default, deserialize_with = "failure_default")]
     dynamic_title: DefaultTrueBool,
@@ -114,6 +151,14 @@ pub struct Config<T> {
     #[serde(default, deserialize_with = "failure_default")]
     alt_send_esc: DefaultTrueBool,
 
+    /// Responses identifying the terminal
+    #[serde(default, deserialize_with = "failure_default")]
+    pub identity: TerminalIdentity,
+
+    /// Permission for applications to read the clipboard
+    #[serde(default, deserialize_with = "failure_default")]
+    pub clipboard_read: ClipboardReadPermission,
+
     /// Shell startup directory
     #[serde(default, deserialize_with = "option_explicit_none")]
     pub working_directory: Option<PathBuf>,
@@ -123,7 +168,11 @@ pub struct Config<T> {
     pub debug: Debug,
 
     /// Additional configuration options not directly required by the terminal
-    #[serde(flatten)]
+    #[serde(
+        flatten,
+        bound(deserialize = "T: Deserialize<'de>"),
+        deserialize_with = "diagnostics::deserialize_flattened"
+    )]
     pub ui_config: T,
 
     /// Remain open after child process exits
@@ -216,6 +265,17 @@ impl<T> Config<T> {
     pub fn background_opacity(&self) -> f32 {
         self.background_opacity.0 as f32
     }
+
+    /// Subpixel layout used for font rasterization on a background with the given opacity
+    ///
+    /// Subpixel antialiasing causes color fringes on translucent windows, so grayscale
+    /// antialiasing is used there unless a layout has been set explicitly.
+    pub fn subpixel(&self, background_opacity: f32) -> ::font::Subpixel {
+        match self.font.subpixel {
+            Subpixel::Auto if background_opacity < 1. => ::font::Subpixel::None,
+            subpixel => subpixel.into(),
+        }
+    }
 }
 
 #[serde(default)]
@@ -225,6 +285,8 @@ pub struct Selection {
     semantic_escape_chars: EscapeChars,
     #[serde(deserialize_with = "failure_default")]
     pub save_to_clipboard: bool,
+    #[serde(deserialize_with = "failure_default")]
+    pub lines: LineSelection,
 }
 
 impl Selection {
@@ -233,6 +295,36 @@ impl Selection {
     }
 }
 
+/// Extent of line selections
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LineSelection {
+    /// Entire lines, following line wraps
+    Logical,
+    /// Only the rows on the screen
+    Visual,
+}
+
+impl Default for LineSelection {
+    fn default() -> Self {
+        LineSelection::Logical
+    }
+}
+
+/// Permission for applications to read the clipboard through `OSC 52`
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClipboardReadPermission {
+    Allow,
+    Deny,
+    /// Ask for confirmation on every request
+    Ask,
+}
+
+impl Default for ClipboardReadPermission {
+    fn default() -> Self {
+        ClipboardReadPermission::Ask
+    }
+}
+
 #[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
 struct EscapeChars(String);
 
@@ -253,6 +345,12 @@ pub struct Cursor {
     thickness: Percentage,
     #[serde(deserialize_with = "failure_default")]
     unfocused_hollow: DefaultTrueBool,
+    #[serde(deserialize_with = "failure_default")]
+    pub blinking: CursorBlinking,
+    #[serde(deserialize_with = "failure_default")]
+    blink_interval: u16,
+    #[serde(deserialize_with = "failure_default")]
+    blink_timeout: u8,
 }
 
 impl Cursor {
@@ -265,6 +363,21 @@ impl Cursor {
     pub fn thickness(self) -> f64 {
         self.thickness.0 as f64
     }
+
+    /// Time the blinking cursor stays visible or hidden
+    #[inline]
+    pub fn blink_interval(self) -> Duration {
+        Duration::from_millis(u64::from(self.blink_interval))
+    }
+
+    /// Time without input after which the cursor stops blinking
+    #[inline]
+    pub fn blink_timeout(self) -> Option<Duration> {
+        match self.blink_timeout {
+            0 => None,
+            timeout => Some(Duration::from_secs(u64::from(timeout))),
+        }
+    }
 }
 
 impl Default for Cursor {
@@ -274,10 +387,32 @@ impl Default for Cursor {
             vi_mode_style: Default::default(),
             thickness: Percentage::new(DEFAULT_CURSOR_THICKNESS),
             unfocused_hollow: Default::default(),
+            blinking: Default::default(),
+            blink_interval: 750,
+            blink_timeout: 5,
         }
     }
 }
 
+/// Blinking of the terminal cursor.
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CursorBlinking {
+    /// Never blink, even when requested by applications
+    Never,
+    /// Steady unless applications request blinking
+    Off,
+    /// Blink unless applications request a steady cursor
+    On,
+    /// Always blink, even when applications request a steady cursor
+    Always,
+}
+
+impl Default for CursorBlinking {
+    fn default() -> Self {
+        CursorBlinking::Off
+    }
+}
+
 pub fn deserialize_cursor_thickness<'a, D>(deserializer: D) -> Result<Percentage, D::Error>
 where
     D: Deserializer<'a>,
@@ -368,21 +503,12 @@ impl Default for DefaultTrueBool {
     }
 }
 
-fn fallback_default<T, E>(err: E) -> T
-where
-    T: Default,
-    E: Display,
-{
-    error!(target: LOG_TARGET_CONFIG, "Problem with config: {}; using default value", err);
-    T::default()
-}
-
 pub fn failure_default<'a, D, T>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'a>,
     T: Deserialize<'a> + Default,
 {
-    Ok(T::deserialize(Value::deserialize(deserializer)?).unwrap_or_else(fallback_default))
+    Ok(diagnostics::deserialize_or_default(Value::deserialize(deserializer)?))
 }
 
 pub fn option_explicit_none<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
@@ -392,7 +518,7 @@ where
 {
     Ok(match Value::deserialize(deserializer)? {
         Value::String(ref value) if value.to_lowercase() == "none" => None,
-        value => Some(T::deserialize(value).unwrap_or_else(fallback_default)),
+        value => Some(diagnostics::deserialize_or_default(value)),
     })
 }
 
@@ -403,7 +529,7 @@ where
 {
     Ok(match Value::deserialize(deserializer)? {
         Value::String(value) => T::from(value),
-        value => T::deserialize(value).unwrap_or_else(fallback_default),
+        value => diagnostics::deserialize_or_default(value),
     })
 }
 
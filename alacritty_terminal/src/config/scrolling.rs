@@ -1,17 +1,32 @@
-use log::error;
 use serde::{Deserialize, Deserializer};
 
-use crate::config::{failure_default, LOG_TARGET_CONFIG, MAX_SCROLLBACK_LINES};
+use crate::config::{diagnostics, failure_default, MAX_SCROLLBACK_LINES};
 
 /// Struct for scrolling related settings
 #[serde(default)]
-#[derive(Deserialize, Copy, Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Deserialize, Copy, Clone, Default, Debug, PartialEq)]
 pub struct Scrolling {
     #[serde(deserialize_with = "failure_default")]
     history: ScrollingHistory,
     #[serde(deserialize_with = "failure_default")]
     multiplier: ScrollingMultiplier,
 
+    /// Arrow keys sent for every scrolled line in alternate scroll mode.
+    #[serde(deserialize_with = "failure_default")]
+    alternate_multiplier: Option<ScrollingMultiplier>,
+
+    /// Scale of the pixel deltas reported by touchpads.
+    #[serde(deserialize_with = "failure_default")]
+    touchpad_multiplier: Option<TouchpadMultiplier>,
+
+    /// Record output of the alternate screen in the scrollback history.
+    #[serde(deserialize_with = "failure_default")]
+    pub alt_screen_history: bool,
+
+    /// Ignore requests from applications to clear the scrollback history.
+    #[serde(deserialize_with = "failure_default")]
+    pub preserve_history: bool,
+
     // TODO: REMOVED
     #[serde(deserialize_with = "failure_default")]
     pub auto_scroll: Option<bool>,
@@ -22,21 +37,44 @@ pub struct Scrolling {
 }
 
 impl Scrolling {
+    /// Number of lines kept in memory.
     pub fn history(self) -> u32 {
-        self.history.0
+        match self.history {
+            ScrollingHistory::Lines(lines) => lines,
+            ScrollingHistory::Unlimited => MAX_SCROLLBACK_LINES,
+        }
+    }
+
+    /// Whether lines exceeding the in-memory history are archived on disk.
+    pub fn unlimited_history(self) -> bool {
+        self.history == ScrollingHistory::Unlimited
     }
 
+    /// Lines scrolled in the history for every line of a mouse wheel tick.
     pub fn multiplier(self) -> u8 {
         self.multiplier.0
     }
 
+    /// Arrow keys sent for every line of a mouse wheel tick on the alternate screen.
+    ///
+    /// Falls back to the deprecated `faux_multiplier` and then to the primary `multiplier`. A
+    /// multiplier of `0` disables alternate scroll mode.
+    pub fn alternate_multiplier(self) -> u8 {
+        self.alternate_multiplier.or(self.faux_multiplier).unwrap_or(self.multiplier).0
+    }
+
+    /// Scale of touchpad scrolling, which defaults to the multiplier of the scrolled screen.
+    pub fn touchpad_multiplier(self) -> Option<f64> {
+        self.touchpad_multiplier.map(|tm| f64::from(tm.0))
+    }
+
     pub fn faux_multiplier(self) -> Option<u8> {
         self.faux_multiplier.map(|sm| sm.0)
     }
 
     // Update the history size, used in ref tests
     pub fn set_history(&mut self, history: u32) {
-        self.history = ScrollingHistory(history);
+        self.history = ScrollingHistory::Lines(history);
     }
 }
 
@@ -49,12 +87,36 @@ impl Default for ScrollingMultiplier {
     }
 }
 
+/// Positive scale of touchpad scrolling.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct TouchpadMultiplier(f32);
+
+impl<'de> Deserialize<'de> for TouchpadMultiplier {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = f32::deserialize(deserializer)?;
+        if value > 0. && value.is_finite() {
+            Ok(TouchpadMultiplier(value))
+        } else {
+            Err(serde::de::Error::custom(format!(
+                "invalid touchpad multiplier {}, expected a positive number",
+                value
+            )))
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
-struct ScrollingHistory(u32);
+enum ScrollingHistory {
+    Lines(u32),
+    Unlimited,
+}
 
 impl Default for ScrollingHistory {
     fn default() -> Self {
-        Self(10_000)
+        ScrollingHistory::Lines(10_000)
     }
 }
 
@@ -64,26 +126,24 @@ impl<'de> Deserialize<'de> for ScrollingHistory {
         D: Deserializer<'de>,
     {
         let value = serde_yaml::Value::deserialize(deserializer)?;
+        if value.as_str().map(|s| s.eq_ignore_ascii_case("unlimited")).unwrap_or(false) {
+            return Ok(ScrollingHistory::Unlimited);
+        }
+
         match u32::deserialize(value) {
             Ok(lines) => {
                 if lines > MAX_SCROLLBACK_LINES {
-                    error!(
-                        target: LOG_TARGET_CONFIG,
-                        "Problem with config: scrollback size is {}, but expected a maximum of \
-                         {}; using {1} instead",
-                        lines,
-                        MAX_SCROLLBACK_LINES,
-                    );
-                    Ok(ScrollingHistory(MAX_SCROLLBACK_LINES))
+                    diagnostics::invalid_value(format_args!(
+                        "scrollback size is {}, but expected a maximum of {}; using {1} instead",
+                        lines, MAX_SCROLLBACK_LINES
+                    ));
+                    Ok(ScrollingHistory::Lines(MAX_SCROLLBACK_LINES))
                 } else {
-                    Ok(ScrollingHistory(lines))
+                    Ok(ScrollingHistory::Lines(lines))
                 }
             },
             Err(err) => {
-                error!(
-                    target: LOG_TARGET_CONFIG,
-                    "Problem with config: {}; using default value", err
-                );
+                diagnostics::invalid_value(format_args!("{}; using default value", err));
                 Ok(Default::default())
             },
         }
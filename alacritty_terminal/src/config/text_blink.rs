@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::failure_default;
+
+#[serde(default)]
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TextBlinkConfig {
+    /// How blinking text is rendered
+    #[serde(deserialize_with = "failure_default")]
+    pub style: TextBlinkStyle,
+
+    /// Slow blink interval in milliseconds
+    #[serde(deserialize_with = "failure_default")]
+    interval: u16,
+
+    /// Rapid blink interval in milliseconds
+    #[serde(deserialize_with = "failure_default")]
+    rapid_interval: u16,
+}
+
+impl Default for TextBlinkConfig {
+    fn default() -> TextBlinkConfig {
+        TextBlinkConfig { style: Default::default(), interval: 500, rapid_interval: 200 }
+    }
+}
+
+impl TextBlinkConfig {
+    /// Time slowly blinking text stays visible or hidden
+    #[inline]
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(u64::from(self.interval))
+    }
+
+    /// Time rapidly blinking text stays visible or hidden
+    #[inline]
+    pub fn rapid_interval(&self) -> Duration {
+        Duration::from_millis(u64::from(self.rapid_interval))
+    }
+}
+
+/// Presentation of text with the slow or rapid blink attribute.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub enum TextBlinkStyle {
+    /// Toggle the text's visibility
+    Blink,
+    /// Draw the text with the bold font instead
+    Bold,
+    /// Draw the text with the italic font instead
+    Italic,
+}
+
+impl Default for TextBlinkStyle {
+    fn default() -> Self {
+        TextBlinkStyle::Blink
+    }
+}
@@ -53,6 +53,22 @@ pub struct WindowConfig {
     #[serde(deserialize_with = "option_explicit_none")]
     pub gtk_theme_variant: Option<String>,
 
+    /// Allow applications to resize the window
+    #[serde(deserialize_with = "failure_default")]
+    pub allow_resize: bool,
+
+    /// Name of the monitor the window is opened on
+    #[serde(deserialize_with = "option_explicit_none")]
+    pub monitor: Option<String>,
+
+    /// Restore the size and position of the last closed window
+    #[serde(deserialize_with = "failure_default")]
+    pub remember_geometry: bool,
+
+    /// Request compositor blur behind transparent windows
+    #[serde(deserialize_with = "failure_default")]
+    pub blur: bool,
+
     /// TODO: DEPRECATED
     #[serde(deserialize_with = "failure_default")]
     pub start_maximized: Option<bool>,
@@ -69,6 +85,11 @@ impl WindowConfig {
             _ => self.startup_mode,
         }
     }
+
+    pub fn set_startup_mode(&mut self, startup_mode: StartupMode) {
+        self.startup_mode = startup_mode;
+        self.start_maximized = None;
+    }
 }
 
 impl Default for WindowConfig {
@@ -83,6 +104,10 @@ impl Default for WindowConfig {
             class: Default::default(),
             embed: Default::default(),
             gtk_theme_variant: Default::default(),
+            allow_resize: Default::default(),
+            monitor: Default::default(),
+            remember_geometry: Default::default(),
+            blur: Default::default(),
             start_maximized: Default::default(),
             title: default_title(),
         }
@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 use std::path::PathBuf;
 
+use crate::ansi::{ControlStringKind, Notification, Progress};
 use crate::message_bar::Message;
 use crate::term::SizeInfo;
 
@@ -11,8 +12,11 @@ pub enum Event {
     MouseCursorDirty,
     Message(Message),
     Title(String),
+    Progress(Progress),
+    Notification(Notification),
+    TextAreaResize(u32, u32),
     Wakeup,
-    Urgent,
+    Bell,
     Exit,
 }
 
@@ -32,4 +36,9 @@ pub trait OnResize {
 /// Event Loop for notifying the renderer about terminal events
 pub trait EventListener {
     fn send_event(&self, event: Event);
+
+    /// Receive SOS, PM and APC strings which are not handled by the terminal
+    ///
+    /// This allows embedders to implement custom application commands.
+    fn control_string(&self, _kind: ControlStringKind, _data: &[u8]) {}
 }
@@ -5,6 +5,7 @@ use std::fs::File;
 use std::io::{self, ErrorKind, Read, Write};
 use std::marker::Send;
 use std::sync::Arc;
+use std::time::Instant;
 
 use log::error;
 #[cfg(not(windows))]
@@ -323,13 +324,25 @@ where
             };
 
             'event_loop: loop {
-                if let Err(err) = self.poll.poll(&mut events, None) {
+                // Wake up once the synchronized update times out
+                let sync_timeout = state.parser.sync_timeout();
+                let timeout =
+                    sync_timeout.map(|timeout| timeout.saturating_duration_since(Instant::now()));
+
+                if let Err(err) = self.poll.poll(&mut events, timeout) {
                     match err.kind() {
                         ErrorKind::Interrupted => continue,
                         _ => panic!("EventLoop polling error: {:?}", err),
                     }
                 }
 
+                // Draw the buffered updates when the application didn't end them in time
+                if sync_timeout.map_or(false, |timeout| timeout <= Instant::now()) {
+                    let mut terminal = self.terminal.lock();
+                    state.parser.stop_sync(&mut *terminal, &mut self.pty.writer());
+                    self.event_proxy.send_event(Event::Wakeup);
+                }
+
                 for event in events.iter() {
                     match event.token() {
                         token if token == channel_token => {
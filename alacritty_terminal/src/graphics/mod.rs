@@ -0,0 +1,169 @@
+//! Images embedded in the terminal grid
+//!
+//! Decoded images are stored in the cells they cover. Every cell holds a reference to the
+//! texture of its image, so the texture can be released by the renderer once the last cell
+//! showing it has been overwritten or dropped from the scrollback history.
+use std::mem;
+use std::sync::{Arc, Weak};
+
+use parking_lot::Mutex;
+
+pub mod sixel;
+
+/// Largest width or height of a graphic, in pixels.
+pub const MAX_GRAPHIC_DIMENSIONS: usize = 4096;
+
+/// Unique identifier of a graphic added to the terminal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct GraphicId(pub u64);
+
+/// Decoded image.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Graphic {
+    pub width: usize,
+    pub height: usize,
+
+    /// RGBA pixels, row by row.
+    pub pixels: Vec<u8>,
+}
+
+/// Graphic which has to be uploaded to the GPU.
+#[derive(Debug)]
+pub struct GraphicData {
+    pub id: GraphicId,
+
+    /// Cell dimensions when the graphic was inserted.
+    ///
+    /// The graphic keeps covering the same cells when the font size changes, so this is
+    /// required to map cells back to the graphic's pixels.
+    pub cell_width: usize,
+    pub cell_height: usize,
+
+    pub graphic: Graphic,
+}
+
+/// Reference to a texture shared by all cells of a graphic.
+///
+/// Dropping the last reference queues the texture for removal.
+#[derive(Debug)]
+pub struct TextureRef {
+    pub id: GraphicId,
+    remove_queue: Weak<Mutex<Vec<GraphicId>>>,
+}
+
+impl Drop for TextureRef {
+    fn drop(&mut self) {
+        if let Some(remove_queue) = self.remove_queue.upgrade() {
+            remove_queue.lock().push(self.id);
+        }
+    }
+}
+
+/// Part of a graphic shown in a single cell.
+#[derive(Clone, Debug)]
+pub struct GraphicCell {
+    pub texture: Arc<TextureRef>,
+
+    /// Position of the cell's top left corner inside the graphic, in pixels.
+    pub offset_x: u16,
+    pub offset_y: u16,
+}
+
+impl PartialEq for GraphicCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.texture.id == other.texture.id
+            && self.offset_x == other.offset_x
+            && self.offset_y == other.offset_y
+    }
+}
+
+impl Eq for GraphicCell {}
+
+/// Texture changes which have to be applied by the renderer.
+#[derive(Debug, Default)]
+pub struct UpdateQueues {
+    /// Graphics which have not been uploaded yet.
+    pub pending: Vec<GraphicData>,
+
+    /// Textures which are no longer referenced by any cell.
+    pub remove_queue: Vec<GraphicId>,
+}
+
+/// Graphics state of a terminal.
+#[derive(Debug, Default)]
+pub struct Graphics {
+    /// Last identifier handed out to a graphic.
+    last_id: u64,
+
+    /// Graphics waiting to be uploaded.
+    pending: Vec<GraphicData>,
+
+    /// Textures released by the grid.
+    remove_queue: Arc<Mutex<Vec<GraphicId>>>,
+
+    /// Cell dimensions in pixels.
+    pub cell_width: usize,
+    pub cell_height: usize,
+}
+
+impl Graphics {
+    pub fn new(cell_width: f32, cell_height: f32) -> Self {
+        Graphics {
+            cell_width: cell_width as usize,
+            cell_height: cell_height as usize,
+            ..Default::default()
+        }
+    }
+
+    /// Queue a graphic for upload and create the texture reference used by its cells.
+    pub fn insert(&mut self, graphic: Graphic) -> Arc<TextureRef> {
+        self.last_id += 1;
+        let id = GraphicId(self.last_id);
+
+        self.pending.push(GraphicData {
+            id,
+            cell_width: self.cell_width,
+            cell_height: self.cell_height,
+            graphic,
+        });
+
+        Arc::new(TextureRef { id, remove_queue: Arc::downgrade(&self.remove_queue) })
+    }
+
+    /// Take all texture changes accumulated since the last call.
+    pub fn take_queues(&mut self) -> Option<UpdateQueues> {
+        let remove_queue = mem::replace(&mut *self.remove_queue.lock(), Vec::new());
+
+        if self.pending.is_empty() && remove_queue.is_empty() {
+            return None;
+        }
+
+        Some(UpdateQueues { pending: mem::replace(&mut self.pending, Vec::new()), remove_queue })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Graphic, Graphics};
+
+    #[test]
+    fn dropped_texture_is_queued_for_removal() {
+        let mut graphics = Graphics::new(10., 20.);
+        let texture = graphics.insert(Graphic { width: 1, height: 1, pixels: vec![0, 0, 0, 255] });
+        let id = texture.id;
+
+        let queues = graphics.take_queues().unwrap();
+        assert_eq!(queues.pending.len(), 1);
+        assert_eq!(queues.pending[0].cell_height, 20);
+        assert!(queues.remove_queue.is_empty());
+        assert!(graphics.take_queues().is_none());
+
+        let clone = texture.clone();
+        drop(texture);
+        assert!(graphics.take_queues().is_none());
+
+        drop(clone);
+        let queues = graphics.take_queues().unwrap();
+        assert_eq!(queues.remove_queue, vec![id]);
+    }
+}
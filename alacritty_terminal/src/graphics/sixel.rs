@@ -0,0 +1,406 @@
+//! Decoder for sixel images
+//!
+//! Sixel images are sent in a DCS sequence (`ESC P P1 ; P2 ; P3 q <data> ESC \`). Every data
+//! byte describes a column of six vertical pixels, which are painted with the color register
+//! selected last.
+use std::cmp;
+
+use crate::graphics::{Graphic, MAX_GRAPHIC_DIMENSIONS};
+use crate::term::color::Rgb;
+
+/// Number of available color registers.
+const MAX_COLOR_REGISTERS: usize = 256;
+
+/// Number of parameters kept for a single command.
+const MAX_COMMAND_PARAMS: usize = 5;
+
+/// Number of pixel rows described by a single sixel.
+const SIXEL_HEIGHT: usize = 6;
+
+/// Default color registers of the VT340, as RGB percentages.
+const VT340_PALETTE: [(u16, u16, u16); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Commands which take numeric parameters.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Command {
+    /// `" Pan ; Pad ; Ph ; Pv`
+    RasterAttributes,
+    /// `# Pc` to select a register, `# Pc ; Pu ; Px ; Py ; Pz` to define it
+    ColorIntroducer,
+    /// `! Pn`
+    Repeat,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum State {
+    Ground,
+    Params(Command),
+}
+
+/// Incremental sixel decoder.
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+
+    /// Parameters of the command being parsed.
+    params: Vec<u16>,
+
+    /// Color registers.
+    palette: Vec<Rgb>,
+
+    /// Color register used for new pixels.
+    register: usize,
+
+    /// Number of times the next sixel is repeated.
+    repeat: usize,
+
+    /// Position of the next sixel.
+    x: usize,
+    y: usize,
+
+    /// Dimensions of the image decoded so far.
+    width: usize,
+    height: usize,
+
+    /// RGBA pixels; fully transparent pixels have not been painted.
+    pixels: Vec<u8>,
+    buffer_width: usize,
+    buffer_height: usize,
+
+    /// Leave pixels which are not painted transparent.
+    transparent_background: bool,
+}
+
+impl Parser {
+    /// Create a decoder from the parameters of the DCS sequence.
+    pub fn new(params: &[i64]) -> Parser {
+        let palette = (0..MAX_COLOR_REGISTERS)
+            .map(|i| match VT340_PALETTE.get(i) {
+                Some(&(r, g, b)) => rgb_from_percent(r, g, b),
+                None => Rgb::default(),
+            })
+            .collect();
+
+        Parser {
+            state: State::Ground,
+            params: Vec::new(),
+            palette,
+            register: 0,
+            repeat: 1,
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            buffer_width: 0,
+            buffer_height: 0,
+            transparent_background: params.get(1) == Some(&1),
+        }
+    }
+
+    /// Process a single byte of sixel data.
+    pub fn put(&mut self, byte: u8) {
+        if let State::Params(command) = self.state {
+            match byte {
+                b'0'..=b'9' => {
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    let param = self.params.last_mut().unwrap();
+                    *param = param.saturating_mul(10).saturating_add(u16::from(byte - b'0'));
+                    return;
+                },
+                b';' => {
+                    if self.params.is_empty() {
+                        self.params.push(0);
+                    }
+                    if self.params.len() < MAX_COMMAND_PARAMS {
+                        self.params.push(0);
+                    }
+                    return;
+                },
+                _ => {
+                    self.dispatch(command);
+                    self.params.clear();
+                    self.state = State::Ground;
+                },
+            }
+        }
+
+        match byte {
+            b'"' => self.state = State::Params(Command::RasterAttributes),
+            b'#' => self.state = State::Params(Command::ColorIntroducer),
+            b'!' => self.state = State::Params(Command::Repeat),
+            b'$' => self.x = 0,
+            b'-' => {
+                self.x = 0;
+                self.y += SIXEL_HEIGHT;
+            },
+            b'?'..=b'~' => self.draw_sixel(byte - b'?'),
+            _ => (),
+        }
+    }
+
+    /// Finish decoding and return the image, if any pixels were described.
+    pub fn finish(mut self) -> Option<Graphic> {
+        if let State::Params(command) = self.state {
+            self.dispatch(command);
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+
+        let background = if self.transparent_background {
+            [0, 0, 0, 0]
+        } else {
+            let color = self.palette[0];
+            [color.r, color.g, color.b, 255]
+        };
+
+        let mut pixels = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks(self.buffer_width * 4).take(self.height) {
+            for pixel in row[..self.width * 4].chunks(4) {
+                if pixel[3] == 0 {
+                    pixels.extend_from_slice(&background);
+                } else {
+                    pixels.extend_from_slice(pixel);
+                }
+            }
+        }
+
+        Some(Graphic { width: self.width, height: self.height, pixels })
+    }
+
+    fn dispatch(&mut self, command: Command) {
+        let mut params = [0; MAX_COMMAND_PARAMS];
+        params[..self.params.len()].copy_from_slice(&self.params);
+        let param = |index: usize| params[index];
+
+        match command {
+            Command::RasterAttributes => {
+                let width = cmp::min(usize::from(param(2)), MAX_GRAPHIC_DIMENSIONS);
+                let height = cmp::min(usize::from(param(3)), MAX_GRAPHIC_DIMENSIONS);
+                self.resize(width, height);
+            },
+            Command::ColorIntroducer => {
+                let register = usize::from(param(0)) % MAX_COLOR_REGISTERS;
+
+                if self.params.len() >= MAX_COMMAND_PARAMS {
+                    let (x, y, z) = (param(2), param(3), param(4));
+                    match param(1) {
+                        1 => self.palette[register] = rgb_from_hls(x, y, z),
+                        2 => self.palette[register] = rgb_from_percent(x, y, z),
+                        _ => (),
+                    }
+                }
+
+                self.register = register;
+            },
+            Command::Repeat => self.repeat = cmp::max(usize::from(param(0)), 1),
+        }
+    }
+
+    /// Paint the pixels of a sixel at the current position.
+    fn draw_sixel(&mut self, bits: u8) {
+        let repeat = self.repeat;
+        self.repeat = 1;
+
+        let start = self.x;
+        self.x += repeat;
+
+        let end = cmp::min(self.x, MAX_GRAPHIC_DIMENSIONS);
+        let bottom = cmp::min(self.y + SIXEL_HEIGHT, MAX_GRAPHIC_DIMENSIONS);
+        if start >= end || self.y >= bottom || bits == 0 {
+            return;
+        }
+
+        let last_row = (0..SIXEL_HEIGHT).rev().find(|row| (bits >> row) & 1 != 0).unwrap_or(0);
+        self.resize(end, cmp::min(self.y + last_row + 1, bottom));
+
+        let color = self.palette[self.register];
+        let pixel = [color.r, color.g, color.b, 255];
+        for row in (0..SIXEL_HEIGHT).filter(|row| (bits >> row) & 1 != 0) {
+            let y = self.y + row;
+            if y >= bottom {
+                break;
+            }
+
+            let offset = (y * self.buffer_width + start) * 4;
+            for chunk in self.pixels[offset..offset + (end - start) * 4].chunks_mut(4) {
+                chunk.copy_from_slice(&pixel);
+            }
+        }
+    }
+
+    /// Grow the image to at least the specified dimensions.
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = cmp::max(self.width, width);
+        self.height = cmp::max(self.height, height);
+
+        if self.width <= self.buffer_width && self.height <= self.buffer_height {
+            return;
+        }
+
+        // Grow the buffer geometrically, since images without raster attributes grow one column
+        // at a time.
+        let grow = |current: usize, required: usize| {
+            if required <= current {
+                current
+            } else {
+                cmp::min(cmp::max(required, current * 2), MAX_GRAPHIC_DIMENSIONS)
+            }
+        };
+        let buffer_width = grow(self.buffer_width, self.width);
+        let buffer_height = grow(self.buffer_height, self.height);
+
+        let mut pixels = vec![0; buffer_width * buffer_height * 4];
+        if self.buffer_width > 0 {
+            for (old, new) in
+                self.pixels.chunks(self.buffer_width * 4).zip(pixels.chunks_mut(buffer_width * 4))
+            {
+                new[..old.len()].copy_from_slice(old);
+            }
+        }
+
+        self.pixels = pixels;
+        self.buffer_width = buffer_width;
+        self.buffer_height = buffer_height;
+    }
+}
+
+/// Convert RGB percentages to a color.
+fn rgb_from_percent(r: u16, g: u16, b: u16) -> Rgb {
+    let scale = |value: u16| (u32::from(cmp::min(value, 100)) * 255 / 100) as u8;
+    Rgb { r: scale(r), g: scale(g), b: scale(b) }
+}
+
+/// Convert sixel HLS values to a color.
+///
+/// Unlike the usual HLS model, sixel hues start with blue at 0 degrees, followed by red at 120
+/// and green at 240 degrees.
+fn rgb_from_hls(hue: u16, lightness: u16, saturation: u16) -> Rgb {
+    let lightness = f32::from(cmp::min(lightness, 100)) / 100.;
+    if saturation == 0 {
+        let value = (lightness * 255.).round() as u8;
+        return Rgb { r: value, g: value, b: value };
+    }
+
+    let hue = f32::from((hue % 360 + 240) % 360) / 360.;
+    let saturation = f32::from(cmp::min(saturation, 100)) / 100.;
+
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+
+    let channel = |t: f32| {
+        let t = if t < 0. {
+            t + 1.
+        } else if t > 1. {
+            t - 1.
+        } else {
+            t
+        };
+
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+
+        (value * 255.).round() as u8
+    };
+
+    Rgb { r: channel(hue + 1. / 3.), g: channel(hue), b: channel(hue - 1. / 3.) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rgb_from_hls, Parser};
+    use crate::term::color::Rgb;
+
+    fn decode(params: &[i64], data: &[u8]) -> Option<crate::graphics::Graphic> {
+        let mut parser = Parser::new(params);
+        for &byte in data {
+            parser.put(byte);
+        }
+        parser.finish()
+    }
+
+    fn pixel(graphic: &crate::graphics::Graphic, x: usize, y: usize) -> &[u8] {
+        let offset = (y * graphic.width + x) * 4;
+        &graphic.pixels[offset..offset + 4]
+    }
+
+    #[test]
+    fn empty_image() {
+        assert_eq!(decode(&[], b""), None);
+        assert_eq!(decode(&[], b"#1"), None);
+    }
+
+    #[test]
+    fn draw_sixels() {
+        // One column with the top and bottom pixel set, followed by a full column.
+        let graphic = decode(&[0, 1], b"#1;2;100;0;0#1`~").unwrap();
+
+        assert_eq!(graphic.width, 2);
+        assert_eq!(graphic.height, 6);
+        assert_eq!(pixel(&graphic, 0, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 0, 1), [0, 0, 0, 0]);
+        assert_eq!(pixel(&graphic, 0, 5), [255, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 1, 0), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn repeat_and_new_line() {
+        let graphic = decode(&[0, 1], b"!3~-@").unwrap();
+
+        assert_eq!(graphic.width, 3);
+        assert_eq!(graphic.height, 7);
+        assert_eq!(pixel(&graphic, 2, 5), [0, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 0, 6), [0, 0, 0, 255]);
+        assert_eq!(pixel(&graphic, 1, 6), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn raster_attributes_and_background() {
+        let graphic = decode(&[0, 0], b"#0;2;0;0;100\"1;1;4;8#1~").unwrap();
+
+        assert_eq!(graphic.width, 4);
+        assert_eq!(graphic.height, 8);
+        assert_eq!(pixel(&graphic, 0, 0), [51, 51, 204, 255]);
+        assert_eq!(pixel(&graphic, 3, 7), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn hls_colors() {
+        assert_eq!(rgb_from_hls(0, 50, 100), Rgb { r: 0, g: 0, b: 255 });
+        assert_eq!(rgb_from_hls(120, 50, 100), Rgb { r: 255, g: 0, b: 0 });
+        assert_eq!(rgb_from_hls(240, 50, 100), Rgb { r: 0, g: 255, b: 0 });
+        assert_eq!(rgb_from_hls(0, 100, 0), Rgb { r: 255, g: 255, b: 255 });
+    }
+}
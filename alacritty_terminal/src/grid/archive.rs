@@ -0,0 +1,274 @@
+//! Disk-backed storage for scrollback which no longer fits into memory.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+
+use super::{GridCell, Row};
+use crate::term::cell::Flags;
+
+/// Number of rows encoded together into a single segment.
+const SEGMENT_LINES: usize = 1_000;
+
+/// Cell which can be stored in the scrollback archive.
+pub trait ArchiveCell: Sized {
+    /// Append the binary representation of the cell to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Read a cell from the start of `buf`, advancing it past the cell.
+    fn decode(buf: &mut &[u8]) -> io::Result<Self>;
+}
+
+/// List of rows evicted from the top of the scrollback buffer.
+///
+/// Rows are ordered from oldest to newest and never change once they have been archived. The
+/// newest rows are kept in memory until a full segment has accumulated, which is then encoded,
+/// compressed and appended to an anonymous temporary file.
+pub struct Archive<T> {
+    /// Backing file for all encoded segments.
+    file: File,
+
+    /// Offset of every compressed segment in the file, followed by the end of the last segment.
+    offsets: Vec<u64>,
+
+    /// Rows which have not been written to the file yet.
+    pending: Vec<Row<T>>,
+
+    /// Most recently decoded segment.
+    cache: Option<(usize, Vec<Row<T>>)>,
+}
+
+impl<T> std::fmt::Debug for Archive<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Archive")
+            .field("segments", &(self.offsets.len() - 1))
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl<T> Archive<T> {
+    pub fn new() -> io::Result<Self> {
+        let file = tempfile::tempfile()?;
+        Ok(Self { file, offsets: vec![0], pending: Vec::new(), cache: None })
+    }
+
+    /// Number of rows stored in the archive.
+    #[inline]
+    pub fn len(&self) -> usize {
+        (self.offsets.len() - 1) * SEGMENT_LINES + self.pending.len()
+    }
+
+    /// Remove all rows from the archive.
+    pub fn clear(&mut self) {
+        self.offsets.truncate(1);
+        self.pending.clear();
+        self.cache = None;
+
+        // Releasing disk space is not required for correctness, since segments are always
+        // written at an explicit offset
+        let _ = self.file.set_len(0);
+    }
+}
+
+impl<T: ArchiveCell + Clone> Archive<T> {
+    /// Add a row which is newer than all rows currently in the archive.
+    pub fn push(&mut self, row: Row<T>) -> io::Result<()> {
+        self.pending.push(row);
+
+        if self.pending.len() >= SEGMENT_LINES {
+            let mut encoded = Vec::new();
+            for row in &self.pending {
+                row.encode(&mut encoded);
+            }
+
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::fast());
+            encoder.write_all(&encoded)?;
+            let data = encoder.finish()?;
+
+            let offset = self.offsets[self.offsets.len() - 1];
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.file.write_all(&data)?;
+
+            self.offsets.push(offset + data.len() as u64);
+            self.pending.clear();
+        }
+
+        Ok(())
+    }
+
+    /// Copy of the row at `index`, counting from the oldest row.
+    pub fn get(&mut self, index: usize) -> io::Result<Row<T>> {
+        let segment = index / SEGMENT_LINES;
+        if segment == self.offsets.len() - 1 {
+            return Ok(self.pending[index % SEGMENT_LINES].clone());
+        }
+
+        match &self.cache {
+            Some((cached, rows)) if *cached == segment => Ok(rows[index % SEGMENT_LINES].clone()),
+            _ => {
+                let rows = self.read_segment(segment)?;
+                let row = rows[index % SEGMENT_LINES].clone();
+                self.cache = Some((segment, rows));
+                Ok(row)
+            },
+        }
+    }
+
+    /// Copy the line ending right before the row at `end`.
+    ///
+    /// Lines wrapped across multiple rows are returned from their first to their last row.
+    pub fn line_before(&mut self, end: usize) -> io::Result<Vec<Row<T>>>
+    where
+        T: GridCell,
+    {
+        let mut rows = vec![self.get(end - 1)?];
+        for index in (0..end - 1).rev() {
+            let row = self.get(index)?;
+            if !is_wrapped(&row) {
+                break;
+            }
+
+            rows.push(row);
+        }
+
+        rows.reverse();
+
+        Ok(rows)
+    }
+
+    /// Copy the line starting at the row at `start`.
+    ///
+    /// Lines wrapped across multiple rows are returned from their first to their last row.
+    pub fn line_at(&mut self, start: usize) -> io::Result<Vec<Row<T>>>
+    where
+        T: GridCell,
+    {
+        let mut rows = Vec::new();
+        for index in start..self.len() {
+            let row = self.get(index)?;
+            let wrapped = is_wrapped(&row);
+            rows.push(row);
+
+            if !wrapped {
+                break;
+            }
+        }
+
+        Ok(rows)
+    }
+
+    fn read_segment(&mut self, segment: usize) -> io::Result<Vec<Row<T>>> {
+        let offset = self.offsets[segment];
+        let mut compressed = vec![0; (self.offsets[segment + 1] - offset) as usize];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut compressed)?;
+
+        let mut data = Vec::new();
+        DeflateDecoder::new(&compressed[..]).read_to_end(&mut data)?;
+
+        let mut data = &data[..];
+        (0..SEGMENT_LINES).map(|_| Row::decode(&mut data)).collect()
+    }
+}
+
+/// Check if a row continues on the next row.
+fn is_wrapped<T: GridCell>(row: &Row<T>) -> bool {
+    row.last().map_or(false, |cell| cell.flags().contains(Flags::WRAPLINE))
+}
+
+/// Archived lines which have been copied back into the history.
+///
+/// The copies are a contiguous range of archived rows at the top of the history. Once the
+/// viewport has moved far enough into the archive, the rows between the copies and the visible
+/// area are only stored in the archive, to keep the history from growing indefinitely.
+#[derive(Debug, Default, Clone)]
+pub struct PagedLines {
+    /// Index of the first archived row which has been copied.
+    start: usize,
+
+    /// Index after the last archived row which has been copied.
+    end: usize,
+
+    /// Number of archived rows and of rows in the history for every copied line, from the top.
+    ///
+    /// These only differ when a line has been reflowed to the current number of columns.
+    lines: VecDeque<(usize, usize)>,
+
+    /// Number of rows in the history which are copies.
+    rows: usize,
+}
+
+impl PagedLines {
+    /// Index of the first archived row which has been copied.
+    #[inline]
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Index after the last archived row which has been copied.
+    #[inline]
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    /// Number of rows in the history which are copies.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Forget all copies, positioning the empty range before the archived row at `index`.
+    pub fn reset(&mut self, index: usize) {
+        self.start = index;
+        self.end = index;
+        self.lines.clear();
+        self.rows = 0;
+    }
+
+    /// Record a line copied above all other copies.
+    pub fn push_front(&mut self, archived: usize, rows: usize) {
+        self.start -= archived;
+        self.lines.push_front((archived, rows));
+        self.rows += rows;
+    }
+
+    /// Record a line copied below all other copies.
+    pub fn push_back(&mut self, archived: usize, rows: usize) {
+        self.end += archived;
+        self.lines.push_back((archived, rows));
+        self.rows += rows;
+    }
+
+    /// Forget the topmost copied line, returning its number of rows in the history.
+    pub fn pop_front(&mut self) -> Option<usize> {
+        let (archived, rows) = self.lines.pop_front()?;
+        self.start += archived;
+        self.rows -= rows;
+        Some(rows)
+    }
+
+    /// Forget the bottommost copied line, returning its number of rows in the history.
+    pub fn pop_back(&mut self) -> Option<usize> {
+        let (archived, rows) = self.lines.pop_back()?;
+        self.end -= archived;
+        self.rows -= rows;
+        Some(rows)
+    }
+
+    /// Number of rows in the history of the topmost copied line.
+    #[inline]
+    pub fn first_rows(&self) -> Option<usize> {
+        self.lines.front().map(|&(_, rows)| rows)
+    }
+
+    /// Number of rows in the history of the bottommost copied line.
+    #[inline]
+    pub fn last_rows(&self) -> Option<usize> {
+        self.lines.back().map(|&(_, rows)| rows)
+    }
+}
@@ -15,8 +15,10 @@
 //! A specialized 2d grid implementation optimized for use in a terminal.
 
 use std::cmp::{max, min, Ordering};
+use std::mem;
 use std::ops::{Deref, Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo};
 
+use log::error;
 use serde::{Deserialize, Serialize};
 
 use crate::index::{Column, IndexRange, Line, Point};
@@ -32,6 +34,10 @@ mod tests;
 mod storage;
 use self::storage::Storage;
 
+mod archive;
+pub use self::archive::ArchiveCell;
+use self::archive::{Archive, PagedLines};
+
 /// Bidirection iterator
 pub trait BidirectionalIterator: Iterator {
     fn prev(&mut self) -> Option<Self::Item>;
@@ -64,6 +70,22 @@ impl<T: PartialEq> ::std::cmp::PartialEq for Grid<T> {
     }
 }
 
+impl<T: Clone> Clone for Grid<T> {
+    fn clone(&self) -> Self {
+        // Archived lines are not part of the clone, since they are backed by a unique file
+        Grid {
+            raw: self.raw.clone(),
+            cols: self.cols,
+            lines: self.lines,
+            display_offset: self.display_offset,
+            selection: self.selection.clone(),
+            max_scroll_limit: self.max_scroll_limit,
+            archive: None,
+            paged: Default::default(),
+        }
+    }
+}
+
 pub trait GridCell {
     fn is_empty(&self) -> bool;
     fn flags(&self) -> &Flags;
@@ -73,7 +95,7 @@ pub trait GridCell {
     ///
     /// This is a faster alternative to [`PartialEq`],
     /// but might report inequal cells as equal.
-    fn fast_eq(&self, other: Self) -> bool;
+    fn fast_eq(&self, other: &Self) -> bool;
 }
 
 /// Represents the terminal display contents
@@ -103,7 +125,7 @@ pub trait GridCell {
 ///                           ^
 ///                          cols
 /// ```
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Grid<T> {
     /// Lines in the grid. Each row holds a list of cells corresponding to the
     /// columns in that row.
@@ -128,6 +150,27 @@ pub struct Grid<T> {
 
     /// Maximum number of lines in history.
     max_scroll_limit: usize,
+
+    /// Lines which have been pushed out of the history.
+    ///
+    /// When present, lines are paged back in from the archive once the display offset exceeds
+    /// the history size.
+    #[serde(skip)]
+    archive: Option<Archive<T>>,
+
+    /// Archived lines which have been copied into the history.
+    #[serde(skip)]
+    paged: PagedLines,
+}
+
+/// Position of a cell within the unwrapped text, independent of the number of columns.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LogicalPoint {
+    /// Number of logical lines above the logical line of the origin, negative below it.
+    pub line: isize,
+
+    /// Index of the cell within its logical line.
+    pub offset: usize,
 }
 
 #[derive(Copy, Clone)]
@@ -139,10 +182,19 @@ pub enum Scroll {
     Bottom,
 }
 
-impl<T: GridCell + PartialEq + Copy> Grid<T> {
+impl<T: GridCell + PartialEq + Clone + Default + ArchiveCell> Grid<T> {
     pub fn new(lines: Line, cols: Column, scrollback: usize, template: T) -> Grid<T> {
         let raw = Storage::with_capacity(lines, Row::new(cols, &template));
-        Grid { raw, cols, lines, display_offset: 0, selection: None, max_scroll_limit: scrollback }
+        Grid {
+            raw,
+            cols,
+            lines,
+            display_offset: 0,
+            selection: None,
+            max_scroll_limit: scrollback,
+            archive: None,
+            paged: Default::default(),
+        }
     }
 
     /// Clamp a buffer point to the visible region.
@@ -162,33 +214,498 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         Point { line: self.lines.0 + self.display_offset - point.line.0 - 1, col: point.col }
     }
 
+    /// Convert a buffer point to its position in the unwrapped text.
+    ///
+    /// Logical lines are counted relative to the one containing the `origin` buffer line, which
+    /// usually is the line of the cursor, since its content is preserved when the grid reflows.
+    pub fn logical_point(&self, point: Point<usize>, origin: usize) -> LogicalPoint {
+        let start = self.logical_line_start(point.line);
+        let origin = self.logical_line_start(origin);
+
+        let line = if start >= origin {
+            (origin + 1..=start).filter(|&line| self.is_logical_line_start(line)).count() as isize
+        } else {
+            -((start..origin).filter(|&line| self.is_logical_line_start(line)).count() as isize)
+        };
+
+        let offset: usize = (point.line + 1..=start).map(|line| self.logical_row_width(line)).sum();
+
+        LogicalPoint { line, offset: offset + point.col.0 }
+    }
+
+    /// Convert a position in the unwrapped text back to a buffer point.
+    ///
+    /// Positions which are no longer part of the buffer are clamped to its start or end.
+    pub fn resolve_logical_point(&self, point: LogicalPoint, origin: usize) -> Point<usize> {
+        let mut start = self.logical_line_start(origin);
+
+        for _ in 0..point.line {
+            if start + 1 >= self.len() {
+                return Point::new(self.len() - 1, Column(0));
+            }
+            start = self.logical_line_start(start + 1);
+        }
+
+        for _ in point.line..0 {
+            let mut end = start;
+            while end > 0 && self.is_wrapped(end) {
+                end -= 1;
+            }
+
+            if end == 0 {
+                return Point::new(0, self.cols - 1);
+            }
+            start = end - 1;
+        }
+
+        let mut line = start;
+        let mut offset = point.offset;
+        while offset >= self.logical_row_width(line) && line > 0 && self.is_wrapped(line) {
+            offset -= self.logical_row_width(line);
+            line -= 1;
+        }
+
+        Point::new(line, min(Column(offset), self.cols - 1))
+    }
+
+    /// Check if the row continues on the line below it.
+    fn is_wrapped(&self, line: usize) -> bool {
+        self.raw[line][self.cols - 1].flags().contains(Flags::WRAPLINE)
+    }
+
+    fn is_logical_line_start(&self, line: usize) -> bool {
+        line + 1 == self.len() || !self.is_wrapped(line + 1)
+    }
+
+    fn logical_line_start(&self, mut line: usize) -> usize {
+        while !self.is_logical_line_start(line) {
+            line += 1;
+        }
+        line
+    }
+
+    /// Number of cells a row contributes to its logical line.
+    fn logical_row_width(&self, line: usize) -> usize {
+        let row = &self.raw[line];
+        let last = self.cols - 1;
+
+        // Leading spacers only exist because a wide char did not fit at the end of the row
+        let leading_spacer = self.is_wrapped(line)
+            && row[last].flags().contains(Flags::WIDE_CHAR_SPACER)
+            && (last == Column(0) || !row[last - 1].flags().contains(Flags::WIDE_CHAR));
+
+        if leading_spacer {
+            self.cols.0 - 1
+        } else {
+            self.cols.0
+        }
+    }
+
     /// Update the size of the scrollback history
     pub fn update_history(&mut self, history_size: usize) {
-        let current_history_size = self.history_size();
-        if current_history_size > history_size {
-            self.raw.shrink_lines(current_history_size - history_size);
-        }
-        self.display_offset = min(self.display_offset, history_size);
         self.max_scroll_limit = history_size;
+        self.trim_history(false);
+        self.display_offset = min(self.display_offset, self.history_size());
+    }
+
+    /// Enable or disable archiving of lines which are pushed out of the history.
+    pub fn set_history_archive(&mut self, enabled: bool) {
+        if !enabled {
+            self.release_paged_lines();
+            self.archive = None;
+            self.trim_history(false);
+        } else if self.archive.is_none() {
+            self.archive = Archive::new()
+                .map_err(|err| error!("Unable to create scrollback archive: {}", err))
+                .ok();
+            self.paged.reset(0);
+        }
     }
 
     pub fn scroll_display(&mut self, scroll: Scroll) {
         match scroll {
-            Scroll::Lines(count) => {
-                self.display_offset = min(
-                    max((self.display_offset as isize) + count, 0isize) as usize,
-                    self.history_size(),
-                );
+            Scroll::Lines(count) if count > 0 => self.scroll_display_up(count as usize),
+            Scroll::Lines(count) => self.scroll_display_down(count.abs() as usize),
+            Scroll::PageUp => self.scroll_display_up(self.lines.0),
+            Scroll::PageDown => self.scroll_display_down(self.lines.0),
+            Scroll::Top => self.scroll_display_top(),
+            Scroll::Bottom => {
+                // Lines far back in the archive are not kept once the viewport has left them
+                if self.is_detached() {
+                    self.release_paged_lines();
+                    self.restore_history();
+                }
+
+                self.display_offset = 0;
+                self.trim_history(false);
             },
-            Scroll::PageUp => {
-                self.display_offset = min(self.display_offset + self.lines.0, self.history_size());
+        }
+    }
+
+    fn scroll_display_up(&mut self, count: usize) {
+        let offset = self.display_offset + count;
+        while self.history_size() < offset && self.page_in_above() {}
+        self.display_offset = min(offset, self.history_size());
+
+        self.trim_history(true);
+    }
+
+    fn scroll_display_down(&mut self, count: usize) {
+        let mut offset = self.display_offset.saturating_sub(count);
+
+        // Archived lines between the history and the visible area must never become visible
+        while offset < 2 * self.lines.0 {
+            match self.page_in_below() {
+                Some(rows) => offset += rows,
+                None => break,
+            }
+        }
+        self.display_offset = min(offset, self.history_size());
+
+        self.trim_history(false);
+    }
+
+    fn scroll_display_top(&mut self) {
+        let archived = if self.archive.is_some() { self.paged.start() } else { 0 };
+
+        if self.history_size() + archived > self.max_scroll_limit {
+            // Jump to the oldest lines, without loading any of the lines in between
+            self.archive_history();
+            self.release_paged_lines();
+            self.paged.reset(0);
+            while self.history_size() < 2 * self.lines.0 && self.page_in_below().is_some() {}
+        } else {
+            while self.page_in_above() {}
+        }
+        self.display_offset = self.history_size();
+
+        self.trim_history(true);
+    }
+
+    /// Check if archived lines which are not part of the history separate it from the visible
+    /// area.
+    fn is_detached(&self) -> bool {
+        self.archive.as_ref().map_or(false, |archive| self.paged.end() < archive.len())
+    }
+
+    /// Copy the archived line right above the history into the history.
+    ///
+    /// Returns `false` if there is no archived line above the history.
+    fn page_in_above(&mut self) -> bool {
+        let start = self.paged.start();
+        let rows = match &mut self.archive {
+            Some(archive) if start > 0 => archive.line_before(start),
+            _ => return false,
+        };
+
+        let rows = match rows {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("Unable to restore lines from scrollback archive: {}", err);
+                self.disable_archive();
+                return false;
             },
-            Scroll::PageDown => {
-                self.display_offset -= min(self.display_offset, self.lines.0);
+        };
+
+        let archived = rows.len();
+        let rows = self.reflow_archived(rows);
+        self.paged.push_front(archived, rows.len());
+
+        for row in rows.into_iter().rev() {
+            let top = self.raw.len();
+            self.raw.initialize(1, &T::default(), self.cols);
+            self.raw[top] = row;
+        }
+
+        true
+    }
+
+    /// Copy the archived line right below the history into the history.
+    ///
+    /// Returns the number of rows added to the history, or `None` if the history is already next
+    /// to the visible area.
+    fn page_in_below(&mut self) -> Option<usize> {
+        if !self.is_detached() {
+            return None;
+        }
+
+        let end = self.paged.end();
+        let rows = match self.archive.as_mut()?.line_at(end) {
+            Ok(rows) => rows,
+            Err(err) => {
+                error!("Unable to restore lines from scrollback archive: {}", err);
+                self.disable_archive();
+                return None;
             },
-            Scroll::Top => self.display_offset = self.history_size(),
-            Scroll::Bottom => self.display_offset = 0,
+        };
+
+        let archived = rows.len();
+        let rows = self.reflow_archived(rows);
+        let count = rows.len();
+        self.paged.push_back(archived, count);
+
+        // Move the visible lines down, freeing the rows right above them
+        let lines = self.lines.0;
+        self.raw.initialize(count, &T::default(), self.cols);
+        self.raw.rotate(-(count as isize));
+        self.reverse_lines(0..lines + count);
+        self.reverse_lines(0..lines);
+        self.reverse_lines(lines..lines + count);
+
+        for (i, row) in rows.into_iter().enumerate() {
+            self.raw[lines + count - 1 - i] = row;
         }
+
+        // Selections are stored relative to the bottom of the history
+        self.selection = None;
+
+        Some(count)
+    }
+
+    /// Rewrap a line restored from the archive to the current number of columns.
+    fn reflow_archived(&self, rows: Vec<Row<T>>) -> Vec<Row<T>> {
+        let cols = self.cols.0;
+        if rows.iter().all(|row| row.len() == cols) {
+            return rows;
+        }
+
+        let template = T::default();
+
+        // Keep the wrap flag if the line continues in the history
+        let wrapped = rows
+            .last()
+            .and_then(|row| row.last())
+            .map_or(false, |cell| cell.flags().contains(Flags::WRAPLINE));
+
+        // Join all rows into a single line
+        let mut cells = Vec::new();
+        for mut row in rows {
+            let len = row.len();
+            let mut row_cells = row.front_split_off(len);
+
+            if let Some(cell) = row_cells.last_mut() {
+                cell.flags_mut().remove(Flags::WRAPLINE);
+            }
+
+            // Remove leading spacer of a wide char which has been wrapped to the next row
+            let len = row_cells.len();
+            if len >= 1
+                && row_cells[len - 1].flags().contains(Flags::WIDE_CHAR_SPACER)
+                && (len == 1 || !row_cells[len - 2].flags().contains(Flags::WIDE_CHAR))
+            {
+                row_cells.pop();
+            }
+
+            cells.append(&mut row_cells);
+        }
+
+        // Strip trailing empty cells
+        let len = cells.iter().rposition(|cell| !cell.is_empty()).map_or(0, |i| i + 1);
+        cells.truncate(len);
+
+        // Split line into rows of the current width
+        let mut reflowed = Vec::new();
+        while cells.len() > cols {
+            // Wrap wide chars which would be split by the end of the row
+            let mut len = cols;
+            if cols > 1 && cells[cols - 1].flags().contains(Flags::WIDE_CHAR) {
+                len -= 1;
+            }
+
+            let remaining = cells.split_off(len);
+            let mut row_cells = mem::replace(&mut cells, remaining);
+
+            if len < cols {
+                let mut spacer = template.clone();
+                spacer.flags_mut().insert(Flags::WIDE_CHAR_SPACER);
+                row_cells.push(spacer);
+            }
+
+            row_cells[cols - 1].flags_mut().insert(Flags::WRAPLINE);
+            reflowed.push(Row::from_vec(row_cells, cols));
+        }
+
+        let occ = cells.len();
+        cells.resize(cols, template);
+        if wrapped {
+            cells[cols - 1].flags_mut().insert(Flags::WRAPLINE);
+        }
+        reflowed.push(Row::from_vec(cells, occ));
+
+        reflowed
+    }
+
+    /// Remove lines exceeding the history limit, archiving them if necessary.
+    ///
+    /// Lines around the viewport are kept, so the history can exceed its limit while archived
+    /// lines are shown. With `below_first`, lines are removed below the viewport before the ones
+    /// above it.
+    fn trim_history(&mut self, below_first: bool) {
+        let excess = self.history_size().saturating_sub(self.max_scroll_limit);
+        if excess == 0 {
+            return;
+        }
+
+        if self.archive.is_none() {
+            self.raw.shrink_lines(excess);
+            self.display_offset = min(self.display_offset, self.history_size());
+            return;
+        }
+
+        let margin = if self.display_offset == 0 { 0 } else { self.lines.0 };
+        let above = self.history_size().saturating_sub(self.display_offset + margin);
+        let below = self.display_offset.saturating_sub(self.lines.0 + margin);
+
+        if below_first {
+            let removed = self.page_out_below(min(excess, below));
+            self.page_out_above(min(excess - removed, above));
+        } else {
+            let removed = self.page_out_above(min(excess, above));
+            self.page_out_below(min(excess - removed, below));
+        }
+    }
+
+    /// Remove up to `count` lines from the top of the history, archiving them if necessary.
+    ///
+    /// Lines copied from the archive are only removed as a whole. Returns the number of removed
+    /// lines.
+    fn page_out_above(&mut self, count: usize) -> usize {
+        let mut removed = 0;
+        while removed < count {
+            match self.paged.first_rows() {
+                Some(rows) if removed + rows > count => break,
+                Some(rows) => {
+                    self.paged.pop_front();
+                    self.raw.shrink_lines(rows);
+                    removed += rows;
+                },
+                None => {
+                    let top = self.raw.len() - 1;
+                    let row =
+                        mem::replace(&mut self.raw[top], Row::new_lazy(self.cols, &T::default()));
+                    self.raw.shrink_lines(1);
+                    self.archive_row(row);
+                    removed += 1;
+                },
+            }
+        }
+
+        // Without any copies left, the history is next to the visible area again
+        if self.paged.rows() == 0 {
+            if let Some(archive) = &self.archive {
+                self.paged.reset(archive.len());
+            }
+        }
+        self.display_offset = min(self.display_offset, self.history_size());
+
+        removed
+    }
+
+    /// Remove up to `count` lines from the bottom of the history.
+    ///
+    /// Since the removed lines are loaded from the archive again, all lines of the history are
+    /// archived first. Returns the number of removed lines.
+    fn page_out_below(&mut self, count: usize) -> usize {
+        if count == 0 {
+            return 0;
+        }
+
+        self.archive_history();
+
+        let mut removed = 0;
+        while let Some(rows) = self.paged.last_rows() {
+            if removed + rows > count {
+                break;
+            }
+
+            self.paged.pop_back();
+            removed += rows;
+        }
+
+        if removed == 0 {
+            return 0;
+        }
+
+        // Move the removed lines to the top of the buffer and drop them
+        let lines = self.lines.0;
+        self.reverse_lines(0..lines + removed);
+        self.reverse_lines(0..removed);
+        self.reverse_lines(removed..lines + removed);
+        self.raw.rotate(removed as isize);
+        self.raw.shrink_lines(removed);
+
+        // Keep the viewport at the same lines
+        self.display_offset -= removed;
+        self.selection = None;
+
+        removed
+    }
+
+    /// Archive all lines of the history which are not copies of archived lines already.
+    fn archive_history(&mut self) {
+        let lines = self.lines.0;
+        let own_rows = self.history_size() - self.paged.rows();
+        for i in (lines..lines + own_rows).rev() {
+            let archive = match &mut self.archive {
+                Some(archive) => archive,
+                None => return,
+            };
+
+            if let Err(err) = archive.push(self.raw[i].clone()) {
+                error!("Unable to write to scrollback archive: {}", err);
+                self.disable_archive();
+                return;
+            }
+
+            self.paged.push_back(1, 1);
+        }
+    }
+
+    /// Add a line which has been pushed out of the history to the archive.
+    fn archive_row(&mut self, row: Row<T>) {
+        let archive = match &mut self.archive {
+            Some(archive) => archive,
+            None => return,
+        };
+
+        let attached = self.paged.end() == archive.len();
+        if let Err(err) = archive.push(row) {
+            error!("Unable to write to scrollback archive: {}", err);
+            self.disable_archive();
+        } else if attached && self.paged.rows() == 0 {
+            self.paged.reset(archive.len());
+        }
+    }
+
+    /// Remove all copies of archived lines from the history.
+    ///
+    /// This is required before modifying the history in ways the archive would not reflect.
+    fn release_paged_lines(&mut self) {
+        let len = match &self.archive {
+            Some(archive) => archive.len(),
+            None => return,
+        };
+
+        // Copies are always at the top of the history
+        self.raw.shrink_lines(self.paged.rows());
+        self.paged.reset(len);
+        self.display_offset = min(self.display_offset, self.history_size());
+    }
+
+    /// Fill the history with the most recently archived lines.
+    fn restore_history(&mut self) {
+        while self.history_size() < self.max_scroll_limit && self.page_in_above() {}
+    }
+
+    /// Stop archiving lines after the archive has failed.
+    fn disable_archive(&mut self) {
+        // Copies can only be kept when nothing separates them from the visible area
+        if self.is_detached() {
+            self.release_paged_lines();
+        }
+
+        self.archive = None;
+        self.paged.reset(0);
     }
 
     pub fn resize(
@@ -204,6 +721,9 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
             return;
         }
 
+        // Only resize lines which are part of the regular history
+        self.release_paged_lines();
+
         match self.lines.cmp(&lines) {
             Ordering::Less => self.grow_lines(lines, cursor_pos, template),
             Ordering::Greater => self.shrink_lines(lines, cursor_pos, template),
@@ -215,10 +735,30 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
             Ordering::Greater => self.shrink_cols(reflow, cols, template),
             Ordering::Equal => (),
         }
+
+        self.restore_history();
+
+        // Lines might have been moved between the history and the visible area
+        self.compact_rows(0..self.raw.len());
+    }
+
+    /// Update how the cells of the lines in `range` are stored.
+    ///
+    /// Lines in the history only store their cells up to the last one with any content, while
+    /// all cells of the visible lines are allocated.
+    fn compact_rows(&mut self, range: Range<usize>) {
+        let lines = self.lines.0;
+        for line in range {
+            if line < lines {
+                self.raw[line].expand();
+            } else {
+                self.raw[line].compact();
+            }
+        }
     }
 
     fn increase_scroll_limit(&mut self, count: usize, template: &T) {
-        let count = min(count, self.max_scroll_limit - self.history_size());
+        let count = min(count, self.max_scroll_limit.saturating_sub(self.history_size()));
         if count != 0 {
             self.raw.initialize(count, template, self.cols);
         }
@@ -299,7 +839,7 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
             let mut cells = if row[Column(len - 1)].flags().contains(Flags::WIDE_CHAR) {
                 let mut cells = row.front_split_off(len - 1);
 
-                let mut spacer = *template;
+                let mut spacer = template.clone();
                 spacer.flags_mut().insert(Flags::WIDE_CHAR_SPACER);
                 cells.push(spacer);
 
@@ -370,9 +910,9 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
 
                 // Insert spacer if a wide char would be wrapped into the last column
                 if row.len() >= cols.0 && row[cols - 1].flags().contains(Flags::WIDE_CHAR) {
-                    wrapped.insert(0, row[cols - 1]);
+                    wrapped.insert(0, row[cols - 1].clone());
 
-                    let mut spacer = *template;
+                    let mut spacer = template.clone();
                     spacer.flags_mut().insert(Flags::WIDE_CHAR_SPACER);
                     row[cols - 1] = spacer;
                 }
@@ -422,7 +962,7 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
                     // Make sure new row is at least as long as new width
                     let occ = wrapped.len();
                     if occ < cols.0 {
-                        wrapped.append(&mut vec![*template; cols.0 - occ]);
+                        wrapped.append(&mut vec![template.clone(); cols.0 - occ]);
                     }
                     row = Row::from_vec(wrapped, occ);
                 }
@@ -430,7 +970,12 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         }
 
         let mut reversed: Vec<Row<T>> = new_raw.drain(..).rev().collect();
-        reversed.truncate(self.max_scroll_limit + self.lines.0);
+        let max_len = self.max_scroll_limit + self.lines.0;
+        if reversed.len() > max_len {
+            for row in reversed.drain(max_len..).rev() {
+                self.archive_row(row);
+            }
+        }
         self.raw.replace_inner(reversed);
         self.cols = cols;
     }
@@ -464,8 +1009,9 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         // starts at the top, we can do a full rotation which just involves
         // changing the start index.
         //
-        // To accommodate scroll regions, rows are reordered at the end.
-        if region.start == Line(0) {
+        // To accommodate scroll regions, rows are reordered at the end. Copies of archived lines
+        // must stay at the top of the history, so they are never rotated.
+        if region.start == Line(0) && self.paged.rows() == 0 {
             // Rotate the entire line buffer. If there's a scrolling region
             // active, the bottom lines are restored in the next step.
             self.raw.rotate_up(*positions);
@@ -511,14 +1057,39 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         let num_lines = self.num_lines().0;
         let num_cols = self.num_cols().0;
 
-        if region.start == Line(0) {
+        if region.start == Line(0) && !self.is_detached() {
             // Update display offset when not pinned to active area
             if self.display_offset != 0 {
-                self.display_offset = min(self.display_offset + *positions, self.max_scroll_limit);
+                let max_offset = max(self.max_scroll_limit, self.history_size());
+                self.display_offset = min(self.display_offset + *positions, max_offset);
             }
 
+            // Make room for the new lines by dropping copies of archived lines
+            let required = (self.history_size() + *positions).saturating_sub(self.max_scroll_limit);
+            let mut removed = 0;
+            while removed < required {
+                match self.paged.pop_front() {
+                    Some(rows) => {
+                        self.raw.shrink_lines(rows);
+                        removed += rows;
+                    },
+                    None => break,
+                }
+            }
+            self.display_offset = min(self.display_offset, self.history_size());
+
+            let history_size = self.history_size();
             self.increase_scroll_limit(*positions, template);
 
+            // Archive lines which are about to be pushed out of the history
+            if self.archive.is_some() {
+                let evicted = *positions - (self.history_size() - history_size);
+                for i in (self.raw.len() - evicted..self.raw.len()).rev() {
+                    let row = mem::replace(&mut self.raw[i], Row::new(self.cols, template));
+                    self.archive_row(row);
+                }
+            }
+
             // Rotate the entire line buffer. If there's a scrolling region
             // active, the bottom lines are restored in the next step.
             self.raw.rotate(-(*positions as isize));
@@ -537,6 +1108,10 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
                 self.raw.swap(i, i + *positions);
             }
 
+            // Drop the trailing blank cells of the lines which have been pushed into the history
+            let history_end = min(num_lines + *positions, self.raw.len());
+            self.compact_rows(num_lines..history_end);
+
             // Finally, reset recycled lines
             //
             // Recycled lines are just above the end of the scrolling region.
@@ -544,6 +1119,14 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
                 self.raw[i + fixed_lines].reset(&template);
             }
         } else {
+            // The viewport shows lines far back in the archive, so new lines are archived directly
+            if region.start == Line(0) {
+                for line in IndexRange(Line(0)..positions) {
+                    let row = mem::replace(&mut self.raw[line], Row::new(self.cols, template));
+                    self.archive_row(row);
+                }
+            }
+
             // Rotate selection to track content
             self.selection = self
                 .selection
@@ -562,6 +1145,59 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         }
     }
 
+    /// Insert lines between the history and the visible area.
+    ///
+    /// The rows are expected to be ordered from top to bottom.
+    pub fn insert_history(&mut self, rows: Vec<Row<T>>, template: &T) {
+        // The viewport shows lines far back in the archive, so new lines are archived directly
+        if self.is_detached() {
+            for row in rows {
+                self.archive_row(row);
+            }
+            return;
+        }
+
+        let lines = self.lines.0;
+        let chunk_size = min(lines, self.max_scroll_limit);
+        if chunk_size == 0 {
+            return;
+        }
+
+        let mut rows = rows.into_iter();
+        loop {
+            let chunk: Vec<Row<T>> = rows.by_ref().take(chunk_size).collect();
+            let count = chunk.len();
+            if count == 0 {
+                break;
+            }
+
+            // Push the topmost visible lines into the history
+            self.scroll_up(&(Line(0)..self.lines), Line(count), template);
+
+            // Rotate the visible lines back into place, freeing the lines right above them
+            self.reverse_lines(0..lines + count);
+            self.reverse_lines(0..lines);
+            self.reverse_lines(lines..lines + count);
+
+            for (i, row) in chunk.into_iter().enumerate() {
+                self.raw[lines + count - 1 - i] = row;
+            }
+
+            // Visible lines have been rotated through the history
+            self.compact_rows(0..lines + count);
+        }
+    }
+
+    /// Reverse the order of the lines in a buffer range.
+    fn reverse_lines(&mut self, range: Range<usize>) {
+        let (mut start, mut end) = (range.start, range.end);
+        while start + 1 < end {
+            end -= 1;
+            self.raw.swap(start, end);
+            start += 1;
+        }
+    }
+
     pub fn clear_viewport(&mut self, template: &T) {
         // Determine how many lines to scroll up by.
         let end = Point { line: 0, col: self.num_cols() };
@@ -576,6 +1212,10 @@ impl<T: GridCell + PartialEq + Copy> Grid<T> {
         let region = Line(0)..self.num_lines();
 
         // Reset display offset
+        if self.is_detached() {
+            self.release_paged_lines();
+            self.restore_history();
+        }
         self.display_offset = 0;
 
         // Clear the viewport
@@ -622,6 +1262,11 @@ impl<T> Grid<T> {
     pub fn clear_history(&mut self) {
         // Explicitly purge all lines from history
         self.raw.shrink_lines(self.history_size());
+
+        if let Some(archive) = &mut self.archive {
+            archive.clear();
+        }
+        self.paged.reset(0);
     }
 
     /// Total number of lines in the buffer, this includes scrollback + visible lines
@@ -639,7 +1284,7 @@ impl<T> Grid<T> {
     #[inline]
     pub fn initialize_all(&mut self, template: &T)
     where
-        T: Copy + GridCell,
+        T: Clone + GridCell,
     {
         // Remove all cached lines to clear them of any content
         self.truncate();
@@ -765,7 +1410,7 @@ impl<'point, T> Index<&'point Point> for Grid<T> {
     }
 }
 
-impl<'point, T> IndexMut<&'point Point> for Grid<T> {
+impl<'point, T: Clone> IndexMut<&'point Point> for Grid<T> {
     #[inline]
     fn index_mut<'a, 'b>(&'a mut self, point: &'b Point) -> &'a mut T {
         &mut self[point.line][point.col]
@@ -794,7 +1439,7 @@ pub struct RegionMut<'a, T> {
     raw: &'a mut Storage<T>,
 }
 
-impl<'a, T> RegionMut<'a, T> {
+impl<'a, T: Clone> RegionMut<'a, T> {
     /// Call the provided function for every item in this region
     pub fn each<F: Fn(&mut T)>(self, func: F) {
         for row in self {
@@ -957,7 +1602,7 @@ impl<'a, T: 'a> DisplayIter<'a, T> {
     }
 }
 
-impl<'a, T: Copy + 'a> Iterator for DisplayIter<'a, T> {
+impl<'a, T: Clone + 'a> Iterator for DisplayIter<'a, T> {
     type Item = Indexed<T>;
 
     #[inline]
@@ -969,7 +1614,7 @@ impl<'a, T: Copy + 'a> Iterator for DisplayIter<'a, T> {
 
         // Get the next item.
         let item = Some(Indexed {
-            inner: self.grid.raw[self.offset][self.col],
+            inner: self.grid.raw[self.offset][self.col].clone(),
             line: self.line,
             column: self.col,
         });
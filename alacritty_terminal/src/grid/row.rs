@@ -15,16 +15,23 @@
 //! Defines the Row type which makes up lines in the grid
 
 use std::cmp::{max, min};
+use std::io::{self, Read};
+use std::num::NonZeroU32;
 use std::ops::{Index, IndexMut};
 use std::ops::{Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::slice;
 
 use serde::{Deserialize, Serialize};
 
-use crate::grid::GridCell;
+use crate::grid::{ArchiveCell, GridCell};
 use crate::index::Column;
 
 /// A row in the grid
+///
+/// Rows in the history usually only store their cells up to the last one with any content. All
+/// cells after that are identical to the last stored cell and are allocated once they are
+/// modified. Slicing a row only covers the stored cells, so [`Row::iter`] should be used to
+/// read rows which are not part of the visible area.
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Row<T> {
     inner: Vec<T>,
@@ -33,46 +40,93 @@ pub struct Row<T> {
     ///
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
-    pub(crate) occ: usize,
+    pub(crate) occ: u32,
+
+    /// Time at which the line was completed.
+    ///
+    /// This is stored as seconds relative to an epoch chosen by the owner of the grid, to keep
+    /// the row as small as possible.
+    #[serde(default)]
+    timestamp: Option<NonZeroU32>,
+
+    /// Number of cells after the end of `inner`, which are identical to its last cell.
+    #[serde(default)]
+    implicit: u32,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.inner == other.inner
+        if self.implicit == 0 && other.implicit == 0 {
+            self.inner == other.inner
+        } else {
+            self.len() == other.len() && self.iter().eq(other.iter())
+        }
     }
 }
 
-impl<T: Copy> Row<T> {
+impl<T: Clone> Row<T> {
     pub fn new(columns: Column, template: &T) -> Row<T>
     where
         T: GridCell,
     {
-        let occ = if template.is_empty() { 0 } else { columns.0 };
-        Row { inner: vec![*template; columns.0], occ }
+        let occ = if template.is_empty() { 0 } else { columns.0 as u32 };
+        Row { inner: vec![template.clone(); columns.0], occ, timestamp: None, implicit: 0 }
     }
 
-    pub fn grow(&mut self, cols: Column, template: &T) {
-        if self.inner.len() >= cols.0 {
+    /// Create a row which only allocates its cells once they are modified.
+    pub fn new_lazy(columns: Column, template: &T) -> Row<T>
+    where
+        T: GridCell,
+    {
+        let mut row = Row::new(min(columns, Column(1)), template);
+        row.implicit = columns.0.saturating_sub(1) as u32;
+        row
+    }
+
+    pub fn grow(&mut self, cols: Column, template: &T)
+    where
+        T: PartialEq,
+    {
+        if self.len() >= cols.0 {
             return;
         }
 
-        self.inner.append(&mut vec![*template; cols.0 - self.len()]);
+        // Trailing template cells do not have to be stored
+        if self.implicit > 0 && self.inner.last() == Some(template) {
+            self.implicit += (cols.0 - self.len()) as u32;
+            return;
+        }
+
+        self.expand();
+        self.inner.append(&mut vec![template.clone(); cols.0 - self.len()]);
     }
 
     pub fn shrink(&mut self, cols: Column) -> Option<Vec<T>>
     where
         T: GridCell,
     {
+        if self.len() <= cols.0 {
+            return None;
+        }
+
+        if self.implicit > 0 && !self.inner[self.inner.len() - 1].is_empty() {
+            self.expand();
+        }
+
+        // Only empty implicit cells are removed
         if self.inner.len() <= cols.0 {
+            self.implicit = (cols.0 - self.inner.len()) as u32;
+            self.occ = min(self.occ, cols.0 as u32);
             return None;
         }
+        self.implicit = 0;
 
         // Split off cells for a new row
         let mut new_row = self.inner.split_off(cols.0);
         let index = new_row.iter().rposition(|c| !c.is_empty()).map(|i| i + 1).unwrap_or(0);
         new_row.truncate(index);
 
-        self.occ = min(self.occ, cols.0);
+        self.occ = min(self.occ, cols.0 as u32);
 
         if new_row.is_empty() {
             None
@@ -89,44 +143,71 @@ impl<T: Copy> Row<T> {
     {
         debug_assert!(!self.inner.is_empty());
 
-        let template = *template;
+        // Rows are only reset when they become visible, so all their cells are allocated
+        if self.implicit > 0 {
+            let len = self.len();
+            self.inner.clear();
+            self.inner.resize(len, template.clone());
+            self.implicit = 0;
+            self.occ = 0;
+            self.timestamp = None;
+            return;
+        }
 
         // Mark all cells as dirty if template cell changed
         let len = self.inner.len();
         if !self.inner[len - 1].fast_eq(template) {
-            self.occ = len;
+            self.occ = len as u32;
         }
 
         // Reset every dirty in the row
         // let template = *template;
-        for item in &mut self.inner[..self.occ] {
-            *item = template;
+        for item in &mut self.inner[..self.occ as usize] {
+            *item = template.clone();
         }
 
         self.occ = 0;
+        self.timestamp = None;
     }
-}
 
-#[allow(clippy::len_without_is_empty)]
-impl<T> Row<T> {
+    /// Allocate all implicit cells, so every cell of the row can be modified.
     #[inline]
-    pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
-    }
-
-    #[inline]
-    pub fn len(&self) -> usize {
-        self.inner.len()
+    pub fn expand(&mut self) {
+        if self.implicit > 0 {
+            let len = self.len();
+            let fill = self.inner[self.inner.len() - 1].clone();
+            self.inner.resize(len, fill);
+            self.implicit = 0;
+        }
     }
 
-    #[inline]
-    pub fn last(&self) -> Option<&T> {
-        self.inner.last()
+    /// Stop storing the trailing cells which are identical to the last cell.
+    ///
+    /// The row still has the same number of columns, but all of its trailing blank cells are
+    /// read from a single stored cell.
+    pub fn compact(&mut self)
+    where
+        T: PartialEq,
+    {
+        let len = self.inner.len();
+        let fill = match self.inner.last() {
+            Some(fill) => fill,
+            None => return,
+        };
+
+        let start =
+            self.inner[..len - 1].iter().rposition(|cell| cell != fill).map_or(0, |i| i + 1);
+        if start + 1 < len {
+            self.implicit += (len - start - 1) as u32;
+            self.inner.truncate(start + 1);
+            self.inner.shrink_to_fit();
+        }
     }
 
     #[inline]
     pub fn last_mut(&mut self) -> Option<&mut T> {
-        self.occ = self.inner.len();
+        self.expand();
+        self.occ = self.inner.len() as u32;
         self.inner.last_mut()
     }
 
@@ -135,18 +216,59 @@ impl<T> Row<T> {
     where
         T: GridCell,
     {
-        self.occ += vec.len();
+        self.expand();
+        self.occ += vec.len() as u32;
         self.inner.append(vec);
     }
 
     #[inline]
     pub fn append_front(&mut self, mut vec: Vec<T>) {
-        self.occ += vec.len();
+        self.expand();
+        self.occ += vec.len() as u32;
 
         vec.append(&mut self.inner);
         self.inner = vec;
     }
 
+    #[inline]
+    pub fn front_split_off(&mut self, at: usize) -> Vec<T> {
+        self.expand();
+        self.occ = self.occ.saturating_sub(at as u32);
+
+        let mut split = self.inner.split_off(at);
+        std::mem::swap(&mut split, &mut self.inner);
+        split
+    }
+}
+
+#[allow(clippy::len_without_is_empty)]
+impl<T> Row<T> {
+    #[inline]
+    pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
+        Row { inner: vec, occ: occ as u32, timestamp: None, implicit: 0 }
+    }
+
+    /// Time at which the line was completed, relative to the grid owner's epoch.
+    #[inline]
+    pub fn timestamp(&self) -> Option<NonZeroU32> {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn set_timestamp(&mut self, timestamp: Option<NonZeroU32>) {
+        self.timestamp = timestamp;
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inner.len() + self.implicit as usize
+    }
+
+    #[inline]
+    pub fn last(&self) -> Option<&T> {
+        self.inner.last()
+    }
+
     #[inline]
     pub fn is_empty(&self) -> bool
     where
@@ -155,23 +277,50 @@ impl<T> Row<T> {
         self.inner.iter().all(GridCell::is_empty)
     }
 
+    /// Iterate over all cells of the row, including the implicit ones.
     #[inline]
-    pub fn front_split_off(&mut self, at: usize) -> Vec<T> {
-        self.occ = self.occ.saturating_sub(at);
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        (0..self.len()).map(move |col| &self[Column(col)])
+    }
+}
 
-        let mut split = self.inner.split_off(at);
-        std::mem::swap(&mut split, &mut self.inner);
-        split
+impl<T: ArchiveCell> Row<T> {
+    /// Append the binary representation of the row to `buf`.
+    pub(super) fn encode(&self, buf: &mut Vec<u8>) {
+        let timestamp = self.timestamp.map_or(0, NonZeroU32::get);
+        for field in &[self.inner.len() as u32, self.occ, self.implicit, timestamp] {
+            buf.extend_from_slice(&field.to_le_bytes());
+        }
+
+        for cell in &self.inner {
+            cell.encode(buf);
+        }
+    }
+
+    /// Read a row written by [`Row::encode`] from the start of `buf`, advancing it past the row.
+    pub(super) fn decode(buf: &mut &[u8]) -> io::Result<Row<T>> {
+        let mut fields = [0; 4];
+        for field in &mut fields {
+            let mut bytes = [0; 4];
+            buf.read_exact(&mut bytes)?;
+            *field = u32::from_le_bytes(bytes);
+        }
+        let [len, occ, implicit, timestamp] = fields;
+
+        let inner = (0..len).map(|_| T::decode(buf)).collect::<io::Result<_>>()?;
+
+        Ok(Row { inner, occ, timestamp: NonZeroU32::new(timestamp), implicit })
     }
 }
 
-impl<'a, T> IntoIterator for &'a mut Row<T> {
+impl<'a, T: Clone> IntoIterator for &'a mut Row<T> {
     type IntoIter = slice::IterMut<'a, T>;
     type Item = &'a mut T;
 
     #[inline]
     fn into_iter(self) -> slice::IterMut<'a, T> {
-        self.occ = self.len();
+        self.expand();
+        self.occ = self.len() as u32;
         self.inner.iter_mut()
     }
 }
@@ -181,14 +330,20 @@ impl<T> Index<Column> for Row<T> {
 
     #[inline]
     fn index(&self, index: Column) -> &T {
-        &self.inner[index.0]
+        let stored = self.inner.len();
+        if index.0 >= stored && index.0 < self.len() {
+            &self.inner[stored - 1]
+        } else {
+            &self.inner[index.0]
+        }
     }
 }
 
-impl<T> IndexMut<Column> for Row<T> {
+impl<T: Clone> IndexMut<Column> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: Column) -> &mut T {
-        self.occ = max(self.occ, *index + 1);
+        self.expand();
+        self.occ = max(self.occ, *index as u32 + 1);
         &mut self.inner[index.0]
     }
 }
@@ -206,10 +361,11 @@ impl<T> Index<Range<Column>> for Row<T> {
     }
 }
 
-impl<T> IndexMut<Range<Column>> for Row<T> {
+impl<T: Clone> IndexMut<Range<Column>> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: Range<Column>) -> &mut [T] {
-        self.occ = max(self.occ, *index.end);
+        self.expand();
+        self.occ = max(self.occ, *index.end as u32);
         &mut self.inner[(index.start.0)..(index.end.0)]
     }
 }
@@ -223,10 +379,11 @@ impl<T> Index<RangeTo<Column>> for Row<T> {
     }
 }
 
-impl<T> IndexMut<RangeTo<Column>> for Row<T> {
+impl<T: Clone> IndexMut<RangeTo<Column>> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: RangeTo<Column>) -> &mut [T] {
-        self.occ = max(self.occ, *index.end);
+        self.expand();
+        self.occ = max(self.occ, *index.end as u32);
         &mut self.inner[..(index.end.0)]
     }
 }
@@ -236,14 +393,16 @@ impl<T> Index<RangeFrom<Column>> for Row<T> {
 
     #[inline]
     fn index(&self, index: RangeFrom<Column>) -> &[T] {
+        debug_assert_eq!(self.implicit, 0);
         &self.inner[(index.start.0)..]
     }
 }
 
-impl<T> IndexMut<RangeFrom<Column>> for Row<T> {
+impl<T: Clone> IndexMut<RangeFrom<Column>> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: RangeFrom<Column>) -> &mut [T] {
-        self.occ = self.len();
+        self.expand();
+        self.occ = self.len() as u32;
         &mut self.inner[(index.start.0)..]
     }
 }
@@ -253,14 +412,16 @@ impl<T> Index<RangeFull> for Row<T> {
 
     #[inline]
     fn index(&self, _: RangeFull) -> &[T] {
+        debug_assert_eq!(self.implicit, 0);
         &self.inner[..]
     }
 }
 
-impl<T> IndexMut<RangeFull> for Row<T> {
+impl<T: Clone> IndexMut<RangeFull> for Row<T> {
     #[inline]
     fn index_mut(&mut self, _: RangeFull) -> &mut [T] {
-        self.occ = self.len();
+        self.expand();
+        self.occ = self.len() as u32;
         &mut self.inner[..]
     }
 }
@@ -274,10 +435,11 @@ impl<T> Index<RangeToInclusive<Column>> for Row<T> {
     }
 }
 
-impl<T> IndexMut<RangeToInclusive<Column>> for Row<T> {
+impl<T: Clone> IndexMut<RangeToInclusive<Column>> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: RangeToInclusive<Column>) -> &mut [T] {
-        self.occ = max(self.occ, *index.end);
+        self.expand();
+        self.occ = max(self.occ, *index.end as u32);
         &mut self.inner[..=(index.end.0)]
     }
 }
@@ -1,4 +1,5 @@
 use std::cmp::{max, PartialEq};
+use std::mem;
 use std::ops::{Index, IndexMut};
 use std::vec::Drain;
 
@@ -144,11 +145,12 @@ impl<T> Storage<T> {
     #[inline]
     pub fn initialize(&mut self, additional_rows: usize, template: &T, cols: Column)
     where
-        T: GridCell + Copy,
+        T: GridCell + Clone,
     {
         if self.len + additional_rows > self.inner.len() {
+            // Cells are only allocated once the rows are used
             let realloc_size = max(additional_rows, MAX_CACHE_SIZE);
-            let mut new = vec![Row::new(cols, template); realloc_size];
+            let mut new = vec![Row::new_lazy(cols, template); realloc_size];
             let mut split = self.inner.split_off(self.zero);
             self.inner.append(&mut new);
             self.inner.append(&mut split);
@@ -191,13 +193,10 @@ impl<T> Storage<T> {
     /// Swap implementation for Row<T>.
     ///
     /// Exploits the known size of Row<T> to produce a slightly more efficient
-    /// swap than going through slice::swap.
-    ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// swap than going through slice::swap, which copies through a temporary.
     pub fn swap(&mut self, a: usize, b: usize) {
-        debug_assert_eq!(std::mem::size_of::<Row<T>>(), 32);
+        const QWORDS: usize = mem::size_of::<Row<()>>() / mem::size_of::<usize>();
+        debug_assert_eq!(mem::size_of::<Row<T>>(), QWORDS * mem::size_of::<usize>());
 
         let a = self.compute_index(a);
         let b = self.compute_index(b);
@@ -213,7 +212,7 @@ impl<T> Storage<T> {
             //
             // The optimizer unrolls this loop and vectorizes it.
             let mut tmp: usize;
-            for i in 0..4 {
+            for i in 0..QWORDS as isize {
                 tmp = *a_ptr.offset(i);
                 *a_ptr.offset(i) = *b_ptr.offset(i);
                 *b_ptr.offset(i) = tmp;
@@ -308,8 +307,8 @@ mod tests {
             unimplemented!();
         }
 
-        fn fast_eq(&self, other: Self) -> bool {
-            self == &other
+        fn fast_eq(&self, other: &Self) -> bool {
+            self == other
         }
     }
 
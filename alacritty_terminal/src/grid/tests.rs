@@ -14,8 +14,10 @@
 
 //! Tests for the Grid
 
-use super::{BidirectionalIterator, Grid};
-use crate::grid::GridCell;
+use std::io::{self, Read};
+
+use super::{BidirectionalIterator, Grid, Row, Scroll};
+use crate::grid::{ArchiveCell, GridCell};
 use crate::index::{Column, Line, Point};
 use crate::term::cell::{Cell, Flags};
 
@@ -32,8 +34,20 @@ impl GridCell for usize {
         unimplemented!();
     }
 
-    fn fast_eq(&self, other: Self) -> bool {
-        self == &other
+    fn fast_eq(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+impl ArchiveCell for usize {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(*self as u64).to_le_bytes());
+    }
+
+    fn decode(buf: &mut &[u8]) -> io::Result<Self> {
+        let mut bytes = [0; 8];
+        buf.read_exact(&mut bytes)?;
+        Ok(u64::from_le_bytes(bytes) as usize)
     }
 }
 
@@ -71,6 +85,99 @@ fn visible_to_buffer() {
     assert_eq!(point, Point::new(4, Column(3)));
 }
 
+#[test]
+fn scroll_into_archived_history() {
+    let mut grid = Grid::new(Line(2), Column(1), 3, cell(' '));
+    grid.set_history_archive(true);
+
+    let index_cell = |i| cell(std::char::from_u32(0x100 + i as u32).unwrap());
+    for i in 1..=2500 {
+        grid.scroll_up(&(Line(0)..Line(2)), Line(1), &cell(' '));
+        grid[Line(1)][Column(0)] = index_cell(i);
+    }
+
+    assert_eq!(grid.history_size(), 3);
+
+    // Every line ever written, starting with the initially blank screen
+    let lines: Vec<_> =
+        (0..2502).map(|i| if i < 2 { cell(' ') } else { index_cell(i - 1) }).collect();
+    let top_line = |grid: &Grid<Cell>| grid[1 + grid.display_offset()][Column(0)].clone();
+
+    // Only the oldest lines are loaded when jumping to the top
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 4);
+    assert_eq!(grid.display_offset(), 4);
+    assert_eq!(top_line(&grid), lines[0]);
+
+    for i in 1..=2500 {
+        grid.scroll_display(Scroll::Lines(-1));
+        assert_eq!(top_line(&grid), lines[i]);
+        assert_eq!(grid[grid.display_offset()][Column(0)], lines[i + 1]);
+        assert!(grid.history_size() <= 7);
+    }
+    assert_eq!(grid.display_offset(), 0);
+    assert_eq!(grid.history_size(), 3);
+
+    // Output while the top is visible is appended to the archive
+    grid.scroll_display(Scroll::Top);
+    grid.scroll_up(&(Line(0)..Line(2)), Line(1), &cell(' '));
+    grid[Line(1)][Column(0)] = index_cell(2501);
+    assert_eq!(top_line(&grid), lines[0]);
+
+    grid.scroll_display(Scroll::Bottom);
+    assert_eq!(grid.history_size(), 3);
+    assert_eq!(grid[0][Column(0)], index_cell(2501));
+    assert_eq!(grid[1][Column(0)], index_cell(2500));
+    assert_eq!(grid[2][Column(0)], index_cell(2499));
+
+    grid.clear_history();
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 0);
+}
+
+#[test]
+fn reflow_archived_history() {
+    let mut grid = Grid::new(Line(1), Column(4), 0, cell(' '));
+    grid.set_history_archive(true);
+
+    let mut spacer = wrap_cell(' ');
+    spacer.flags.insert(Flags::WIDE_CHAR_SPACER);
+    let mut wide = cell('字');
+    wide.flags.insert(Flags::WIDE_CHAR);
+    let mut trailing_spacer = cell(' ');
+    trailing_spacer.flags.insert(Flags::WIDE_CHAR_SPACER);
+    let mut combined = cell('e');
+    combined.push_zerowidth('\u{301}');
+
+    let rows = [
+        [cell('a'), cell('b'), cell('c'), spacer],
+        [wide.clone(), trailing_spacer.clone(), combined.clone(), cell(' ')],
+        [cell('x'), cell(' '), cell(' '), cell(' ')],
+    ];
+    for row in rows.iter() {
+        for (i, cell) in row.iter().enumerate() {
+            grid[Line(0)][Column(i)] = cell.clone();
+        }
+        grid.scroll_up(&(Line(0)..Line(1)), Line(1), &cell(' '));
+    }
+
+    assert_eq!(grid.history_size(), 0);
+
+    grid.resize(true, Line(1), Column(3), &mut Point::new(Line(0), Column(0)), &cell(' '));
+    grid.scroll_display(Scroll::Top);
+    assert_eq!(grid.history_size(), 2);
+
+    grid.scroll_display(Scroll::Lines(-1));
+    assert_eq!(grid.history_size(), 3);
+    assert_eq!(grid[3][Column(0)], cell('a'));
+    assert_eq!(grid[3][Column(2)], wrap_cell('c'));
+    assert_eq!(grid[2][Column(0)], wide);
+    assert_eq!(grid[2][Column(1)], trailing_spacer);
+    assert_eq!(grid[2][Column(2)], combined);
+    assert_eq!(grid[1][Column(0)], cell('x'));
+    assert_eq!(grid[1].len(), 3);
+}
+
 // Scroll up moves lines upwards
 #[test]
 fn scroll_up() {
@@ -363,6 +470,49 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[0][Column(1)], cell('2'));
 }
 
+#[test]
+fn compact_history() {
+    let mut grid = Grid::new(Line(2), Column(5), 10, cell(' '));
+    grid[Line(0)][Column(0)] = cell('a');
+    grid[Line(1)][Column(0)] = cell('b');
+    grid.scroll_up(&(Line(0)..Line(2)), Line(2), &cell(' '));
+
+    let mut expected = Row::new(Column(5), &cell(' '));
+    expected[Column(0)] = cell('a');
+    assert_eq!(grid.history_size(), 2);
+    assert_eq!(grid[3], expected);
+    assert_eq!(grid[3].len(), 5);
+    assert_eq!(grid[3][Column(4)], cell(' '));
+    assert_eq!(grid[3].iter().rev().position(|cell| cell.c == 'a'), Some(4));
+
+    // Implicit cells are allocated once they are modified
+    grid[3][Column(4)] = cell('c');
+    assert_eq!(grid[3][Column(3)], cell(' '));
+    assert_eq!(grid[3][Column(4)], cell('c'));
+
+    // Only the allocated cells are reflowed
+    grid.resize(true, Line(2), Column(3), &mut Point::new(Line(1), Column(0)), &cell(' '));
+    assert_eq!(grid.history_size(), 3);
+    assert_eq!(grid[3][Column(1)], cell('c'));
+    assert_eq!(grid[2][Column(0)], cell('b'));
+    assert_eq!(grid[2].len(), 3);
+
+    grid.resize(true, Line(2), Column(5), &mut Point::new(Line(1), Column(0)), &cell(' '));
+    assert_eq!(grid.history_size(), 2);
+    assert_eq!(grid[3][Column(4)], cell('c'));
+    assert_eq!(grid[2][Column(0)], cell('b'));
+    assert_eq!(grid[2].len(), 5);
+
+    // Lines pulled back into the visible area are fully allocated
+    grid.resize(true, Line(5), Column(5), &mut Point::new(Line(1), Column(0)), &cell(' '));
+    assert_eq!(grid.history_size(), 0);
+    for line in 0..3 {
+        assert_eq!(grid[Line(line)][..].len(), 5);
+    }
+    assert_eq!(grid[Line(0)][Column(0)], cell('a'));
+    assert_eq!(grid[Line(1)][Column(0)], cell('b'));
+}
+
 fn cell(c: char) -> Cell {
     let mut cell = Cell::default();
     cell.c = c;
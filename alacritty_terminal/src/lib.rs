@@ -26,6 +26,7 @@ pub mod clipboard;
 pub mod config;
 pub mod event;
 pub mod event_loop;
+pub mod graphics;
 pub mod grid;
 pub mod index;
 #[cfg(target_os = "macos")]
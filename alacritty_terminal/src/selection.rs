@@ -22,6 +22,7 @@ use std::convert::TryFrom;
 use std::mem;
 use std::ops::Range;
 
+use crate::config::LineSelection;
 use crate::index::{Column, Line, Point, Side};
 use crate::term::{Search, Term};
 
@@ -110,6 +111,22 @@ impl Selection {
         self.region.end = Anchor::new(point, side);
     }
 
+    /// Points where the selection was started and where it currently ends.
+    pub fn points(&self) -> (Point<usize>, Point<usize>) {
+        (self.region.start.point, self.region.end.point)
+    }
+
+    /// Swap the point where the selection was started with the point where it ends.
+    pub fn swap_ends(&mut self) {
+        mem::swap(&mut self.region.start, &mut self.region.end);
+    }
+
+    /// Move both ends of the selection, keeping their sides.
+    pub fn set_points(&mut self, start: Point<usize>, end: Point<usize>) {
+        self.region.start.point = start;
+        self.region.end.point = end;
+    }
+
     pub fn rotate(
         mut self,
         num_lines: usize,
@@ -306,8 +323,16 @@ impl Selection {
         mut start: Point<usize>,
         mut end: Point<usize>,
     ) -> Option<SelectionRange> {
-        start = term.line_search_left(start);
-        end = term.line_search_right(end);
+        match term.line_selection() {
+            LineSelection::Logical => {
+                start = term.line_search_left(start);
+                end = term.line_search_right(end);
+            },
+            LineSelection::Visual => {
+                start.col = Column(0);
+                end.col = term.grid().num_cols() - 1;
+            },
+        }
 
         Some(SelectionRange { start, end, is_block: false })
     }
@@ -388,6 +413,7 @@ mod tests {
     use crate::config::MockConfig;
     use crate::event::{Event, EventListener};
     use crate::index::{Column, Line, Point, Side};
+    use crate::term::cell::Flags;
     use crate::term::{SizeInfo, Term};
 
     struct Mock;
@@ -533,6 +559,30 @@ mod tests {
         });
     }
 
+    #[test]
+    fn visual_line_selection() {
+        let mut term = term(5, 3);
+        term.grid_mut()[Line(1)][Column(4)].flags.insert(Flags::WRAPLINE);
+        let selection = Selection::new(SelectionType::Lines, Point::new(0, Column(1)), Side::Left);
+
+        // Logical lines follow the wrap into the previous row
+        assert_eq!(selection.to_range(&term).unwrap(), SelectionRange {
+            start: Point::new(1, Column(0)),
+            end: Point::new(0, Column(4)),
+            is_block: false,
+        });
+
+        let mut config = MockConfig::default();
+        config.selection.lines = LineSelection::Visual;
+        term.update_config(&config);
+
+        assert_eq!(selection.to_range(&term).unwrap(), SelectionRange {
+            start: Point::new(0, Column(0)),
+            end: Point::new(0, Column(4)),
+            is_block: false,
+        });
+    }
+
     #[test]
     fn semantic_selection() {
         let num_lines = 10;
@@ -11,47 +11,107 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use std::io::{self, Read};
+use std::str;
+
 use bitflags::bitflags;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::ansi::{Color, NamedColor};
-use crate::grid::{self, GridCell};
+use crate::graphics::GraphicCell;
+use crate::grid::{self, ArchiveCell, GridCell};
 use crate::index::Column;
-
-// Maximum number of zerowidth characters which will be stored per cell.
-pub const MAX_ZEROWIDTH_CHARS: usize = 5;
+use crate::term::color::Rgb;
 
 bitflags! {
     #[derive(Serialize, Deserialize)]
-    pub struct Flags: u16 {
-        const INVERSE           = 0b00_0000_0001;
-        const BOLD              = 0b00_0000_0010;
-        const ITALIC            = 0b00_0000_0100;
-        const BOLD_ITALIC       = 0b00_0000_0110;
-        const UNDERLINE         = 0b00_0000_1000;
-        const WRAPLINE          = 0b00_0001_0000;
-        const WIDE_CHAR         = 0b00_0010_0000;
-        const WIDE_CHAR_SPACER  = 0b00_0100_0000;
-        const DIM               = 0b00_1000_0000;
-        const DIM_BOLD          = 0b00_1000_0010;
-        const HIDDEN            = 0b01_0000_0000;
-        const STRIKEOUT         = 0b10_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE           = 0b0000_0000_0000_0000_0001;
+        const BOLD              = 0b0000_0000_0000_0000_0010;
+        const ITALIC            = 0b0000_0000_0000_0000_0100;
+        const BOLD_ITALIC       = 0b0000_0000_0000_0000_0110;
+        const UNDERLINE         = 0b0000_0000_0000_0000_1000;
+        const WRAPLINE          = 0b0000_0000_0000_0001_0000;
+        const WIDE_CHAR         = 0b0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER  = 0b0000_0000_0000_0100_0000;
+        const DIM               = 0b0000_0000_0000_1000_0000;
+        const DIM_BOLD          = 0b0000_0000_0000_1000_0010;
+        const HIDDEN            = 0b0000_0000_0001_0000_0000;
+        const STRIKEOUT         = 0b0000_0000_0010_0000_0000;
+        const DOUBLE_UNDERLINE  = 0b0000_0000_0100_0000_0000;
+        const UNDERCURL         = 0b0000_0000_1000_0000_0000;
+        const DOTTED_UNDERLINE  = 0b0000_0001_0000_0000_0000;
+        const DASHED_UNDERLINE  = 0b0000_0010_0000_0000_0000;
+        const PROMPT_START      = 0b0000_0100_0000_0000_0000;
+        const OUTPUT            = 0b0000_1000_0000_0000_0000;
+        const BLINK_SLOW        = 0b0001_0000_0000_0000_0000;
+        const BLINK_FAST        = 0b0010_0000_0000_0000_0000;
+        const BLINK             = 0b0011_0000_0000_0000_0000;
+        const ALL_UNDERLINES    = Self::UNDERLINE.bits | Self::DOUBLE_UNDERLINE.bits
+                                | Self::UNDERCURL.bits | Self::DOTTED_UNDERLINE.bits
+                                | Self::DASHED_UNDERLINE.bits;
     }
 }
 
-const fn default_extra() -> [char; MAX_ZEROWIDTH_CHARS] {
-    [' '; MAX_ZEROWIDTH_CHARS]
+/// Index of a hyperlink in the terminal's hyperlink table
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct HyperlinkId(pub u32);
+
+/// Data which is only required by some cells
+///
+/// This is kept behind a pointer so cells without it stay small.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq)]
+pub struct CellExtra {
+    /// Zero-width characters combined with the cell's character
+    zerowidth: Vec<char>,
+
+    /// Underline color, if it differs from the foreground
+    #[serde(default)]
+    underline_color: Option<Color>,
+
+    /// Part of a graphic displayed in the cell
+    #[serde(skip)]
+    graphic: Option<GraphicCell>,
+
+    /// Hyperlink the cell is part of
+    #[serde(skip)]
+    hyperlink: Option<HyperlinkId>,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Cell {
     pub c: char,
     pub fg: Color,
     pub bg: Color,
     pub flags: Flags,
-    #[serde(default = "default_extra")]
-    pub extra: [char; MAX_ZEROWIDTH_CHARS],
+    #[serde(default, deserialize_with = "deserialize_extra")]
+    pub extra: Option<Box<CellExtra>>,
+}
+
+/// Deserialize cell extras, accepting the fixed-size zero-width arrays of older grids
+fn deserialize_extra<'a, D>(deserializer: D) -> Result<Option<Box<CellExtra>>, D::Error>
+where
+    D: Deserializer<'a>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ExtraRepr {
+        Legacy(Vec<char>),
+        Extra(Option<Box<CellExtra>>),
+    }
+
+    Ok(match ExtraRepr::deserialize(deserializer)? {
+        ExtraRepr::Legacy(chars) => {
+            let zerowidth: Vec<char> = chars.into_iter().take_while(|c| *c != ' ').collect();
+            if zerowidth.is_empty() {
+                None
+            } else {
+                Some(Box::new(CellExtra { zerowidth, ..Default::default() }))
+            }
+        },
+        ExtraRepr::Extra(extra) => extra,
+    })
 }
 
 impl Default for Cell {
@@ -64,12 +124,12 @@ impl GridCell for Cell {
     #[inline]
     fn is_empty(&self) -> bool {
         (self.c == ' ' || self.c == '\t')
-            && self.extra[0] == ' '
+            && self.extra.is_none()
             && self.bg == Color::Named(NamedColor::Background)
             && self.fg == Color::Named(NamedColor::Foreground)
             && !self.flags.intersects(
                 Flags::INVERSE
-                    | Flags::UNDERLINE
+                    | Flags::ALL_UNDERLINES
                     | Flags::STRIKEOUT
                     | Flags::WRAPLINE
                     | Flags::WIDE_CHAR_SPACER,
@@ -87,11 +147,200 @@ impl GridCell for Cell {
     }
 
     #[inline]
-    fn fast_eq(&self, other: Self) -> bool {
+    fn fast_eq(&self, other: &Self) -> bool {
         self.bg == other.bg
     }
 }
 
+bitflags! {
+    /// Fields of an archived cell which differ from the default cell.
+    struct ArchivedFields: u8 {
+        const CHAR  = 0b0_0001;
+        const FG    = 0b0_0010;
+        const BG    = 0b0_0100;
+        const FLAGS = 0b0_1000;
+        const EXTRA = 0b1_0000;
+    }
+}
+
+impl ArchiveCell for Cell {
+    fn encode(&self, buf: &mut Vec<u8>) {
+        // Graphics and hyperlinks are not archived, just like they are not serialized
+        let extra = self
+            .extra
+            .as_ref()
+            .filter(|extra| !extra.zerowidth.is_empty() || extra.underline_color.is_some());
+
+        let default = Cell::default();
+        let mut fields = ArchivedFields::empty();
+        fields.set(ArchivedFields::CHAR, self.c != default.c);
+        fields.set(ArchivedFields::FG, self.fg != default.fg);
+        fields.set(ArchivedFields::BG, self.bg != default.bg);
+        fields.set(ArchivedFields::FLAGS, !self.flags.is_empty());
+        fields.set(ArchivedFields::EXTRA, extra.is_some());
+        buf.push(fields.bits());
+
+        if fields.contains(ArchivedFields::CHAR) {
+            encode_char(self.c, buf);
+        }
+        if fields.contains(ArchivedFields::FG) {
+            encode_color(self.fg, buf);
+        }
+        if fields.contains(ArchivedFields::BG) {
+            encode_color(self.bg, buf);
+        }
+        if fields.contains(ArchivedFields::FLAGS) {
+            buf.extend_from_slice(&self.flags.bits().to_le_bytes());
+        }
+
+        if let Some(extra) = extra {
+            buf.extend_from_slice(&(extra.zerowidth.len() as u32).to_le_bytes());
+            for c in &extra.zerowidth {
+                encode_char(*c, buf);
+            }
+
+            match extra.underline_color {
+                Some(color) => {
+                    buf.push(1);
+                    encode_color(color, buf);
+                },
+                None => buf.push(0),
+            }
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> io::Result<Self> {
+        let fields = ArchivedFields::from_bits_truncate(read_u8(buf)?);
+
+        let mut cell = Cell::default();
+        if fields.contains(ArchivedFields::CHAR) {
+            cell.c = decode_char(buf)?;
+        }
+        if fields.contains(ArchivedFields::FG) {
+            cell.fg = decode_color(buf)?;
+        }
+        if fields.contains(ArchivedFields::BG) {
+            cell.bg = decode_color(buf)?;
+        }
+        if fields.contains(ArchivedFields::FLAGS) {
+            cell.flags = Flags::from_bits_truncate(read_u32(buf)?);
+        }
+
+        if fields.contains(ArchivedFields::EXTRA) {
+            let len = read_u32(buf)?;
+            let zerowidth = (0..len).map(|_| decode_char(buf)).collect::<io::Result<_>>()?;
+            let underline_color = match read_u8(buf)? {
+                0 => None,
+                _ => Some(decode_color(buf)?),
+            };
+            cell.extra =
+                Some(Box::new(CellExtra { zerowidth, underline_color, ..Default::default() }));
+        }
+
+        Ok(cell)
+    }
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid archived cell")
+}
+
+fn read_u8(buf: &mut &[u8]) -> io::Result<u8> {
+    let mut byte = [0];
+    buf.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_u32(buf: &mut &[u8]) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    buf.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn encode_char(c: char, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+}
+
+fn decode_char(buf: &mut &[u8]) -> io::Result<char> {
+    // The length of a UTF-8 sequence is encoded in its first byte
+    let len = match buf.first() {
+        Some(0..=0x7f) => 1,
+        Some(0xc0..=0xdf) => 2,
+        Some(0xe0..=0xef) => 3,
+        _ => 4,
+    };
+
+    let mut bytes = [0; 4];
+    buf.read_exact(&mut bytes[..len])?;
+    str::from_utf8(&bytes[..len]).ok().and_then(|s| s.chars().next()).ok_or_else(invalid_data)
+}
+
+fn encode_color(color: Color, buf: &mut Vec<u8>) {
+    match color {
+        Color::Named(color) => {
+            buf.push(0);
+            buf.extend_from_slice(&(color as u16).to_le_bytes());
+        },
+        Color::Spec(rgb) => buf.extend_from_slice(&[1, rgb.r, rgb.g, rgb.b]),
+        Color::Indexed(index) => buf.extend_from_slice(&[2, index]),
+    }
+}
+
+fn decode_color(buf: &mut &[u8]) -> io::Result<Color> {
+    match read_u8(buf)? {
+        0 => {
+            let mut bytes = [0; 2];
+            buf.read_exact(&mut bytes)?;
+            named_color(u16::from_le_bytes(bytes)).map(Color::Named).ok_or_else(invalid_data)
+        },
+        1 => {
+            let mut rgb = [0; 3];
+            buf.read_exact(&mut rgb)?;
+            Ok(Color::Spec(Rgb { r: rgb[0], g: rgb[1], b: rgb[2] }))
+        },
+        2 => Ok(Color::Indexed(read_u8(buf)?)),
+        _ => Err(invalid_data()),
+    }
+}
+
+/// Named color with the discriminant `index`.
+fn named_color(index: u16) -> Option<NamedColor> {
+    let color = match index {
+        0 => NamedColor::Black,
+        1 => NamedColor::Red,
+        2 => NamedColor::Green,
+        3 => NamedColor::Yellow,
+        4 => NamedColor::Blue,
+        5 => NamedColor::Magenta,
+        6 => NamedColor::Cyan,
+        7 => NamedColor::White,
+        8 => NamedColor::BrightBlack,
+        9 => NamedColor::BrightRed,
+        10 => NamedColor::BrightGreen,
+        11 => NamedColor::BrightYellow,
+        12 => NamedColor::BrightBlue,
+        13 => NamedColor::BrightMagenta,
+        14 => NamedColor::BrightCyan,
+        15 => NamedColor::BrightWhite,
+        256 => NamedColor::Foreground,
+        257 => NamedColor::Background,
+        258 => NamedColor::Cursor,
+        259 => NamedColor::DimBlack,
+        260 => NamedColor::DimRed,
+        261 => NamedColor::DimGreen,
+        262 => NamedColor::DimYellow,
+        263 => NamedColor::DimBlue,
+        264 => NamedColor::DimMagenta,
+        265 => NamedColor::DimCyan,
+        266 => NamedColor::DimWhite,
+        267 => NamedColor::BrightForeground,
+        268 => NamedColor::DimForeground,
+        _ => return None,
+    };
+
+    Some(color)
+}
+
 /// Get the length of occupied cells in a line
 pub trait LineLength {
     /// Calculate the occupied line length
@@ -106,8 +355,8 @@ impl LineLength for grid::Row<Cell> {
             return Column(self.len());
         }
 
-        for (index, cell) in self[..].iter().rev().enumerate() {
-            if cell.c != ' ' || cell.extra[0] != ' ' {
+        for (index, cell) in self.iter().rev().enumerate() {
+            if cell.c != ' ' || cell.zerowidth().is_some() {
                 length = Column(self.len() - index);
                 break;
             }
@@ -134,7 +383,7 @@ impl Cell {
     }
 
     pub fn new(c: char, fg: Color, bg: Color) -> Cell {
-        Cell { extra: [' '; MAX_ZEROWIDTH_CHARS], c, bg, fg, flags: Flags::empty() }
+        Cell { extra: None, c, bg, fg, flags: Flags::empty() }
     }
 
     #[inline]
@@ -143,35 +392,96 @@ impl Cell {
         *self = Cell { c: template.c, bg: template.bg, ..Cell::default() };
     }
 
+    /// Zero-width characters combined with the cell's character
+    #[inline]
+    pub fn zerowidth(&self) -> Option<&[char]> {
+        self.extra.as_ref().map(|extra| &extra.zerowidth[..]).filter(|chars| !chars.is_empty())
+    }
+
+    /// Combine a zero-width character with the cell's character
+    #[inline]
+    pub fn push_zerowidth(&mut self, c: char) {
+        self.extra.get_or_insert_with(Default::default).zerowidth.push(c);
+    }
+
+    /// Replace one half of a wide character with a blank, keeping its attributes
     #[inline]
-    pub fn chars(&self) -> [char; MAX_ZEROWIDTH_CHARS + 1] {
-        unsafe {
-            let mut chars = [std::mem::MaybeUninit::uninit(); MAX_ZEROWIDTH_CHARS + 1];
-            std::ptr::write(chars[0].as_mut_ptr(), self.c);
-            std::ptr::copy_nonoverlapping(
-                self.extra.as_ptr() as *mut std::mem::MaybeUninit<char>,
-                chars.as_mut_ptr().offset(1),
-                self.extra.len(),
-            );
-            std::mem::transmute(chars)
+    pub fn clear_wide(&mut self) {
+        self.flags.remove(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER);
+        self.c = ' ';
+
+        if let Some(extra) = &mut self.extra {
+            extra.zerowidth.clear();
         }
+        self.release_empty_extra();
     }
 
+    /// Color of the underline, if it's not drawn in the foreground color
     #[inline]
-    pub fn push_extra(&mut self, c: char) {
-        for elem in self.extra.iter_mut() {
-            if elem == &' ' {
-                *elem = c;
-                break;
-            }
+    pub fn underline_color(&self) -> Option<Color> {
+        self.extra.as_ref().and_then(|extra| extra.underline_color)
+    }
+
+    #[inline]
+    pub fn set_underline_color(&mut self, color: Option<Color>) {
+        match (&mut self.extra, color) {
+            (Some(extra), _) => {
+                extra.underline_color = color;
+                self.release_empty_extra();
+            },
+            (None, Some(_)) => {
+                self.extra =
+                    Some(Box::new(CellExtra { underline_color: color, ..Default::default() }))
+            },
+            (None, None) => (),
+        }
+    }
+
+    /// Hyperlink the cell is part of
+    #[inline]
+    pub fn hyperlink(&self) -> Option<HyperlinkId> {
+        self.extra.as_ref().and_then(|extra| extra.hyperlink)
+    }
+
+    #[inline]
+    pub fn set_hyperlink(&mut self, hyperlink: Option<HyperlinkId>) {
+        match (&mut self.extra, hyperlink) {
+            (Some(extra), _) => {
+                extra.hyperlink = hyperlink;
+                self.release_empty_extra();
+            },
+            (None, Some(_)) => {
+                self.extra = Some(Box::new(CellExtra { hyperlink, ..Default::default() }))
+            },
+            (None, None) => (),
         }
     }
+
+    /// Release the allocation of the extra data once it no longer holds anything
+    #[inline]
+    fn release_empty_extra(&mut self) {
+        if self.extra.as_ref().map_or(false, |extra| **extra == CellExtra::default()) {
+            self.extra = None;
+        }
+    }
+
+    /// Part of a graphic displayed in the cell
+    #[inline]
+    pub fn graphic(&self) -> Option<&GraphicCell> {
+        self.extra.as_ref().and_then(|extra| extra.graphic.as_ref())
+    }
+
+    #[inline]
+    pub fn set_graphic(&mut self, graphic: GraphicCell) {
+        self.extra.get_or_insert_with(Default::default).graphic = Some(graphic);
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{Cell, LineLength};
 
+    use crate::ansi::Color;
     use crate::grid::Row;
     use crate::index::Column;
 
@@ -192,6 +502,44 @@ mod tests {
 
         assert_eq!(row.line_length(), Column(10));
     }
+
+    #[test]
+    fn underline_color_releases_extra() {
+        let mut cell = Cell::default();
+        cell.set_underline_color(Some(Color::Indexed(1)));
+        assert_eq!(cell.underline_color(), Some(Color::Indexed(1)));
+
+        cell.set_underline_color(None);
+        assert!(cell.extra.is_none());
+    }
+
+    #[test]
+    fn zerowidth_is_not_truncated() {
+        let mut cell = Cell::default();
+        cell.c = 'e';
+        let combining: Vec<char> = (0..10).map(|_| '\u{301}').collect();
+        for c in &combining {
+            cell.push_zerowidth(*c);
+        }
+        assert_eq!(cell.zerowidth(), Some(&combining[..]));
+
+        cell.reset(&Cell::default());
+        assert_eq!(cell.zerowidth(), None);
+        assert!(cell.extra.is_none());
+    }
+
+    #[test]
+    fn deserialize_legacy_zerowidth() {
+        let mut json = serde_json::to_value(Cell::default()).unwrap();
+
+        json["extra"] = serde_json::json!(['\u{301}', ' ']);
+        let cell: Cell = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(cell.zerowidth(), Some(&['\u{301}'][..]));
+
+        json["extra"] = serde_json::json!([' ', ' ']);
+        let cell: Cell = serde_json::from_value(json).unwrap();
+        assert!(cell.extra.is_none());
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -2,12 +2,12 @@ use std::fmt;
 use std::ops::{Index, IndexMut, Mul};
 use std::str::FromStr;
 
-use log::{error, trace};
+use log::trace;
 use serde::de::Visitor;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::ansi;
-use crate::config::{Colors, LOG_TARGET_CONFIG};
+use crate::config::{diagnostics, Colors};
 
 pub const COUNT: usize = 269;
 
@@ -41,6 +41,70 @@ impl Mul<f32> for Rgb {
     }
 }
 
+impl Rgb {
+    /// Relative luminance as defined by WCAG.
+    pub fn luminance(self) -> f64 {
+        let channel = |value: u8| {
+            let value = f64::from(value) / 255.;
+            if value <= 0.03928 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+    }
+
+    /// Contrast ratio as defined by WCAG, ranging from 1 to 21.
+    pub fn contrast(self, other: Rgb) -> f64 {
+        let (luminance, other) = (self.luminance(), other.luminance());
+        (luminance.max(other) + 0.05) / (luminance.min(other) + 0.05)
+    }
+
+    /// Mix with another color, where a `ratio` of 1 results in `other`.
+    fn mix(self, other: Rgb, ratio: f64) -> Rgb {
+        let channel =
+            |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * ratio).round() as u8;
+        Rgb {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+
+    /// Move towards white or black until the contrast to `background` reaches `minimum`.
+    ///
+    /// If even white or black cannot reach it, the one with the higher contrast is used.
+    pub fn with_contrast(self, background: Rgb, minimum: f64) -> Rgb {
+        if self.contrast(background) >= minimum {
+            return self;
+        }
+
+        let white = Rgb { r: 0xff, g: 0xff, b: 0xff };
+        let black = Rgb::default();
+        let target =
+            if white.contrast(background) > black.contrast(background) { white } else { black };
+
+        if target.contrast(background) <= minimum {
+            return target;
+        }
+
+        // Find the smallest change which is sufficient
+        let (mut low, mut high) = (0., 1.);
+        for _ in 0..8 {
+            let ratio = (low + high) / 2.;
+            if self.mix(target, ratio).contrast(background) >= minimum {
+                high = ratio;
+            } else {
+                low = ratio;
+            }
+        }
+
+        self.mix(target, high)
+    }
+}
+
 /// Deserialize an Rgb from a hex string
 ///
 /// This is *not* the deserialize impl for Rgb since we want a symmetric
@@ -92,10 +156,7 @@ impl<'de> Deserialize<'de> for Rgb {
         match value.deserialize_str(RgbVisitor) {
             Ok(rgb) => Ok(rgb),
             Err(err) => {
-                error!(
-                    target: LOG_TARGET_CONFIG,
-                    "Problem with config: {}; using color #000000", err
-                );
+                diagnostics::invalid_value(format_args!("{}; using color #000000", err));
                 Ok(Rgb::default())
             },
         }
@@ -179,8 +240,8 @@ impl List {
         self[ansi::NamedColor::Foreground] = colors.primary.foreground;
         self[ansi::NamedColor::Background] = colors.primary.background;
 
-        // Background for custom cursor colors
-        self[ansi::NamedColor::Cursor] = colors.cursor.cursor.unwrap_or_else(Rgb::default);
+        // Background for custom cursor colors, reported as the foreground when unset
+        self[ansi::NamedColor::Cursor] = colors.cursor.cursor.unwrap_or(colors.primary.foreground);
 
         // Dims
         self[ansi::NamedColor::DimForeground] =
@@ -0,0 +1,135 @@
+//! Tracking of the screen regions which changed between two frames.
+
+use std::cmp::{max, min};
+use std::mem;
+
+use crate::config::Config;
+use crate::grid::Row;
+use crate::index::{Column, Line};
+use crate::selection::SelectionRange;
+use crate::term::cell::Cell;
+use crate::term::{RenderableCursor, Term};
+
+/// Range of changed cells within a single line.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LineDamageBounds {
+    /// Damaged line in the viewport.
+    pub line: Line,
+
+    /// Leftmost damaged column.
+    pub left: Column,
+
+    /// Rightmost damaged column.
+    pub right: Column,
+}
+
+impl LineDamageBounds {
+    fn expand(&mut self, left: Column, right: Column) {
+        self.left = min(self.left, left);
+        self.right = max(self.right, right);
+    }
+}
+
+/// Regions of the viewport which changed since the last frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TermDamage {
+    /// The entire viewport needs to be redrawn.
+    Full,
+
+    /// Only the listed parts of the viewport changed, ordered from top to bottom.
+    Partial(Vec<LineDamageBounds>),
+}
+
+/// Viewport state at the time of the last frame.
+#[derive(Debug, Default)]
+pub(super) struct TermDamageState {
+    /// Visible rows, from top to bottom.
+    rows: Vec<Row<Cell>>,
+
+    cursor: Option<RenderableCursor>,
+    selection: Option<SelectionRange>,
+
+    /// Changes which cannot be detected by comparing the viewport, like palette updates.
+    full: bool,
+}
+
+impl<T> Term<T> {
+    /// Collect all changes to the viewport since the last call.
+    ///
+    /// Lines are compared against the state of the previous frame, so changes which are reverted
+    /// before the next frame do not cause any damage.
+    pub fn damage<C>(&mut self, config: &Config<C>) -> TermDamage {
+        let cursor = self.renderable_cursor(config);
+        let selection = self.grid.selection.as_ref().and_then(|s| s.to_range(self));
+
+        let num_lines = self.grid.num_lines().0;
+        let num_cols = self.grid.num_cols();
+        let display_offset = self.grid.display_offset();
+
+        let grid = &self.grid;
+        let state = &mut self.damage;
+        let full = mem::replace(&mut state.full, false)
+            || state.selection != selection
+            || state.rows.len() != num_lines
+            || state.rows.first().map_or(true, |row| row.len() != num_cols.0);
+
+        let last_cursor = mem::replace(&mut state.cursor, Some(cursor));
+        state.selection = selection;
+
+        if full {
+            state.rows = (0..num_lines)
+                .map(|line| grid[num_lines - 1 - line + display_offset].clone())
+                .collect();
+            return TermDamage::Full;
+        }
+
+        let mut damage: Vec<LineDamageBounds> = Vec::new();
+        for (line, last_row) in state.rows.iter_mut().enumerate() {
+            let row = &grid[num_lines - 1 - line + display_offset];
+            if row == last_row {
+                continue;
+            }
+
+            let left = (0..num_cols.0).find(|&col| row[Column(col)] != last_row[Column(col)]);
+            let right = (0..num_cols.0).rfind(|&col| row[Column(col)] != last_row[Column(col)]);
+            if let (Some(left), Some(right)) = (left, right) {
+                damage.push(LineDamageBounds {
+                    line: Line(line),
+                    left: Column(left),
+                    right: Column(right),
+                });
+            }
+
+            last_row.clone_from(row);
+        }
+
+        // Redraw the cursor at both its previous and its current position
+        if last_cursor != Some(cursor) {
+            for point in last_cursor.iter().map(|cursor| cursor.point).chain(Some(cursor.point)) {
+                // Include the neighbouring cells, to cover both halves of wide characters
+                let left = point.col.0.saturating_sub(1);
+                let right = min(point.col + 1, num_cols - 1);
+
+                match damage.iter_mut().find(|bounds| bounds.line == point.line) {
+                    Some(bounds) => bounds.expand(Column(left), right),
+                    None if point.line.0 < num_lines => damage.push(LineDamageBounds {
+                        line: point.line,
+                        left: Column(left),
+                        right,
+                    }),
+                    None => (),
+                }
+            }
+
+            damage.sort_by_key(|bounds| bounds.line);
+        }
+
+        TermDamage::Partial(damage)
+    }
+
+    /// Force the entire viewport to be redrawn with the next frame.
+    #[inline]
+    pub fn mark_fully_damaged(&mut self) {
+        self.damage.full = true;
+    }
+}
@@ -0,0 +1,199 @@
+//! Export of the terminal history for log capture and bug reports.
+
+use std::fmt::Write;
+
+use crate::ansi::{Color, NamedColor};
+use crate::grid::Row;
+use crate::index::Column;
+use crate::term::cell::{Cell, Flags, LineLength};
+use crate::term::color::Rgb;
+use crate::term::Term;
+use crate::util;
+
+/// Visual style of a consecutive run of cells.
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Style {
+    fg: Rgb,
+    bg: Option<Rgb>,
+    flags: Flags,
+}
+
+impl Style {
+    fn css(self) -> String {
+        let mut css = format!("color: {};", hex(self.fg));
+
+        if let Some(bg) = self.bg {
+            let _ = write!(css, " background-color: {};", hex(bg));
+        }
+        if self.flags.contains(Flags::BOLD) {
+            css.push_str(" font-weight: bold;");
+        }
+        if self.flags.contains(Flags::ITALIC) {
+            css.push_str(" font-style: italic;");
+        }
+        if self.flags.contains(Flags::DIM) {
+            css.push_str(" opacity: 0.66;");
+        }
+        if self.flags.contains(Flags::HIDDEN) {
+            css.push_str(" visibility: hidden;");
+        }
+
+        match (self.flags.intersects(Flags::ALL_UNDERLINES), self.flags.contains(Flags::STRIKEOUT))
+        {
+            (true, true) => css.push_str(" text-decoration: underline line-through;"),
+            (true, false) => css.push_str(" text-decoration: underline;"),
+            (false, true) => css.push_str(" text-decoration: line-through;"),
+            (false, false) => (),
+        }
+
+        css
+    }
+}
+
+impl<T> Term<T> {
+    /// Convert the history and the visible area to a String.
+    ///
+    /// With `timestamps`, every line is prefixed by the time at which it was completed.
+    pub fn history_to_string(&self, timestamps: bool) -> String {
+        let mut res = String::new();
+
+        let last_col = self.grid.num_cols() - 1;
+        for line in (0..self.grid.len()).rev() {
+            if timestamps && self.is_line_start(line) {
+                res.push_str(&self.timestamp_prefix(line));
+            }
+
+            res += &self.line_to_string(line, Column(0)..last_col, line == 0);
+        }
+
+        res
+    }
+
+    /// Convert the history and the visible area to an HTML document.
+    ///
+    /// Colors and text attributes are reconstructed from the cells, using the current palette.
+    pub fn history_to_html(&self, timestamps: bool) -> String {
+        let fg = self.colors[NamedColor::Foreground];
+        let bg = self.colors[NamedColor::Background];
+
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        let title = self.title.as_ref().unwrap_or(&self.default_title);
+        let _ = writeln!(html, "<title>{}</title>", escape(title));
+        html.push_str("</head>\n");
+        let _ =
+            writeln!(html, "<body style=\"color: {}; background-color: {};\">", hex(fg), hex(bg));
+        html.push_str("<pre>");
+
+        for line in (0..self.grid.len()).rev() {
+            if timestamps && self.is_line_start(line) {
+                html.push_str(&self.timestamp_prefix(line));
+            }
+
+            self.line_to_html(&self.grid[line], &mut html);
+        }
+
+        html.push_str("</pre>\n</body>\n</html>\n");
+
+        html
+    }
+
+    /// Check if a line in the buffer is not the continuation of a wrapped line.
+    fn is_line_start(&self, line: usize) -> bool {
+        line + 1 == self.grid.len() || !self.is_wrapped(line + 1)
+    }
+
+    fn is_wrapped(&self, line: usize) -> bool {
+        self.grid[line][self.grid.num_cols() - 1].flags.contains(Flags::WRAPLINE)
+    }
+
+    /// Completion time of the line starting at `line`, padded to a fixed width.
+    fn timestamp_prefix(&self, mut line: usize) -> String {
+        // Wrapped lines are only complete once their last row is
+        while line > 0 && self.is_wrapped(line) {
+            line -= 1;
+        }
+
+        match self.line_timestamp(line) {
+            Some(time) => format!("[{}] ", util::format_time_of_day(time)),
+            None => " ".repeat(11),
+        }
+    }
+
+    fn line_to_html(&self, row: &Row<Cell>, html: &mut String) {
+        let mut style = None;
+        let mut text = String::new();
+
+        let line_length = row.line_length();
+        for cell in row.iter().take(line_length.0) {
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            let cell_style = self.cell_style(cell);
+            if style != Some(cell_style) {
+                push_span(html, style, &text);
+                text.clear();
+                style = Some(cell_style);
+            }
+
+            text.push(cell.c);
+            for c in cell.zerowidth().into_iter().flatten() {
+                text.push(*c);
+            }
+        }
+
+        push_span(html, style, &text);
+
+        let wrapped = line_length.0 > 0 && row[line_length - 1].flags.contains(Flags::WRAPLINE);
+        if !wrapped {
+            html.push('\n');
+        }
+    }
+
+    fn cell_style(&self, cell: &Cell) -> Style {
+        let mut fg = self.color_rgb(cell.fg);
+        let mut bg = match cell.bg {
+            Color::Named(NamedColor::Background) => None,
+            color => Some(self.color_rgb(color)),
+        };
+
+        if cell.flags.contains(Flags::INVERSE) {
+            let inverse_fg = bg.unwrap_or(self.colors[NamedColor::Background]);
+            bg = Some(fg);
+            fg = inverse_fg;
+        }
+
+        // Only keep attributes which are carried over to the HTML export
+        let flags = cell.flags
+            & (Flags::BOLD
+                | Flags::ITALIC
+                | Flags::DIM
+                | Flags::ALL_UNDERLINES
+                | Flags::STRIKEOUT
+                | Flags::HIDDEN);
+
+        Style { fg, bg, flags }
+    }
+
+    fn color_rgb(&self, color: Color) -> Rgb {
+        match color {
+            Color::Spec(rgb) => rgb,
+            Color::Named(ansi) => self.colors[ansi],
+            Color::Indexed(idx) => self.colors[idx],
+        }
+    }
+}
+
+fn push_span(html: &mut String, style: Option<Style>, text: &str) {
+    if let Some(style) = style.filter(|_| !text.is_empty()) {
+        let _ = write!(html, "<span style=\"{}\">{}</span>", style.css(), escape(text));
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
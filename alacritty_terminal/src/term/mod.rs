@@ -14,8 +14,12 @@
 //
 //! Exports the `Term` type which is a high-level API for the Grid
 use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::num::NonZeroU32;
 use std::ops::{Index, IndexMut, Range};
-use std::time::{Duration, Instant};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use std::{io, mem, ptr, str};
 
 use log::{debug, trace};
@@ -23,22 +27,35 @@ use serde::{Deserialize, Serialize};
 use unicode_width::UnicodeWidthChar;
 
 use crate::ansi::{
-    self, Attr, CharsetIndex, Color, CursorStyle, Handler, NamedColor, StandardCharset, TermInfo,
+    self, Attr, AttributeChangeExtent, CharsetIndex, Color, ControlStringKind, CursorStyle,
+    Handler, Hyperlink, KeyboardModes, KeyboardModesApplyBehavior, ModeState, ModifyOtherKeys,
+    NamedColor, Notification, Progress, PromptMark, Rect, StandardCharset, TermInfo,
 };
 use crate::clipboard::{Clipboard, ClipboardType};
-use crate::config::{Config, VisualBellAnimation};
+use crate::config::{
+    ClipboardReadPermission, Colors, Config, CursorBlinking, LineSelection, TerminalIdentity,
+    TextBlinkStyle, VisualBellAnimation,
+};
 use crate::event::{Event, EventListener};
+use crate::graphics::{Graphic, GraphicCell, Graphics, UpdateQueues};
 use crate::grid::{
     BidirectionalIterator, DisplayIter, Grid, GridCell, IndexRegion, Indexed, Scroll,
 };
 use crate::index::{self, Column, IndexRange, Line, Point, Side};
+use crate::message_bar::Message;
 use crate::selection::{Selection, SelectionRange};
-use crate::term::cell::{Cell, Flags, LineLength};
+use crate::term::cell::{Cell, Flags, HyperlinkId, LineLength};
 use crate::term::color::Rgb;
-use crate::vi_mode::{ViModeCursor, ViMotion};
+use crate::term::damage::TermDamageState;
+use crate::util;
+use crate::vi_mode::{InlineSearch, ViModeCursor, ViMotion};
 
 pub mod cell;
 pub mod color;
+pub mod damage;
+mod export;
+pub mod snapshot;
+mod wide;
 
 /// Used to match equal brackets, when performing a bracket-pair selection.
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
@@ -46,9 +63,32 @@ const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('
 /// Max size of the window title stack.
 const TITLE_STACK_MAX_DEPTH: usize = 4096;
 
+/// Max size of the keyboard protocol flags stack.
+const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = TITLE_STACK_MAX_DEPTH;
+
+/// Keyboard protocol flags which are implemented.
+///
+/// Associated text is not reported, since the text of a key is only known after its escape
+/// sequence has been sent.
+const SUPPORTED_KEYBOARD_MODES: KeyboardModes = KeyboardModes::from_bits_truncate(
+    KeyboardModes::DISAMBIGUATE_ESC_CODES.bits()
+        | KeyboardModes::REPORT_EVENT_TYPES.bits()
+        | KeyboardModes::REPORT_ALTERNATE_KEYS.bits()
+        | KeyboardModes::REPORT_ALL_KEYS_AS_ESC.bits(),
+);
+
 /// Default tab interval, corresponding to terminfo `it` value.
 const INITIAL_TABSTOPS: usize = 8;
 
+/// Minimum number of hyperlinks before unused ones are removed.
+const HYPERLINK_GC_THRESHOLD: usize = 4096;
+
+/// Max width and height in pixels of the text area requested through XTWINOPS.
+const MAX_TEXT_AREA_SIZE: usize = 0x4000;
+
+/// Message target of the clipboard read confirmation prompt.
+pub const CLIPBOARD_READ_TARGET: &str = "clipboard_read";
+
 /// A type that can expand a given point to a region
 ///
 /// Usually this is implemented for some 2-D array type since
@@ -213,6 +253,9 @@ pub struct RenderableCellsIter<'a, C> {
     config: &'a Config<C>,
     colors: &'a color::List,
     selection: Option<SelectionRange<Line>>,
+    text_blink: &'a TextBlink,
+    time: Instant,
+    contrast: ContrastCache,
 }
 
 impl<'a, C> RenderableCellsIter<'a, C> {
@@ -261,7 +304,30 @@ impl<'a, C> RenderableCellsIter<'a, C> {
             selection: selection_range,
             config,
             colors: &term.colors,
+            text_blink: &term.text_blink,
+            time: Instant::now(),
+            contrast: ContrastCache::default(),
+        }
+    }
+
+    /// Create a renderable cell, applying the current state of blinking text.
+    fn renderable_cell(&mut self, cell: Indexed<Cell>, selected: bool) -> RenderableCell {
+        let mut renderable_cell =
+            RenderableCell::new(self.config, self.colors, &mut self.contrast, cell, selected);
+
+        let flags = renderable_cell.flags;
+        if flags.intersects(Flags::BLINK) {
+            match self.text_blink.style() {
+                TextBlinkStyle::Blink if !self.text_blink.visible_at_instant(flags, self.time) => {
+                    renderable_cell.flags.insert(Flags::HIDDEN)
+                },
+                TextBlinkStyle::Bold => renderable_cell.flags.insert(Flags::BOLD),
+                TextBlinkStyle::Italic => renderable_cell.flags.insert(Flags::ITALIC),
+                TextBlinkStyle::Blink => (),
+            }
         }
+
+        renderable_cell
     }
 
     /// Check selection state of a cell.
@@ -289,7 +355,7 @@ impl<'a, C> RenderableCellsIter<'a, C> {
         }
 
         let num_cols = self.grid.num_cols().0;
-        let cell = self.grid[&point];
+        let cell = &self.grid[&point];
 
         // Check if wide char's spacers are selected
         if cell.flags.contains(Flags::WIDE_CHAR) {
@@ -320,13 +386,35 @@ impl<'a, C> RenderableCellsIter<'a, C> {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+/// Last foreground color adjusted to the minimum contrast.
+///
+/// Neighbouring cells usually share their colors, so most cells can reuse the previous result.
+#[derive(Debug, Default)]
+struct ContrastCache {
+    last: Option<(Rgb, Rgb, Rgb)>,
+}
+
+impl ContrastCache {
+    fn with_contrast(&mut self, fg: Rgb, bg: Rgb, minimum: f64) -> Rgb {
+        match self.last {
+            Some((last_fg, last_bg, adjusted)) if last_fg == fg && last_bg == bg => adjusted,
+            _ => {
+                let adjusted = fg.with_contrast(bg, minimum);
+                self.last = Some((fg, bg, adjusted));
+                adjusted
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub enum RenderableCellContent {
-    Chars([char; cell::MAX_ZEROWIDTH_CHARS + 1]),
+    /// Cell character followed by its zero-width characters
+    Chars((char, Option<Vec<char>>)),
     Cursor(CursorKey),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct RenderableCell {
     /// A _Display_ line (not necessarily an _Active_ line)
     pub line: Line,
@@ -335,13 +423,17 @@ pub struct RenderableCell {
     pub fg: Rgb,
     pub bg: Rgb,
     pub bg_alpha: f32,
+    pub underline: Rgb,
     pub flags: Flags,
+    pub graphic: Option<GraphicCell>,
+    pub hyperlink: Option<HyperlinkId>,
 }
 
 impl RenderableCell {
     fn new<C>(
         config: &Config<C>,
         colors: &color::List,
+        contrast: &mut ContrastCache,
         cell: Indexed<Cell>,
         selected: bool,
     ) -> Self {
@@ -373,14 +465,27 @@ impl RenderableCell {
             fg_rgb = col;
         }
 
+        // Keep text readable on its background, unless it is meant to be invisible
+        let minimum_contrast = config.colors.minimum_contrast();
+        if minimum_contrast > 1. && !cell.flags.contains(Flags::HIDDEN) {
+            fg_rgb = contrast.with_contrast(fg_rgb, bg_rgb, minimum_contrast);
+        }
+
+        // Underlines without an explicit color follow the foreground
+        let underline_rgb =
+            cell.underline_color().map_or(fg_rgb, |color| Self::compute_bg_rgb(colors, color));
+
         RenderableCell {
             line: cell.line,
             column: cell.column,
-            inner: RenderableCellContent::Chars(cell.chars()),
+            inner: RenderableCellContent::Chars((cell.c, cell.zerowidth().map(|z| z.to_vec()))),
             fg: fg_rgb,
+            underline: underline_rgb,
             bg: bg_rgb,
             bg_alpha,
             flags: cell.flags,
+            graphic: cell.graphic().cloned(),
+            hyperlink: cell.hyperlink(),
         }
     }
 
@@ -457,8 +562,8 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
 
                 // Handle cell below cursor
                 if self.cursor.rendered {
-                    let mut cell =
-                        RenderableCell::new(self.config, self.colors, self.inner.next()?, selected);
+                    let cell = self.inner.next()?;
+                    let mut cell = self.renderable_cell(cell, selected);
 
                     if self.cursor.key.style == CursorStyle::Block {
                         mem::swap(&mut cell.bg, &mut cell.fg);
@@ -475,13 +580,12 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
 
                     let buffer_point = self.grid.visible_to_buffer(self.cursor.point);
                     let cell = Indexed {
-                        inner: self.grid[buffer_point.line][buffer_point.col],
+                        inner: self.grid[buffer_point.line][buffer_point.col].clone(),
                         column: self.cursor.point.col,
                         line: self.cursor.point.line,
                     };
 
-                    let mut renderable_cell =
-                        RenderableCell::new(self.config, self.colors, cell, selected);
+                    let mut renderable_cell = self.renderable_cell(cell, selected);
 
                     renderable_cell.inner = RenderableCellContent::Cursor(self.cursor.key);
 
@@ -497,7 +601,7 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
                 let selected = self.is_selected(Point::new(cell.line, cell.column));
 
                 if !cell.is_empty() || selected {
-                    return Some(RenderableCell::new(self.config, self.colors, cell, selected));
+                    return Some(self.renderable_cell(cell, selected));
                 }
             }
         }
@@ -507,28 +611,41 @@ impl<'a, C> Iterator for RenderableCellsIter<'a, C> {
 pub mod mode {
     use bitflags::bitflags;
 
+    use crate::ansi::KeyboardModes;
+
     bitflags! {
         pub struct TermMode: u32 {
-            const NONE                = 0;
-            const SHOW_CURSOR         = 0b0000_0000_0000_0000_0001;
-            const APP_CURSOR          = 0b0000_0000_0000_0000_0010;
-            const APP_KEYPAD          = 0b0000_0000_0000_0000_0100;
-            const MOUSE_REPORT_CLICK  = 0b0000_0000_0000_0000_1000;
-            const BRACKETED_PASTE     = 0b0000_0000_0000_0001_0000;
-            const SGR_MOUSE           = 0b0000_0000_0000_0010_0000;
-            const MOUSE_MOTION        = 0b0000_0000_0000_0100_0000;
-            const LINE_WRAP           = 0b0000_0000_0000_1000_0000;
-            const LINE_FEED_NEW_LINE  = 0b0000_0000_0001_0000_0000;
-            const ORIGIN              = 0b0000_0000_0010_0000_0000;
-            const INSERT              = 0b0000_0000_0100_0000_0000;
-            const FOCUS_IN_OUT        = 0b0000_0000_1000_0000_0000;
-            const ALT_SCREEN          = 0b0000_0001_0000_0000_0000;
-            const MOUSE_DRAG          = 0b0000_0010_0000_0000_0000;
-            const MOUSE_MODE          = 0b0000_0010_0000_0100_1000;
-            const UTF8_MOUSE          = 0b0000_0100_0000_0000_0000;
-            const ALTERNATE_SCROLL    = 0b0000_1000_0000_0000_0000;
-            const VI                  = 0b0001_0000_0000_0000_0000;
-            const ANY                 = std::u32::MAX;
+            const NONE                    = 0;
+            const SHOW_CURSOR             = 0b0000_0000_0000_0000_0000_0000_0001;
+            const APP_CURSOR              = 0b0000_0000_0000_0000_0000_0000_0010;
+            const APP_KEYPAD              = 0b0000_0000_0000_0000_0000_0000_0100;
+            const MOUSE_REPORT_CLICK      = 0b0000_0000_0000_0000_0000_0000_1000;
+            const BRACKETED_PASTE         = 0b0000_0000_0000_0000_0000_0001_0000;
+            const SGR_MOUSE               = 0b0000_0000_0000_0000_0000_0010_0000;
+            const MOUSE_MOTION            = 0b0000_0000_0000_0000_0000_0100_0000;
+            const LINE_WRAP               = 0b0000_0000_0000_0000_0000_1000_0000;
+            const LINE_FEED_NEW_LINE      = 0b0000_0000_0000_0000_0001_0000_0000;
+            const ORIGIN                  = 0b0000_0000_0000_0000_0010_0000_0000;
+            const INSERT                  = 0b0000_0000_0000_0000_0100_0000_0000;
+            const FOCUS_IN_OUT            = 0b0000_0000_0000_0000_1000_0000_0000;
+            const ALT_SCREEN              = 0b0000_0000_0000_0001_0000_0000_0000;
+            const MOUSE_DRAG              = 0b0000_0000_0000_0010_0000_0000_0000;
+            const MOUSE_MODE              = 0b0000_0000_0000_0010_0000_0100_1000;
+            const UTF8_MOUSE              = 0b0000_0000_0000_0100_0000_0000_0000;
+            const ALTERNATE_SCROLL        = 0b0000_0000_0000_1000_0000_0000_0000;
+            const VI                      = 0b0000_0000_0001_0000_0000_0000_0000;
+            const DISAMBIGUATE_ESC_CODES  = 0b0000_0000_0010_0000_0000_0000_0000;
+            const REPORT_EVENT_TYPES      = 0b0000_0000_0100_0000_0000_0000_0000;
+            const REPORT_ALTERNATE_KEYS   = 0b0000_0000_1000_0000_0000_0000_0000;
+            const REPORT_ALL_KEYS_AS_ESC  = 0b0000_0001_0000_0000_0000_0000_0000;
+            const REPORT_ASSOCIATED_TEXT  = 0b0000_0010_0000_0000_0000_0000_0000;
+            const KITTY_KEYBOARD_PROTOCOL = 0b0000_0011_1110_0000_0000_0000_0000;
+            const MODIFY_OTHER_KEYS       = 0b0000_0100_0000_0000_0000_0000_0000;
+            const MODIFY_ALL_KEYS         = 0b0000_1000_0000_0000_0000_0000_0000;
+            const LEFT_RIGHT_MARGIN       = 0b0001_0000_0000_0000_0000_0000_0000;
+            const SGR_PIXELS_MOUSE        = 0b0010_0000_0000_0000_0000_0000_0000;
+            const COLOR_PALETTE_UPDATES   = 0b0100_0000_0000_0000_0000_0000_0000;
+            const ANY                     = std::u32::MAX;
         }
     }
 
@@ -537,6 +654,13 @@ pub mod mode {
             TermMode::SHOW_CURSOR | TermMode::LINE_WRAP | TermMode::ALTERNATE_SCROLL
         }
     }
+
+    impl From<KeyboardModes> for TermMode {
+        fn from(modes: KeyboardModes) -> TermMode {
+            // Keyboard protocol flags are stored in the same order, starting at bit 17
+            TermMode::from_bits_truncate(u32::from(modes.bits()) << 17)
+        }
+    }
 }
 
 pub use crate::term::mode::TermMode;
@@ -554,17 +678,22 @@ impl CharsetMapping for StandardCharset {
     fn map(&self, c: char) -> char {
         match *self {
             StandardCharset::Ascii => c,
+            StandardCharset::UnitedKingdom => match c {
+                '#' => '£',
+                _ => c,
+            },
             StandardCharset::SpecialCharacterAndLineDrawing => match c {
+                '_' => ' ',
                 '`' => '◆',
                 'a' => '▒',
-                'b' => '\t',
-                'c' => '\u{000c}',
-                'd' => '\r',
-                'e' => '\n',
+                'b' => '\u{2409}',
+                'c' => '\u{240c}',
+                'd' => '\u{240d}',
+                'e' => '\u{240a}',
                 'f' => '°',
                 'g' => '±',
                 'h' => '\u{2424}',
-                'i' => '\u{000b}',
+                'i' => '\u{240b}',
                 'j' => '┘',
                 'k' => '┐',
                 'l' => '┌',
@@ -609,7 +738,7 @@ impl IndexMut<CharsetIndex> for Charsets {
     }
 }
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Clone)]
 pub struct Cursor {
     /// The location of this cursor
     pub point: Point,
@@ -742,6 +871,175 @@ impl VisualBell {
     }
 }
 
+pub struct TextBlink {
+    /// Presentation of blinking text
+    style: TextBlinkStyle,
+
+    /// Time slowly blinking text stays visible or hidden
+    interval: Duration,
+
+    /// Time rapidly blinking text stays visible or hidden
+    rapid_interval: Duration,
+
+    /// Start of the first visible phase
+    start_time: Instant,
+}
+
+impl TextBlink {
+    pub fn new<C>(config: &Config<C>) -> TextBlink {
+        let text_blink_config = &config.text_blink;
+        TextBlink {
+            style: text_blink_config.style,
+            interval: text_blink_config.interval(),
+            rapid_interval: text_blink_config.rapid_interval(),
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Presentation of blinking text.
+    #[inline]
+    pub fn style(&self) -> TextBlinkStyle {
+        self.style
+    }
+
+    /// Check whether text with the given blink flags is shown at a particular instant.
+    pub fn visible_at_instant(&self, flags: Flags, instant: Instant) -> bool {
+        match self.blink_interval(flags) {
+            Some(interval) => {
+                let elapsed = instant.saturating_duration_since(self.start_time);
+                (elapsed.as_nanos() / interval.as_nanos()) % 2 == 0
+            },
+            None => true,
+        }
+    }
+
+    /// Get the next time the visibility of text with any of the given blink flags changes.
+    pub fn next_toggle(&self, flags: Flags) -> Option<Instant> {
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.start_time).as_nanos();
+
+        [Flags::BLINK_SLOW, Flags::BLINK_FAST]
+            .iter()
+            .filter(|blink| flags.contains(**blink))
+            .filter_map(|blink| self.blink_interval(*blink))
+            .map(|interval| {
+                let remaining = interval.as_nanos() - elapsed % interval.as_nanos();
+                now + Duration::from_nanos(remaining as u64)
+            })
+            .min()
+    }
+
+    /// Interval at which text with the given flags blinks, if it blinks at all.
+    fn blink_interval(&self, flags: Flags) -> Option<Duration> {
+        let interval = if self.style != TextBlinkStyle::Blink {
+            return None;
+        } else if flags.contains(Flags::BLINK_FAST) {
+            self.rapid_interval
+        } else if flags.contains(Flags::BLINK_SLOW) {
+            self.interval
+        } else {
+            return None;
+        };
+
+        // An interval of zero disables blinking
+        if interval == Duration::from_secs(0) {
+            None
+        } else {
+            Some(interval)
+        }
+    }
+
+    pub fn update_config<C>(&mut self, config: &Config<C>) {
+        let text_blink_config = &config.text_blink;
+        self.style = text_blink_config.style;
+        self.interval = text_blink_config.interval();
+        self.rapid_interval = text_blink_config.rapid_interval();
+    }
+}
+
+pub struct CursorBlink {
+    /// Configured blinking behavior
+    blinking: CursorBlinking,
+
+    /// Blinking requested by the application
+    requested: Option<bool>,
+
+    /// Time the cursor stays visible or hidden
+    interval: Duration,
+
+    /// Time without input after which the cursor stops blinking
+    timeout: Option<Duration>,
+
+    /// Start of the first visible phase, reset by user input
+    start_time: Instant,
+}
+
+impl CursorBlink {
+    pub fn new<C>(config: &Config<C>) -> CursorBlink {
+        CursorBlink {
+            blinking: config.cursor.blinking,
+            requested: None,
+            interval: config.cursor.blink_interval(),
+            timeout: config.cursor.blink_timeout(),
+            start_time: Instant::now(),
+        }
+    }
+
+    /// Restart blinking with a visible cursor.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.start_time = Instant::now();
+    }
+
+    /// Check whether the cursor blinks, ignoring the timeout.
+    pub fn enabled(&self) -> bool {
+        let enabled = match self.blinking {
+            CursorBlinking::Never => false,
+            CursorBlinking::Off => self.requested.unwrap_or(false),
+            CursorBlinking::On => self.requested.unwrap_or(true),
+            CursorBlinking::Always => true,
+        };
+
+        // An interval of zero disables blinking
+        enabled && self.interval != Duration::from_secs(0)
+    }
+
+    /// Check whether the blinking cursor is shown at a particular instant.
+    pub fn visible_at_instant(&self, instant: Instant) -> bool {
+        let elapsed = instant.saturating_duration_since(self.start_time);
+        if !self.enabled() || self.timeout.map_or(false, |timeout| elapsed >= timeout) {
+            return true;
+        }
+
+        (elapsed.as_nanos() / self.interval.as_nanos()) % 2 == 0
+    }
+
+    /// Get the next time the visibility of the blinking cursor changes.
+    pub fn next_toggle(&self) -> Option<Instant> {
+        if !self.enabled() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(self.start_time);
+        let remaining = self.interval.as_nanos() - elapsed.as_nanos() % self.interval.as_nanos();
+        let toggle = now + Duration::from_nanos(remaining as u64);
+
+        // Show the cursor once the timeout is reached, then stop blinking
+        match self.timeout.map(|timeout| self.start_time + timeout) {
+            Some(end) if end <= now => None,
+            Some(end) => Some(toggle.min(end)),
+            None => Some(toggle),
+        }
+    }
+
+    pub fn update_config<C>(&mut self, config: &Config<C>) {
+        self.blinking = config.cursor.blinking;
+        self.interval = config.cursor.blink_interval();
+        self.timeout = config.cursor.blink_timeout();
+    }
+}
+
 /// Terminal size info.
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 pub struct SizeInfo {
@@ -829,6 +1127,9 @@ pub struct Term<T> {
     /// Index into `charsets`, pointing to what ASCII is currently being mapped to.
     active_charset: CharsetIndex,
 
+    /// Charset used for the next character only, set by SS2 and SS3.
+    single_shift: Option<CharsetIndex>,
+
     /// Tabstops.
     tabs: TabStops,
 
@@ -840,10 +1141,19 @@ pub struct Term<T> {
     /// Range going from top to bottom of the terminal, indexed from the top of the viewport.
     scroll_region: Range<Line>,
 
+    /// Left and right margins.
+    ///
+    /// Range going from the left to the right margin, covering all columns unless DECLRMM is set.
+    margins: Range<Column>,
+
     pub dirty: bool,
 
     pub visual_bell: VisualBell,
 
+    pub text_blink: TextBlink,
+
+    pub cursor_blink: CursorBlink,
+
     /// Saved cursor from main grid.
     cursor_save: Cursor,
 
@@ -852,6 +1162,26 @@ pub struct Term<T> {
 
     semantic_escape_chars: String,
 
+    line_selection: LineSelection,
+
+    /// Responses identifying the terminal.
+    identity: TerminalIdentity,
+
+    /// Record output of the alternate screen in the primary history.
+    alt_screen_history: bool,
+
+    /// Ignore requests to clear the history.
+    preserve_history: bool,
+
+    /// Reference point for the completion time of every line.
+    timestamp_epoch: SystemTime,
+
+    /// Show the time at which each line was completed.
+    pub show_line_timestamps: bool,
+
+    /// Viewport state of the last frame, used to find the regions which need to be redrawn.
+    damage: TermDamageState,
+
     /// Colors used for rendering.
     colors: color::List,
 
@@ -888,6 +1218,49 @@ pub struct Term<T> {
     /// Stack of saved window titles. When a title is popped from this stack, the `title` for the
     /// term is set, and the Glutin window's title attribute is changed through the event listener.
     title_stack: Vec<Option<String>>,
+
+    /// Graphics added to the grid.
+    graphics: Graphics,
+
+    /// Targets of the hyperlinks in the grid.
+    hyperlinks: HashMap<HyperlinkId, Hyperlink>,
+
+    /// Lookup of existing entries in `hyperlinks`.
+    hyperlink_ids: HashMap<Hyperlink, HyperlinkId>,
+
+    /// ID of the next new hyperlink.
+    ///
+    /// IDs are never reused, so hyperlinks which have been removed can't resolve to a new target.
+    next_hyperlink_id: u32,
+
+    /// Number of hyperlinks at which the ones no longer used by any cell are removed.
+    hyperlink_gc_threshold: usize,
+
+    /// Permission for applications to read the clipboard.
+    clipboard_read: ClipboardReadPermission,
+
+    /// Clipboard read request waiting for confirmation, with its clipboard and terminator.
+    pending_clipboard_read: Option<(u8, String)>,
+
+    /// Applications are allowed to resize the window.
+    allow_resize: bool,
+
+    /// Area affected by DECCARA.
+    attribute_change_extent: AttributeChangeExtent,
+
+    /// Progress of a long running task reported by the application.
+    progress: Progress,
+
+    /// Working directory of the shell reported through `OSC 7`.
+    working_directory: Option<PathBuf>,
+
+    /// Stack of keyboard protocol flags, the last entry is active.
+    keyboard_mode_stack: Vec<KeyboardModes>,
+
+    /// Keyboard protocol flags of the inactive screen.
+    ///
+    /// The primary and alternate screen each have their own stack.
+    inactive_keyboard_mode_stack: Vec<KeyboardModes>,
 }
 
 impl<T> Term<T> {
@@ -919,23 +1292,28 @@ impl<T> Term<T> {
         let num_lines = size.lines();
 
         let history_size = config.scrolling.history() as usize;
-        let grid = Grid::new(num_lines, num_cols, history_size, Cell::default());
+        let mut grid = Grid::new(num_lines, num_cols, history_size, Cell::default());
+        grid.set_history_archive(config.scrolling.unlimited_history());
         let alt = Grid::new(num_lines, num_cols, 0 /* scroll history */, Cell::default());
 
         let tabs = TabStops::new(grid.num_cols());
 
         let scroll_region = Line(0)..grid.num_lines();
+        let margins = Column(0)..grid.num_cols();
 
         let colors = color::List::from(&config.colors);
 
         Term {
             dirty: false,
             visual_bell: VisualBell::new(config),
+            text_blink: TextBlink::new(config),
+            cursor_blink: CursorBlink::new(config),
             input_needs_wrap: false,
             grid,
             alt_grid: alt,
             alt: false,
             active_charset: Default::default(),
+            single_shift: None,
             cursor: Default::default(),
             vi_mode_cursor: Default::default(),
             cursor_save: Default::default(),
@@ -943,10 +1321,18 @@ impl<T> Term<T> {
             tabs,
             mode: Default::default(),
             scroll_region,
+            margins,
             colors,
             color_modified: [false; color::COUNT],
             original_colors: colors,
             semantic_escape_chars: config.selection.semantic_escape_chars().to_owned(),
+            line_selection: config.selection.lines,
+            identity: config.identity.clone(),
+            alt_screen_history: config.scrolling.alt_screen_history,
+            preserve_history: config.scrolling.preserve_history,
+            timestamp_epoch: SystemTime::now(),
+            show_line_timestamps: false,
+            damage: TermDamageState::default(),
             cursor_style: None,
             default_cursor_style: config.cursor.style,
             vi_mode_cursor_style: config.cursor.vi_mode_style,
@@ -957,6 +1343,19 @@ impl<T> Term<T> {
             title: None,
             default_title: config.window.title.clone(),
             title_stack: Vec::new(),
+            graphics: Graphics::new(size.cell_width, size.cell_height),
+            hyperlinks: HashMap::new(),
+            hyperlink_ids: HashMap::new(),
+            next_hyperlink_id: 0,
+            hyperlink_gc_threshold: HYPERLINK_GC_THRESHOLD,
+            clipboard_read: config.clipboard_read,
+            pending_clipboard_read: None,
+            allow_resize: config.window.allow_resize,
+            attribute_change_extent: Default::default(),
+            progress: Progress::Hidden,
+            working_directory: None,
+            keyboard_mode_stack: Vec::new(),
+            inactive_keyboard_mode_stack: Vec::new(),
         }
     }
 
@@ -965,16 +1364,15 @@ impl<T> Term<T> {
         T: EventListener,
     {
         self.semantic_escape_chars = config.selection.semantic_escape_chars().to_owned();
-        self.original_colors.fill_named(&config.colors);
-        self.original_colors.fill_cube(&config.colors);
-        self.original_colors.fill_gray_ramp(&config.colors);
-        for i in 0..color::COUNT {
-            if !self.color_modified[i] {
-                self.colors[i] = self.original_colors[i];
-            }
-        }
+        self.line_selection = config.selection.lines;
+        self.identity = config.identity.clone();
+        self.alt_screen_history = config.scrolling.alt_screen_history;
+        self.preserve_history = config.scrolling.preserve_history;
+        self.update_colors(&config.colors);
         self.visual_bell.update_config(config);
-        if let Some(0) = config.scrolling.faux_multiplier() {
+        self.text_blink.update_config(config);
+        self.cursor_blink.update_config(config);
+        if config.scrolling.alternate_multiplier() == 0 {
             self.mode.remove(TermMode::ALTERNATE_SCROLL);
         }
         self.default_cursor_style = config.cursor.style;
@@ -982,6 +1380,8 @@ impl<T> Term<T> {
 
         self.default_title = config.window.title.clone();
         self.dynamic_title = config.dynamic_title();
+        self.clipboard_read = config.clipboard_read;
+        self.allow_resize = config.window.allow_resize;
 
         if self.dynamic_title {
             self.set_title(self.title.clone());
@@ -989,11 +1389,9 @@ impl<T> Term<T> {
             self.event_proxy.send_event(Event::Title(self.default_title.clone()));
         }
 
-        if self.alt {
-            self.alt_grid.update_history(config.scrolling.history() as usize);
-        } else {
-            self.grid.update_history(config.scrolling.history() as usize);
-        }
+        let primary_grid = if self.alt { &mut self.alt_grid } else { &mut self.grid };
+        primary_grid.set_history_archive(config.scrolling.unlimited_history());
+        primary_grid.update_history(config.scrolling.history() as usize);
     }
 
     /// Convert the active selection to a String.
@@ -1004,15 +1402,13 @@ impl<T> Term<T> {
         let mut res = String::new();
 
         if is_block {
-            for line in (end.line + 1..=start.line).rev() {
-                res += &self.line_to_string(line, start.col..end.col, start.col.0 != 0);
+            for line in (end.line..=start.line).rev() {
+                res += &self.block_line_to_string(line, start.col, end.col);
 
-                // If the last column is included, newline is appended automatically
-                if end.col != self.cols() - 1 {
-                    res += "\n";
+                if line != end.line {
+                    res.push('\n');
                 }
             }
-            res += &self.line_to_string(end.line, start.col..end.col, true);
         } else {
             res = self.bounds_to_string(start, end);
         }
@@ -1053,7 +1449,7 @@ impl<T> Term<T> {
 
         let mut tab_mode = false;
         for col in IndexRange::from(cols.start..line_length) {
-            let cell = grid_line[col];
+            let cell = &grid_line[col];
 
             // Skip over cells until next tab-stop once a tab was found
             if tab_mode {
@@ -1073,7 +1469,7 @@ impl<T> Term<T> {
                 text.push(cell.c);
 
                 // Push zero-width characters
-                for c in (&cell.chars()[1..]).iter().take_while(|c| **c != ' ') {
+                for c in cell.zerowidth().into_iter().flatten() {
                     text.push(*c);
                 }
             }
@@ -1099,10 +1495,172 @@ impl<T> Term<T> {
         text
     }
 
+    /// Convert the columns `start..=end` of a line to a String.
+    ///
+    /// Every column produces exactly one cell of text, so the lines of a block selection stay
+    /// aligned. Blanks are kept, tabs are expanded and halves of wide characters which do not fit
+    /// into the block are replaced by spaces.
+    fn block_line_to_string(&self, line: usize, start: Column, end: Column) -> String {
+        let mut text = String::new();
+
+        let grid_line = &self.grid[line];
+        for col in (start.0..=end.0).map(Column) {
+            let cell = &grid_line[col];
+
+            let cut = if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                // Spacers are covered by their wide char, unless it is outside of the block
+                if col != start && grid_line[col - 1].flags.contains(Flags::WIDE_CHAR) {
+                    continue;
+                }
+                true
+            } else {
+                cell.flags.contains(Flags::WIDE_CHAR) && col == end
+            };
+
+            if cut || cell.c == '\t' {
+                text.push(' ');
+                continue;
+            }
+
+            text.push(cell.c);
+            for c in cell.zerowidth().into_iter().flatten() {
+                text.push(*c);
+            }
+        }
+
+        text
+    }
+
     pub fn visible_to_buffer(&self, point: Point) -> Point<usize> {
         self.grid.visible_to_buffer(point)
     }
 
+    /// Buffer line of the closest prompt above the top of the viewport.
+    pub fn previous_prompt(&self) -> Option<usize> {
+        let top = self.grid.display_offset() + self.grid.num_lines().0 - 1;
+        (top + 1..self.grid.len()).find(|&line| self.is_prompt_start(line))
+    }
+
+    /// Buffer line of the closest prompt below the top of the viewport.
+    pub fn next_prompt(&self) -> Option<usize> {
+        let top = self.grid.display_offset() + self.grid.num_lines().0 - 1;
+        (0..top).rev().find(|&line| self.is_prompt_start(line))
+    }
+
+    /// Buffer range of the output of the last command marked through shell integration.
+    pub fn last_command_output(&self) -> Option<(Point<usize>, Point<usize>)> {
+        let mut range: Option<(Point<usize>, Point<usize>)> = None;
+
+        for line in 0..self.grid.len() {
+            for (col, cell) in self.grid[line].iter().enumerate().rev() {
+                let point = Point::new(line, Column(col));
+                if cell.flags.contains(Flags::OUTPUT) {
+                    range = Some((point, range.map_or(point, |(_, end)| end)));
+                } else if range.is_some() && cell.flags.contains(Flags::PROMPT_START) {
+                    // Stop at the prompt of the command which produced the output
+                    return range;
+                }
+            }
+        }
+
+        range
+    }
+
+    /// Buffer range of the output of the command which owns the buffer line.
+    ///
+    /// This is the output between the closest prompt at or above the line and the next prompt.
+    pub fn command_output_at(&self, line: usize) -> Option<(Point<usize>, Point<usize>)> {
+        let prompt = (line..self.grid.len()).find(|&line| self.is_prompt_start(line))?;
+        let next_prompt = (0..prompt).rev().find(|&line| self.is_prompt_start(line));
+
+        let mut range: Option<(Point<usize>, Point<usize>)> = None;
+        for line in next_prompt.map_or(0, |line| line + 1)..=prompt {
+            for (col, cell) in self.grid[line].iter().enumerate().rev() {
+                if cell.flags.contains(Flags::OUTPUT) {
+                    let point = Point::new(line, Column(col));
+                    range = Some((point, range.map_or(point, |(_, end)| end)));
+                }
+            }
+        }
+
+        range
+    }
+
+    /// Push the screen into the history, keeping the lines of the active prompt.
+    ///
+    /// Without prompt marks from shell integration, only the line of the cursor is kept.
+    pub fn clear_to_prompt(&mut self) {
+        if self.mode.contains(TermMode::ALT_SCREEN) {
+            return;
+        }
+
+        let num_lines = self.grid.num_lines();
+        let cursor_line = self.cursor.point.line.0;
+        let prompt_line = (0..=cursor_line)
+            .rev()
+            .find(|&line| self.is_prompt_start(num_lines.0 - line - 1))
+            .unwrap_or(cursor_line);
+
+        self.grid.selection = None;
+        self.grid.scroll_display(Scroll::Bottom);
+
+        if prompt_line != 0 {
+            let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
+            self.grid.scroll_up(&(Line(0)..num_lines), Line(prompt_line), &template);
+            self.cursor.point.line -= prompt_line;
+        }
+
+        self.dirty = true;
+    }
+
+    /// Remove all lines from the history, even if applications are not allowed to.
+    pub fn clear_history(&mut self) {
+        self.grid.selection = None;
+        self.grid.clear_history();
+    }
+
+    /// Time at which a line in the buffer was completed.
+    ///
+    /// Lines which have not been completed yet, like the one containing the cursor, have no
+    /// timestamp.
+    pub fn line_timestamp(&self, line: usize) -> Option<SystemTime> {
+        let timestamp = self.grid[line].timestamp()?;
+        Some(self.timestamp_epoch + Duration::from_secs(u64::from(timestamp.get() - 1)))
+    }
+
+    /// Record the completion time of the line containing the cursor.
+    #[inline]
+    fn stamp_line(&mut self) {
+        let elapsed = self.timestamp_epoch.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+        let timestamp = u32::try_from(elapsed).ok().and_then(|secs| secs.checked_add(1));
+        self.grid[self.cursor.point.line].set_timestamp(timestamp.and_then(NonZeroU32::new));
+    }
+
+    /// Convert a rectangle to the lines and columns it covers, respecting origin mode.
+    fn rect_bounds(&self, rect: Rect) -> Option<(Range<Line>, Range<Column>)> {
+        let (offset, max_line, col_offset, max_col) = if self.mode.contains(TermMode::ORIGIN) {
+            (self.scroll_region.start, self.scroll_region.end, self.margins.start, self.margins.end)
+        } else {
+            (Line(0), self.grid.num_lines(), Column(0), self.grid.num_cols())
+        };
+
+        let top = Line(rect.top.saturating_sub(1)) + offset;
+        let bottom = min(Line(rect.bottom) + offset, max_line);
+        let left = Column(rect.left.saturating_sub(1)) + col_offset;
+        let right = min(Column(rect.right) + col_offset, max_col);
+
+        if top >= bottom || left >= right {
+            return None;
+        }
+
+        Some((top..bottom, left..right))
+    }
+
+    /// Check if a prompt starts in the buffer line.
+    fn is_prompt_start(&self, line: usize) -> bool {
+        self.grid[line].iter().any(|cell| cell.flags.contains(Flags::PROMPT_START))
+    }
+
     /// Access to the raw grid data structure
     ///
     /// This is a bit of a hack; when the window is closed, the event processor
@@ -1128,8 +1686,96 @@ impl<T> Term<T> {
         RenderableCellsIter::new(&self, config, selection)
     }
 
+    /// Replace the color palette, keeping colors which have been changed by escape sequences.
+    pub fn update_colors(&mut self, colors: &Colors) {
+        self.original_colors.fill_named(colors);
+        self.original_colors.fill_cube(colors);
+        self.original_colors.fill_gray_ramp(colors);
+        for i in 0..color::COUNT {
+            if !self.color_modified[i] {
+                self.colors[i] = self.original_colors[i];
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Dark (`1`) or light (`2`) color scheme report, as sent for DEC mode 2031.
+    pub fn color_scheme_report(&self) -> String {
+        // The perceived brightness of the background decides between dark and light
+        let bg = self.colors[NamedColor::Background];
+        let luminance =
+            0.2126 * f32::from(bg.r) + 0.7152 * f32::from(bg.g) + 0.0722 * f32::from(bg.b);
+
+        format!("\x1b[?997;{}n", if luminance < 128. { 1 } else { 2 })
+    }
+
+    /// Working directory of the shell, if it has been reported by the shell.
+    pub fn working_directory(&self) -> Option<&Path> {
+        self.working_directory.as_ref().map(PathBuf::as_path)
+    }
+
+    /// Target of a hyperlink stored in the grid.
+    pub fn hyperlink_uri(&self, id: HyperlinkId) -> Option<&str> {
+        self.hyperlinks.get(&id).map(|hyperlink| hyperlink.uri.as_str())
+    }
+
+    /// Remove all hyperlinks which are not used by any cell.
+    fn collect_hyperlinks(&mut self) {
+        let mut used = HashSet::new();
+        for grid in &[&self.grid, &self.alt_grid] {
+            for line in 0..grid.len() {
+                used.extend(grid[line].iter().filter_map(Cell::hyperlink));
+            }
+        }
+
+        let cursors = [&self.cursor, &self.cursor_save, &self.cursor_save_alt];
+        used.extend(cursors.iter().filter_map(|cursor| cursor.template.hyperlink()));
+
+        self.hyperlinks.retain(|id, _| used.contains(id));
+        self.hyperlink_ids.retain(|_, id| used.contains(id));
+
+        // Collect again once the table has grown by the number of remaining hyperlinks
+        self.hyperlink_gc_threshold = max(2 * self.hyperlinks.len(), HYPERLINK_GC_THRESHOLD);
+    }
+
+    /// Answer the clipboard read request waiting for confirmation.
+    ///
+    /// Returns the response which has to be written to the child, if any.
+    pub fn answer_clipboard_read(&mut self, allow: bool) -> Option<String> {
+        let (clipboard, terminator) = self.pending_clipboard_read.take()?;
+
+        if allow {
+            self.clipboard_response(clipboard, &terminator)
+        } else {
+            debug!("Denied clipboard read request");
+            None
+        }
+    }
+
+    /// Escape sequence reporting the content of a clipboard.
+    fn clipboard_response(&mut self, clipboard: u8, terminator: &str) -> Option<String> {
+        let clipboard_type = match clipboard {
+            b'c' => ClipboardType::Clipboard,
+            b'p' | b's' => ClipboardType::Selection,
+            _ => return None,
+        };
+
+        let text = self.clipboard.load(clipboard_type);
+        let base64 = base64::encode(&text);
+        Some(format!("\x1b]52;{};{}{}", clipboard as char, base64, terminator))
+    }
+
+    /// Take the texture changes caused by graphics since the last frame.
+    pub fn graphics_take_queues(&mut self) -> Option<UpdateQueues> {
+        self.graphics.take_queues()
+    }
+
     /// Resize terminal to new dimensions
     pub fn resize(&mut self, size: &SizeInfo) {
+        self.graphics.cell_width = size.cell_width as usize;
+        self.graphics.cell_height = size.cell_height as usize;
+
         let old_cols = self.grid.num_cols();
         let old_lines = self.grid.num_lines();
         let mut num_cols = size.cols();
@@ -1140,7 +1786,14 @@ impl<T> Term<T> {
             return;
         }
 
-        self.grid.selection = None;
+        // Remember the selection's position in the unwrapped text, so it survives reflow
+        let origin = self.grid.num_lines().0 - 1 - self.cursor.point.line.0;
+        let selection = self.grid.selection.take().map(|selection| {
+            let (start, end) = selection.points();
+            let start = self.grid.logical_point(start, origin);
+            let end = self.grid.logical_point(end, origin);
+            (selection, start, end)
+        });
         self.alt_grid.selection = None;
 
         // Should not allow less than 2 cols, causes all sorts of checks to be required.
@@ -1163,8 +1816,9 @@ impl<T> Term<T> {
         self.grid.resize(!is_alt, num_lines, num_cols, &mut self.cursor.point, &Cell::default());
         self.alt_grid.resize(is_alt, num_lines, num_cols, alt_cursor_point, &Cell::default());
 
-        // Reset scrolling region to new size
+        // Reset scrolling region and margins to new size
         self.scroll_region = Line(0)..self.grid.num_lines();
+        self.margins = Column(0)..self.grid.num_cols();
 
         // Ensure cursors are in-bounds.
         self.cursor.point.col = min(self.cursor.point.col, num_cols - 1);
@@ -1178,6 +1832,14 @@ impl<T> Term<T> {
 
         // Recreate tabs list
         self.tabs.resize(self.grid.num_cols());
+
+        if let Some((mut selection, start, end)) = selection {
+            let origin = self.grid.num_lines().0 - 1 - self.cursor.point.line.0;
+            let start = self.grid.resolve_logical_point(start, origin);
+            let end = self.grid.resolve_logical_point(end, origin);
+            selection.set_points(start, end);
+            self.grid.selection = Some(selection);
+        }
     }
 
     #[inline]
@@ -1192,13 +1854,34 @@ impl<T> Term<T> {
 
     pub fn swap_alt(&mut self) {
         if self.alt {
-            let template = self.cursor.template;
+            if self.alt_screen_history {
+                // Keep the final content of the alternate screen, without trailing empty lines
+                let mut rows: Vec<_> = (0..self.grid.num_lines().0)
+                    .map(|line| self.grid[Line(line)].clone())
+                    .collect();
+                while rows.last().map_or(false, |row| row.is_empty()) {
+                    rows.pop();
+                }
+                self.alt_grid.insert_history(rows, &Cell::default());
+            }
+
+            let template = self.cursor.template.clone();
             self.grid.region_mut(..).each(|c| c.reset(&template));
         }
         self.grid.selection = None;
 
         self.alt = !self.alt;
         mem::swap(&mut self.grid, &mut self.alt_grid);
+
+        mem::swap(&mut self.keyboard_mode_stack, &mut self.inactive_keyboard_mode_stack);
+        self.update_keyboard_mode();
+    }
+
+    /// Make the flags on top of the keyboard protocol stack active.
+    fn update_keyboard_mode(&mut self) {
+        let modes = self.keyboard_mode_stack.last().copied().unwrap_or(KeyboardModes::NO_MODE);
+        self.mode.remove(TermMode::KITTY_KEYBOARD_PROTOCOL);
+        self.mode.insert(TermMode::from(modes));
     }
 
     /// Scroll screen down
@@ -1213,7 +1896,22 @@ impl<T> Term<T> {
 
         // Scroll between origin and bottom
         let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
-        self.grid.scroll_down(&(origin..self.scroll_region.end), lines, &template);
+        if self.has_horizontal_margins() {
+            // Move cells starting from the bottom, so the source is not overwritten
+            for line in (origin.0..self.scroll_region.end.0).rev() {
+                let line = Line(line);
+                for col in self.margins.start.0..self.margins.end.0 {
+                    let col = Column(col);
+                    self.grid[line][col] = if line >= origin + lines {
+                        self.grid[line - lines][col].clone()
+                    } else {
+                        template.clone()
+                    };
+                }
+            }
+        } else {
+            self.grid.scroll_down(&(origin..self.scroll_region.end), lines, &template);
+        }
     }
 
     /// Scroll screen up
@@ -1227,19 +1925,69 @@ impl<T> Term<T> {
 
         // Scroll from origin to bottom less number of lines
         let template = Cell { bg: self.cursor.template.bg, ..Cell::default() };
-        self.grid.scroll_up(&(origin..self.scroll_region.end), lines, &template);
+        if self.has_horizontal_margins() {
+            // Only the cells between the margins move, nothing enters the scrollback history
+            for line in origin.0..self.scroll_region.end.0 {
+                let line = Line(line);
+                for col in self.margins.start.0..self.margins.end.0 {
+                    let col = Column(col);
+                    self.grid[line][col] = if line + lines < self.scroll_region.end {
+                        self.grid[line + lines][col].clone()
+                    } else {
+                        template.clone()
+                    };
+                }
+            }
+        } else {
+            // Record lines leaving the alternate screen in the primary history
+            if self.alt && self.alt_screen_history && origin == Line(0) {
+                let rows = (0..lines.0).map(|line| self.grid[Line(line)].clone()).collect();
+                self.alt_grid.insert_history(rows, &Cell::default());
+            }
+
+            self.grid.scroll_up(&(origin..self.scroll_region.end), lines, &template);
+        }
     }
 
-    fn deccolm(&mut self)
-    where
+    /// Check if the left and right margins do not cover the entire width of the terminal.
+    #[inline]
+    fn has_horizontal_margins(&self) -> bool {
+        self.margins.start != Column(0) || self.margins.end != self.grid.num_cols()
+    }
+
+    /// Rightmost column the cursor can reach by writing or moving forward.
+    ///
+    /// The right margin only applies while the cursor is inside the margins.
+    #[inline]
+    fn right_boundary(&self) -> Column {
+        if self.cursor.point.col < self.margins.end {
+            self.margins.end
+        } else {
+            self.grid.num_cols()
+        }
+    }
+
+    /// Leftmost column the cursor can reach by moving backward.
+    #[inline]
+    fn left_boundary(&self) -> Column {
+        if self.cursor.point.col >= self.margins.start {
+            self.margins.start
+        } else {
+            Column(0)
+        }
+    }
+
+    fn deccolm(&mut self)
+    where
         T: EventListener,
     {
         // Setting 132 column font makes no sense, but run the other side effects
-        // Clear scrolling region
+        // Clear scrolling region and margins
         self.set_scrolling_region(1, self.grid.num_lines().0);
+        self.margins = Column(0)..self.grid.num_cols();
 
         // Clear grid
-        let template = self.cursor.template;
+        let template = self.cursor.template.clone();
         self.grid.region_mut(..).each(|c| c.reset(&template));
     }
 
@@ -1262,13 +2010,23 @@ impl<T> Term<T> {
     }
 
     /// Toggle the vi mode.
+    ///
+    /// Selections are kept when entering vi mode, so they can be adjusted with vi motions.
     #[inline]
     pub fn toggle_vi_mode(&mut self) {
         self.mode ^= TermMode::VI;
-        self.grid.selection = None;
 
-        // Reset vi mode cursor position to match primary cursor
-        if self.mode.contains(TermMode::VI) {
+        if !self.mode.contains(TermMode::VI) {
+            self.grid.selection = None;
+        } else if let Some(selection) = self.grid.selection.as_ref().filter(|s| !s.is_empty()) {
+            let (_, end) = selection.points();
+
+            // Continue the selection from its end
+            self.vi_cursor_to_buffer_point(end);
+        } else {
+            self.grid.selection = None;
+
+            // Reset vi mode cursor position to match primary cursor
             let line = min(self.cursor.point.line + self.grid.display_offset(), self.lines() - 1);
             self.vi_mode_cursor = ViModeCursor::new(Point::new(line, self.cursor.point.col));
         }
@@ -1276,6 +2034,35 @@ impl<T> Term<T> {
         self.dirty = true;
     }
 
+    /// Swap the ends of the selection, moving the vi mode cursor to the new end.
+    pub fn vi_swap_selection_ends(&mut self) {
+        let end = match &mut self.grid.selection {
+            Some(selection) if self.mode.contains(TermMode::VI) => {
+                selection.swap_ends();
+                Some(selection.points().1)
+            },
+            _ => None,
+        };
+
+        if let Some(end) = end {
+            self.vi_cursor_to_buffer_point(end);
+            self.dirty = true;
+        }
+    }
+
+    /// Scroll the point of the buffer into view and move the vi mode cursor to it.
+    fn vi_cursor_to_buffer_point(&mut self, point: Point<usize>) {
+        let bottom = self.grid.display_offset();
+        let top = bottom + self.grid.num_lines().0 - 1;
+        if point.line > top {
+            self.grid.scroll_display(Scroll::Lines((point.line - top) as isize));
+        } else if point.line < bottom {
+            self.grid.scroll_display(Scroll::Lines(-((bottom - point.line) as isize)));
+        }
+
+        self.vi_mode_cursor = ViModeCursor::new(self.grid.clamp_buffer_to_visible(point));
+    }
+
     /// Move vi mode cursor.
     #[inline]
     pub fn vi_motion(&mut self, motion: ViMotion)
@@ -1290,6 +2077,27 @@ impl<T> Term<T> {
         // Move cursor
         self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
 
+        self.vi_update_selection();
+    }
+
+    /// Move vi mode cursor to the next occurrence of a character in its line.
+    #[inline]
+    pub fn vi_inline_search(&mut self, search: InlineSearch)
+    where
+        T: EventListener,
+    {
+        // Require vi mode to be active
+        if !self.mode.contains(TermMode::VI) {
+            return;
+        }
+
+        self.vi_mode_cursor = self.vi_mode_cursor.inline_search(self, search);
+
+        self.vi_update_selection();
+    }
+
+    /// Extend the selection to the vi mode cursor after it has been moved.
+    fn vi_update_selection(&mut self) {
         // Update selection if one is active
         let viewport_point = self.visible_to_buffer(self.vi_mode_cursor.point);
         if let Some(selection) = &mut self.grid.selection {
@@ -1308,6 +2116,11 @@ impl<T> Term<T> {
         &self.semantic_escape_chars
     }
 
+    #[inline]
+    pub fn line_selection(&self) -> LineSelection {
+        self.line_selection
+    }
+
     /// Insert a linebreak at the current cursor position.
     #[inline]
     fn wrapline(&mut self)
@@ -1321,6 +2134,7 @@ impl<T> Term<T> {
         trace!("Wrapping input");
 
         self.grid[&self.cursor.point].flags.insert(Flags::WRAPLINE);
+        self.stamp_line();
 
         if (self.cursor.point.line + 1) >= self.scroll_region.end {
             self.linefeed();
@@ -1328,7 +2142,7 @@ impl<T> Term<T> {
             self.cursor.point.line += 1;
         }
 
-        self.cursor.point.col = Column(0);
+        self.cursor.point.col = self.left_boundary();
         self.input_needs_wrap = false;
     }
 
@@ -1339,11 +2153,28 @@ impl<T> Term<T> {
         T: EventListener,
     {
         let cell = &mut self.grid[&self.cursor.point];
-        *cell = self.cursor.template;
-        cell.c = self.cursor.charsets[self.active_charset].map(c);
+        *cell = self.cursor.template.clone();
+        cell.c = c;
         cell
     }
 
+    /// Replace the underline style of the cursor template.
+    #[inline]
+    fn set_underline_style(&mut self, style: Flags) {
+        self.cursor.template.flags.remove(Flags::ALL_UNDERLINES);
+        self.cursor.template.flags.insert(style);
+    }
+
+    /// Get the next time the visibility of the blinking cursor changes.
+    pub fn cursor_blink_deadline(&self) -> Option<Instant> {
+        let shown = self.mode.contains(TermMode::SHOW_CURSOR) && !self.mode.contains(TermMode::VI);
+        if shown && self.is_focused {
+            self.cursor_blink.next_toggle()
+        } else {
+            None
+        }
+    }
+
     /// Get rendering information about the active cursor.
     fn renderable_cursor<C>(&self, config: &Config<C>) -> RenderableCursor {
         let vi_mode = self.mode.contains(TermMode::VI);
@@ -1364,6 +2195,11 @@ impl<T> Term<T> {
             CursorStyle::Hidden
         } else if !self.is_focused && config.cursor.unfocused_hollow() {
             CursorStyle::HollowBlock
+        } else if self.is_focused
+            && !vi_mode
+            && !self.cursor_blink.visible_at_instant(Instant::now())
+        {
+            CursorStyle::Hidden
         } else {
             let cursor_style = self.cursor_style.unwrap_or(self.default_cursor_style);
 
@@ -1378,13 +2214,18 @@ impl<T> Term<T> {
         let (text_color, cursor_color) = if vi_mode {
             (config.vi_mode_cursor_text_color(), config.vi_mode_cursor_cursor_color())
         } else {
-            let cursor_cursor_color = config.cursor_cursor_color().map(|c| self.colors[c]);
+            // Colors set through OSC 12 apply even without a configured cursor color
+            let cursor_cursor_color = if self.color_modified[NamedColor::Cursor as usize] {
+                Some(self.colors[NamedColor::Cursor])
+            } else {
+                config.cursor_cursor_color().map(|c| self.colors[c])
+            };
             (config.cursor_text_color(), cursor_cursor_color)
         };
 
         // Expand across wide cell when inside wide char or spacer
         let buffer_point = self.visible_to_buffer(point);
-        let cell = self.grid[buffer_point.line][buffer_point.col];
+        let cell = &self.grid[buffer_point.line][buffer_point.col];
         let is_wide = if cell.flags.contains(Flags::WIDE_CHAR_SPACER)
             && self.grid[buffer_point.line][buffer_point.col - 1].flags.contains(Flags::WIDE_CHAR)
         {
@@ -1420,6 +2261,10 @@ impl<T: EventListener> Handler for Term<T> {
     /// A character to be displayed
     #[inline]
     fn input(&mut self, c: char) {
+        // Translate the character through the invoked charset before it is stored
+        let charset = self.single_shift.take().unwrap_or(self.active_charset);
+        let c = self.cursor.charsets[charset].map(c);
+
         // Number of cells the char will occupy
         let width = match c.width() {
             Some(width) => width,
@@ -1428,12 +2273,17 @@ impl<T: EventListener> Handler for Term<T> {
 
         // Handle zero-width characters
         if width == 0 {
-            let mut col = self.cursor.point.col.0.saturating_sub(1);
+            // The cursor stays on the last character written to the final column
+            let mut col = self.cursor.point.col.0;
+            if !self.input_needs_wrap {
+                col = col.saturating_sub(1);
+            }
+
             let line = self.cursor.point.line;
             if self.grid[line][Column(col)].flags.contains(Flags::WIDE_CHAR_SPACER) {
                 col = col.saturating_sub(1);
             }
-            self.grid[line][Column(col)].push_extra(c);
+            self.grid[line][Column(col)].push_zerowidth(c);
             return;
         }
 
@@ -1442,30 +2292,42 @@ impl<T: EventListener> Handler for Term<T> {
             self.wrapline();
         }
 
-        let num_cols = self.grid.num_cols();
+        let right = self.right_boundary();
 
         // If in insert mode, first shift cells to the right
-        if self.mode.contains(TermMode::INSERT) && self.cursor.point.col + width < num_cols {
+        if self.mode.contains(TermMode::INSERT) && self.cursor.point.col + width < right {
             let line = self.cursor.point.line;
             let col = self.cursor.point.col;
+
+            // Wide chars shifted across the margin are removed entirely
+            self.clear_split_wide_chars(line, col..right);
+            self.clear_split_wide_chars(line, col..right - width);
+
             let line = &mut self.grid[line];
 
             let src = line[col..].as_ptr();
             let dst = line[(col + width)..].as_mut_ptr();
             unsafe {
-                ptr::copy(src, dst, (num_cols - col - width).0);
+                ptr::copy(src, dst, (right - col - width).0);
             }
         }
 
         if width == 1 {
+            let (start, end) = (self.cursor.point.col, self.cursor.point.col + 1);
+            self.clear_split_wide_chars(self.cursor.point.line, start..end);
             self.write_at_cursor(c);
         } else {
             // Insert extra placeholder before wide char if glyph doesn't fit in this row anymore
-            if self.cursor.point.col + 1 >= num_cols {
+            if self.cursor.point.col + 1 >= right {
+                let (start, end) = (self.cursor.point.col, self.cursor.point.col + 1);
+                self.clear_split_wide_chars(self.cursor.point.line, start..end);
                 self.write_at_cursor(' ').flags.insert(Flags::WIDE_CHAR_SPACER);
                 self.wrapline();
             }
 
+            let col = self.cursor.point.col;
+            self.clear_split_wide_chars(self.cursor.point.line, col..col + 2);
+
             // Write full width glyph to current cursor cell
             self.write_at_cursor(c).flags.insert(Flags::WIDE_CHAR);
 
@@ -1474,7 +2336,10 @@ impl<T: EventListener> Handler for Term<T> {
             self.write_at_cursor(' ').flags.insert(Flags::WIDE_CHAR_SPACER);
         }
 
-        if self.cursor.point.col + 1 < num_cols {
+        // Only the first character of a prompt carries its mark
+        self.cursor.template.flags.remove(Flags::PROMPT_START);
+
+        if self.cursor.point.col + 1 < right {
             self.cursor.point.col += 1;
         } else {
             self.input_needs_wrap = true;
@@ -1489,17 +2354,148 @@ impl<T: EventListener> Handler for Term<T> {
         self.grid.region_mut(..).each(|c| c.reset(&template));
     }
 
+    #[inline]
+    fn insert_graphic(&mut self, graphic: Graphic) {
+        let cell_width = self.graphics.cell_width;
+        let cell_height = self.graphics.cell_height;
+        if cell_width == 0 || cell_height == 0 {
+            return;
+        }
+
+        trace!("Inserting graphic: width={}, height={}", graphic.width, graphic.height);
+
+        let lines = (graphic.height + cell_height - 1) / cell_height;
+        let cols = (graphic.width + cell_width - 1) / cell_width;
+        let start_col = self.cursor.point.col;
+        let end_col = min(start_col + cols, self.grid.num_cols());
+
+        let texture = self.graphics.insert(graphic);
+
+        // Cover the cells below the cursor, scrolling when the bottom is reached
+        for line in 0..lines {
+            if line > 0 {
+                self.linefeed();
+            }
+
+            let row = &mut self.grid[self.cursor.point.line];
+            for (col, offset) in (start_col.0..end_col.0).zip(0..) {
+                let cell = &mut row[Column(col)];
+                cell.reset(&self.cursor.template);
+                cell.set_graphic(GraphicCell {
+                    texture: texture.clone(),
+                    offset_x: (offset * cell_width) as u16,
+                    offset_y: (line * cell_height) as u16,
+                });
+            }
+        }
+
+        // Continue with the text below the graphic
+        self.linefeed();
+        self.cursor.point.col = start_col;
+        self.input_needs_wrap = false;
+    }
+
+    #[inline]
+    fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
+        trace!("Setting hyperlink: {:?}", hyperlink);
+
+        // Identical hyperlinks share an entry, so repeatedly printed links don't grow the table
+        let id = match hyperlink {
+            Some(hyperlink) => match self.hyperlink_ids.get(&hyperlink) {
+                Some(&id) => Some(id),
+                None => {
+                    // The previous hyperlink of the cursor is only kept if a cell still uses it
+                    if self.hyperlinks.len() >= self.hyperlink_gc_threshold {
+                        self.cursor.template.set_hyperlink(None);
+                        self.collect_hyperlinks();
+                    }
+
+                    let mut id = HyperlinkId(self.next_hyperlink_id);
+                    while self.hyperlinks.contains_key(&id) {
+                        id.0 = id.0.wrapping_add(1);
+                    }
+                    self.next_hyperlink_id = id.0.wrapping_add(1);
+
+                    self.hyperlinks.insert(id, hyperlink.clone());
+                    self.hyperlink_ids.insert(hyperlink, id);
+                    Some(id)
+                },
+            },
+            None => None,
+        };
+
+        self.cursor.template.set_hyperlink(id);
+    }
+
+    #[inline]
+    fn set_prompt_mark(&mut self, mark: PromptMark) {
+        trace!("Setting prompt mark: {:?}", mark);
+        let flags = &mut self.cursor.template.flags;
+        match mark {
+            PromptMark::PromptStart => {
+                flags.remove(Flags::OUTPUT);
+                flags.insert(Flags::PROMPT_START);
+            },
+            PromptMark::CommandStart => flags.remove(Flags::PROMPT_START),
+            PromptMark::OutputStart => {
+                flags.remove(Flags::PROMPT_START);
+                flags.insert(Flags::OUTPUT);
+            },
+            PromptMark::CommandEnd => flags.remove(Flags::PROMPT_START | Flags::OUTPUT),
+        }
+    }
+
+    #[inline]
+    fn set_progress(&mut self, progress: Progress) {
+        trace!("Setting progress: {:?}", progress);
+
+        // Keep the last percentage when only the state changes
+        let percentage = match self.progress {
+            Progress::Normal(percentage) => Some(percentage),
+            Progress::Error(percentage) | Progress::Paused(percentage) => percentage,
+            Progress::Hidden | Progress::Indeterminate => None,
+        };
+        self.progress = match progress {
+            Progress::Error(None) => Progress::Error(percentage),
+            Progress::Paused(None) => Progress::Paused(percentage),
+            progress => progress,
+        };
+
+        self.event_proxy.send_event(Event::Progress(self.progress));
+    }
+
+    #[inline]
+    fn set_working_directory(&mut self, host: &str, path: PathBuf) {
+        trace!("Setting working directory: {:?} on {:?}", path, host);
+
+        // Directories on other machines, like after connecting through SSH, are unusable
+        self.working_directory = Some(path).filter(|_| util::is_local_host(host));
+    }
+
+    #[inline]
+    fn desktop_notification(&mut self, notification: Notification) {
+        trace!("Requesting desktop notification: {:?}", notification);
+        self.event_proxy.send_event(Event::Notification(notification));
+    }
+
+    #[inline]
+    fn control_string(&mut self, kind: ControlStringKind, data: &[u8]) {
+        trace!("Passing {:?} string of {} bytes to the event listener", kind, data.len());
+        self.event_proxy.control_string(kind, data);
+    }
+
     #[inline]
     fn goto(&mut self, line: Line, col: Column) {
         trace!("Going to: line={}, col={}", line, col);
-        let (y_offset, max_y) = if self.mode.contains(TermMode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
+        let (y_offset, max_y, x_offset, max_x) = if self.mode.contains(TermMode::ORIGIN) {
+            let margins = &self.margins;
+            (self.scroll_region.start, self.scroll_region.end - 1, margins.start, margins.end - 1)
         } else {
-            (Line(0), self.grid.num_lines() - 1)
+            (Line(0), self.grid.num_lines() - 1, Column(0), self.grid.num_cols() - 1)
         };
 
         self.cursor.point.line = min(line + y_offset, max_y);
-        self.cursor.point.col = min(col, self.grid.num_cols() - 1);
+        self.cursor.point.col = min(col + x_offset, max_x);
         self.input_needs_wrap = false;
     }
 
@@ -1517,13 +2513,22 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn insert_blank(&mut self, count: Column) {
-        // Ensure inserting within terminal bounds
+        // Cells outside of the margins are not affected
+        if !self.margins.contains(&self.cursor.point.col) {
+            return;
+        }
 
-        let count = min(count, self.grid.num_cols() - self.cursor.point.col);
+        // Ensure inserting within the margins
+        let right = self.margins.end;
+        let count = min(count, right - self.cursor.point.col);
 
         let source = self.cursor.point.col;
         let destination = self.cursor.point.col + count;
-        let num_cells = (self.grid.num_cols() - destination).0;
+        let num_cells = (right - destination).0;
+
+        // Wide chars shifted across the margin are removed entirely
+        self.clear_split_wide_chars(self.cursor.point.line, source..right);
+        self.clear_split_wide_chars(self.cursor.point.line, source..right - count);
 
         let line = &mut self.grid[self.cursor.point.line];
 
@@ -1558,21 +2563,32 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn move_forward(&mut self, cols: Column) {
         trace!("Moving forward: {}", cols);
-        self.cursor.point.col = min(self.cursor.point.col + cols, self.grid.num_cols() - 1);
+        self.cursor.point.col = min(self.cursor.point.col + cols, self.right_boundary() - 1);
         self.input_needs_wrap = false;
     }
 
     #[inline]
     fn move_backward(&mut self, cols: Column) {
         trace!("Moving backward: {}", cols);
-        self.cursor.point.col -= min(self.cursor.point.col, cols);
+        let left = self.left_boundary();
+        self.cursor.point.col = max(self.cursor.point.col - min(self.cursor.point.col, cols), left);
         self.input_needs_wrap = false;
     }
 
     #[inline]
-    fn identify_terminal<W: io::Write>(&mut self, writer: &mut W) {
-        trace!("Reporting terminal identity");
-        let _ = writer.write_all(b"\x1b[?6c");
+    fn identify_terminal<W: io::Write>(&mut self, writer: &mut W, intermediate: Option<char>) {
+        trace!("Reporting terminal identity: {:?}", intermediate);
+        let response = match intermediate {
+            Some('>') => self.identity.secondary_device_attributes(),
+            _ => self.identity.primary_device_attributes(),
+        };
+        let _ = writer.write_all(response.as_bytes());
+    }
+
+    #[inline]
+    fn answerback<W: io::Write>(&mut self, writer: &mut W) {
+        trace!("Sending answerback");
+        let _ = writer.write_all(self.identity.answerback().as_bytes());
     }
 
     #[inline]
@@ -1591,6 +2607,12 @@ impl<T: EventListener> Handler for Term<T> {
         };
     }
 
+    #[inline]
+    fn report_color_scheme<W: io::Write>(&mut self, writer: &mut W) {
+        trace!("Reporting color scheme");
+        let _ = writer.write_all(self.color_scheme_report().as_bytes());
+    }
+
     #[inline]
     fn move_down_and_cr(&mut self, lines: Line) {
         trace!("Moving down and cr: {}", lines);
@@ -1614,7 +2636,8 @@ impl<T: EventListener> Handler for Term<T> {
             return;
         }
 
-        while self.cursor.point.col < self.grid.num_cols() && count != 0 {
+        let right = self.right_boundary();
+        while self.cursor.point.col < right && count != 0 {
             count -= 1;
 
             let cell = &mut self.grid[&self.cursor.point];
@@ -1623,7 +2646,7 @@ impl<T: EventListener> Handler for Term<T> {
             }
 
             loop {
-                if (self.cursor.point.col + 1) == self.grid.num_cols() {
+                if (self.cursor.point.col + 1) == right {
                     break;
                 }
 
@@ -1640,7 +2663,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn backspace(&mut self) {
         trace!("Backspace");
-        if self.cursor.point.col > Column(0) {
+        if self.cursor.point.col > self.left_boundary() {
             self.cursor.point.col -= 1;
             self.input_needs_wrap = false;
         }
@@ -1650,7 +2673,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn carriage_return(&mut self) {
         trace!("Carriage return");
-        self.cursor.point.col = Column(0);
+        self.cursor.point.col = self.left_boundary();
         self.input_needs_wrap = false;
     }
 
@@ -1658,6 +2681,7 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn linefeed(&mut self) {
         trace!("Linefeed");
+        self.stamp_line();
         let next = self.cursor.point.line + 1;
         if next == self.scroll_region.end {
             self.scroll_up(Line(1));
@@ -1671,7 +2695,7 @@ impl<T: EventListener> Handler for Term<T> {
     fn bell(&mut self) {
         trace!("Bell");
         self.visual_bell.ring();
-        self.event_proxy.send_event(Event::Urgent);
+        self.event_proxy.send_event(Event::Bell);
     }
 
     #[inline]
@@ -1732,7 +2756,9 @@ impl<T: EventListener> Handler for Term<T> {
     #[inline]
     fn insert_blank_lines(&mut self, lines: Line) {
         trace!("Inserting blank {} lines", lines);
-        if self.scroll_region.contains(&self.cursor.point.line) {
+        if self.scroll_region.contains(&self.cursor.point.line)
+            && self.margins.contains(&self.cursor.point.col)
+        {
             let origin = self.cursor.point.line;
             self.scroll_down_relative(origin, lines);
         }
@@ -1745,7 +2771,10 @@ impl<T: EventListener> Handler for Term<T> {
 
         trace!("Deleting {} lines", lines);
 
-        if lines.0 > 0 && self.scroll_region.contains(&self.cursor.point.line) {
+        if lines.0 > 0
+            && self.scroll_region.contains(&self.cursor.point.line)
+            && self.margins.contains(&self.cursor.point.col)
+        {
             self.scroll_up_relative(origin, lines);
         }
     }
@@ -1756,6 +2785,8 @@ impl<T: EventListener> Handler for Term<T> {
         let start = self.cursor.point.col;
         let end = min(start + count, self.grid.num_cols());
 
+        self.clear_split_wide_chars(self.cursor.point.line, start..end);
+
         let row = &mut self.grid[self.cursor.point.line];
         // Cleared cells have current background color set
         for c in &mut row[start..end] {
@@ -1765,14 +2796,21 @@ impl<T: EventListener> Handler for Term<T> {
 
     #[inline]
     fn delete_chars(&mut self, count: Column) {
-        let cols = self.grid.num_cols();
-
-        // Ensure deleting within terminal bounds
-        let count = min(count, cols);
+        // Cells outside of the margins are not affected
+        if !self.margins.contains(&self.cursor.point.col) {
+            return;
+        }
 
+        // Ensure deleting within the margins
+        let right = self.margins.end;
         let start = self.cursor.point.col;
-        let end = min(start + count, cols - 1);
-        let n = (cols - end).0;
+        let count = min(count, right - start);
+
+        let end = start + count;
+        let n = (right - end).0;
+
+        self.clear_split_wide_chars(self.cursor.point.line, start..right);
+        self.clear_split_wide_chars(self.cursor.point.line, start..end);
 
         let line = &mut self.grid[self.cursor.point.line];
 
@@ -1785,16 +2823,21 @@ impl<T: EventListener> Handler for Term<T> {
 
         // Clear last `count` cells in line. If deleting 1 char, need to delete
         // 1 cell.
-        let end = cols - count;
-        for c in &mut line[end..] {
+        let end = right - count;
+        for c in &mut line[end..right] {
             c.reset(&self.cursor.template);
         }
+
+        // Leading spacers of wrapped wide chars are no longer in the last column
+        self.clear_orphaned_wide_chars(self.cursor.point.line, start..end);
     }
 
     #[inline]
     fn move_backward_tabs(&mut self, count: i64) {
         trace!("Moving backward {} tabs", count);
 
+        // Every tab moves the cursor by at least one column
+        let count = min(count, self.grid.num_cols().0 as i64);
         for _ in 0..count {
             let mut col = self.cursor.point.col;
             for i in (0..(col.0)).rev() {
@@ -1803,13 +2846,36 @@ impl<T: EventListener> Handler for Term<T> {
                     break;
                 }
             }
+
+            if col == self.cursor.point.col {
+                break;
+            }
             self.cursor.point.col = col;
         }
     }
 
     #[inline]
     fn move_forward_tabs(&mut self, count: i64) {
-        trace!("[unimplemented] Moving forward {} tabs", count);
+        trace!("Moving forward {} tabs", count);
+
+        // Unlike `put_tab`, this only moves the cursor and never wraps
+        let last_col = self.right_boundary() - 1;
+        let count = min(count, self.grid.num_cols().0 as i64);
+        for _ in 0..count {
+            let mut col = last_col;
+            for i in (self.cursor.point.col.0 + 1)..last_col.0 {
+                if self.tabs[index::Column(i)] {
+                    col = index::Column(i);
+                    break;
+                }
+            }
+
+            if col <= self.cursor.point.col {
+                break;
+            }
+            self.cursor.point.col = col;
+        }
+        self.input_needs_wrap = false;
     }
 
     #[inline]
@@ -1817,7 +2883,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Saving cursor position");
         let cursor = if self.alt { &mut self.cursor_save_alt } else { &mut self.cursor_save };
 
-        *cursor = self.cursor;
+        *cursor = self.cursor.clone();
     }
 
     #[inline]
@@ -1825,7 +2891,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Restoring cursor position");
         let source = if self.alt { &self.cursor_save_alt } else { &self.cursor_save };
 
-        self.cursor = *source;
+        self.cursor = source.clone();
         self.cursor.point.line = min(self.cursor.point.line, self.grid.num_lines() - 1);
         self.cursor.point.col = min(self.cursor.point.col, self.grid.num_cols() - 1);
     }
@@ -1835,15 +2901,19 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Clearing line: {:?}", mode);
 
         let col = self.cursor.point.col;
+        let num_cols = self.grid.num_cols();
 
         match mode {
             ansi::LineClearMode::Right => {
+                self.clear_split_wide_chars(self.cursor.point.line, col..num_cols);
                 let row = &mut self.grid[self.cursor.point.line];
                 for cell in &mut row[col..] {
                     cell.reset(&self.cursor.template);
                 }
             },
             ansi::LineClearMode::Left => {
+                let end = col + 1;
+                self.clear_split_wide_chars(self.cursor.point.line, Column(0)..end);
                 let row = &mut self.grid[self.cursor.point.line];
                 for cell in &mut row[..=col] {
                     cell.reset(&self.cursor.template);
@@ -1864,6 +2934,7 @@ impl<T: EventListener> Handler for Term<T> {
         trace!("Setting color[{}] = {:?}", index, color);
         self.colors[index] = color;
         self.color_modified[index] = true;
+        self.mark_fully_damaged();
     }
 
     /// Write a foreground/background color escape sequence with the current color
@@ -1884,12 +2955,25 @@ impl<T: EventListener> Handler for Term<T> {
         let _ = writer.write_all(response.as_bytes());
     }
 
+    /// Write an indexed color escape sequence with the current color
+    #[inline]
+    fn color_sequence<W: io::Write>(&mut self, writer: &mut W, index: usize, terminator: &str) {
+        trace!("Writing escape sequence for color[{}]", index);
+        let color = self.colors[index];
+        let response = format!(
+            "\x1b]4;{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
+            index, color.r, color.g, color.b, terminator
+        );
+        let _ = writer.write_all(response.as_bytes());
+    }
+
     /// Reset the indexed color to original value
     #[inline]
     fn reset_color(&mut self, index: usize) {
         trace!("Resetting color[{}]", index);
         self.colors[index] = self.original_colors[index];
         self.color_modified[index] = false;
+        self.mark_fully_damaged();
     }
 
     /// Set the clipboard
@@ -1911,22 +2995,31 @@ impl<T: EventListener> Handler for Term<T> {
     /// Write clipboard data to child.
     #[inline]
     fn write_clipboard<W: io::Write>(&mut self, clipboard: u8, writer: &mut W, terminator: &str) {
-        let clipboard_type = match clipboard {
-            b'c' => ClipboardType::Clipboard,
-            b'p' | b's' => ClipboardType::Selection,
-            _ => return,
-        };
-
-        let text = self.clipboard.load(clipboard_type);
-        let base64 = base64::encode(&text);
-        let escape = format!("\x1b]52;{};{}{}", clipboard as char, base64, terminator);
-        let _ = writer.write_all(escape.as_bytes());
+        match self.clipboard_read {
+            ClipboardReadPermission::Allow => {
+                if let Some(escape) = self.clipboard_response(clipboard, terminator) {
+                    let _ = writer.write_all(escape.as_bytes());
+                }
+            },
+            ClipboardReadPermission::Deny => debug!("Denied clipboard read request"),
+            ClipboardReadPermission::Ask => {
+                self.pending_clipboard_read = Some((clipboard, terminator.to_owned()));
+
+                let text = String::from(
+                    "An application requested to read the clipboard. Press Y to allow or N to \
+                     deny.",
+                );
+                let mut message = Message::new(text, color::YELLOW);
+                message.set_target(CLIPBOARD_READ_TARGET.to_owned());
+                self.event_proxy.send_event(Event::Message(message));
+            },
+        }
     }
 
     #[inline]
     fn clear_screen(&mut self, mode: ansi::ClearMode) {
         trace!("Clearing screen: {:?}", mode);
-        let template = self.cursor.template;
+        let template = self.cursor.template.clone();
 
         // Remove active selections
         self.grid.selection = None;
@@ -1942,11 +3035,17 @@ impl<T: EventListener> Handler for Term<T> {
                 }
                 // Clear up to the current column in the current line
                 let end = min(self.cursor.point.col + 1, self.grid.num_cols());
+                self.clear_split_wide_chars(self.cursor.point.line, Column(0)..end);
                 for cell in &mut self.grid[self.cursor.point.line][..end] {
                     cell.reset(&template);
                 }
             },
             ansi::ClearMode::Below => {
+                let num_cols = self.grid.num_cols();
+                self.clear_split_wide_chars(
+                    self.cursor.point.line,
+                    self.cursor.point.col..num_cols,
+                );
                 for cell in &mut self.grid[self.cursor.point.line][self.cursor.point.col..] {
                     cell.reset(&template);
                 }
@@ -1964,6 +3063,9 @@ impl<T: EventListener> Handler for Term<T> {
                     self.grid.clear_viewport(&template);
                 }
             },
+            ansi::ClearMode::Saved if self.preserve_history => {
+                debug!("Ignoring request to clear the history");
+            },
             ansi::ClearMode::Saved => self.grid.clear_history(),
         }
     }
@@ -1982,6 +3084,12 @@ impl<T: EventListener> Handler for Term<T> {
         }
     }
 
+    #[inline]
+    fn set_default_tabstops(&mut self) {
+        trace!("Setting default tabstops");
+        self.tabs = TabStops::new(self.grid.num_cols());
+    }
+
     // Reset all important fields in the term struct
     #[inline]
     fn reset_state(&mut self) {
@@ -1991,18 +3099,49 @@ impl<T: EventListener> Handler for Term<T> {
         self.input_needs_wrap = false;
         self.cursor = Default::default();
         self.active_charset = Default::default();
-        self.mode = Default::default();
+        self.single_shift = None;
+        // Vi mode is controlled by the user, not the application
+        self.mode = TermMode::default() | (self.mode & TermMode::VI);
         self.cursor_save = Default::default();
         self.cursor_save_alt = Default::default();
         self.colors = self.original_colors;
         self.color_modified = [false; color::COUNT];
         self.cursor_style = None;
+        self.cursor_blink.requested = None;
         self.grid.reset(&Cell::default());
         self.alt_grid.reset(&Cell::default());
         self.scroll_region = Line(0)..self.grid.num_lines();
+        self.margins = Column(0)..self.grid.num_cols();
         self.tabs = TabStops::new(self.grid.num_cols());
         self.title_stack = Vec::new();
-        self.title = None;
+        self.set_title(None);
+        self.hyperlinks = HashMap::new();
+        self.hyperlink_ids = HashMap::new();
+        self.hyperlink_gc_threshold = HYPERLINK_GC_THRESHOLD;
+        self.attribute_change_extent = Default::default();
+        if self.progress != Progress::Hidden {
+            self.set_progress(Progress::Hidden);
+        }
+        self.keyboard_mode_stack = Vec::new();
+        self.inactive_keyboard_mode_stack = Vec::new();
+    }
+
+    #[inline]
+    fn soft_reset(&mut self) {
+        trace!("Soft reset");
+        self.input_needs_wrap = false;
+        self.mode.remove(
+            TermMode::INSERT | TermMode::ORIGIN | TermMode::APP_CURSOR | TermMode::APP_KEYPAD,
+        );
+        self.mode.insert(TermMode::SHOW_CURSOR | TermMode::LINE_WRAP);
+        self.scroll_region = Line(0)..self.grid.num_lines();
+        self.margins = Column(0)..self.grid.num_cols();
+        self.cursor.charsets = Default::default();
+        self.active_charset = Default::default();
+        self.single_shift = None;
+        self.terminal_attribute(Attr::Reset);
+        self.cursor_save = Default::default();
+        self.cursor_save_alt = Default::default();
     }
 
     #[inline]
@@ -2026,7 +3165,9 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::Reset => {
                 self.cursor.template.fg = Color::Named(NamedColor::Foreground);
                 self.cursor.template.bg = Color::Named(NamedColor::Background);
-                self.cursor.template.flags = Flags::empty();
+                // Shell integration zones are not part of the text attributes
+                self.cursor.template.flags &= Flags::PROMPT_START | Flags::OUTPUT;
+                self.cursor.template.set_underline_color(None);
             },
             Attr::Reverse => self.cursor.template.flags.insert(Flags::INVERSE),
             Attr::CancelReverse => self.cursor.template.flags.remove(Flags::INVERSE),
@@ -2036,15 +3177,26 @@ impl<T: EventListener> Handler for Term<T> {
             Attr::CancelBoldDim => self.cursor.template.flags.remove(Flags::BOLD | Flags::DIM),
             Attr::Italic => self.cursor.template.flags.insert(Flags::ITALIC),
             Attr::CancelItalic => self.cursor.template.flags.remove(Flags::ITALIC),
-            Attr::Underline => self.cursor.template.flags.insert(Flags::UNDERLINE),
-            Attr::CancelUnderline => self.cursor.template.flags.remove(Flags::UNDERLINE),
+            Attr::Underline => self.set_underline_style(Flags::UNDERLINE),
+            Attr::DoubleUnderline => self.set_underline_style(Flags::DOUBLE_UNDERLINE),
+            Attr::Undercurl => self.set_underline_style(Flags::UNDERCURL),
+            Attr::DottedUnderline => self.set_underline_style(Flags::DOTTED_UNDERLINE),
+            Attr::DashedUnderline => self.set_underline_style(Flags::DASHED_UNDERLINE),
+            Attr::CancelUnderline => self.cursor.template.flags.remove(Flags::ALL_UNDERLINES),
+            Attr::UnderlineColor(color) => self.cursor.template.set_underline_color(color),
+            Attr::BlinkSlow => {
+                self.cursor.template.flags.remove(Flags::BLINK);
+                self.cursor.template.flags.insert(Flags::BLINK_SLOW);
+            },
+            Attr::BlinkFast => {
+                self.cursor.template.flags.remove(Flags::BLINK);
+                self.cursor.template.flags.insert(Flags::BLINK_FAST);
+            },
+            Attr::CancelBlink => self.cursor.template.flags.remove(Flags::BLINK),
             Attr::Hidden => self.cursor.template.flags.insert(Flags::HIDDEN),
             Attr::CancelHidden => self.cursor.template.flags.remove(Flags::HIDDEN),
             Attr::Strike => self.cursor.template.flags.insert(Flags::STRIKEOUT),
             Attr::CancelStrike => self.cursor.template.flags.remove(Flags::STRIKEOUT),
-            _ => {
-                debug!("Term got unhandled attr: {:?}", attr);
-            },
         }
     }
 
@@ -2082,22 +3234,28 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.insert(TermMode::BRACKETED_PASTE),
             // Mouse encodings are mutually exlusive
             ansi::Mode::SgrMouse => {
-                self.mode.remove(TermMode::UTF8_MOUSE);
+                self.mode.remove(TermMode::UTF8_MOUSE | TermMode::SGR_PIXELS_MOUSE);
                 self.mode.insert(TermMode::SGR_MOUSE);
             },
             ansi::Mode::Utf8Mouse => {
-                self.mode.remove(TermMode::SGR_MOUSE);
+                self.mode.remove(TermMode::SGR_MOUSE | TermMode::SGR_PIXELS_MOUSE);
                 self.mode.insert(TermMode::UTF8_MOUSE);
             },
+            ansi::Mode::SgrPixelsMouse => {
+                self.mode.remove(TermMode::SGR_MOUSE | TermMode::UTF8_MOUSE);
+                self.mode.insert(TermMode::SGR_PIXELS_MOUSE);
+            },
+            ansi::Mode::ColorPaletteUpdates => self.mode.insert(TermMode::COLOR_PALETTE_UPDATES),
             ansi::Mode::AlternateScroll => self.mode.insert(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.insert(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.insert(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.insert(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => self.mode.insert(TermMode::LEFT_RIGHT_MARGIN),
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.insert(TermMode::INSERT), // heh
-            ansi::Mode::BlinkingCursor => {
-                trace!("... unimplemented mode");
-            },
+            ansi::Mode::BlinkingCursor => self.set_cursor_blinking(Some(true)),
+            // Synchronized updates are handled by the parser
+            ansi::Mode::SyncUpdate => (),
         }
     }
 
@@ -2131,18 +3289,55 @@ impl<T: EventListener> Handler for Term<T> {
             ansi::Mode::BracketedPaste => self.mode.remove(TermMode::BRACKETED_PASTE),
             ansi::Mode::SgrMouse => self.mode.remove(TermMode::SGR_MOUSE),
             ansi::Mode::Utf8Mouse => self.mode.remove(TermMode::UTF8_MOUSE),
+            ansi::Mode::SgrPixelsMouse => self.mode.remove(TermMode::SGR_PIXELS_MOUSE),
+            ansi::Mode::ColorPaletteUpdates => self.mode.remove(TermMode::COLOR_PALETTE_UPDATES),
             ansi::Mode::AlternateScroll => self.mode.remove(TermMode::ALTERNATE_SCROLL),
             ansi::Mode::LineWrap => self.mode.remove(TermMode::LINE_WRAP),
             ansi::Mode::LineFeedNewLine => self.mode.remove(TermMode::LINE_FEED_NEW_LINE),
             ansi::Mode::Origin => self.mode.remove(TermMode::ORIGIN),
+            ansi::Mode::LeftRightMargin => {
+                self.mode.remove(TermMode::LEFT_RIGHT_MARGIN);
+                self.margins = Column(0)..self.grid.num_cols();
+            },
             ansi::Mode::DECCOLM => self.deccolm(),
             ansi::Mode::Insert => self.mode.remove(TermMode::INSERT),
-            ansi::Mode::BlinkingCursor => {
-                trace!("... unimplemented mode");
-            },
+            ansi::Mode::BlinkingCursor => self.set_cursor_blinking(Some(false)),
+            // Synchronized updates are handled by the parser
+            ansi::Mode::SyncUpdate => (),
         }
     }
 
+    #[inline]
+    fn mode_state(&self, mode: ansi::Mode) -> ModeState {
+        let flag = match mode {
+            ansi::Mode::SwapScreenAndSetRestoreCursor => TermMode::ALT_SCREEN,
+            ansi::Mode::ShowCursor => TermMode::SHOW_CURSOR,
+            ansi::Mode::CursorKeys => TermMode::APP_CURSOR,
+            ansi::Mode::ReportMouseClicks => TermMode::MOUSE_REPORT_CLICK,
+            ansi::Mode::ReportCellMouseMotion => TermMode::MOUSE_DRAG,
+            ansi::Mode::ReportAllMouseMotion => TermMode::MOUSE_MOTION,
+            ansi::Mode::ReportFocusInOut => TermMode::FOCUS_IN_OUT,
+            ansi::Mode::BracketedPaste => TermMode::BRACKETED_PASTE,
+            ansi::Mode::SgrMouse => TermMode::SGR_MOUSE,
+            ansi::Mode::Utf8Mouse => TermMode::UTF8_MOUSE,
+            ansi::Mode::SgrPixelsMouse => TermMode::SGR_PIXELS_MOUSE,
+            ansi::Mode::ColorPaletteUpdates => TermMode::COLOR_PALETTE_UPDATES,
+            ansi::Mode::AlternateScroll => TermMode::ALTERNATE_SCROLL,
+            ansi::Mode::LineWrap => TermMode::LINE_WRAP,
+            ansi::Mode::LineFeedNewLine => TermMode::LINE_FEED_NEW_LINE,
+            ansi::Mode::Origin => TermMode::ORIGIN,
+            ansi::Mode::LeftRightMargin => TermMode::LEFT_RIGHT_MARGIN,
+            ansi::Mode::Insert => TermMode::INSERT,
+            // The column count never changes
+            ansi::Mode::DECCOLM => return ModeState::PermanentlyReset,
+            ansi::Mode::BlinkingCursor => return ModeState::from(self.cursor_blink.enabled()),
+            // Synchronized updates are handled by the parser
+            ansi::Mode::SyncUpdate => return ModeState::NotRecognized,
+        };
+
+        ModeState::from(self.mode.contains(flag))
+    }
+
     #[inline]
     fn set_scrolling_region(&mut self, top: usize, bottom: usize) {
         if top >= bottom {
@@ -2165,50 +3360,208 @@ impl<T: EventListener> Handler for Term<T> {
     }
 
     #[inline]
-    fn set_keypad_application_mode(&mut self) {
-        trace!("Setting keypad application mode");
-        self.mode.insert(TermMode::APP_KEYPAD);
-    }
+    fn set_left_and_right_margins(&mut self, left: usize, right: usize) {
+        if !self.mode.contains(TermMode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
 
-    #[inline]
-    fn unset_keypad_application_mode(&mut self) {
-        trace!("Unsetting keypad application mode");
-        self.mode.remove(TermMode::APP_KEYPAD);
-    }
+        let right = min(right, self.grid.num_cols().0);
+        if left >= right {
+            debug!("Invalid left and right margins: ({};{})", left, right);
+            return;
+        }
 
-    #[inline]
-    fn configure_charset(&mut self, index: CharsetIndex, charset: StandardCharset) {
-        trace!("Configuring charset {:?} as {:?}", index, charset);
-        self.cursor.charsets[index] = charset;
+        trace!("Setting left and right margins: ({};{})", left, right);
+
+        self.margins = Column(left.saturating_sub(1))..Column(right);
+        self.goto(Line(0), Column(0));
     }
 
     #[inline]
-    fn set_active_charset(&mut self, index: CharsetIndex) {
-        trace!("Setting active charset {:?}", index);
-        self.active_charset = index;
+    fn copy_rect(&mut self, src: Rect, top: usize, left: usize) {
+        trace!("Copying {:?} to ({};{})", src, top, left);
+        let (lines, cols) = match self.rect_bounds(src) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let (dst_lines, dst_cols) =
+            match self.rect_bounds(Rect { top, left, bottom: top, right: left }) {
+                Some(bounds) => bounds,
+                None => return,
+            };
+
+        // Copy the source first, since it might overlap the destination
+        let rows: Vec<Vec<Cell>> = (lines.start.0..lines.end.0)
+            .map(|line| self.grid[Line(line)][cols.clone()].to_vec())
+            .collect();
+
+        // Cells moved past the bottom or right margin are dropped
+        let max_line = if self.mode.contains(TermMode::ORIGIN) {
+            self.scroll_region.end
+        } else {
+            self.grid.num_lines()
+        };
+        for (line, row) in (dst_lines.start.0..max_line.0).zip(rows) {
+            let dst_end = min(dst_cols.start + row.len(), self.grid.num_cols());
+            self.clear_split_wide_chars(Line(line), dst_cols.start..dst_end);
+
+            for (col, cell) in (dst_cols.start.0..self.grid.num_cols().0).zip(row) {
+                self.grid[Line(line)][Column(col)] = cell;
+            }
+
+            // Wide chars might have been cut in half by the edges of the source
+            self.clear_orphaned_wide_chars(Line(line), dst_cols.start..dst_end);
+        }
     }
 
     #[inline]
-    fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
-        trace!("Setting cursor style {:?}", style);
-        self.cursor_style = style;
+    fn fill_rect(&mut self, rect: Rect, c: char) {
+        trace!("Filling {:?} with {:?}", rect, c);
+        let (lines, cols) = match self.rect_bounds(rect) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let mut template = self.cursor.template.clone();
+        template.flags.remove(Flags::PROMPT_START);
+        template.c = c;
+
+        for line in lines.start.0..lines.end.0 {
+            self.clear_split_wide_chars(Line(line), cols.clone());
+            for cell in &mut self.grid[Line(line)][cols.clone()] {
+                *cell = template.clone();
+            }
+        }
     }
 
     #[inline]
-    fn set_title(&mut self, title: Option<String>) {
-        trace!("Setting title to '{:?}'", title);
-
-        self.title = title.clone();
+    fn erase_rect(&mut self, rect: Rect) {
+        trace!("Erasing {:?}", rect);
+        let (lines, cols) = match self.rect_bounds(rect) {
+            Some(bounds) => bounds,
+            None => return,
+        };
 
-        if self.dynamic_title {
-            let title = title.unwrap_or_else(|| self.default_title.clone());
-            self.event_proxy.send_event(Event::Title(title));
+        for line in lines.start.0..lines.end.0 {
+            self.clear_split_wide_chars(Line(line), cols.clone());
+            for cell in &mut self.grid[Line(line)][cols.clone()] {
+                cell.reset(&self.cursor.template);
+            }
         }
     }
 
     #[inline]
-    fn push_title(&mut self) {
-        trace!("Pushing '{:?}' onto title stack", self.title);
+    fn change_rect_attribute(&mut self, rect: Rect, attr: Attr) {
+        trace!("Changing {:?} of {:?}", attr, rect);
+
+        // In a stream, the start column may be right of the end column
+        let num_cols = self.grid.num_cols();
+        let lines = match self.rect_bounds(Rect { left: 1, right: num_cols.0, ..rect }) {
+            Some((lines, _)) => lines,
+            None => return,
+        };
+        let start_col = min(Column(rect.left.saturating_sub(1)), num_cols);
+        let end_col = min(Column(rect.right), num_cols);
+
+        let (flags, insert) = match attr {
+            Attr::Reset => {
+                (Flags::BOLD | Flags::ALL_UNDERLINES | Flags::BLINK | Flags::INVERSE, false)
+            },
+            Attr::Bold => (Flags::BOLD, true),
+            Attr::Underline => (Flags::UNDERLINE, true),
+            Attr::BlinkSlow => (Flags::BLINK_SLOW, true),
+            Attr::Reverse => (Flags::INVERSE, true),
+            Attr::CancelBoldDim => (Flags::BOLD | Flags::DIM, false),
+            Attr::CancelUnderline => (Flags::ALL_UNDERLINES, false),
+            Attr::CancelBlink => (Flags::BLINK, false),
+            Attr::CancelReverse => (Flags::INVERSE, false),
+            _ => {
+                debug!("Unsupported attribute for DECCARA: {:?}", attr);
+                return;
+            },
+        };
+
+        for line in lines.start.0..lines.end.0 {
+            // Streams cover everything between the start and end of the area
+            let (left, right) = match self.attribute_change_extent {
+                AttributeChangeExtent::Rectangle => (start_col, end_col),
+                AttributeChangeExtent::Stream => (
+                    if line == lines.start.0 { start_col } else { Column(0) },
+                    if line + 1 == lines.end.0 { end_col } else { num_cols },
+                ),
+            };
+
+            for cell in &mut self.grid[Line(line)][left..max(left, right)] {
+                cell.flags.set(flags, insert);
+            }
+        }
+    }
+
+    #[inline]
+    fn set_attribute_change_extent(&mut self, extent: AttributeChangeExtent) {
+        trace!("Setting attribute change extent: {:?}", extent);
+        self.attribute_change_extent = extent;
+    }
+
+    #[inline]
+    fn set_keypad_application_mode(&mut self) {
+        trace!("Setting keypad application mode");
+        self.mode.insert(TermMode::APP_KEYPAD);
+    }
+
+    #[inline]
+    fn unset_keypad_application_mode(&mut self) {
+        trace!("Unsetting keypad application mode");
+        self.mode.remove(TermMode::APP_KEYPAD);
+    }
+
+    #[inline]
+    fn configure_charset(&mut self, index: CharsetIndex, charset: StandardCharset) {
+        trace!("Configuring charset {:?} as {:?}", index, charset);
+        self.cursor.charsets[index] = charset;
+    }
+
+    #[inline]
+    fn set_active_charset(&mut self, index: CharsetIndex) {
+        trace!("Setting active charset {:?}", index);
+        self.active_charset = index;
+    }
+
+    #[inline]
+    fn set_single_shift(&mut self, index: CharsetIndex) {
+        trace!("Setting single shift {:?}", index);
+        self.single_shift = Some(index);
+    }
+
+    #[inline]
+    fn set_cursor_style(&mut self, style: Option<CursorStyle>) {
+        trace!("Setting cursor style {:?}", style);
+        self.cursor_style = style;
+    }
+
+    #[inline]
+    fn set_cursor_blinking(&mut self, blinking: Option<bool>) {
+        trace!("Setting cursor blinking {:?}", blinking);
+        self.cursor_blink.requested = blinking;
+        self.cursor_blink.reset();
+    }
+
+    #[inline]
+    fn set_title(&mut self, title: Option<String>) {
+        trace!("Setting title to '{:?}'", title);
+
+        self.title = title.clone();
+
+        if self.dynamic_title {
+            let title = title.unwrap_or_else(|| self.default_title.clone());
+            self.event_proxy.send_event(Event::Title(title));
+        }
+    }
+
+    #[inline]
+    fn push_title(&mut self) {
+        trace!("Pushing '{:?}' onto title stack", self.title);
 
         if self.title_stack.len() >= TITLE_STACK_MAX_DEPTH {
             let removed = self.title_stack.remove(0);
@@ -2230,6 +3583,161 @@ impl<T: EventListener> Handler for Term<T> {
             self.set_title(popped);
         }
     }
+
+    #[inline]
+    fn push_keyboard_mode(&mut self, mode: KeyboardModes) {
+        trace!("Pushing '{:?}' onto keyboard mode stack", mode);
+
+        if self.keyboard_mode_stack.len() >= KEYBOARD_MODE_STACK_MAX_DEPTH {
+            self.keyboard_mode_stack.remove(0);
+        }
+
+        self.keyboard_mode_stack.push(mode & SUPPORTED_KEYBOARD_MODES);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn pop_keyboard_modes(&mut self, to_pop: u16) {
+        trace!("Popping {} keyboard modes from stack", to_pop);
+
+        let new_len = self.keyboard_mode_stack.len().saturating_sub(to_pop as usize);
+        self.keyboard_mode_stack.truncate(new_len);
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn set_keyboard_mode(&mut self, mode: KeyboardModes, behavior: KeyboardModesApplyBehavior) {
+        trace!("Setting keyboard mode '{:?}' with {:?}", mode, behavior);
+
+        if self.keyboard_mode_stack.is_empty() {
+            self.keyboard_mode_stack.push(KeyboardModes::NO_MODE);
+        }
+
+        let mode = mode & SUPPORTED_KEYBOARD_MODES;
+        let active = self.keyboard_mode_stack.last_mut().unwrap();
+        match behavior {
+            KeyboardModesApplyBehavior::Replace => *active = mode,
+            KeyboardModesApplyBehavior::Union => active.insert(mode),
+            KeyboardModesApplyBehavior::Difference => active.remove(mode),
+        }
+
+        self.update_keyboard_mode();
+    }
+
+    #[inline]
+    fn set_modify_other_keys(&mut self, mode: ModifyOtherKeys) {
+        trace!("Setting modifyOtherKeys mode to {:?}", mode);
+
+        self.mode.remove(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS);
+        match mode {
+            ModifyOtherKeys::Reset => (),
+            ModifyOtherKeys::EnableExceptWellDefined => {
+                self.mode.insert(TermMode::MODIFY_OTHER_KEYS)
+            },
+            ModifyOtherKeys::EnableAll => self.mode.insert(TermMode::MODIFY_ALL_KEYS),
+        }
+    }
+
+    #[inline]
+    fn report_terminfo_capability<W: io::Write>(&mut self, writer: &mut W, name: &str) {
+        trace!("Reporting terminfo capability {:?}", name);
+
+        let hex_name = hex_encode(name);
+        let response = match terminfo_capability(name) {
+            Some("") => format!("\x1bP1+r{}\x1b\\", hex_name),
+            Some(value) => format!("\x1bP1+r{}={}\x1b\\", hex_name, hex_encode(value)),
+            None => format!("\x1bP0+r{}\x1b\\", hex_name),
+        };
+        let _ = writer.write_all(response.as_bytes());
+    }
+
+    #[inline]
+    fn report_modify_other_keys<W: io::Write>(&mut self, writer: &mut W) {
+        let level = if self.mode.contains(TermMode::MODIFY_ALL_KEYS) {
+            2
+        } else if self.mode.contains(TermMode::MODIFY_OTHER_KEYS) {
+            1
+        } else {
+            0
+        };
+
+        trace!("Reporting modifyOtherKeys mode {}", level);
+        let _ = write!(writer, "\x1b[>4;{}m", level);
+    }
+
+    #[inline]
+    fn report_keyboard_mode<W: io::Write>(&mut self, writer: &mut W) {
+        let modes = self.keyboard_mode_stack.last().copied().unwrap_or(KeyboardModes::NO_MODE);
+        trace!("Reporting keyboard mode '{:?}'", modes);
+        let _ = write!(writer, "\x1b[?{}u", modes.bits());
+    }
+
+    #[inline]
+    fn text_area_size_pixels<W: io::Write>(&mut self, writer: &mut W) {
+        let width = self.graphics.cell_width * self.cols().0;
+        let height = self.graphics.cell_height * self.lines().0;
+        let _ = write!(writer, "\x1b[4;{};{}t", height, width);
+    }
+
+    #[inline]
+    fn text_area_size_chars<W: io::Write>(&mut self, writer: &mut W) {
+        let _ = write!(writer, "\x1b[8;{};{}t", self.lines(), self.cols());
+    }
+
+    #[inline]
+    fn cell_size_pixels<W: io::Write>(&mut self, writer: &mut W) {
+        let (width, height) = (self.graphics.cell_width, self.graphics.cell_height);
+        let _ = write!(writer, "\x1b[6;{};{}t", height, width);
+    }
+
+    #[inline]
+    fn resize_text_area_pixels(&mut self, height: Option<usize>, width: Option<usize>) {
+        if !self.allow_resize {
+            debug!("Ignoring request to resize the window");
+            return;
+        }
+
+        let width = width.unwrap_or(self.graphics.cell_width * self.cols().0);
+        let height = height.unwrap_or(self.graphics.cell_height * self.lines().0);
+        let width = min(width, MAX_TEXT_AREA_SIZE) as u32;
+        let height = min(height, MAX_TEXT_AREA_SIZE) as u32;
+        self.event_proxy.send_event(Event::TextAreaResize(width, height));
+    }
+
+    #[inline]
+    fn resize_text_area_chars(&mut self, lines: Option<usize>, cols: Option<usize>) {
+        let height = lines.map(|lines| lines.saturating_mul(self.graphics.cell_height));
+        let width = cols.map(|cols| cols.saturating_mul(self.graphics.cell_width));
+        self.resize_text_area_pixels(height, width);
+    }
+}
+
+/// Value of a terminfo capability reported through XTGETTCAP.
+///
+/// Boolean capabilities have an empty value.
+fn terminfo_capability(name: &str) -> Option<&'static str> {
+    let value = match name {
+        "TN" | "name" => "alacritty",
+        "Co" | "colors" => "256",
+        // Truecolor support, as detected by tmux and neovim
+        "RGB" | "Tc" => "",
+        "setrgbf" => "\x1b[38;2;%p1%d;%p2%d;%p3%dm",
+        "setrgbb" => "\x1b[48;2;%p1%d;%p2%d;%p3%dm",
+        "Setulc" => "\x1b[58;2;%p1%{65536}%/%d;%p1%{256}%/%{255}%&%d;%p1%{255}%&%dm",
+        "Ms" => "\x1b]52;%p1%s;%p2%s\x07",
+        "Ss" => "\x1b[%p1%d q",
+        "Se" => "\x1b[0 q",
+        "Sync" => "\x1bP=%p1%ds\x1b\\",
+        "Smulx" => "\x1b[4:%p1%dm",
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Encode a string as hex, like in XTGETTCAP requests and responses.
+fn hex_encode(input: &str) -> String {
+    input.bytes().map(|byte| format!("{:02X}", byte)).collect()
 }
 
 struct TabStops {
@@ -2280,13 +3788,42 @@ impl IndexMut<Column> for TabStops {
     }
 }
 
+/// Terminal test helpers.
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{SizeInfo, Term};
+
+    use crate::clipboard::Clipboard;
+    use crate::config::Config;
+
+    /// Size of a terminal with 7 columns and 17 lines.
+    pub fn size_info() -> SizeInfo {
+        SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        }
+    }
+
+    /// Create a terminal with 7 columns and 17 lines.
+    pub fn mock_term<C, T>(config: &Config<C>, event_proxy: T) -> Term<T> {
+        Term::new(config, &size_info(), Clipboard::new_nop(), event_proxy)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use std::cell::RefCell;
     use std::mem;
+    use std::rc::Rc;
 
-    use crate::ansi::{self, CharsetIndex, Handler, StandardCharset};
+    use crate::ansi::{self, CharsetIndex, Handler, Progress, StandardCharset};
     use crate::clipboard::Clipboard;
     use crate::config::MockConfig;
     use crate::event::{Event, EventListener};
@@ -2294,12 +3831,50 @@ mod tests {
     use crate::index::{Column, Line, Point, Side};
     use crate::selection::{Selection, SelectionType};
     use crate::term::cell::{Cell, Flags};
+    use crate::term::damage::{LineDamageBounds, TermDamage};
+    use crate::term::test::mock_term;
 
     struct Mock;
     impl EventListener for Mock {
         fn send_event(&self, _event: Event) {}
     }
 
+    #[test]
+    fn minimum_contrast() {
+        let config: MockConfig = serde_yaml::from_str(
+            "colors: {primary: {background: '#000000'}, minimum_contrast: 4.5}",
+        )
+        .unwrap();
+        let mut term = mock_term(&config, Mock);
+
+        let dark = Rgb { r: 0x20, g: 0x20, b: 0x40 };
+        term.grid[Line(0)][Column(0)].c = 'a';
+        term.grid[Line(0)][Column(0)].fg = Color::Spec(dark);
+        term.grid[Line(0)][Column(1)].c = 'b';
+        term.grid[Line(0)][Column(1)].fg = Color::Spec(dark);
+        term.grid[Line(0)][Column(1)].flags.insert(Flags::HIDDEN);
+        term.grid[Line(0)][Column(2)].c = 'c';
+        term.grid[Line(0)][Column(2)].fg = Color::Spec(dark);
+        term.grid[Line(0)][Column(3)].c = 'd';
+        term.grid[Line(0)][Column(3)].fg = Color::Spec(Rgb { r: 0xff, g: 0xff, b: 0xff });
+
+        let cells: Vec<_> = term
+            .renderable_cells(&config)
+            .filter(|cell| cell.line == Line(0))
+            .filter(|cell| match cell.inner {
+                RenderableCellContent::Chars(_) => true,
+                RenderableCellContent::Cursor(_) => false,
+            })
+            .collect();
+        let contrast = cells[0].fg.contrast(cells[0].bg);
+        assert!(contrast >= 4.5 && contrast < 5., "contrast {}", contrast);
+        assert_eq!(cells[1].fg, dark);
+        assert_eq!(cells[2].fg, dark.with_contrast(cells[2].bg, 4.5));
+        assert_eq!(cells[3].fg, Rgb { r: 0xff, g: 0xff, b: 0xff });
+
+        assert_eq!(dark.with_contrast(Rgb { r: 0x80, g: 0x80, b: 0x80 }, 21.), Rgb::default());
+    }
+
     #[test]
     fn semantic_selection_works() {
         let size = SizeInfo {
@@ -2385,6 +3960,66 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
     }
 
+    #[test]
+    fn block_selection_works() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        let mut grid: Grid<Cell> = Grid::new(Line(3), Column(5), 0, Cell::default());
+        grid[Line(0)][Column(0)].c = 'a';
+        grid[Line(0)][Column(1)].c = 'b';
+        grid[Line(0)][Column(2)].c = '汉';
+        grid[Line(0)][Column(2)].flags.insert(Flags::WIDE_CHAR);
+        grid[Line(0)][Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        grid[Line(1)][Column(0)].c = '汉';
+        grid[Line(1)][Column(0)].flags.insert(Flags::WIDE_CHAR);
+        grid[Line(1)][Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        grid[Line(1)][Column(2)].c = 'c';
+        grid[Line(1)][Column(3)].c = '\t';
+
+        mem::swap(&mut term.grid, &mut grid);
+
+        // Wide chars cut in half at the edges and blank lines are padded to the block's width
+        let mut selection =
+            Selection::new(SelectionType::Block, Point { line: 2, col: Column(1) }, Side::Left);
+        selection.update(Point { line: 0, col: Column(2) }, Side::Right);
+        *term.selection_mut() = Some(selection);
+        assert_eq!(term.selection_to_string(), Some(String::from("b \n c\n  ")));
+
+        // Wide chars within the block are kept and tabs are expanded
+        let mut selection =
+            Selection::new(SelectionType::Block, Point { line: 2, col: Column(0) }, Side::Left);
+        selection.update(Point { line: 1, col: Column(4) }, Side::Right);
+        *term.selection_mut() = Some(selection);
+        assert_eq!(term.selection_to_string(), Some(String::from("ab汉 \n汉c  ")));
+    }
+
+    #[test]
+    fn vi_mode_keeps_selection() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        for c in "abcdef".chars() {
+            term.input(c);
+        }
+
+        let mut selection =
+            Selection::new(SelectionType::Simple, Point::new(16, Column(1)), Side::Left);
+        selection.update(Point::new(16, Column(2)), Side::Right);
+        *term.selection_mut() = Some(selection);
+
+        // The vi mode cursor continues the selection from its end
+        term.toggle_vi_mode();
+        assert_eq!(term.vi_mode_cursor.point, Point::new(Line(0), Column(2)));
+        term.vi_motion(ViMotion::Right);
+        assert_eq!(term.selection_to_string(), Some(String::from("bcd")));
+
+        // Swapping the ends adjusts the start of the selection
+        term.vi_swap_selection_ends();
+        assert_eq!(term.vi_mode_cursor.point, Point::new(Line(0), Column(1)));
+        term.vi_motion(ViMotion::Left);
+        assert_eq!(term.selection_to_string(), Some(String::from("abcd")));
+
+        term.toggle_vi_mode();
+        assert_eq!(term.selection(), &None);
+    }
+
     #[test]
     fn selecting_empty_line() {
         let size = SizeInfo {
@@ -2430,6 +4065,618 @@ mod tests {
         assert_eq!(deserialized, grid);
     }
 
+    #[test]
+    fn insert_graphic() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        term.goto(Line(1), Column(1));
+
+        // Covers two lines and three columns
+        term.insert_graphic(Graphic { width: 7, height: 4, pixels: vec![255; 7 * 4 * 4] });
+
+        let graphic = term.grid[Line(2)][Column(3)].graphic().unwrap();
+        assert_eq!((graphic.offset_x, graphic.offset_y), (6, 3));
+        assert!(term.grid[Line(2)][Column(4)].graphic().is_none());
+        assert!(term.grid[Line(3)][Column(1)].graphic().is_none());
+        assert_eq!(term.cursor.point, Point::new(Line(3), Column(1)));
+
+        let id = graphic.texture.id;
+        assert_eq!(term.graphics_take_queues().unwrap().pending[0].id, id);
+
+        // Texture is released once all its cells are overwritten
+        term.goto(Line(1), Column(0));
+        term.clear_line(ansi::LineClearMode::All);
+        assert!(term.graphics_take_queues().is_none());
+        term.goto(Line(2), Column(0));
+        term.clear_line(ansi::LineClearMode::All);
+        assert_eq!(term.graphics_take_queues().unwrap().remove_queue, vec![id]);
+    }
+
+    #[test]
+    fn clipboard_read_permission() {
+        let mut config = MockConfig::default();
+        config.clipboard_read = ClipboardReadPermission::Deny;
+        let mut term = mock_term(&config, Mock);
+
+        let mut writer = Vec::new();
+        term.write_clipboard(b'c', &mut writer, "\x07");
+        assert!(writer.is_empty());
+
+        config.clipboard_read = ClipboardReadPermission::Allow;
+        term.update_config(&config);
+        term.write_clipboard(b'c', &mut writer, "\x07");
+        assert_eq!(writer, b"\x1b]52;c;\x07");
+
+        // Reads are delayed until the user answers the prompt
+        config.clipboard_read = ClipboardReadPermission::Ask;
+        term.update_config(&config);
+        writer.clear();
+        term.write_clipboard(b'p', &mut writer, "\x1b\\");
+        assert!(writer.is_empty());
+        assert_eq!(term.answer_clipboard_read(true), Some(String::from("\x1b]52;p;\x1b\\")));
+        assert_eq!(term.answer_clipboard_read(true), None);
+
+        term.write_clipboard(b'c', &mut writer, "\x07");
+        assert_eq!(term.answer_clipboard_read(false), None);
+        assert_eq!(term.answer_clipboard_read(true), None);
+    }
+
+    #[test]
+    fn keyboard_mode_stack() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        term.push_keyboard_mode(KeyboardModes::REPORT_EVENT_TYPES);
+        term.set_keyboard_mode(
+            KeyboardModes::REPORT_ALL_KEYS_AS_ESC,
+            KeyboardModesApplyBehavior::Union,
+        );
+        assert!(term
+            .mode()
+            .contains(TermMode::REPORT_EVENT_TYPES | TermMode::REPORT_ALL_KEYS_AS_ESC));
+        assert!(!term.mode().contains(TermMode::DISAMBIGUATE_ESC_CODES));
+
+        let mut writer = Vec::new();
+        term.report_keyboard_mode(&mut writer);
+        assert_eq!(writer, b"\x1b[?10u");
+
+        // Alternate screen has its own stack
+        term.swap_alt();
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+        term.swap_alt();
+
+        term.pop_keyboard_modes(1);
+        assert_eq!(
+            *term.mode() & TermMode::KITTY_KEYBOARD_PROTOCOL,
+            TermMode::DISAMBIGUATE_ESC_CODES
+        );
+
+        term.pop_keyboard_modes(5);
+        assert!(!term.mode().intersects(TermMode::KITTY_KEYBOARD_PROTOCOL));
+
+        // Unsupported flags are not reported as active
+        term.push_keyboard_mode(
+            KeyboardModes::DISAMBIGUATE_ESC_CODES | KeyboardModes::REPORT_ASSOCIATED_TEXT,
+        );
+        writer.clear();
+        term.report_keyboard_mode(&mut writer);
+        assert_eq!(writer, b"\x1b[?1u");
+    }
+
+    #[test]
+    fn modify_other_keys() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.set_modify_other_keys(ModifyOtherKeys::EnableAll);
+        assert!(term.mode().contains(TermMode::MODIFY_ALL_KEYS));
+
+        let mut writer = Vec::new();
+        term.report_modify_other_keys(&mut writer);
+        assert_eq!(writer, b"\x1b[>4;2m");
+
+        term.set_modify_other_keys(ModifyOtherKeys::EnableExceptWellDefined);
+        assert!(term.mode().contains(TermMode::MODIFY_OTHER_KEYS));
+        assert!(!term.mode().contains(TermMode::MODIFY_ALL_KEYS));
+
+        // Mode is cleared by RIS
+        term.reset_state();
+        assert!(!term.mode().intersects(TermMode::MODIFY_OTHER_KEYS | TermMode::MODIFY_ALL_KEYS));
+    }
+
+    #[test]
+    fn clamp_text_area_resize() {
+        #[derive(Clone, Default)]
+        struct Resizes(Rc<RefCell<Vec<(u32, u32)>>>);
+        impl EventListener for Resizes {
+            fn send_event(&self, event: Event) {
+                if let Event::TextAreaResize(width, height) = event {
+                    self.0.borrow_mut().push((width, height));
+                }
+            }
+        }
+
+        let mut config = MockConfig::default();
+        config.window.allow_resize = true;
+        let resizes = Resizes::default();
+        let mut term = mock_term(&config, resizes.clone());
+
+        term.resize_text_area_chars(Some(usize::max_value()), None);
+        term.resize_text_area_pixels(Some(30), Some(usize::max_value()));
+
+        let max = MAX_TEXT_AREA_SIZE as u32;
+        assert_eq!(*resizes.0.borrow(), [(21, max), (max, 30)]);
+    }
+
+    #[test]
+    fn report_terminfo_capability() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        let mut writer = Vec::new();
+        term.report_terminfo_capability(&mut writer, "RGB");
+        assert_eq!(writer, b"\x1bP1+r524742\x1b\\");
+
+        writer.clear();
+        term.report_terminfo_capability(&mut writer, "Co");
+        assert_eq!(writer, b"\x1bP1+r436F=323536\x1b\\");
+
+        writer.clear();
+        term.report_terminfo_capability(&mut writer, "Smulx");
+        assert_eq!(writer, &b"\x1bP1+r536D756C78=1B5B343A25703125646D\x1b\\"[..]);
+
+        writer.clear();
+        term.report_terminfo_capability(&mut writer, "foo");
+        assert_eq!(writer, b"\x1bP0+r666F6F\x1b\\");
+    }
+
+    #[test]
+    fn report_mode_state() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        assert_eq!(term.mode_state(ansi::Mode::BracketedPaste), ModeState::Reset);
+        term.set_mode(ansi::Mode::BracketedPaste);
+        assert_eq!(term.mode_state(ansi::Mode::BracketedPaste), ModeState::Set);
+
+        term.set_mode(ansi::Mode::SwapScreenAndSetRestoreCursor);
+        assert_eq!(term.mode_state(ansi::Mode::SwapScreenAndSetRestoreCursor), ModeState::Set);
+
+        assert_eq!(term.mode_state(ansi::Mode::LineWrap), ModeState::Set);
+        assert_eq!(term.mode_state(ansi::Mode::DECCOLM), ModeState::PermanentlyReset);
+    }
+
+    #[test]
+    fn report_color_scheme() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.set_mode(ansi::Mode::ColorPaletteUpdates);
+        assert_eq!(term.mode_state(ansi::Mode::ColorPaletteUpdates), ModeState::Set);
+
+        let mut writer = Vec::new();
+        term.report_color_scheme(&mut writer);
+        assert_eq!(writer, b"\x1b[?997;1n");
+
+        let mut colors = Colors::default();
+        colors.primary.background = Rgb { r: 0xee, g: 0xee, b: 0xdd };
+        term.update_colors(&colors);
+        assert_eq!(term.color_scheme_report(), "\x1b[?997;2n");
+    }
+
+    #[test]
+    fn dynamic_cursor_color() {
+        let config = MockConfig::default();
+        let mut term = mock_term(&config, Mock);
+        let cursor = NamedColor::Cursor as usize;
+
+        // Without a configured cursor color, queries report the foreground
+        let mut writer = Vec::new();
+        term.dynamic_color_sequence(&mut writer, 12, cursor, "\x07");
+        assert_eq!(writer, b"\x1b]12;rgb:eaea/eaea/eaea\x07");
+        assert_eq!(term.renderable_cursor(&config).cursor_color, None);
+
+        let color = Rgb { r: 0xff, g: 0x00, b: 0x80 };
+        term.set_color(cursor, color);
+        assert_eq!(term.renderable_cursor(&config).cursor_color, Some(color));
+
+        // Configuration reloads keep colors set by the application
+        term.update_config(&config);
+        assert_eq!(term.renderable_cursor(&config).cursor_color, Some(color));
+
+        term.reset_color(cursor);
+        assert_eq!(term.renderable_cursor(&config).cursor_color, None);
+    }
+
+    #[test]
+    fn prompt_marks() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        // Prompt, command, two lines of output and the next prompt
+        term.set_prompt_mark(ansi::PromptMark::PromptStart);
+        term.terminal_attribute(ansi::Attr::Reset);
+        term.input('$');
+        term.set_prompt_mark(ansi::PromptMark::CommandStart);
+        term.input('l');
+        term.input('s');
+        term.linefeed();
+        term.carriage_return();
+        term.set_prompt_mark(ansi::PromptMark::OutputStart);
+        term.input('a');
+        term.linefeed();
+        term.carriage_return();
+        term.input('b');
+        term.linefeed();
+        term.carriage_return();
+        term.set_prompt_mark(ansi::PromptMark::CommandEnd);
+        term.set_prompt_mark(ansi::PromptMark::PromptStart);
+        term.input('$');
+        term.input('$');
+
+        assert!(term.grid[16][Column(0)].flags.contains(Flags::PROMPT_START));
+        assert!(!term.grid[16][Column(1)].flags.contains(Flags::PROMPT_START));
+        assert!(!term.grid[13][Column(1)].flags.contains(Flags::PROMPT_START));
+
+        assert_eq!(term.previous_prompt(), None);
+        assert_eq!(term.next_prompt(), Some(13));
+        assert_eq!(
+            term.last_command_output(),
+            Some((Point::new(15, Column(0)), Point::new(14, Column(0))))
+        );
+
+        let output = Some((Point::new(15, Column(0)), Point::new(14, Column(0))));
+        assert_eq!(term.command_output_at(16), output);
+        assert_eq!(term.command_output_at(14), output);
+        assert_eq!(term.command_output_at(13), None);
+        assert_eq!(term.command_output_at(0), None);
+
+        let (start, end) = term.last_command_output().unwrap();
+        assert_eq!(term.bounds_to_string(start, end), "a\nb");
+    }
+
+    #[test]
+    fn clear_to_prompt() {
+        let mut config = MockConfig::default();
+        config.scrolling.preserve_history = true;
+        let mut term = mock_term(&config, Mock);
+
+        // Output followed by a prompt spanning two lines
+        term.input('a');
+        term.linefeed();
+        term.carriage_return();
+        term.set_prompt_mark(ansi::PromptMark::PromptStart);
+        term.input('$');
+        term.linefeed();
+        term.carriage_return();
+        term.input('>');
+
+        term.clear_to_prompt();
+
+        assert_eq!(term.grid().history_size(), 1);
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, '$');
+        assert_eq!(term.grid()[Line(1)][Column(0)].c, '>');
+        assert_eq!(term.cursor.point.line, Line(1));
+
+        // Applications can't clear the history
+        term.clear_screen(ansi::ClearMode::Saved);
+        assert_eq!(term.grid().history_size(), 1);
+
+        term.clear_history();
+        assert_eq!(term.grid().history_size(), 0);
+    }
+
+    #[test]
+    fn soft_reset() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.input('a');
+        term.set_mode(ansi::Mode::Insert);
+        term.set_mode(ansi::Mode::Origin);
+        term.unset_mode(ansi::Mode::LineWrap);
+        term.unset_mode(ansi::Mode::ShowCursor);
+        term.set_scrolling_region(2, 5);
+        term.configure_charset(CharsetIndex::G0, StandardCharset::SpecialCharacterAndLineDrawing);
+        term.terminal_attribute(ansi::Attr::Bold);
+        term.goto(Line(1), Column(2));
+        term.save_cursor_position();
+
+        term.soft_reset();
+
+        // Screen content and cursor position are kept
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+        assert_eq!(term.cursor.point, Point::new(Line(2), Column(2)));
+
+        assert!(!term.mode.intersects(TermMode::INSERT | TermMode::ORIGIN));
+        assert!(term.mode.contains(TermMode::LINE_WRAP | TermMode::SHOW_CURSOR));
+        assert_eq!(term.scroll_region, Line(0)..Line(17));
+        assert!(term.cursor.template.flags.is_empty());
+        assert_eq!(term.cursor_save.point, Point::default());
+
+        term.input('q');
+        assert_eq!(term.grid[Line(2)][Column(2)].c, 'q');
+    }
+
+    #[test]
+    fn left_and_right_margins() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        for line in 0..17 {
+            term.goto(Line(line), Column(0));
+            for c in "abcdefg".chars() {
+                term.input(c);
+            }
+        }
+
+        // Without DECLRMM the sequence saves the cursor position
+        term.goto(Line(1), Column(1));
+        term.set_left_and_right_margins(2, 4);
+        assert_eq!(term.margins, Column(0)..Column(7));
+        assert_eq!(term.cursor_save.point, Point::new(Line(1), Column(1)));
+
+        term.set_mode(ansi::Mode::LeftRightMargin);
+        term.set_left_and_right_margins(2, 4);
+        assert_eq!(term.margins, Column(1)..Column(4));
+        assert_eq!(term.cursor.point, Point::new(Line(0), Column(0)));
+
+        // Text wraps at the right margin and continues at the left margin
+        term.goto(Line(0), Column(2));
+        for c in "xyz".chars() {
+            term.input(c);
+        }
+        assert_eq!(term.grid[Line(0)][Column(2)].c, 'x');
+        assert_eq!(term.grid[Line(0)][Column(3)].c, 'y');
+        assert_eq!(term.grid[Line(1)][Column(1)].c, 'z');
+
+        // Carriage return and cursor movement stop at the margins
+        term.carriage_return();
+        assert_eq!(term.cursor.point.col, Column(1));
+        term.move_backward(Column(5));
+        assert_eq!(term.cursor.point.col, Column(1));
+        term.move_forward(Column(5));
+        assert_eq!(term.cursor.point.col, Column(3));
+
+        // Origin mode is relative to the left margin
+        term.set_mode(ansi::Mode::Origin);
+        term.goto(Line(0), Column(5));
+        assert_eq!(term.cursor.point, Point::new(Line(0), Column(3)));
+
+        // Leaving DECLRMM resets the margins
+        term.unset_mode(ansi::Mode::LeftRightMargin);
+        assert_eq!(term.margins, Column(0)..Column(7));
+    }
+
+    #[test]
+    fn margin_scrolling_and_editing() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        for line in 0..17 {
+            term.goto(Line(line), Column(0));
+            for c in "abcdefg".chars() {
+                term.input(c);
+            }
+        }
+        term.goto(Line(1), Column(1));
+        term.input('z');
+
+        term.set_mode(ansi::Mode::LeftRightMargin);
+        term.set_left_and_right_margins(2, 4);
+
+        // Scrolling only moves the cells between the margins
+        term.scroll_up(Line(1));
+        assert_eq!(term.grid[Line(0)][Column(0)].c, 'a');
+        assert_eq!(term.grid[Line(0)][Column(1)].c, 'z');
+        assert_eq!(term.grid[Line(0)][Column(4)].c, 'e');
+        assert_eq!(term.grid[Line(16)][Column(0)].c, 'a');
+        assert_eq!(term.grid[Line(16)][Column(1)].c, ' ');
+        assert_eq!(term.grid[Line(16)][Column(4)].c, 'e');
+        assert_eq!(term.grid.history_size(), 0);
+
+        term.goto(Line(0), Column(0));
+        term.scroll_down(Line(1));
+        assert_eq!(term.grid[Line(0)][Column(1)].c, ' ');
+        assert_eq!(term.grid[Line(1)][Column(1)].c, 'z');
+
+        // Inserting and deleting characters is limited to the margins
+        term.goto(Line(2), Column(1));
+        term.insert_blank(Column(1));
+        assert_eq!(term.grid[Line(2)][Column(1)].c, ' ');
+        assert_eq!(term.grid[Line(2)][Column(2)].c, 'b');
+        assert_eq!(term.grid[Line(2)][Column(3)].c, 'c');
+        assert_eq!(term.grid[Line(2)][Column(4)].c, 'e');
+
+        term.delete_chars(Column(10));
+        assert_eq!(term.grid[Line(2)][Column(0)].c, 'a');
+        assert_eq!(term.grid[Line(2)][Column(3)].c, ' ');
+        assert_eq!(term.grid[Line(2)][Column(4)].c, 'e');
+    }
+
+    #[test]
+    fn progress_keeps_percentage() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.set_progress(Progress::Normal(30));
+        term.set_progress(Progress::Paused(None));
+        assert_eq!(term.progress, Progress::Paused(Some(30)));
+        term.set_progress(Progress::Error(None));
+        assert_eq!(term.progress, Progress::Error(Some(30)));
+
+        term.set_progress(Progress::Indeterminate);
+        term.set_progress(Progress::Error(None));
+        assert_eq!(term.progress, Progress::Error(None));
+
+        term.set_progress(Progress::Normal(50));
+        term.reset_state();
+        assert_eq!(term.progress, Progress::Hidden);
+    }
+
+    #[test]
+    fn blinking_text() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.terminal_attribute(Attr::BlinkSlow);
+        term.input('a');
+        term.terminal_attribute(Attr::BlinkFast);
+        term.input('b');
+        term.terminal_attribute(Attr::CancelBlink);
+        term.input('c');
+
+        let line = &term.grid[Line(0)];
+        assert_eq!(line[Column(0)].flags & Flags::BLINK, Flags::BLINK_SLOW);
+        assert_eq!(line[Column(1)].flags & Flags::BLINK, Flags::BLINK_FAST);
+        assert!(!line[Column(2)].flags.intersects(Flags::BLINK));
+
+        // Blinking text is shown first and alternates with every interval
+        let start = term.text_blink.start_time;
+        let visible = |flags, millis| {
+            term.text_blink.visible_at_instant(flags, start + Duration::from_millis(millis))
+        };
+        assert!(visible(Flags::BLINK_SLOW, 0));
+        assert!(!visible(Flags::BLINK_SLOW, 500));
+        assert!(visible(Flags::BLINK_SLOW, 1000));
+        assert!(!visible(Flags::BLINK_FAST, 200));
+        assert!(visible(Flags::BLINK_FAST, 400));
+        assert!(visible(Flags::empty(), 500));
+    }
+
+    #[test]
+    fn blinking_cursor() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        // The cursor is steady until an application requests blinking
+        assert!(!term.cursor_blink.enabled());
+        term.set_mode(ansi::Mode::BlinkingCursor);
+        assert!(term.cursor_blink.enabled());
+        assert!(term.cursor_blink_deadline().is_some());
+
+        // Blinking stops with a visible cursor after the timeout
+        let start = term.cursor_blink.start_time;
+        let visible =
+            |millis| term.cursor_blink.visible_at_instant(start + Duration::from_millis(millis));
+        assert!(visible(0));
+        assert!(!visible(750));
+        assert!(visible(1500));
+        assert!(visible(5250));
+
+        term.set_cursor_blinking(Some(false));
+        assert!(!term.cursor_blink.enabled());
+        assert_eq!(term.cursor_blink_deadline(), None);
+    }
+
+    #[test]
+    fn remote_working_directory() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.set_working_directory("localhost", PathBuf::from("/tmp"));
+        assert_eq!(term.working_directory(), Some(Path::new("/tmp")));
+
+        term.set_working_directory("remote.invalid", PathBuf::from("/srv"));
+        assert_eq!(term.working_directory(), None);
+    }
+
+    #[test]
+    fn collect_unused_hyperlinks() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        // The first link is kept in view, while all others overwrite each other
+        term.set_hyperlink(Some(Hyperlink { id: None, uri: String::from("kept") }));
+        term.input('a');
+        for i in 0..3 * HYPERLINK_GC_THRESHOLD {
+            term.set_hyperlink(Some(Hyperlink { id: None, uri: i.to_string() }));
+            term.goto_col(Column(1));
+            term.input('b');
+        }
+
+        assert!(term.hyperlinks.len() <= HYPERLINK_GC_THRESHOLD);
+        let uri = |term: &Term<Mock>, col| {
+            let id = term.grid[Line(0)][Column(col)].hyperlink().unwrap();
+            term.hyperlink_uri(id).map(String::from)
+        };
+        assert_eq!(uri(&term, 0), Some(String::from("kept")));
+        assert_eq!(uri(&term, 1), Some((3 * HYPERLINK_GC_THRESHOLD - 1).to_string()));
+    }
+
+    #[test]
+    fn full_reset() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.set_hyperlink(Some(Hyperlink { id: None, uri: "https://example.com".into() }));
+        term.input('a');
+        term.set_title(Some("Test".into()));
+        term.push_title();
+        term.push_keyboard_mode(KeyboardModes::DISAMBIGUATE_ESC_CODES);
+        term.set_color(NamedColor::Foreground as usize, Rgb { r: 1, g: 2, b: 3 });
+        term.mode.insert(TermMode::VI);
+
+        term.reset_state();
+
+        assert_eq!(term.grid[Line(0)][Column(0)], Cell::default());
+        assert_eq!(term.title, None);
+        assert!(term.title_stack.is_empty());
+        assert!(term.hyperlinks.is_empty());
+        assert!(term.keyboard_mode_stack.is_empty());
+        assert!(!term.color_modified.iter().any(|&modified| modified));
+        assert_eq!(term.mode, TermMode::default() | TermMode::VI);
+    }
+
+    #[test]
+    fn rectangular_area_operations() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        let text = |term: &Term<Mock>, line: usize| -> String {
+            term.grid[Line(line)][..].iter().map(|cell| cell.c).collect()
+        };
+
+        term.fill_rect(Rect { top: 1, left: 2, bottom: 2, right: 4 }, 'x');
+        assert_eq!(text(&term, 0), " xxx   ");
+        assert_eq!(text(&term, 1), " xxx   ");
+        assert_eq!(text(&term, 2), "       ");
+
+        // Overlapping copy, clipped at the right margin
+        term.input('a');
+        term.copy_rect(Rect { top: 1, left: 1, bottom: 2, right: 4 }, 2, 5);
+        assert_eq!(text(&term, 1), " xxxaxx");
+        assert_eq!(text(&term, 2), "     xx");
+
+        term.erase_rect(Rect { top: 2, left: 3, bottom: 3, right: 100 });
+        assert_eq!(text(&term, 0), "axxx   ");
+        assert_eq!(text(&term, 1), " x     ");
+        assert_eq!(text(&term, 2), "       ");
+
+        // Attribute changes cover the whole lines between start and end by default
+        let area = Rect { top: 1, left: 3, bottom: 2, right: 2 };
+        term.change_rect_attribute(area, Attr::Bold);
+        assert!(!term.grid[Line(0)][Column(1)].flags.contains(Flags::BOLD));
+        assert!(term.grid[Line(0)][Column(6)].flags.contains(Flags::BOLD));
+        assert!(term.grid[Line(1)][Column(0)].flags.contains(Flags::BOLD));
+        assert!(!term.grid[Line(1)][Column(2)].flags.contains(Flags::BOLD));
+
+        term.set_attribute_change_extent(AttributeChangeExtent::Rectangle);
+        term.change_rect_attribute(Rect { top: 1, left: 1, bottom: 2, right: 7 }, Attr::Reset);
+        term.change_rect_attribute(area, Attr::Reverse);
+        assert!(!term.grid[Line(0)][Column(6)].flags.contains(Flags::BOLD));
+        assert!(!term.grid[Line(0)][Column(6)].flags.contains(Flags::INVERSE));
+        assert!(!term.grid[Line(1)][Column(0)].flags.contains(Flags::INVERSE));
+    }
+
+    #[test]
+    fn report_window_size() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        let mut writer = Vec::new();
+        term.text_area_size_pixels(&mut writer);
+        term.cell_size_pixels(&mut writer);
+        term.text_area_size_chars(&mut writer);
+        assert_eq!(writer, b"\x1b[4;51;21t\x1b[6;3;3t\x1b[8;17;7t");
+    }
+
+    #[test]
+    fn indexed_color_sequence() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        let original = term.colors[200usize];
+
+        term.set_color(200, Rgb { r: 0x12, g: 0x34, b: 0x56 });
+        let mut writer = Vec::new();
+        term.color_sequence(&mut writer, 200, "\x1b\\");
+        assert_eq!(writer, b"\x1b]4;200;rgb:1212/3434/5656\x1b\\");
+
+        term.reset_color(200);
+        assert_eq!(term.colors[200usize], original);
+    }
+
     #[test]
     fn input_line_drawing_character() {
         let size = SizeInfo {
@@ -2449,6 +4696,257 @@ mod tests {
         assert_eq!(term.grid()[&cursor].c, '▒');
     }
 
+    #[test]
+    fn mouse_encodings_are_exclusive() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        let encodings = TermMode::SGR_MOUSE | TermMode::UTF8_MOUSE | TermMode::SGR_PIXELS_MOUSE;
+
+        term.set_mode(ansi::Mode::SgrMouse);
+        term.set_mode(ansi::Mode::SgrPixelsMouse);
+        assert_eq!(term.mode & encodings, TermMode::SGR_PIXELS_MOUSE);
+        assert_eq!(term.mode_state(ansi::Mode::SgrPixelsMouse), ModeState::Set);
+
+        term.set_mode(ansi::Mode::Utf8Mouse);
+        assert_eq!(term.mode & encodings, TermMode::UTF8_MOUSE);
+
+        term.set_mode(ansi::Mode::SgrPixelsMouse);
+        term.unset_mode(ansi::Mode::SgrPixelsMouse);
+        assert!(!term.mode.intersects(encodings));
+    }
+
+    #[test]
+    fn identity_responses() {
+        let mut config = MockConfig::default();
+        config.identity = serde_yaml::from_str("answerback: 'alacritty {version}'").unwrap();
+        let mut term = mock_term(&config, Mock);
+
+        let mut response = Vec::new();
+        term.answerback(&mut response);
+        let expected = format!("alacritty {}", env!("CARGO_PKG_VERSION"));
+        assert_eq!(String::from_utf8(response).unwrap(), expected);
+
+        let mut response = Vec::new();
+        term.identify_terminal(&mut response, None);
+        assert_eq!(response, b"\x1b[?6c");
+
+        let mut response = Vec::new();
+        term.identify_terminal(&mut response, Some('>'));
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("\x1b[>0;") && response.ends_with(";1c"));
+        assert!(!response.contains('{'));
+    }
+
+    #[test]
+    fn alt_screen_history() {
+        let mut config = MockConfig::default();
+        config.scrolling.alt_screen_history = true;
+        let mut term = mock_term(&config, Mock);
+
+        term.input('p');
+        term.swap_alt();
+
+        // Scroll a line off the top of the alternate screen
+        term.goto(Line(0), Column(0));
+        term.input('a');
+        term.goto(Line(16), Column(0));
+        term.linefeed();
+        term.input('b');
+
+        term.swap_alt();
+
+        assert_eq!(term.grid().history_size(), 18);
+        assert_eq!(term.grid()[Line(0)][Column(0)].c, 'p');
+        assert_eq!(term.grid()[17][Column(0)].c, 'b');
+        assert_eq!(term.grid()[34][Column(0)].c, 'a');
+    }
+
+    #[test]
+    fn export_history() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        term.input('a');
+        term.terminal_attribute(Attr::Bold);
+        term.input('<');
+        term.terminal_attribute(Attr::Reset);
+        term.carriage_return();
+        term.linefeed();
+        term.input('b');
+
+        assert!(term.history_to_string(false).starts_with("a<\nb\n\n"));
+
+        let html = term.history_to_html(false);
+        assert!(html.contains("font-weight: bold;\">&lt;</span>\n"));
+        assert!(html.contains(">b</span>\n"));
+        assert!(html.ends_with("</pre>\n</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn snapshot_after_byte_stream() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        let mut parser = ansi::Processor::new();
+        for byte in b"\x1b]2;title\x07hello\r\n\x1b[1mbold\x1b[0m" {
+            parser.advance(&mut term, *byte, &mut io::sink());
+        }
+
+        let snapshot = term.snapshot();
+        assert_eq!(snapshot.diff_text("hello\nbold\n"), None);
+        assert!(snapshot.lines[1][0].flags.contains(Flags::BOLD));
+        assert!(!snapshot.lines[1][4].flags.contains(Flags::BOLD));
+        assert_eq!(snapshot.cursor, Point::new(Line(1), Column(4)));
+        assert_eq!(snapshot.title.as_deref(), Some("title"));
+
+        let diff = snapshot.diff_text("hello\nbald\nmissing");
+        assert_eq!(diff.as_deref(), Some("@@ line 1 @@\n-bald\n+bold\n@@ line 2 @@\n-missing\n"));
+    }
+
+    #[test]
+    fn tab_stops() {
+        let size = SizeInfo { width: 60.0, ..test::size_info() };
+        let mut term = Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock);
+        let mut parser = ansi::Processor::new();
+        let mut advance = |term: &mut Term<Mock>, bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(term, *byte, &mut io::sink());
+            }
+        };
+
+        // Replace the default stops with a custom one at column 3
+        advance(&mut term, b"\x1b[3g\x1b[4G\x1bH\r\t");
+        assert_eq!(term.cursor.point.col, Column(3));
+        advance(&mut term, b"\r\x1b[2I");
+        assert_eq!(term.cursor.point.col, Column(19));
+
+        // Custom stops survive a soft reset
+        advance(&mut term, b"\x1b[!p\r\t");
+        assert_eq!(term.cursor.point.col, Column(3));
+
+        // CTC can clear all stops and set new ones
+        advance(&mut term, b"\x1b[5W\x1b[6G\x1b[0W\r\t");
+        assert_eq!(term.cursor.point.col, Column(5));
+        advance(&mut term, b"\x1b[2W\r\t");
+        assert_eq!(term.cursor.point.col, Column(19));
+
+        // DECST8C restores a stop every 8 columns
+        advance(&mut term, b"\x1b[?5W\r\t\t");
+        assert_eq!(term.cursor.point.col, Column(16));
+
+        // Huge counts stop at the edges of the screen
+        advance(&mut term, b"\x1b[99999999999I");
+        assert_eq!(term.cursor.point.col, Column(19));
+        advance(&mut term, b"\x1b[99999999999Z");
+        assert_eq!(term.cursor.point.col, Column(0));
+    }
+
+    #[test]
+    fn selection_survives_reflow() {
+        let mut size = test::size_info();
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        for c in "hello world".chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+        for c in "foo bar".chars() {
+            term.input(c);
+        }
+
+        // Select "world", which is split across two rows
+        let mut selection =
+            Selection::new(SelectionType::Simple, Point::new(16, Column(6)), Side::Left);
+        selection.update(Point::new(15, Column(3)), Side::Right);
+        *term.selection_mut() = Some(selection);
+        assert_eq!(term.selection_to_string(), Some(String::from("world")));
+
+        // Unwrap the line
+        size.width = 42.0;
+        term.resize(&size);
+        assert_eq!(term.selection_to_string(), Some(String::from("world")));
+
+        // Wrap it again at a different position
+        size.width = 12.0;
+        term.resize(&size);
+        assert_eq!(term.selection_to_string(), Some(String::from("world")));
+    }
+
+    #[test]
+    fn damage_tracking() {
+        let config = MockConfig::default();
+        let mut term = mock_term(&config, Mock);
+
+        assert_eq!(term.damage(&config), TermDamage::Full);
+        assert_eq!(term.damage(&config), TermDamage::Partial(Vec::new()));
+
+        term.goto(Line(2), Column(3));
+        term.input('a');
+        let bounds = LineDamageBounds { line: Line(2), left: Column(3), right: Column(5) };
+        assert_eq!(
+            term.damage(&config),
+            TermDamage::Partial(vec![
+                LineDamageBounds { line: Line(0), left: Column(0), right: Column(1) },
+                bounds,
+            ])
+        );
+
+        // Rewriting identical content does not cause any damage
+        term.goto(Line(2), Column(3));
+        term.input('a');
+        assert_eq!(term.damage(&config), TermDamage::Partial(Vec::new()));
+
+        term.set_color(0, Rgb { r: 0xff, g: 0, b: 0 });
+        assert_eq!(term.damage(&config), TermDamage::Full);
+    }
+
+    #[test]
+    fn line_timestamps() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        for c in "abcdefghij".chars() {
+            term.input(c);
+        }
+        term.carriage_return();
+        term.linefeed();
+        term.input('k');
+
+        // Both rows of the wrapped line are complete, the cursor line is not
+        let num_lines = term.grid.num_lines().0;
+        assert!(term.line_timestamp(num_lines - 1).is_some());
+        assert!(term.line_timestamp(num_lines - 2).is_some());
+        assert!(term.line_timestamp(num_lines - 3).is_none());
+
+        let text = term.history_to_string(true);
+        let mut lines = text.lines();
+        let first = lines.next().unwrap();
+        assert_eq!(first.len(), 21);
+        assert!(first.starts_with('[') && first.ends_with("] abcdefghij"));
+        assert_eq!(lines.next(), Some("           k"));
+    }
+
+    #[test]
+    fn input_single_shift() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+        term.configure_charset(CharsetIndex::G2, StandardCharset::SpecialCharacterAndLineDrawing);
+        term.configure_charset(CharsetIndex::G3, StandardCharset::UnitedKingdom);
+
+        // Single shifts only affect the next character
+        term.set_single_shift(CharsetIndex::G2);
+        term.input('q');
+        term.input('q');
+        term.set_single_shift(CharsetIndex::G3);
+        term.input('#');
+        term.input('#');
+
+        // Locking shifts stay active
+        term.set_active_charset(CharsetIndex::G2);
+        term.input('x');
+        term.input('x');
+
+        let line = &term.grid()[Line(0)];
+        let chars: String = (0..6).map(|col| line[Column(col)].c).collect();
+        assert_eq!(chars, "─q£#││");
+    }
+
     #[test]
     fn clear_saved_lines() {
         let size = SizeInfo {
@@ -2658,6 +5156,21 @@ mod tests {
         term.set_title(None);
         assert_eq!(term.title, None);
     }
+
+    #[test]
+    fn combining_marks() {
+        let mut term = mock_term(&MockConfig::default(), Mock);
+
+        // Marks of dead keys are added to the preceding character, even in the last column
+        for c in "e\u{301}xxxxxe\u{301}\u{323}".chars() {
+            term.input(c);
+        }
+
+        assert_eq!(term.grid[Line(0)][Column(0)].zerowidth(), Some(&['\u{301}'][..]));
+        assert_eq!(term.grid[Line(0)][Column(5)].zerowidth(), None);
+        assert_eq!(term.grid[Line(0)][Column(6)].zerowidth(), Some(&['\u{301}', '\u{323}'][..]));
+        assert_eq!(term.grid[Line(1)][Column(0)].c, ' ');
+    }
 }
 
 #[cfg(all(test, feature = "bench"))]
@@ -0,0 +1,133 @@
+//! Structured copies of the terminal state, for asserting on it without a renderer.
+
+use std::fmt::Write;
+
+use crate::ansi::{Color, CursorStyle};
+use crate::index::{Column, Line, Point};
+use crate::term::cell::{Cell, Flags};
+use crate::term::{Term, TermMode};
+
+/// Copy of a single cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellSnapshot {
+    pub c: char,
+    pub zerowidth: Vec<char>,
+    pub fg: Color,
+    pub bg: Color,
+    pub flags: Flags,
+}
+
+impl From<&Cell> for CellSnapshot {
+    fn from(cell: &Cell) -> Self {
+        Self {
+            c: cell.c,
+            zerowidth: cell.zerowidth().map(<[char]>::to_vec).unwrap_or_default(),
+            fg: cell.fg,
+            bg: cell.bg,
+            flags: cell.flags,
+        }
+    }
+}
+
+/// Copy of the terminal state, excluding the scrollback history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermSnapshot {
+    /// Cells of every line on the screen, from top to bottom.
+    ///
+    /// This ignores the display offset, so scrolling through the history does not change it.
+    pub lines: Vec<Vec<CellSnapshot>>,
+
+    /// Cursor position on the screen.
+    pub cursor: Point,
+
+    /// Cursor shape requested by the application, if any.
+    pub cursor_style: Option<CursorStyle>,
+
+    pub mode: TermMode,
+    pub title: Option<String>,
+}
+
+impl TermSnapshot {
+    /// Text of all lines, without trailing whitespace.
+    ///
+    /// Spacers of wide characters are omitted and zero-width characters are kept.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for line in &self.lines {
+            let mut line_text = String::new();
+            for cell in line.iter().filter(|cell| !cell.flags.contains(Flags::WIDE_CHAR_SPACER)) {
+                line_text.push(cell.c);
+                line_text.extend(&cell.zerowidth);
+            }
+
+            text.push_str(line_text.trim_end());
+            text.push('\n');
+        }
+
+        text
+    }
+
+    /// Compare the text of the snapshot with the `expected` text.
+    ///
+    /// Returns `None` if they are identical, otherwise a line-by-line description of all
+    /// differences, with the expected lines prefixed by `-` and the actual lines by `+`.
+    /// Trailing whitespace and trailing empty lines are not significant.
+    pub fn diff_text(&self, expected: &str) -> Option<String> {
+        let actual = self.text();
+        let actual: Vec<&str> = trimmed_lines(&actual);
+        let expected: Vec<&str> = trimmed_lines(expected);
+
+        let mut diff = String::new();
+        for line in 0..actual.len().max(expected.len()) {
+            let expected = expected.get(line).copied();
+            let actual = actual.get(line).copied();
+            if expected == actual {
+                continue;
+            }
+
+            let _ = writeln!(diff, "@@ line {} @@", line);
+            if let Some(expected) = expected {
+                let _ = writeln!(diff, "-{}", expected);
+            }
+            if let Some(actual) = actual {
+                let _ = writeln!(diff, "+{}", actual);
+            }
+        }
+
+        if diff.is_empty() {
+            None
+        } else {
+            Some(diff)
+        }
+    }
+}
+
+fn trimmed_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    while lines.last().map_or(false, |line| line.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+impl<T> Term<T> {
+    /// Copy the state of the screen.
+    pub fn snapshot(&self) -> TermSnapshot {
+        let num_cols = self.grid.num_cols().0;
+        let lines = (0..self.grid.num_lines().0)
+            .map(|line| {
+                let row = &self.grid[Line(line)];
+                (0..num_cols).map(|col| CellSnapshot::from(&row[Column(col)])).collect()
+            })
+            .collect();
+
+        TermSnapshot {
+            lines,
+            cursor: self.cursor.point,
+            cursor_style: self.cursor_style,
+            mode: self.mode,
+            title: self.title.clone(),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Consistency of double-width characters.
+//!
+//! Every `WIDE_CHAR` cell is directly followed by a `WIDE_CHAR_SPACER` on the same row. The only
+//! other spacers are leading spacers in the last column of a wrapped row, which stand in for a
+//! wide character that did not fit and was moved to the start of the next row.
+//!
+//! Operations which modify only some cells of a row have to make sure they do not split a wide
+//! character, otherwise one of its halves would be left behind on its own.
+
+use std::ops::Range;
+
+use crate::index::{Column, Line};
+use crate::term::cell::Flags;
+use crate::term::Term;
+
+impl<T> Term<T> {
+    /// Remove wide characters which cross the edges of `cols`.
+    ///
+    /// This must be called before the cells in `cols` are modified, since afterwards it is no
+    /// longer possible to tell which halves belonged together.
+    pub(super) fn clear_split_wide_chars(&mut self, line: Line, cols: Range<Column>) {
+        let num_cols = self.grid.num_cols();
+        if cols.start >= cols.end || cols.end > num_cols {
+            return;
+        }
+
+        let row = &mut self.grid[line];
+
+        let start = cols.start;
+        if start > Column(0)
+            && row[start].flags.contains(Flags::WIDE_CHAR_SPACER)
+            && row[start - 1].flags.contains(Flags::WIDE_CHAR)
+        {
+            row[start - 1].clear_wide();
+            row[start].clear_wide();
+        }
+
+        let end = cols.end;
+        if end < num_cols && row[end - 1].flags.contains(Flags::WIDE_CHAR) {
+            row[end - 1].clear_wide();
+            row[end].clear_wide();
+        }
+    }
+
+    /// Remove halves of wide characters in `cols` which have lost their counterpart.
+    ///
+    /// This is used after cells have been moved around without regard for wide characters.
+    pub(super) fn clear_orphaned_wide_chars(&mut self, line: Line, cols: Range<Column>) {
+        let num_cols = self.grid.num_cols();
+        let row = &mut self.grid[line];
+
+        for col in cols.start.0..cols.end.0.min(num_cols.0) {
+            let flags = row[Column(col)].flags;
+
+            let orphaned = if flags.contains(Flags::WIDE_CHAR) {
+                col + 1 >= num_cols.0
+                    || !row[Column(col + 1)].flags.contains(Flags::WIDE_CHAR_SPACER)
+            } else if flags.contains(Flags::WIDE_CHAR_SPACER) {
+                let leading = col + 1 == num_cols.0 && flags.contains(Flags::WRAPLINE);
+                !leading && (col == 0 || !row[Column(col - 1)].flags.contains(Flags::WIDE_CHAR))
+            } else {
+                false
+            };
+
+            if orphaned {
+                row[Column(col)].clear_wide();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ansi::{Handler, LineClearMode};
+    use crate::clipboard::Clipboard;
+    use crate::config::MockConfig;
+    use crate::event::{Event, EventListener};
+    use crate::index::{Column, Line};
+    use crate::term::cell::Flags;
+    use crate::term::{SizeInfo, Term};
+
+    struct Mock;
+    impl EventListener for Mock {
+        fn send_event(&self, _event: Event) {}
+    }
+
+    fn term() -> Term<Mock> {
+        let size = SizeInfo {
+            width: 21.0,
+            height: 51.0,
+            cell_width: 3.0,
+            cell_height: 3.0,
+            padding_x: 0.0,
+            padding_y: 0.0,
+            dpr: 1.0,
+        };
+        Term::new(&MockConfig::default(), &size, Clipboard::new_nop(), Mock)
+    }
+
+    /// Check that every wide character on the line is intact.
+    fn assert_wide_chars_valid(term: &Term<Mock>, line: Line) {
+        let row = &term.grid()[line];
+        let num_cols = term.grid().num_cols().0;
+
+        for col in 0..num_cols {
+            let flags = row[Column(col)].flags;
+            if flags.contains(Flags::WIDE_CHAR) {
+                assert!(col + 1 < num_cols, "wide char in last column");
+                assert!(row[Column(col + 1)].flags.contains(Flags::WIDE_CHAR_SPACER));
+            } else if flags.contains(Flags::WIDE_CHAR_SPACER) && col + 1 < num_cols {
+                assert!(col > 0 && row[Column(col - 1)].flags.contains(Flags::WIDE_CHAR));
+            }
+        }
+    }
+
+    fn text(term: &Term<Mock>, line: Line) -> String {
+        let row = &term.grid()[line];
+        (0..term.grid().num_cols().0)
+            .map(|col| &row[Column(col)])
+            .filter(|cell| !cell.flags.contains(Flags::WIDE_CHAR_SPACER))
+            .map(|cell| cell.c)
+            .collect()
+    }
+
+    /// Write `汉字` at the start of the first line.
+    fn write_wide(term: &mut Term<Mock>) {
+        term.goto(Line(0), Column(0));
+        term.input('汉');
+        term.input('字');
+        assert_wide_chars_valid(term, Line(0));
+    }
+
+    #[test]
+    fn overwrite_spacer() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(1));
+        term.input('a');
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), " a字   ");
+    }
+
+    #[test]
+    fn overwrite_wide_char() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(2));
+        term.input('a');
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "汉a    ");
+    }
+
+    #[test]
+    fn overwrite_with_shifted_wide_char() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(1));
+        term.input('字');
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), " 字    ");
+    }
+
+    #[test]
+    fn insert_blank_in_wide_char() {
+        let mut term = term();
+        for _ in 0..3 {
+            term.input('汉');
+        }
+
+        // The last wide char is pushed across the right edge
+        term.goto(Line(0), Column(1));
+        term.insert_blank(Column(2));
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "    汉 ");
+    }
+
+    #[test]
+    fn delete_chars_in_wide_char() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(1));
+        term.delete_chars(Column(2));
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "       ");
+    }
+
+    #[test]
+    fn erase_chars_in_wide_char() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(1));
+        term.erase_chars(Column(2));
+
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "       ");
+    }
+
+    #[test]
+    fn clear_line_in_wide_char() {
+        let mut term = term();
+        write_wide(&mut term);
+
+        term.goto(Line(0), Column(2));
+        term.clear_line(LineClearMode::Left);
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "       ");
+
+        write_wide(&mut term);
+        term.goto(Line(0), Column(1));
+        term.clear_line(LineClearMode::Right);
+        assert_wide_chars_valid(&term, Line(0));
+        assert_eq!(text(&term, Line(0)), "       ");
+    }
+
+    #[test]
+    fn wrapped_wide_char() {
+        let mut term = term();
+        for c in "abcdef汉".chars() {
+            term.input(c);
+        }
+
+        // The wide char is moved to the next line, leaving a leading spacer behind
+        assert_wide_chars_valid(&term, Line(0));
+        assert_wide_chars_valid(&term, Line(1));
+        assert!(term.grid()[Line(0)][Column(6)].flags.contains(Flags::WIDE_CHAR_SPACER));
+
+        term.goto(Line(1), Column(1));
+        term.input('a');
+
+        assert_wide_chars_valid(&term, Line(1));
+        assert_eq!(text(&term, Line(1)), " a     ");
+    }
+}
@@ -12,8 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(windows)]
+use std::env;
 use std::ffi::OsStr;
+use std::path::Path;
 use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{cmp, io};
 
 #[cfg(not(windows))]
@@ -43,14 +47,64 @@ pub fn limit<T: Ord>(value: T, min: T, max: T) -> T {
     cmp::min(cmp::max(value, min), max)
 }
 
+/// Format the local time of day as `HH:MM:SS`.
+pub fn format_time_of_day(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+    let tm = time::at(time::Timespec::new(secs as i64, 0));
+    time::strftime("%T", &tm).unwrap_or_default()
+}
+
+/// Check if a host name refers to this machine.
+pub fn is_local_host(host: &str) -> bool {
+    host.is_empty()
+        || host.eq_ignore_ascii_case("localhost")
+        || hostname().map_or(false, |hostname| host.eq_ignore_ascii_case(&hostname))
+}
+
+/// Name of this machine.
 #[cfg(not(windows))]
+pub fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+        return None;
+    }
+
+    let len = buf.iter().position(|&b| b == 0)?;
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+/// Name of this machine.
+#[cfg(windows)]
+pub fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME").ok()
+}
+
 pub fn start_daemon<I, S>(program: &str, args: I) -> io::Result<()>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
+    start_daemon_in(program, args, None)
+}
+
+/// Start a daemon, optionally in a different working directory.
+#[cfg(not(windows))]
+pub fn start_daemon_in<I, S>(
+    program: &str,
+    args: I,
+    working_directory: Option<&Path>,
+) -> io::Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
     unsafe {
-        Command::new(program)
+        command
             .args(args)
             .stdin(Stdio::null())
             .stdout(Stdio::null())
@@ -74,17 +128,27 @@ where
     }
 }
 
+/// Start a daemon, optionally in a different working directory.
 #[cfg(windows)]
-pub fn start_daemon<I, S>(program: &str, args: I) -> io::Result<()>
+pub fn start_daemon_in<I, S>(
+    program: &str,
+    args: I,
+    working_directory: Option<&Path>,
+) -> io::Result<()>
 where
     I: IntoIterator<Item = S>,
     S: AsRef<OsStr>,
 {
+    let mut command = Command::new(program);
+    if let Some(working_directory) = working_directory {
+        command.current_dir(working_directory);
+    }
+
     // Setting all the I/O handles to null and setting the
     // CREATE_NEW_PROCESS_GROUP and CREATE_NO_WINDOW has the effect
     // that console applications will run without opening a new
     // console window.
-    Command::new(program)
+    command
         .args(args)
         .stdin(Stdio::null())
         .stdout(Stdio::null())
@@ -96,7 +160,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::limit;
+    use super::{is_local_host, limit};
 
     #[test]
     fn limit_works() {
@@ -104,4 +168,11 @@ mod tests {
         assert_eq!(10, limit(5, 10, 100));
         assert_eq!(100, limit(1000, 10, 100));
     }
+
+    #[test]
+    fn local_host() {
+        assert!(is_local_host(""));
+        assert!(is_local_host("LocalHost"));
+        assert!(!is_local_host("remote.invalid"));
+    }
 }
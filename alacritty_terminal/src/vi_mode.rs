@@ -51,6 +51,24 @@ pub enum ViMotion {
     Bracket,
 }
 
+/// Search for a character in the line of the vi mode cursor.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InlineSearch {
+    /// Character to search for.
+    pub c: char,
+    /// Search towards the start of the line.
+    pub left: bool,
+    /// Stop next to the character, instead of on top of it.
+    pub stop_short: bool,
+}
+
+impl InlineSearch {
+    /// Search for the same character in the opposite direction.
+    pub fn reversed(self) -> Self {
+        Self { left: !self.left, ..self }
+    }
+}
+
 /// Cursor tracking vi mode position.
 #[derive(Default, Copy, Clone)]
 pub struct ViModeCursor {
@@ -144,6 +162,47 @@ impl ViModeCursor {
         self
     }
 
+    /// Move vi mode cursor to the next occurrence of a character in its line.
+    ///
+    /// Wrapped lines are searched as a single line. The cursor does not move when there is no
+    /// match.
+    #[must_use = "this returns the result of the operation, without modifying the original"]
+    pub fn inline_search<T: EventListener>(
+        mut self,
+        term: &mut Term<T>,
+        search: InlineSearch,
+    ) -> Self {
+        let mut point = expand_wide(term, term.visible_to_buffer(self.point), search.left);
+        let mut previous = point;
+        let mut adjacent = true;
+
+        let target = loop {
+            if is_line_end(term, point, search.left) {
+                return self;
+            }
+            point = advance(term, point, search.left);
+
+            let cell = &term.grid()[point.line][point.col];
+            if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                continue;
+            }
+
+            // Stopping short of an adjacent match would not move the cursor, so repeating the
+            // search skips over it instead
+            if cell.c == search.c && !(search.stop_short && adjacent) {
+                break if search.stop_short { expand_wide(term, previous, true) } else { point };
+            }
+
+            previous = point;
+            adjacent = false;
+        };
+
+        scroll_to_point(term, target);
+        self.point = term.grid().clamp_buffer_to_visible(target);
+
+        self
+    }
+
     /// Get target cursor point for vim-like page movement.
     #[must_use = "this returns the result of the operation, without modifying the original"]
     pub fn scroll<T: EventListener>(mut self, term: &Term<T>, lines: isize) -> Self {
@@ -268,7 +327,7 @@ fn semantic<T: EventListener>(
     // Expand semantically based on movement direction
     let expand_semantic = |point: Point<usize>| {
         // Do not expand when currently on a semantic escape char
-        let cell = term.grid()[point.line][point.col];
+        let cell = &term.grid()[point.line][point.col];
         if term.semantic_escape_chars().contains(cell.c)
             && !cell.flags.contains(Flags::WIDE_CHAR_SPACER)
         {
@@ -355,7 +414,7 @@ where
     P: Into<Point<usize>>,
 {
     let mut point = point.into();
-    let cell = term.grid()[point.line][point.col];
+    let cell = &term.grid()[point.line][point.col];
 
     if cell.flags.contains(Flags::WIDE_CHAR) && !left {
         point.col += 1;
@@ -395,7 +454,7 @@ fn advance<T>(term: &Term<T>, point: Point<usize>, left: bool) -> Point<usize> {
 
 /// Check if cell at point contains whitespace.
 fn is_space<T>(term: &Term<T>, point: Point<usize>) -> bool {
-    let cell = term.grid()[point.line][point.col];
+    let cell = &term.grid()[point.line][point.col];
     cell.c == ' ' || cell.c == '\t' && !cell.flags().contains(Flags::WIDE_CHAR_SPACER)
 }
 
@@ -403,6 +462,18 @@ fn is_wrap<T>(term: &Term<T>, point: Point<usize>) -> bool {
     term.grid()[point.line][point.col].flags.contains(Flags::WRAPLINE)
 }
 
+/// Check if point is at the start or end of a line, following line wraps.
+fn is_line_end<T>(term: &Term<T>, point: Point<usize>, left: bool) -> bool {
+    let cols = term.grid().num_cols();
+    if is_boundary(term, point, left) {
+        true
+    } else if left {
+        point.col.0 == 0 && !is_wrap(term, Point::new(point.line + 1, cols - 1))
+    } else {
+        point.col + 1 == cols && !is_wrap(term, point)
+    }
+}
+
 /// Check if point is at screen boundary.
 fn is_boundary<T>(term: &Term<T>, point: Point<usize>, left: bool) -> bool {
     (point.line == 0 && point.col + 1 >= term.grid().num_cols() && !left)
@@ -796,4 +867,46 @@ mod tests {
         cursor = cursor.motion(&mut term, ViMotion::WordLeft);
         assert_eq!(cursor.point, Point::new(Line(0), Column(0)));
     }
+
+    #[test]
+    fn inline_search() {
+        let mut term = term();
+        for (col, c) in "a汉b a".chars().enumerate() {
+            term.grid_mut()[Line(0)][Column(col + (col > 1) as usize)].c = c;
+        }
+        term.grid_mut()[Line(0)][Column(1)].flags.insert(Flags::WIDE_CHAR);
+        term.grid_mut()[Line(0)][Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        term.grid_mut()[Line(0)][Column(19)].flags.insert(Flags::WRAPLINE);
+        term.grid_mut()[Line(1)][Column(2)].c = 'a';
+
+        let search = |c, left, stop_short| InlineSearch { c, left, stop_short };
+        let mut cursor = ViModeCursor::new(Point::new(Line(0), Column(0)));
+
+        cursor = cursor.inline_search(&mut term, search('a', false, false));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(5)));
+
+        // Wrapped lines are searched as a single line
+        cursor = cursor.inline_search(&mut term, search('a', false, false));
+        assert_eq!(cursor.point, Point::new(Line(1), Column(2)));
+
+        cursor = cursor.inline_search(&mut term, search('b', true, false));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(3)));
+
+        // Stopping short of a wide char
+        cursor = cursor.inline_search(&mut term, search('汉', true, true));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(3)));
+        cursor = cursor.inline_search(&mut term, search('a', true, true));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(1)));
+        cursor = cursor.inline_search(&mut term, search('a', false, true));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(4)));
+
+        // Missing characters do not move the cursor
+        cursor = cursor.inline_search(&mut term, search('x', false, false));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(4)));
+        cursor = cursor.inline_search(&mut term, search('a', false, false));
+        assert_eq!(cursor.point, Point::new(Line(0), Column(5)));
+        cursor = cursor.inline_search(&mut term, search('a', false, false));
+        cursor = cursor.inline_search(&mut term, search('a', false, false));
+        assert_eq!(cursor.point, Point::new(Line(1), Column(2)));
+    }
 }
@@ -113,8 +113,8 @@ fn ref_test(dir: &Path) {
     if grid != term_grid {
         for i in 0..grid.len() {
             for j in 0..grid.num_cols().0 {
-                let cell = term_grid[i][Column(j)];
-                let original_cell = grid[i][Column(j)];
+                let cell = &term_grid[i][Column(j)];
+                let original_cell = &grid[i][Column(j)];
                 if original_cell != cell {
                     println!(
                         "[{i}][{j}] {original:?} => {now:?}",
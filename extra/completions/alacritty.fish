@@ -83,6 +83,19 @@ complete \
   -l "position" \
   -d "Window position <x-pos> <y-pos>"
 
+complete \
+  -c alacritty \
+  -x \
+  -l "monitor" \
+  -d "Monitor the window is opened on"
+
+complete \
+  -c alacritty \
+  -x \
+  -a "Windowed Maximized Fullscreen SimpleFullscreen" \
+  -l "startup-mode" \
+  -d "Startup mode of the window"
+
 complete \
   -c alacritty \
   -s "e" \
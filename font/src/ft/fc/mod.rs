@@ -240,10 +240,10 @@ impl From<isize> for Rgba {
 /// Hinting Style
 #[derive(Debug, Copy, Clone)]
 pub enum HintStyle {
-    None,
-    Slight,
-    Medium,
-    Full,
+    None = 0,
+    Slight = 1,
+    Medium = 2,
+    Full = 3,
 }
 
 impl fmt::Display for HintStyle {
@@ -24,6 +24,7 @@ use libc::{c_char, c_double, c_int};
 use super::ffi::FcResultMatch;
 use super::ffi::{FcBool, FcFontRenderPrepare, FcPatternGetBool, FcPatternGetDouble};
 use super::ffi::{FcChar8, FcConfigSubstitute, FcDefaultSubstitute, FcPattern, FcPatternHash};
+use super::ffi::{FcMatrix, FcPatternAddBool, FcPatternGetMatrix};
 use super::ffi::{FcPatternAddCharSet, FcPatternDestroy, FcPatternDuplicate, FcPatternGetCharSet};
 use super::ffi::{FcPatternAddDouble, FcPatternAddString, FcPatternCreate, FcPatternGetString};
 use super::ffi::{FcPatternAddInteger, FcPatternGetInteger, FcPatternPrint};
@@ -489,6 +490,10 @@ impl PatternRef {
         FcPatternAddInteger(self.as_ptr(), object.as_ptr() as *mut c_char, int as c_int) == 1
     }
 
+    unsafe fn add_boolean(&self, object: &[u8], value: bool) -> bool {
+        FcPatternAddBool(self.as_ptr(), object.as_ptr() as *mut c_char, value as FcBool) == 1
+    }
+
     unsafe fn add_double(&self, object: &[u8], value: f64) -> bool {
         FcPatternAddDouble(self.as_ptr(), object.as_ptr() as *mut c_char, value as c_double) == 1
     }
@@ -513,6 +518,14 @@ impl PatternRef {
         HintStylePropertyIter::new(self)
     }
 
+    pub fn set_hintstyle(&mut self, hintstyle: HintStyle) -> bool {
+        unsafe { self.add_integer(b"hintstyle\0", hintstyle as isize) }
+    }
+
+    pub fn set_autohint(&mut self, autohint: bool) -> bool {
+        unsafe { self.add_boolean(b"autohint\0", autohint) }
+    }
+
     pub fn lcdfilter(&self) -> LcdFilterPropertyIter {
         LcdFilterPropertyIter::new(self)
     }
@@ -591,6 +604,26 @@ impl PatternRef {
         }
     }
 
+    /// Transformation matrix used to synthesize oblique styles.
+    pub fn get_matrix(&self) -> Option<FcMatrix> {
+        unsafe {
+            let mut matrix: *mut _ = ptr::null_mut();
+
+            let result = FcPatternGetMatrix(
+                self.as_ptr(),
+                b"matrix\0".as_ptr() as *mut c_char,
+                0,
+                &mut matrix,
+            );
+
+            if result == FcResultMatch {
+                Some(*matrix)
+            } else {
+                None
+            }
+        }
+    }
+
     pub fn file(&self, index: usize) -> Option<PathBuf> {
         unsafe { self.get_string(b"file\0").nth(index) }.map(From::from)
     }
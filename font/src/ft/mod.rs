@@ -29,8 +29,8 @@ pub mod fc;
 use fc::{CharSet, FTFaceLocation, Pattern, PatternHash, PatternRef};
 
 use super::{
-    BitmapBuffer, FontDesc, FontKey, GlyphKey, Metrics, Rasterize, RasterizedGlyph, Size, Slant,
-    Style, Weight,
+    BitmapBuffer, FontDesc, FontKey, GlyphKey, Hinting, Metrics, Rasterize, RasterizedGlyph, Size,
+    Slant, Style, Subpixel, Weight,
 };
 
 struct FallbackFont {
@@ -63,6 +63,8 @@ struct FaceLoadingProperties {
     lcd_filter: c_uint,
     non_scalable: Option<f32>,
     colored: bool,
+    embolden: bool,
+    matrix: Option<freetype::Matrix>,
     pixelsize_fixup_factor: Option<f64>,
     ft_face: Rc<FTFace>,
 }
@@ -81,6 +83,8 @@ impl fmt::Debug for FaceLoadingProperties {
                 freetype::RenderMode::Max => "Max",
             })
             .field("lcd_filter", &self.lcd_filter)
+            .field("embolden", &self.embolden)
+            .field("matrix", &self.matrix)
             .finish()
     }
 }
@@ -92,6 +96,8 @@ pub struct FreeTypeRasterizer {
     ft_faces: HashMap<FTFaceLocation, Rc<FTFace>>,
     fallback_lists: HashMap<FontKey, FallbackList>,
     device_pixel_ratio: f32,
+    style_synthesis: bool,
+    subpixel: Subpixel,
 }
 
 #[inline]
@@ -99,6 +105,11 @@ fn to_freetype_26_6(f: f32) -> isize {
     ((1i32 << 6) as f32 * f) as isize
 }
 
+#[inline]
+fn to_freetype_16_16(f: f64) -> freetype::ffi::FT_Fixed {
+    (f * 65536.0) as freetype::ffi::FT_Fixed
+}
+
 impl Rasterize for FreeTypeRasterizer {
     type Err = Error;
 
@@ -111,6 +122,8 @@ impl Rasterize for FreeTypeRasterizer {
             fallback_lists: HashMap::new(),
             library,
             device_pixel_ratio,
+            style_synthesis: true,
+            subpixel: Subpixel::Auto,
         })
     }
 
@@ -170,6 +183,14 @@ impl Rasterize for FreeTypeRasterizer {
     fn update_dpr(&mut self, device_pixel_ratio: f32) {
         self.device_pixel_ratio = device_pixel_ratio;
     }
+
+    fn set_style_synthesis(&mut self, enabled: bool) {
+        self.style_synthesis = enabled;
+    }
+
+    fn set_subpixel(&mut self, subpixel: Subpixel) {
+        self.subpixel = subpixel;
+    }
 }
 
 pub trait IntoFontconfigType {
@@ -189,6 +210,33 @@ impl IntoFontconfigType for Slant {
     }
 }
 
+impl IntoFontconfigType for Hinting {
+    type FcType = fc::HintStyle;
+
+    fn into_fontconfig_type(&self) -> Self::FcType {
+        match *self {
+            Hinting::None => fc::HintStyle::None,
+            Hinting::Slight => fc::HintStyle::Slight,
+            Hinting::Full => fc::HintStyle::Full,
+        }
+    }
+}
+
+impl IntoFontconfigType for Subpixel {
+    type FcType = Option<fc::Rgba>;
+
+    fn into_fontconfig_type(&self) -> Self::FcType {
+        match *self {
+            Subpixel::Auto => None,
+            Subpixel::None => Some(fc::Rgba::None),
+            Subpixel::Rgb => Some(fc::Rgba::Rgb),
+            Subpixel::Bgr => Some(fc::Rgba::Bgr),
+            Subpixel::Vrgb => Some(fc::Rgba::Vrgb),
+            Subpixel::Vbgr => Some(fc::Rgba::Vbgr),
+        }
+    }
+}
+
 impl IntoFontconfigType for Weight {
     type FcType = fc::Weight;
 
@@ -229,6 +277,19 @@ impl FreeTypeRasterizer {
             },
         }
 
+        // Override hinting configured through fontconfig
+        if let Some(hinting) = desc.hinting {
+            pattern.set_hintstyle(hinting.into_fontconfig_type());
+        }
+        if let Some(autohint) = desc.autohint {
+            pattern.set_autohint(autohint);
+        }
+
+        // Override the subpixel layout reported by fontconfig
+        if let Some(rgba) = self.subpixel.into_fontconfig_type() {
+            pattern.set_rgba(&rgba);
+        }
+
         // Hash requested pattern
         let hash = pattern.hash();
 
@@ -335,12 +396,24 @@ impl FreeTypeRasterizer {
 
             let pixelsize_fixup_factor = pattern.pixelsizefixupfactor().next();
 
+            // Fontconfig requests emboldening and shearing for faces which
+            // lack the requested weight or slant
+            let embolden = pattern.embolden().next().unwrap_or(false);
+            let matrix = pattern.get_matrix().map(|matrix| freetype::Matrix {
+                xx: to_freetype_16_16(matrix.xx),
+                xy: to_freetype_16_16(matrix.xy),
+                yx: to_freetype_16_16(matrix.yx),
+                yy: to_freetype_16_16(matrix.yy),
+            });
+
             let face = FaceLoadingProperties {
                 load_flags: Self::ft_load_flags(pattern),
                 render_mode: Self::ft_render_mode(pattern),
                 lcd_filter: Self::ft_lcd_filter(pattern),
                 non_scalable,
                 colored: ft_face.has_color(),
+                embolden,
+                matrix,
                 pixelsize_fixup_factor,
                 ft_face,
             };
@@ -422,9 +495,21 @@ impl FreeTypeRasterizer {
             freetype::ffi::FT_Library_SetLcdFilter(ft_lib, face.lcd_filter);
         }
 
+        // Faces are shared between font keys, so the transform must always be reset
+        let mut matrix = match face.matrix {
+            Some(matrix) if self.style_synthesis => matrix,
+            _ => freetype::Matrix { xx: 0x10000, xy: 0, yx: 0, yy: 0x10000 },
+        };
+        face.ft_face.set_transform(&mut matrix, &mut freetype::Vector { x: 0, y: 0 });
+
         face.ft_face.load_glyph(index as u32, face.load_flags)?;
 
         let glyph = face.ft_face.glyph();
+
+        if face.embolden && self.style_synthesis {
+            Self::embolden(&face.ft_face, glyph)?;
+        }
+
         glyph.render_glyph(face.render_mode)?;
 
         let (pixel_height, pixel_width, buf) = Self::normalize_buffer(&glyph.bitmap())?;
@@ -452,6 +537,28 @@ impl FreeTypeRasterizer {
         }
     }
 
+    /// Embolden a glyph outline using the same strength as FreeType's `FT_GlyphSlot_Embolden`.
+    fn embolden(ft_face: &FTFace, glyph: &freetype::GlyphSlot) -> Result<(), Error> {
+        let slot = glyph.raw();
+        if slot.format != freetype::ffi::FT_GLYPH_FORMAT_OUTLINE {
+            return Ok(());
+        }
+
+        let metrics = ft_face.size_metrics().ok_or(Error::MissingSizeMetrics)?;
+        let strength = ((i64::from(ft_face.em_size()) * metrics.y_scale as i64) >> 16) / 24;
+
+        let err = unsafe {
+            let outline = &slot.outline as *const _ as *mut _;
+            freetype::ffi::FT_Outline_Embolden(outline, strength as freetype::ffi::FT_Pos)
+        };
+
+        if err == freetype::ffi::FT_Err_Ok {
+            Ok(())
+        } else {
+            Err(freetype::Error::from(err).into())
+        }
+    }
+
     fn ft_load_flags(pattern: &PatternRef) -> freetype::face::LoadFlag {
         let antialias = pattern.antialias().next().unwrap_or(true);
         let hinting = pattern.hintstyle().next().unwrap_or(fc::HintStyle::Slight);
@@ -459,6 +566,7 @@ impl FreeTypeRasterizer {
         let embedded_bitmaps = pattern.embeddedbitmap().next().unwrap_or(true);
         let scalable = pattern.scalable().next().unwrap_or(true);
         let color = pattern.color().next().unwrap_or(false);
+        let autohint = pattern.autohint().next().unwrap_or(false);
 
         use freetype::face::LoadFlag;
         let mut flags = match (antialias, hinting, rgba) {
@@ -486,8 +594,6 @@ impl FreeTypeRasterizer {
             (true, _, fc::Rgba::Vrgb) | (true, _, fc::Rgba::Vbgr) => LoadFlag::TARGET_LCD_V,
             // For non-rgba modes with either Medium or Full hinting, just use
             // the default hinting algorithm.
-            //
-            // TODO should Medium/Full control whether to use the auto hinter?
             (true, _, fc::Rgba::Unknown) => LoadFlag::TARGET_NORMAL,
             (true, _, fc::Rgba::None) => LoadFlag::TARGET_NORMAL,
         };
@@ -498,6 +604,11 @@ impl FreeTypeRasterizer {
             flags |= LoadFlag::NO_BITMAP;
         }
 
+        // Prefer FreeType's auto hinter over the font's own instructions
+        if autohint && !flags.contains(LoadFlag::NO_HINTING) {
+            flags |= LoadFlag::FORCE_AUTOHINT;
+        }
+
         if color {
             flags |= LoadFlag::COLOR;
         }
@@ -738,3 +849,16 @@ impl From<freetype::Error> for Error {
 }
 
 unsafe impl Send for FreeTypeRasterizer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn synthesized_oblique_matrix() {
+        // Fontconfig shears missing italic faces by 0.2
+        assert_eq!(to_freetype_16_16(1.), 0x10000);
+        assert_eq!(to_freetype_16_16(0.2), 0x3333);
+        assert_eq!(to_freetype_16_16(0.), 0);
+    }
+}
@@ -45,6 +45,29 @@ pub use darwin::*;
 pub struct FontDesc {
     name: String,
     style: Style,
+    hinting: Option<Hinting>,
+    autohint: Option<bool>,
+}
+
+/// Hinting strength applied to glyph outlines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hinting {
+    None,
+    Slight,
+    Full,
+}
+
+/// Subpixel layout used for LCD antialiasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Subpixel {
+    /// Use the layout configured by the system
+    Auto,
+    /// Grayscale antialiasing
+    None,
+    Rgb,
+    Bgr,
+    Vrgb,
+    Vbgr,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -83,7 +106,16 @@ impl FontDesc {
     where
         S: Into<String>,
     {
-        FontDesc { name: name.into(), style }
+        FontDesc { name: name.into(), style, hinting: None, autohint: None }
+    }
+
+    /// Override the hinting settings of the system for this font
+    ///
+    /// This is currently only supported by the FreeType backend.
+    pub fn with_hinting(mut self, hinting: Option<Hinting>, autohint: Option<bool>) -> FontDesc {
+        self.hinting = hinting;
+        self.autohint = autohint;
+        self
     }
 }
 
@@ -234,4 +266,10 @@ pub trait Rasterize {
 
     /// Update the Rasterizer's DPI factor
     fn update_dpr(&mut self, device_pixel_ratio: f32);
+
+    /// Toggle synthesized bold and italic for faces missing those styles
+    fn set_style_synthesis(&mut self, _enabled: bool) {}
+
+    /// Override the subpixel layout used for antialiasing
+    fn set_subpixel(&mut self, _subpixel: Subpixel) {}
 }